@@ -0,0 +1,112 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Benchmark comparing the virtual machine and the just-in-time compiler with a direct walk over the tokens on the
+//! Mandelbrot set example.
+//!
+//! Run with `cargo bench -p rustfuck_lib`.
+
+extern crate rustfuck_lib;
+
+use std::time::Duration;
+use std::time::Instant;
+
+use rustfuck_lib::Bytecode;
+use rustfuck_lib::Jit;
+use rustfuck_lib::Lexer;
+use rustfuck_lib::Optimizer;
+use rustfuck_lib::Program;
+use rustfuck_lib::Token;
+use rustfuck_lib::VirtualMachine;
+use rustfuck_lib::language::Brainfuck;
+
+/// The program to execute.
+static MANDELBROT: &str = include_str!("../../examples/mandelbrot.bf");
+
+/// Execute the `tokens` one after another, without any preprocessing besides matching the loops' brackets, and return
+/// the output.
+fn walk(tokens: &[Token]) -> Vec<u8> {
+    // The index of the matching bracket for each bracket.
+    let mut jumps: Vec<usize> = vec![0; tokens.len()];
+    let mut open: Vec<usize> = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        match *token {
+            Token::BeginLoop(..) => open.push(index),
+            Token::EndLoop(..) => {
+                let start: usize = open.pop().expect("The loops are balanced.");
+                jumps[start] = index;
+                jumps[index] = start;
+            },
+            _ => {},
+        }
+    }
+
+    let mut tape: Vec<u8> = vec![0; 30_000];
+    let mut pointer: usize = 0;
+    let mut output: Vec<u8> = Vec::new();
+    let mut counter: usize = 0;
+    while counter < tokens.len() {
+        match tokens[counter] {
+            Token::Add(..) => tape[pointer] = tape[pointer].wrapping_add(1),
+            Token::Sub(..) => tape[pointer] = tape[pointer].wrapping_sub(1),
+            Token::Right(..) => pointer += 1,
+            Token::Left(..) => pointer -= 1,
+            // Without any input, the cell is left unchanged, as with the default options.
+            Token::Read(..) => {},
+            Token::Write(..) => output.push(tape[pointer]),
+            Token::BeginLoop(..) if tape[pointer] == 0 => counter = jumps[counter],
+            Token::EndLoop(..) if tape[pointer] != 0 => counter = jumps[counter],
+            Token::BeginLoop(..) | Token::EndLoop(..) => {},
+        }
+
+        counter += 1;
+    }
+
+    output
+}
+
+/// Execute the `tokens` on the virtual machine after optimizing and compiling them, and return the output.
+fn execute(tokens: &[Token]) -> Vec<u8> {
+    let program = Optimizer::new().optimize(Program::new(tokens).expect("The loops are balanced."));
+    let bytecode = Bytecode::new(&program);
+
+    let mut output: Vec<u8> = Vec::new();
+    VirtualMachine::new().run(&bytecode, &mut "".as_bytes(), &mut output).expect("The program does not fail.");
+    output
+}
+
+/// Execute the `tokens` with the just-in-time compiler after optimizing and compiling them, and return the output.
+fn compile(tokens: &[Token]) -> Vec<u8> {
+    let program = Optimizer::new().optimize(Program::new(tokens).expect("The loops are balanced."));
+    let bytecode = Bytecode::new(&program);
+
+    let mut output: Vec<u8> = Vec::new();
+    Jit::new().run(&bytecode, &mut "".as_bytes(), &mut output).expect("The program does not fail.");
+    output
+}
+
+/// Run the `function` on the `tokens`, and print and return the output together with the elapsed time.
+fn measure(name: &str, function: fn(&[Token]) -> Vec<u8>, tokens: &[Token]) -> (Vec<u8>, Duration) {
+    let start = Instant::now();
+    let output: Vec<u8> = function(tokens);
+    let elapsed: Duration = start.elapsed();
+
+    println!("{name:<16} {milliseconds:>8} ms", name = name, milliseconds = elapsed.as_millis());
+    (output, elapsed)
+}
+
+/// Run the benchmark.
+fn main() {
+    let tokens: Vec<Token> = Brainfuck::new(MANDELBROT).tokenize();
+    let (walked, walk_time) = measure("token walk", walk, &tokens);
+    let (executed, execute_time) = measure("virtual machine", execute, &tokens);
+    let (compiled, compile_time) = measure("jit", compile, &tokens);
+
+    assert_eq!(walked, executed, "The outputs differ.");
+    assert_eq!(walked, compiled, "The outputs differ.");
+    println!("speedup (vm)     {speedup:>8.1}x", speedup = walk_time.as_secs_f64() / execute_time.as_secs_f64());
+    println!("speedup (jit)    {speedup:>8.1}x", speedup = walk_time.as_secs_f64() / compile_time.as_secs_f64());
+}
@@ -0,0 +1,13 @@
+
+import { readFileSync } from "node:fs";
+
+try {
+    process.stdout.write(run(readFileSync(process.stdin.fd)));
+} catch (error) {
+    if (!(error instanceof RuntimeError)) {
+        throw error;
+    }
+    process.stdout.write(error.output);
+    process.stderr.write(`${error.message}\n`);
+    process.exitCode = 3;
+}
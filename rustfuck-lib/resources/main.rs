@@ -0,0 +1,11 @@
+
+fn main() {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut output = io::BufWriter::new(stdout.lock());
+    if let Err(error) = run(&mut stdin.lock(), &mut output) {
+        let _ = output.flush();
+        eprintln!("{}", error);
+        std::process::exit(if error.kind() == io::ErrorKind::InvalidData { 3 } else { 1 });
+    }
+}
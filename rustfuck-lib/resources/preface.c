@@ -1,5 +1,12 @@
+#define _GNU_SOURCE
+
+#include "stddef.h"
+#include "stdint.h"
 #include "stdio.h"
+#include "stdlib.h"
+#include "string.h"
+
+typedef {cell_type} cell;
 
-int main() {
-    char tape[30000] = {0};
-    char *ptr = tape;
+{runtime}int main() {
+    cell *ptr = {tape};
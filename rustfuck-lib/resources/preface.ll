@@ -0,0 +1,9 @@
+declare i32 @getchar()
+declare i32 @putchar(i32)
+declare void @exit(i32) noreturn
+
+{runtime}define i32 @main() {
+entry:
+  %ptr = alloca i64
+  store i64 0, ptr %ptr
+{setup}
\ No newline at end of file
@@ -0,0 +1,25 @@
+export class RuntimeError extends Error {
+    constructor(message) {
+        super(message);
+        this.name = "RuntimeError";
+        this.output = new Uint8Array(0);
+    }
+}
+
+{runtime}export function run(input) {
+    const output = [];
+    try {
+        execute(input, output);
+    } catch (error) {
+        if (error instanceof RuntimeError) {
+            error.output = Uint8Array.from(output);
+        }
+        throw error;
+    }
+    return Uint8Array.from(output);
+}
+
+function execute(input, output) {
+    let tape = new {array}({tape_size});
+    let ptr = 0;
+    const bytes = input.values();
@@ -0,0 +1,10 @@
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+type Cell = {cell_type};
+
+{runtime}#[allow(unused_mut, unused_variables)]
+pub fn run<R: Read, W: Write>(input: &mut R, output: &mut W) -> io::Result<()> {
+    let mut tape: Vec<Cell> = vec![0; {tape_size}];
+    let mut ptr: usize = 0;
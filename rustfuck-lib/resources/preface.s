@@ -0,0 +1,4 @@
+{runtime}    .text
+    .globl _start
+_start:
+{setup}    xorl %r12d, %r12d
@@ -1,10 +1,12 @@
 {
     (*ptr)++;
-    ptr++;
-    ptr--;
+    ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
     while (*ptr) {
-        *ptr = getchar();
-        (*ptr)--;
+        *ptr = bf_read(*ptr);
+        *ptr -= 2;
+        ptr = ptr - tape >= 2 ? ptr - 2 : ptr + 29998;
+        *ptr += 3;
+        ptr = ptr - tape < 29997 ? ptr + 3 : ptr - 29997;
     }
     putchar(*ptr);
 }
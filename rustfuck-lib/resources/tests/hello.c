@@ -1,112 +1,138 @@
+#define _GNU_SOURCE
+
+#include "stddef.h"
+#include "stdint.h"
 #include "stdio.h"
+#include "stdlib.h"
+#include "string.h"
+
+typedef uint8_t cell;
+
+static cell tape[30000];
+
+static inline cell *bf_wrap(cell *ptr, ptrdiff_t offset) {
+    size_t length = sizeof(tape) / sizeof(cell);
+    size_t distance = (size_t) (offset < 0 ? -offset : offset) % length;
+    size_t position = (size_t) (ptr - tape) + (offset < 0 ? length - distance : distance);
+    return tape + position % length;
+}
+
+static inline cell *bf_search_right(cell *ptr) {
+    cell *zero = memchr(ptr, 0, sizeof(tape) - (ptr - tape));
+    if (!zero) {
+        zero = memchr(tape, 0, ptr - tape);
+    }
+    if (!zero) {
+        for (;;) {}
+    }
+    return zero;
+}
+
+static inline cell *bf_search_left(cell *ptr) {
+    cell *zero = memrchr(tape, 0, ptr - tape + 1);
+    if (!zero) {
+        zero = memrchr(ptr + 1, 0, sizeof(tape) - (ptr - tape) - 1);
+    }
+    if (!zero) {
+        for (;;) {}
+    }
+    return zero;
+}
+
+static inline cell bf_read(cell value) {
+    int character = getchar();
+    return character == EOF ? value : (cell) character;
+}
 
 int main() {
-    char tape[30000] = {0};
-    char *ptr = tape;
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
+    cell *ptr = tape;
+    *ptr += 8;
     while (*ptr) {
-        ptr++;
-        (*ptr)++;
-        (*ptr)++;
-        (*ptr)++;
-        (*ptr)++;
-        while (*ptr) {
-            ptr++;
-            (*ptr)++;
-            (*ptr)++;
-            ptr++;
-            (*ptr)++;
-            (*ptr)++;
-            (*ptr)++;
-            ptr++;
-            (*ptr)++;
-            (*ptr)++;
-            (*ptr)++;
-            ptr++;
-            (*ptr)++;
-            ptr--;
-            ptr--;
-            ptr--;
-            ptr--;
-            (*ptr)--;
+        if (ptr - tape < 29994) {
+            ptr[1] += 4;
+            ptr[2] += ptr[1] * 2;
+            ptr[3] += ptr[1] * 3;
+            ptr[4] += ptr[1] * 3;
+            ptr[5] += ptr[1];
+            ptr[1] = 0;
+            ptr[2]++;
+            ptr[3]++;
+            ptr[4]--;
+            ptr[6]++;
+        } else {
+            *bf_wrap(ptr, 1) += 4;
+            *bf_wrap(ptr, 2) += *bf_wrap(ptr, 1) * 2;
+            *bf_wrap(ptr, 3) += *bf_wrap(ptr, 1) * 3;
+            *bf_wrap(ptr, 4) += *bf_wrap(ptr, 1) * 3;
+            *bf_wrap(ptr, 5) += *bf_wrap(ptr, 1);
+            *bf_wrap(ptr, 1) = 0;
+            (*bf_wrap(ptr, 2))++;
+            (*bf_wrap(ptr, 3))++;
+            (*bf_wrap(ptr, 4))--;
+            (*bf_wrap(ptr, 6))++;
         }
-        ptr++;
-        (*ptr)++;
-        ptr++;
-        (*ptr)++;
-        ptr++;
-        (*ptr)--;
-        ptr++;
-        ptr++;
-        (*ptr)++;
-        while (*ptr) {
-            ptr--;
+        ptr = ptr - tape < 29994 ? ptr + 6 : ptr - 29994;
+        ptr = bf_search_left(ptr);
+        if (ptr - tape >= 1) {
+            ptr[-1]--;
+        } else {
+            (*bf_wrap(ptr, -1))--;
         }
-        ptr--;
-        (*ptr)--;
+        ptr = ptr - tape >= 1 ? ptr - 1 : ptr + 29999;
     }
-    ptr++;
-    ptr++;
-    putchar(*ptr);
-    ptr++;
-    (*ptr)--;
-    (*ptr)--;
-    (*ptr)--;
-    putchar(*ptr);
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
-    putchar(*ptr);
-    putchar(*ptr);
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
-    putchar(*ptr);
-    ptr++;
-    ptr++;
-    putchar(*ptr);
-    ptr--;
-    (*ptr)--;
-    putchar(*ptr);
-    ptr--;
+    ptr = ptr - tape < 29998 ? ptr + 2 : ptr - 29998;
+    if (ptr - tape < 29999) {
+        putchar(*ptr);
+        ptr[1] -= 3;
+    } else {
+        putchar(*ptr);
+        *bf_wrap(ptr, 1) -= 3;
+    }
+    ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
     putchar(*ptr);
-    (*ptr)++;
-    (*ptr)++;
-    (*ptr)++;
+    *ptr += 7;
     putchar(*ptr);
-    (*ptr)--;
-    (*ptr)--;
-    (*ptr)--;
-    (*ptr)--;
-    (*ptr)--;
-    (*ptr)--;
     putchar(*ptr);
-    (*ptr)--;
-    (*ptr)--;
-    (*ptr)--;
-    (*ptr)--;
-    (*ptr)--;
-    (*ptr)--;
-    (*ptr)--;
-    (*ptr)--;
+    *ptr += 3;
     putchar(*ptr);
-    ptr++;
-    ptr++;
-    (*ptr)++;
+    ptr = ptr - tape < 29998 ? ptr + 2 : ptr - 29998;
+    if (ptr - tape >= 1) {
+        putchar(*ptr);
+        ptr[-1]--;
+    } else {
+        putchar(*ptr);
+        (*bf_wrap(ptr, -1))--;
+    }
+    ptr = ptr - tape >= 1 ? ptr - 1 : ptr + 29999;
     putchar(*ptr);
-    ptr++;
-    (*ptr)++;
-    (*ptr)++;
+    ptr = ptr - tape >= 1 ? ptr - 1 : ptr + 29999;
+    if (ptr - tape < 29998) {
+        putchar(*ptr);
+        *ptr += 3;
+        putchar(*ptr);
+        *ptr -= 6;
+        putchar(*ptr);
+        *ptr -= 8;
+        putchar(*ptr);
+        ptr[2]++;
+    } else {
+        putchar(*ptr);
+        *ptr += 3;
+        putchar(*ptr);
+        *ptr -= 6;
+        putchar(*ptr);
+        *ptr -= 8;
+        putchar(*ptr);
+        (*bf_wrap(ptr, 2))++;
+    }
+    ptr = ptr - tape < 29998 ? ptr + 2 : ptr - 29998;
+    if (ptr - tape < 29999) {
+        putchar(*ptr);
+        ptr[1] += 2;
+    } else {
+        putchar(*ptr);
+        *bf_wrap(ptr, 1) += 2;
+    }
+    ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
     putchar(*ptr);
 }
@@ -0,0 +1,332 @@
+declare i32 @getchar()
+declare i32 @putchar(i32)
+declare void @exit(i32) noreturn
+
+@tape = internal global [30000 x i8] zeroinitializer
+
+define internal i64 @bf_wrap(i64 %ptr, i64 %offset) {
+entry:
+  %negative = icmp slt i64 %offset, 0
+  %raised = add i64 %offset, 30000
+  %distance = select i1 %negative, i64 %raised, i64 %offset
+  %position = add i64 %ptr, %distance
+  %inside = icmp ult i64 %position, 30000
+  %reduced = sub i64 %position, 30000
+  %result = select i1 %inside, i64 %position, i64 %reduced
+  ret i64 %result
+}
+
+declare ptr @memchr(ptr, i32, i64)
+declare ptr @memrchr(ptr, i32, i64)
+
+define internal i64 @bf_search_right(i64 %ptr) {
+entry:
+  %start = getelementptr inbounds i8, ptr @tape, i64 %ptr
+  %length = sub i64 30000, %ptr
+  %found = call ptr @memchr(ptr %start, i32 0, i64 %length)
+  %missing = icmp eq ptr %found, null
+  br i1 %missing, label %wrap, label %done
+
+wrap:
+  %wrapped = call ptr @memchr(ptr @tape, i32 0, i64 %ptr)
+  %endless = icmp eq ptr %wrapped, null
+  br i1 %endless, label %forever, label %done
+
+forever:
+  br label %forever
+
+done:
+  %result = phi ptr [ %found, %entry ], [ %wrapped, %wrap ]
+  %address = ptrtoint ptr %result to i64
+  %tape = ptrtoint ptr @tape to i64
+  %index = sub i64 %address, %tape
+  ret i64 %index
+}
+
+define internal i64 @bf_search_left(i64 %ptr) {
+entry:
+  %length = add i64 %ptr, 1
+  %found = call ptr @memrchr(ptr @tape, i32 0, i64 %length)
+  %missing = icmp eq ptr %found, null
+  br i1 %missing, label %wrap, label %done
+
+wrap:
+  %start = getelementptr inbounds i8, ptr @tape, i64 %length
+  %rest = sub i64 30000, %length
+  %wrapped = call ptr @memrchr(ptr %start, i32 0, i64 %rest)
+  %endless = icmp eq ptr %wrapped, null
+  br i1 %endless, label %forever, label %done
+
+forever:
+  br label %forever
+
+done:
+  %result = phi ptr [ %found, %entry ], [ %wrapped, %wrap ]
+  %address = ptrtoint ptr %result to i64
+  %tape = ptrtoint ptr @tape to i64
+  %index = sub i64 %address, %tape
+  ret i64 %index
+}
+
+define i32 @main() {
+entry:
+  %ptr = alloca i64
+  store i64 0, ptr %ptr
+  %t1 = load i64, ptr %ptr
+  %t2 = getelementptr inbounds i8, ptr @tape, i64 %t1
+  %t3 = load i8, ptr %t2
+  %t4 = add i8 %t3, 8
+  store i8 %t4, ptr %t2
+  br label %loop1
+
+loop1:
+  %t5 = load i64, ptr %ptr
+  %t6 = getelementptr inbounds i8, ptr @tape, i64 %t5
+  %t7 = load i8, ptr %t6
+  %t8 = icmp ne i8 %t7, 0
+  br i1 %t8, label %loop1.body, label %loop1.end
+
+loop1.body:
+  %t9 = load i64, ptr %ptr
+  %t10 = call i64 @bf_wrap(i64 %t9, i64 1)
+  %t11 = getelementptr inbounds i8, ptr @tape, i64 %t10
+  %t12 = load i8, ptr %t11
+  %t13 = add i8 %t12, 4
+  store i8 %t13, ptr %t11
+  %t14 = load i64, ptr %ptr
+  %t15 = call i64 @bf_wrap(i64 %t14, i64 1)
+  %t16 = getelementptr inbounds i8, ptr @tape, i64 %t15
+  %t17 = load i8, ptr %t16
+  %t18 = icmp ne i8 %t17, 0
+  br i1 %t18, label %multiply2, label %multiply2.end
+
+multiply2:
+  %t19 = load i64, ptr %ptr
+  %t20 = call i64 @bf_wrap(i64 %t19, i64 1)
+  %t21 = getelementptr inbounds i8, ptr @tape, i64 %t20
+  %t22 = load i8, ptr %t21
+  %t23 = mul i8 %t22, 2
+  %t24 = load i64, ptr %ptr
+  %t25 = call i64 @bf_wrap(i64 %t24, i64 2)
+  %t26 = getelementptr inbounds i8, ptr @tape, i64 %t25
+  %t27 = load i8, ptr %t26
+  %t28 = add i8 %t27, %t23
+  store i8 %t28, ptr %t26
+  %t29 = load i64, ptr %ptr
+  %t30 = call i64 @bf_wrap(i64 %t29, i64 1)
+  %t31 = getelementptr inbounds i8, ptr @tape, i64 %t30
+  %t32 = load i8, ptr %t31
+  %t33 = mul i8 %t32, 3
+  %t34 = load i64, ptr %ptr
+  %t35 = call i64 @bf_wrap(i64 %t34, i64 3)
+  %t36 = getelementptr inbounds i8, ptr @tape, i64 %t35
+  %t37 = load i8, ptr %t36
+  %t38 = add i8 %t37, %t33
+  store i8 %t38, ptr %t36
+  %t39 = load i64, ptr %ptr
+  %t40 = call i64 @bf_wrap(i64 %t39, i64 1)
+  %t41 = getelementptr inbounds i8, ptr @tape, i64 %t40
+  %t42 = load i8, ptr %t41
+  %t43 = mul i8 %t42, 3
+  %t44 = load i64, ptr %ptr
+  %t45 = call i64 @bf_wrap(i64 %t44, i64 4)
+  %t46 = getelementptr inbounds i8, ptr @tape, i64 %t45
+  %t47 = load i8, ptr %t46
+  %t48 = add i8 %t47, %t43
+  store i8 %t48, ptr %t46
+  %t49 = load i64, ptr %ptr
+  %t50 = call i64 @bf_wrap(i64 %t49, i64 1)
+  %t51 = getelementptr inbounds i8, ptr @tape, i64 %t50
+  %t52 = load i8, ptr %t51
+  %t53 = load i64, ptr %ptr
+  %t54 = call i64 @bf_wrap(i64 %t53, i64 5)
+  %t55 = getelementptr inbounds i8, ptr @tape, i64 %t54
+  %t56 = load i8, ptr %t55
+  %t57 = add i8 %t56, %t52
+  store i8 %t57, ptr %t55
+  br label %multiply2.end
+
+multiply2.end:
+  %t58 = load i64, ptr %ptr
+  %t59 = call i64 @bf_wrap(i64 %t58, i64 1)
+  %t60 = getelementptr inbounds i8, ptr @tape, i64 %t59
+  store i8 0, ptr %t60
+  %t61 = load i64, ptr %ptr
+  %t62 = call i64 @bf_wrap(i64 %t61, i64 2)
+  %t63 = getelementptr inbounds i8, ptr @tape, i64 %t62
+  %t64 = load i8, ptr %t63
+  %t65 = add i8 %t64, 1
+  store i8 %t65, ptr %t63
+  %t66 = load i64, ptr %ptr
+  %t67 = call i64 @bf_wrap(i64 %t66, i64 3)
+  %t68 = getelementptr inbounds i8, ptr @tape, i64 %t67
+  %t69 = load i8, ptr %t68
+  %t70 = add i8 %t69, 1
+  store i8 %t70, ptr %t68
+  %t71 = load i64, ptr %ptr
+  %t72 = call i64 @bf_wrap(i64 %t71, i64 4)
+  %t73 = getelementptr inbounds i8, ptr @tape, i64 %t72
+  %t74 = load i8, ptr %t73
+  %t75 = add i8 %t74, 255
+  store i8 %t75, ptr %t73
+  %t76 = load i64, ptr %ptr
+  %t77 = call i64 @bf_wrap(i64 %t76, i64 6)
+  %t78 = getelementptr inbounds i8, ptr @tape, i64 %t77
+  %t79 = load i8, ptr %t78
+  %t80 = add i8 %t79, 1
+  store i8 %t80, ptr %t78
+  %t81 = load i64, ptr %ptr
+  %t82 = call i64 @bf_wrap(i64 %t81, i64 6)
+  store i64 %t82, ptr %ptr
+  %t83 = load i64, ptr %ptr
+  %t84 = call i64 @bf_search_left(i64 %t83)
+  store i64 %t84, ptr %ptr
+  %t85 = load i64, ptr %ptr
+  %t86 = call i64 @bf_wrap(i64 %t85, i64 -1)
+  %t87 = getelementptr inbounds i8, ptr @tape, i64 %t86
+  %t88 = load i8, ptr %t87
+  %t89 = add i8 %t88, 255
+  store i8 %t89, ptr %t87
+  %t90 = load i64, ptr %ptr
+  %t91 = call i64 @bf_wrap(i64 %t90, i64 -1)
+  store i64 %t91, ptr %ptr
+  br label %loop1
+
+loop1.end:
+  %t92 = load i64, ptr %ptr
+  %t93 = call i64 @bf_wrap(i64 %t92, i64 2)
+  store i64 %t93, ptr %ptr
+  %t94 = load i64, ptr %ptr
+  %t95 = getelementptr inbounds i8, ptr @tape, i64 %t94
+  %t96 = load i8, ptr %t95
+  %t97 = zext i8 %t96 to i32
+  %t98 = call i32 @putchar(i32 %t97)
+  %t99 = load i64, ptr %ptr
+  %t100 = call i64 @bf_wrap(i64 %t99, i64 1)
+  %t101 = getelementptr inbounds i8, ptr @tape, i64 %t100
+  %t102 = load i8, ptr %t101
+  %t103 = add i8 %t102, 253
+  store i8 %t103, ptr %t101
+  %t104 = load i64, ptr %ptr
+  %t105 = call i64 @bf_wrap(i64 %t104, i64 1)
+  store i64 %t105, ptr %ptr
+  %t106 = load i64, ptr %ptr
+  %t107 = getelementptr inbounds i8, ptr @tape, i64 %t106
+  %t108 = load i8, ptr %t107
+  %t109 = zext i8 %t108 to i32
+  %t110 = call i32 @putchar(i32 %t109)
+  %t111 = load i64, ptr %ptr
+  %t112 = getelementptr inbounds i8, ptr @tape, i64 %t111
+  %t113 = load i8, ptr %t112
+  %t114 = add i8 %t113, 7
+  store i8 %t114, ptr %t112
+  %t115 = load i64, ptr %ptr
+  %t116 = getelementptr inbounds i8, ptr @tape, i64 %t115
+  %t117 = load i8, ptr %t116
+  %t118 = zext i8 %t117 to i32
+  %t119 = call i32 @putchar(i32 %t118)
+  %t120 = load i64, ptr %ptr
+  %t121 = getelementptr inbounds i8, ptr @tape, i64 %t120
+  %t122 = load i8, ptr %t121
+  %t123 = zext i8 %t122 to i32
+  %t124 = call i32 @putchar(i32 %t123)
+  %t125 = load i64, ptr %ptr
+  %t126 = getelementptr inbounds i8, ptr @tape, i64 %t125
+  %t127 = load i8, ptr %t126
+  %t128 = add i8 %t127, 3
+  store i8 %t128, ptr %t126
+  %t129 = load i64, ptr %ptr
+  %t130 = getelementptr inbounds i8, ptr @tape, i64 %t129
+  %t131 = load i8, ptr %t130
+  %t132 = zext i8 %t131 to i32
+  %t133 = call i32 @putchar(i32 %t132)
+  %t134 = load i64, ptr %ptr
+  %t135 = call i64 @bf_wrap(i64 %t134, i64 2)
+  store i64 %t135, ptr %ptr
+  %t136 = load i64, ptr %ptr
+  %t137 = getelementptr inbounds i8, ptr @tape, i64 %t136
+  %t138 = load i8, ptr %t137
+  %t139 = zext i8 %t138 to i32
+  %t140 = call i32 @putchar(i32 %t139)
+  %t141 = load i64, ptr %ptr
+  %t142 = call i64 @bf_wrap(i64 %t141, i64 -1)
+  %t143 = getelementptr inbounds i8, ptr @tape, i64 %t142
+  %t144 = load i8, ptr %t143
+  %t145 = add i8 %t144, 255
+  store i8 %t145, ptr %t143
+  %t146 = load i64, ptr %ptr
+  %t147 = call i64 @bf_wrap(i64 %t146, i64 -1)
+  store i64 %t147, ptr %ptr
+  %t148 = load i64, ptr %ptr
+  %t149 = getelementptr inbounds i8, ptr @tape, i64 %t148
+  %t150 = load i8, ptr %t149
+  %t151 = zext i8 %t150 to i32
+  %t152 = call i32 @putchar(i32 %t151)
+  %t153 = load i64, ptr %ptr
+  %t154 = call i64 @bf_wrap(i64 %t153, i64 -1)
+  store i64 %t154, ptr %ptr
+  %t155 = load i64, ptr %ptr
+  %t156 = getelementptr inbounds i8, ptr @tape, i64 %t155
+  %t157 = load i8, ptr %t156
+  %t158 = zext i8 %t157 to i32
+  %t159 = call i32 @putchar(i32 %t158)
+  %t160 = load i64, ptr %ptr
+  %t161 = getelementptr inbounds i8, ptr @tape, i64 %t160
+  %t162 = load i8, ptr %t161
+  %t163 = add i8 %t162, 3
+  store i8 %t163, ptr %t161
+  %t164 = load i64, ptr %ptr
+  %t165 = getelementptr inbounds i8, ptr @tape, i64 %t164
+  %t166 = load i8, ptr %t165
+  %t167 = zext i8 %t166 to i32
+  %t168 = call i32 @putchar(i32 %t167)
+  %t169 = load i64, ptr %ptr
+  %t170 = getelementptr inbounds i8, ptr @tape, i64 %t169
+  %t171 = load i8, ptr %t170
+  %t172 = add i8 %t171, 250
+  store i8 %t172, ptr %t170
+  %t173 = load i64, ptr %ptr
+  %t174 = getelementptr inbounds i8, ptr @tape, i64 %t173
+  %t175 = load i8, ptr %t174
+  %t176 = zext i8 %t175 to i32
+  %t177 = call i32 @putchar(i32 %t176)
+  %t178 = load i64, ptr %ptr
+  %t179 = getelementptr inbounds i8, ptr @tape, i64 %t178
+  %t180 = load i8, ptr %t179
+  %t181 = add i8 %t180, 248
+  store i8 %t181, ptr %t179
+  %t182 = load i64, ptr %ptr
+  %t183 = getelementptr inbounds i8, ptr @tape, i64 %t182
+  %t184 = load i8, ptr %t183
+  %t185 = zext i8 %t184 to i32
+  %t186 = call i32 @putchar(i32 %t185)
+  %t187 = load i64, ptr %ptr
+  %t188 = call i64 @bf_wrap(i64 %t187, i64 2)
+  %t189 = getelementptr inbounds i8, ptr @tape, i64 %t188
+  %t190 = load i8, ptr %t189
+  %t191 = add i8 %t190, 1
+  store i8 %t191, ptr %t189
+  %t192 = load i64, ptr %ptr
+  %t193 = call i64 @bf_wrap(i64 %t192, i64 2)
+  store i64 %t193, ptr %ptr
+  %t194 = load i64, ptr %ptr
+  %t195 = getelementptr inbounds i8, ptr @tape, i64 %t194
+  %t196 = load i8, ptr %t195
+  %t197 = zext i8 %t196 to i32
+  %t198 = call i32 @putchar(i32 %t197)
+  %t199 = load i64, ptr %ptr
+  %t200 = call i64 @bf_wrap(i64 %t199, i64 1)
+  %t201 = getelementptr inbounds i8, ptr @tape, i64 %t200
+  %t202 = load i8, ptr %t201
+  %t203 = add i8 %t202, 2
+  store i8 %t203, ptr %t201
+  %t204 = load i64, ptr %ptr
+  %t205 = call i64 @bf_wrap(i64 %t204, i64 1)
+  store i64 %t205, ptr %ptr
+  %t206 = load i64, ptr %ptr
+  %t207 = getelementptr inbounds i8, ptr @tape, i64 %t206
+  %t208 = load i8, ptr %t207
+  %t209 = zext i8 %t208 to i32
+  %t210 = call i32 @putchar(i32 %t209)
+  ret i32 0
+}
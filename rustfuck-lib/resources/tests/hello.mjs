@@ -0,0 +1,89 @@
+export class RuntimeError extends Error {
+    constructor(message) {
+        super(message);
+        this.name = "RuntimeError";
+        this.output = new Uint8Array(0);
+    }
+}
+
+function wrap(ptr, offset) {
+    const position = ptr + (offset < 0 ? offset + 30000 : offset);
+    return position < 30000 ? position : position - 30000;
+}
+
+function searchLeft(tape, ptr) {
+    let position = tape.lastIndexOf(0, ptr);
+    if (position < 0) {
+        position = tape.lastIndexOf(0);
+    }
+    while (position < 0) {
+    }
+    return position;
+}
+
+export function run(input) {
+    const output = [];
+    try {
+        execute(input, output);
+    } catch (error) {
+        if (error instanceof RuntimeError) {
+            error.output = Uint8Array.from(output);
+        }
+        throw error;
+    }
+    return Uint8Array.from(output);
+}
+
+function execute(input, output) {
+    let tape = new Uint8Array(30000);
+    let ptr = 0;
+    const bytes = input.values();
+    tape[ptr] += 8;
+    while (tape[ptr] !== 0) {
+        tape[wrap(ptr, 1)] += 4;
+        if (tape[wrap(ptr, 1)] !== 0) {
+            tape[wrap(ptr, 2)] += tape[wrap(ptr, 1)] * 2;
+            tape[wrap(ptr, 3)] += tape[wrap(ptr, 1)] * 3;
+            tape[wrap(ptr, 4)] += tape[wrap(ptr, 1)] * 3;
+            tape[wrap(ptr, 5)] += tape[wrap(ptr, 1)];
+        }
+        tape[wrap(ptr, 1)] = 0;
+        tape[wrap(ptr, 2)] += 1;
+        tape[wrap(ptr, 3)] += 1;
+        tape[wrap(ptr, 4)] -= 1;
+        tape[wrap(ptr, 6)] += 1;
+        ptr = wrap(ptr, 6);
+        ptr = searchLeft(tape, ptr);
+        tape[wrap(ptr, -1)] -= 1;
+        ptr = wrap(ptr, -1);
+    }
+    ptr = wrap(ptr, 2);
+    output.push(tape[ptr]);
+    tape[wrap(ptr, 1)] -= 3;
+    ptr = wrap(ptr, 1);
+    output.push(tape[ptr]);
+    tape[ptr] += 7;
+    output.push(tape[ptr]);
+    output.push(tape[ptr]);
+    tape[ptr] += 3;
+    output.push(tape[ptr]);
+    ptr = wrap(ptr, 2);
+    output.push(tape[ptr]);
+    tape[wrap(ptr, -1)] -= 1;
+    ptr = wrap(ptr, -1);
+    output.push(tape[ptr]);
+    ptr = wrap(ptr, -1);
+    output.push(tape[ptr]);
+    tape[ptr] += 3;
+    output.push(tape[ptr]);
+    tape[ptr] -= 6;
+    output.push(tape[ptr]);
+    tape[ptr] -= 8;
+    output.push(tape[ptr]);
+    tape[wrap(ptr, 2)] += 1;
+    ptr = wrap(ptr, 2);
+    output.push(tape[ptr]);
+    tape[wrap(ptr, 1)] += 2;
+    ptr = wrap(ptr, 1);
+    output.push(tape[ptr]);
+}
@@ -0,0 +1,84 @@
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+type Cell = u8;
+
+fn wrap(ptr: usize, offset: isize) -> usize {
+    let length: usize = 30000;
+    let position: usize = ptr + if offset < 0 { (offset + length as isize) as usize } else { offset as usize };
+    if position < length { position } else { position - length }
+}
+
+fn search_left(tape: &[Cell], ptr: usize) -> usize {
+    match tape[..=ptr].iter().rposition(|&cell| cell == 0) {
+        Some(position) => position,
+        None => ptr + 1 + tape[ptr + 1..].iter().rposition(|&cell| cell == 0).unwrap_or_else(|| loop {}),
+    }
+}
+
+#[allow(unused_mut, unused_variables)]
+pub fn run<R: Read, W: Write>(input: &mut R, output: &mut W) -> io::Result<()> {
+    let mut tape: Vec<Cell> = vec![0; 30000];
+    let mut ptr: usize = 0;
+    tape[ptr] = tape[ptr].wrapping_add(8);
+    while tape[ptr] != 0 {
+        tape[wrap(ptr, 1)] = tape[wrap(ptr, 1)].wrapping_add(4);
+        if tape[wrap(ptr, 1)] != 0 {
+            tape[wrap(ptr, 2)] = tape[wrap(ptr, 2)].wrapping_add(tape[wrap(ptr, 1)].wrapping_mul(2));
+            tape[wrap(ptr, 3)] = tape[wrap(ptr, 3)].wrapping_add(tape[wrap(ptr, 1)].wrapping_mul(3));
+            tape[wrap(ptr, 4)] = tape[wrap(ptr, 4)].wrapping_add(tape[wrap(ptr, 1)].wrapping_mul(3));
+            tape[wrap(ptr, 5)] = tape[wrap(ptr, 5)].wrapping_add(tape[wrap(ptr, 1)]);
+        }
+        tape[wrap(ptr, 1)] = 0;
+        tape[wrap(ptr, 2)] = tape[wrap(ptr, 2)].wrapping_add(1);
+        tape[wrap(ptr, 3)] = tape[wrap(ptr, 3)].wrapping_add(1);
+        tape[wrap(ptr, 4)] = tape[wrap(ptr, 4)].wrapping_sub(1);
+        tape[wrap(ptr, 6)] = tape[wrap(ptr, 6)].wrapping_add(1);
+        ptr = wrap(ptr, 6);
+        ptr = search_left(&tape, ptr);
+        tape[wrap(ptr, -1)] = tape[wrap(ptr, -1)].wrapping_sub(1);
+        ptr = wrap(ptr, -1);
+    }
+    ptr = wrap(ptr, 2);
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    tape[wrap(ptr, 1)] = tape[wrap(ptr, 1)].wrapping_sub(3);
+    ptr = wrap(ptr, 1);
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    tape[ptr] = tape[ptr].wrapping_add(7);
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    tape[ptr] = tape[ptr].wrapping_add(3);
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    ptr = wrap(ptr, 2);
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    tape[wrap(ptr, -1)] = tape[wrap(ptr, -1)].wrapping_sub(1);
+    ptr = wrap(ptr, -1);
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    ptr = wrap(ptr, -1);
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    tape[ptr] = tape[ptr].wrapping_add(3);
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    tape[ptr] = tape[ptr].wrapping_sub(6);
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    tape[ptr] = tape[ptr].wrapping_sub(8);
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    tape[wrap(ptr, 2)] = tape[wrap(ptr, 2)].wrapping_add(1);
+    ptr = wrap(ptr, 2);
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    tape[wrap(ptr, 1)] = tape[wrap(ptr, 1)].wrapping_add(2);
+    ptr = wrap(ptr, 1);
+    output.write_all(&tape[ptr].to_le_bytes()[..1])?;
+    output.flush()
+}
+
+fn main() {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut output = io::BufWriter::new(stdout.lock());
+    if let Err(error) = run(&mut stdin.lock(), &mut output) {
+        let _ = output.flush();
+        eprintln!("{}", error);
+        std::process::exit(if error.kind() == io::ErrorKind::InvalidData { 3 } else { 1 });
+    }
+}
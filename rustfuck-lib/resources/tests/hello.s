@@ -0,0 +1,272 @@
+    .local bf_tape
+    .comm bf_tape, 30000, 8
+
+    .lcomm bf_output, 4096
+    .lcomm bf_output_length, 8
+
+    .text
+bf_flush:
+    leaq bf_output(%rip), %rsi
+    movq bf_output_length(%rip), %rdx
+    movq $0, bf_output_length(%rip)
+1:
+    testq %rdx, %rdx
+    jz 2f
+    movl $1, %eax
+    movl $1, %edi
+    syscall
+    testq %rax, %rax
+    jle 2f
+    addq %rax, %rsi
+    subq %rax, %rdx
+    jmp 1b
+2:
+    ret
+
+bf_write:
+    movq bf_output_length(%rip), %rax
+    leaq bf_output(%rip), %rcx
+    movb %dil, (%rcx,%rax)
+    incq %rax
+    movq %rax, bf_output_length(%rip)
+    cmpq $4096, %rax
+    je bf_flush
+    ret
+
+    .text
+    .globl _start
+_start:
+    leaq bf_tape(%rip), %rbx
+    xorl %r12d, %r12d
+    addb $8, (%rbx,%r12,1)
+.Lloop1:
+    cmpb $0, (%rbx,%r12,1)
+    je .Lloop1_end
+    movabsq $1, %r13
+    addq %r12, %r13
+    movabsq $-29999, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb $4, (%rbx,%r13,1)
+    movabsq $1, %r13
+    addq %r12, %r13
+    movabsq $-29999, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    cmpb $0, (%rbx,%r13,1)
+    je .Lmultiply2_end
+    movzbl (%rbx,%r13,1), %eax
+    imull $2, %eax, %eax
+    movabsq $2, %r13
+    addq %r12, %r13
+    movabsq $-29998, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb %al, (%rbx,%r13,1)
+    movabsq $1, %r13
+    addq %r12, %r13
+    movabsq $-29999, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movzbl (%rbx,%r13,1), %eax
+    imull $3, %eax, %eax
+    movabsq $3, %r13
+    addq %r12, %r13
+    movabsq $-29997, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb %al, (%rbx,%r13,1)
+    movabsq $1, %r13
+    addq %r12, %r13
+    movabsq $-29999, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movzbl (%rbx,%r13,1), %eax
+    imull $3, %eax, %eax
+    movabsq $4, %r13
+    addq %r12, %r13
+    movabsq $-29996, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb %al, (%rbx,%r13,1)
+    movabsq $1, %r13
+    addq %r12, %r13
+    movabsq $-29999, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movzbl (%rbx,%r13,1), %eax
+    movabsq $5, %r13
+    addq %r12, %r13
+    movabsq $-29995, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb %al, (%rbx,%r13,1)
+.Lmultiply2_end:
+    movabsq $1, %r13
+    addq %r12, %r13
+    movabsq $-29999, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movb $0, (%rbx,%r13,1)
+    movabsq $2, %r13
+    addq %r12, %r13
+    movabsq $-29998, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb $1, (%rbx,%r13,1)
+    movabsq $3, %r13
+    addq %r12, %r13
+    movabsq $-29997, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb $1, (%rbx,%r13,1)
+    movabsq $4, %r13
+    addq %r12, %r13
+    movabsq $-29996, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb $255, (%rbx,%r13,1)
+    movabsq $6, %r13
+    addq %r12, %r13
+    movabsq $-29994, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb $1, (%rbx,%r13,1)
+    movabsq $6, %r13
+    addq %r12, %r13
+    movabsq $-29994, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movq %r13, %r12
+    leaq (%rbx,%r12), %rdi
+    leaq 1(%r12), %rcx
+    xorl %eax, %eax
+    std
+    repne scasb
+    je .Lsearch3_end
+    movabsq $29999, %rcx
+    leaq (%rbx,%rcx), %rdi
+    subq %r12, %rcx
+    repne scasb
+    je .Lsearch3_end
+.Lsearch3:
+    jmp .Lsearch3
+.Lsearch3_end:
+    cld
+    leaq 1(%rdi), %r12
+    subq %rbx, %r12
+    movabsq $29999, %r13
+    addq %r12, %r13
+    movabsq $-1, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb $255, (%rbx,%r13,1)
+    movabsq $29999, %r13
+    addq %r12, %r13
+    movabsq $-1, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movq %r13, %r12
+    jmp .Lloop1
+.Lloop1_end:
+    movabsq $2, %r13
+    addq %r12, %r13
+    movabsq $-29998, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movq %r13, %r12
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    movabsq $1, %r13
+    addq %r12, %r13
+    movabsq $-29999, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb $253, (%rbx,%r13,1)
+    movabsq $1, %r13
+    addq %r12, %r13
+    movabsq $-29999, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movq %r13, %r12
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    addb $7, (%rbx,%r12,1)
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    addb $3, (%rbx,%r12,1)
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    movabsq $2, %r13
+    addq %r12, %r13
+    movabsq $-29998, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movq %r13, %r12
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    movabsq $29999, %r13
+    addq %r12, %r13
+    movabsq $-1, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb $255, (%rbx,%r13,1)
+    movabsq $29999, %r13
+    addq %r12, %r13
+    movabsq $-1, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movq %r13, %r12
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    movabsq $29999, %r13
+    addq %r12, %r13
+    movabsq $-1, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movq %r13, %r12
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    addb $3, (%rbx,%r12,1)
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    addb $250, (%rbx,%r12,1)
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    addb $248, (%rbx,%r12,1)
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    movabsq $2, %r13
+    addq %r12, %r13
+    movabsq $-29998, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb $1, (%rbx,%r13,1)
+    movabsq $2, %r13
+    addq %r12, %r13
+    movabsq $-29998, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movq %r13, %r12
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    movabsq $1, %r13
+    addq %r12, %r13
+    movabsq $-29999, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb $2, (%rbx,%r13,1)
+    movabsq $1, %r13
+    addq %r12, %r13
+    movabsq $-29999, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movq %r13, %r12
+    movb (%rbx,%r12,1), %dil
+    call bf_write
+    call bf_flush
+    movl $60, %eax
+    xorl %edi, %edi
+    syscall
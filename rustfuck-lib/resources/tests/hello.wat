@@ -0,0 +1,327 @@
+(module
+  (import "env" "write_byte" (func $write_byte (param i32)))
+  (memory (export "memory") 1)
+
+  (func $run (export "run")
+    (local $ptr i32)
+    i32.const 104
+    local.set $ptr
+    local.get $ptr
+    local.get $ptr
+    i32.load8_u
+    i32.const 8
+    i32.add
+    i32.store8
+    block
+      loop
+        local.get $ptr
+        i32.load8_u
+        i32.eqz
+        br_if 1
+        local.get $ptr
+        i32.const 1
+        call $bf_wrap
+        local.get $ptr
+        i32.const 1
+        call $bf_wrap
+        i32.load8_u
+        i32.const 4
+        i32.add
+        i32.store8
+        local.get $ptr
+        i32.const 1
+        call $bf_wrap
+        i32.load8_u
+        if
+          local.get $ptr
+          i32.const 2
+          call $bf_wrap
+          local.get $ptr
+          i32.const 2
+          call $bf_wrap
+          i32.load8_u
+          local.get $ptr
+          i32.const 1
+          call $bf_wrap
+          i32.load8_u
+          i32.const 2
+          i32.mul
+          i32.add
+          i32.store8
+          local.get $ptr
+          i32.const 3
+          call $bf_wrap
+          local.get $ptr
+          i32.const 3
+          call $bf_wrap
+          i32.load8_u
+          local.get $ptr
+          i32.const 1
+          call $bf_wrap
+          i32.load8_u
+          i32.const 3
+          i32.mul
+          i32.add
+          i32.store8
+          local.get $ptr
+          i32.const 4
+          call $bf_wrap
+          local.get $ptr
+          i32.const 4
+          call $bf_wrap
+          i32.load8_u
+          local.get $ptr
+          i32.const 1
+          call $bf_wrap
+          i32.load8_u
+          i32.const 3
+          i32.mul
+          i32.add
+          i32.store8
+          local.get $ptr
+          i32.const 5
+          call $bf_wrap
+          local.get $ptr
+          i32.const 5
+          call $bf_wrap
+          i32.load8_u
+          local.get $ptr
+          i32.const 1
+          call $bf_wrap
+          i32.load8_u
+          i32.add
+          i32.store8
+        end
+        local.get $ptr
+        i32.const 1
+        call $bf_wrap
+        i32.const 0
+        i32.store8
+        local.get $ptr
+        i32.const 2
+        call $bf_wrap
+        local.get $ptr
+        i32.const 2
+        call $bf_wrap
+        i32.load8_u
+        i32.const 1
+        i32.add
+        i32.store8
+        local.get $ptr
+        i32.const 3
+        call $bf_wrap
+        local.get $ptr
+        i32.const 3
+        call $bf_wrap
+        i32.load8_u
+        i32.const 1
+        i32.add
+        i32.store8
+        local.get $ptr
+        i32.const 4
+        call $bf_wrap
+        local.get $ptr
+        i32.const 4
+        call $bf_wrap
+        i32.load8_u
+        i32.const 255
+        i32.add
+        i32.store8
+        local.get $ptr
+        i32.const 6
+        call $bf_wrap
+        local.get $ptr
+        i32.const 6
+        call $bf_wrap
+        i32.load8_u
+        i32.const 1
+        i32.add
+        i32.store8
+        local.get $ptr
+        i32.const 6
+        call $bf_wrap
+        local.set $ptr
+        block
+          loop
+            local.get $ptr
+            i32.load8_u
+            i32.eqz
+            br_if 1
+            local.get $ptr
+            i32.const 29999
+            call $bf_wrap
+            local.set $ptr
+            br 0
+          end
+        end
+        local.get $ptr
+        i32.const 29999
+        call $bf_wrap
+        local.get $ptr
+        i32.const 29999
+        call $bf_wrap
+        i32.load8_u
+        i32.const 255
+        i32.add
+        i32.store8
+        local.get $ptr
+        i32.const 29999
+        call $bf_wrap
+        local.set $ptr
+        br 0
+      end
+    end
+    local.get $ptr
+    i32.const 2
+    call $bf_wrap
+    local.set $ptr
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+    local.get $ptr
+    i32.const 1
+    call $bf_wrap
+    local.get $ptr
+    i32.const 1
+    call $bf_wrap
+    i32.load8_u
+    i32.const 253
+    i32.add
+    i32.store8
+    local.get $ptr
+    i32.const 1
+    call $bf_wrap
+    local.set $ptr
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+    local.get $ptr
+    local.get $ptr
+    i32.load8_u
+    i32.const 7
+    i32.add
+    i32.store8
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+    local.get $ptr
+    local.get $ptr
+    i32.load8_u
+    i32.const 3
+    i32.add
+    i32.store8
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+    local.get $ptr
+    i32.const 2
+    call $bf_wrap
+    local.set $ptr
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+    local.get $ptr
+    i32.const 29999
+    call $bf_wrap
+    local.get $ptr
+    i32.const 29999
+    call $bf_wrap
+    i32.load8_u
+    i32.const 255
+    i32.add
+    i32.store8
+    local.get $ptr
+    i32.const 29999
+    call $bf_wrap
+    local.set $ptr
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+    local.get $ptr
+    i32.const 29999
+    call $bf_wrap
+    local.set $ptr
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+    local.get $ptr
+    local.get $ptr
+    i32.load8_u
+    i32.const 3
+    i32.add
+    i32.store8
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+    local.get $ptr
+    local.get $ptr
+    i32.load8_u
+    i32.const 250
+    i32.add
+    i32.store8
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+    local.get $ptr
+    local.get $ptr
+    i32.load8_u
+    i32.const 248
+    i32.add
+    i32.store8
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+    local.get $ptr
+    i32.const 2
+    call $bf_wrap
+    local.get $ptr
+    i32.const 2
+    call $bf_wrap
+    i32.load8_u
+    i32.const 1
+    i32.add
+    i32.store8
+    local.get $ptr
+    i32.const 2
+    call $bf_wrap
+    local.set $ptr
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+    local.get $ptr
+    i32.const 1
+    call $bf_wrap
+    local.get $ptr
+    i32.const 1
+    call $bf_wrap
+    i32.load8_u
+    i32.const 2
+    i32.add
+    i32.store8
+    local.get $ptr
+    i32.const 1
+    call $bf_wrap
+    local.set $ptr
+    local.get $ptr
+    i32.load8_u
+    call $write_byte
+  )
+
+  (func $bf_wrap (param $ptr i32) (param $distance i32) (result i32)
+    (local $position i32)
+    local.get $ptr
+    local.get $distance
+    i32.add
+    local.tee $position
+    local.get $position
+    i32.const 30000
+    i32.sub
+    local.get $position
+    i32.const 30104
+    i32.lt_s
+    select
+  )
+)
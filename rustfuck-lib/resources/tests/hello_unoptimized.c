@@ -0,0 +1,86 @@
+#define _GNU_SOURCE
+
+#include "stddef.h"
+#include "stdint.h"
+#include "stdio.h"
+#include "stdlib.h"
+#include "string.h"
+
+typedef uint8_t cell;
+
+static cell tape[30000];
+
+static inline cell *bf_wrap(cell *ptr, ptrdiff_t offset) {
+    size_t length = sizeof(tape) / sizeof(cell);
+    size_t distance = (size_t) (offset < 0 ? -offset : offset) % length;
+    size_t position = (size_t) (ptr - tape) + (offset < 0 ? length - distance : distance);
+    return tape + position % length;
+}
+
+static inline cell bf_read(cell value) {
+    int character = getchar();
+    return character == EOF ? value : (cell) character;
+}
+
+int main() {
+    cell *ptr = tape;
+    *ptr += 8;
+    while (*ptr) {
+        ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
+        *ptr += 4;
+        while (*ptr) {
+            ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
+            *ptr += 2;
+            ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
+            *ptr += 3;
+            ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
+            *ptr += 3;
+            ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
+            (*ptr)++;
+            ptr = ptr - tape >= 4 ? ptr - 4 : ptr + 29996;
+            (*ptr)--;
+        }
+        ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
+        (*ptr)++;
+        ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
+        (*ptr)++;
+        ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
+        (*ptr)--;
+        ptr = ptr - tape < 29998 ? ptr + 2 : ptr - 29998;
+        (*ptr)++;
+        while (*ptr) {
+            ptr = ptr - tape >= 1 ? ptr - 1 : ptr + 29999;
+        }
+        ptr = ptr - tape >= 1 ? ptr - 1 : ptr + 29999;
+        (*ptr)--;
+    }
+    ptr = ptr - tape < 29998 ? ptr + 2 : ptr - 29998;
+    putchar(*ptr);
+    ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
+    *ptr -= 3;
+    putchar(*ptr);
+    *ptr += 7;
+    putchar(*ptr);
+    putchar(*ptr);
+    *ptr += 3;
+    putchar(*ptr);
+    ptr = ptr - tape < 29998 ? ptr + 2 : ptr - 29998;
+    putchar(*ptr);
+    ptr = ptr - tape >= 1 ? ptr - 1 : ptr + 29999;
+    (*ptr)--;
+    putchar(*ptr);
+    ptr = ptr - tape >= 1 ? ptr - 1 : ptr + 29999;
+    putchar(*ptr);
+    *ptr += 3;
+    putchar(*ptr);
+    *ptr -= 6;
+    putchar(*ptr);
+    *ptr -= 8;
+    putchar(*ptr);
+    ptr = ptr - tape < 29998 ? ptr + 2 : ptr - 29998;
+    (*ptr)++;
+    putchar(*ptr);
+    ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
+    *ptr += 2;
+    putchar(*ptr);
+}
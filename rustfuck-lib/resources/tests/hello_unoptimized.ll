@@ -0,0 +1,290 @@
+declare i32 @getchar()
+declare i32 @putchar(i32)
+declare void @exit(i32) noreturn
+
+@tape = internal global [30000 x i8] zeroinitializer
+
+define internal i64 @bf_wrap(i64 %ptr, i64 %offset) {
+entry:
+  %negative = icmp slt i64 %offset, 0
+  %raised = add i64 %offset, 30000
+  %distance = select i1 %negative, i64 %raised, i64 %offset
+  %position = add i64 %ptr, %distance
+  %inside = icmp ult i64 %position, 30000
+  %reduced = sub i64 %position, 30000
+  %result = select i1 %inside, i64 %position, i64 %reduced
+  ret i64 %result
+}
+
+define i32 @main() {
+entry:
+  %ptr = alloca i64
+  store i64 0, ptr %ptr
+  %t1 = load i64, ptr %ptr
+  %t2 = getelementptr inbounds i8, ptr @tape, i64 %t1
+  %t3 = load i8, ptr %t2
+  %t4 = add i8 %t3, 8
+  store i8 %t4, ptr %t2
+  br label %loop1
+
+loop1:
+  %t5 = load i64, ptr %ptr
+  %t6 = getelementptr inbounds i8, ptr @tape, i64 %t5
+  %t7 = load i8, ptr %t6
+  %t8 = icmp ne i8 %t7, 0
+  br i1 %t8, label %loop1.body, label %loop1.end
+
+loop1.body:
+  %t9 = load i64, ptr %ptr
+  %t10 = call i64 @bf_wrap(i64 %t9, i64 1)
+  store i64 %t10, ptr %ptr
+  %t11 = load i64, ptr %ptr
+  %t12 = getelementptr inbounds i8, ptr @tape, i64 %t11
+  %t13 = load i8, ptr %t12
+  %t14 = add i8 %t13, 4
+  store i8 %t14, ptr %t12
+  br label %loop2
+
+loop2:
+  %t15 = load i64, ptr %ptr
+  %t16 = getelementptr inbounds i8, ptr @tape, i64 %t15
+  %t17 = load i8, ptr %t16
+  %t18 = icmp ne i8 %t17, 0
+  br i1 %t18, label %loop2.body, label %loop2.end
+
+loop2.body:
+  %t19 = load i64, ptr %ptr
+  %t20 = call i64 @bf_wrap(i64 %t19, i64 1)
+  store i64 %t20, ptr %ptr
+  %t21 = load i64, ptr %ptr
+  %t22 = getelementptr inbounds i8, ptr @tape, i64 %t21
+  %t23 = load i8, ptr %t22
+  %t24 = add i8 %t23, 2
+  store i8 %t24, ptr %t22
+  %t25 = load i64, ptr %ptr
+  %t26 = call i64 @bf_wrap(i64 %t25, i64 1)
+  store i64 %t26, ptr %ptr
+  %t27 = load i64, ptr %ptr
+  %t28 = getelementptr inbounds i8, ptr @tape, i64 %t27
+  %t29 = load i8, ptr %t28
+  %t30 = add i8 %t29, 3
+  store i8 %t30, ptr %t28
+  %t31 = load i64, ptr %ptr
+  %t32 = call i64 @bf_wrap(i64 %t31, i64 1)
+  store i64 %t32, ptr %ptr
+  %t33 = load i64, ptr %ptr
+  %t34 = getelementptr inbounds i8, ptr @tape, i64 %t33
+  %t35 = load i8, ptr %t34
+  %t36 = add i8 %t35, 3
+  store i8 %t36, ptr %t34
+  %t37 = load i64, ptr %ptr
+  %t38 = call i64 @bf_wrap(i64 %t37, i64 1)
+  store i64 %t38, ptr %ptr
+  %t39 = load i64, ptr %ptr
+  %t40 = getelementptr inbounds i8, ptr @tape, i64 %t39
+  %t41 = load i8, ptr %t40
+  %t42 = add i8 %t41, 1
+  store i8 %t42, ptr %t40
+  %t43 = load i64, ptr %ptr
+  %t44 = call i64 @bf_wrap(i64 %t43, i64 -4)
+  store i64 %t44, ptr %ptr
+  %t45 = load i64, ptr %ptr
+  %t46 = getelementptr inbounds i8, ptr @tape, i64 %t45
+  %t47 = load i8, ptr %t46
+  %t48 = add i8 %t47, 255
+  store i8 %t48, ptr %t46
+  br label %loop2
+
+loop2.end:
+  %t49 = load i64, ptr %ptr
+  %t50 = call i64 @bf_wrap(i64 %t49, i64 1)
+  store i64 %t50, ptr %ptr
+  %t51 = load i64, ptr %ptr
+  %t52 = getelementptr inbounds i8, ptr @tape, i64 %t51
+  %t53 = load i8, ptr %t52
+  %t54 = add i8 %t53, 1
+  store i8 %t54, ptr %t52
+  %t55 = load i64, ptr %ptr
+  %t56 = call i64 @bf_wrap(i64 %t55, i64 1)
+  store i64 %t56, ptr %ptr
+  %t57 = load i64, ptr %ptr
+  %t58 = getelementptr inbounds i8, ptr @tape, i64 %t57
+  %t59 = load i8, ptr %t58
+  %t60 = add i8 %t59, 1
+  store i8 %t60, ptr %t58
+  %t61 = load i64, ptr %ptr
+  %t62 = call i64 @bf_wrap(i64 %t61, i64 1)
+  store i64 %t62, ptr %ptr
+  %t63 = load i64, ptr %ptr
+  %t64 = getelementptr inbounds i8, ptr @tape, i64 %t63
+  %t65 = load i8, ptr %t64
+  %t66 = add i8 %t65, 255
+  store i8 %t66, ptr %t64
+  %t67 = load i64, ptr %ptr
+  %t68 = call i64 @bf_wrap(i64 %t67, i64 2)
+  store i64 %t68, ptr %ptr
+  %t69 = load i64, ptr %ptr
+  %t70 = getelementptr inbounds i8, ptr @tape, i64 %t69
+  %t71 = load i8, ptr %t70
+  %t72 = add i8 %t71, 1
+  store i8 %t72, ptr %t70
+  br label %loop3
+
+loop3:
+  %t73 = load i64, ptr %ptr
+  %t74 = getelementptr inbounds i8, ptr @tape, i64 %t73
+  %t75 = load i8, ptr %t74
+  %t76 = icmp ne i8 %t75, 0
+  br i1 %t76, label %loop3.body, label %loop3.end
+
+loop3.body:
+  %t77 = load i64, ptr %ptr
+  %t78 = call i64 @bf_wrap(i64 %t77, i64 -1)
+  store i64 %t78, ptr %ptr
+  br label %loop3
+
+loop3.end:
+  %t79 = load i64, ptr %ptr
+  %t80 = call i64 @bf_wrap(i64 %t79, i64 -1)
+  store i64 %t80, ptr %ptr
+  %t81 = load i64, ptr %ptr
+  %t82 = getelementptr inbounds i8, ptr @tape, i64 %t81
+  %t83 = load i8, ptr %t82
+  %t84 = add i8 %t83, 255
+  store i8 %t84, ptr %t82
+  br label %loop1
+
+loop1.end:
+  %t85 = load i64, ptr %ptr
+  %t86 = call i64 @bf_wrap(i64 %t85, i64 2)
+  store i64 %t86, ptr %ptr
+  %t87 = load i64, ptr %ptr
+  %t88 = getelementptr inbounds i8, ptr @tape, i64 %t87
+  %t89 = load i8, ptr %t88
+  %t90 = zext i8 %t89 to i32
+  %t91 = call i32 @putchar(i32 %t90)
+  %t92 = load i64, ptr %ptr
+  %t93 = call i64 @bf_wrap(i64 %t92, i64 1)
+  store i64 %t93, ptr %ptr
+  %t94 = load i64, ptr %ptr
+  %t95 = getelementptr inbounds i8, ptr @tape, i64 %t94
+  %t96 = load i8, ptr %t95
+  %t97 = add i8 %t96, 253
+  store i8 %t97, ptr %t95
+  %t98 = load i64, ptr %ptr
+  %t99 = getelementptr inbounds i8, ptr @tape, i64 %t98
+  %t100 = load i8, ptr %t99
+  %t101 = zext i8 %t100 to i32
+  %t102 = call i32 @putchar(i32 %t101)
+  %t103 = load i64, ptr %ptr
+  %t104 = getelementptr inbounds i8, ptr @tape, i64 %t103
+  %t105 = load i8, ptr %t104
+  %t106 = add i8 %t105, 7
+  store i8 %t106, ptr %t104
+  %t107 = load i64, ptr %ptr
+  %t108 = getelementptr inbounds i8, ptr @tape, i64 %t107
+  %t109 = load i8, ptr %t108
+  %t110 = zext i8 %t109 to i32
+  %t111 = call i32 @putchar(i32 %t110)
+  %t112 = load i64, ptr %ptr
+  %t113 = getelementptr inbounds i8, ptr @tape, i64 %t112
+  %t114 = load i8, ptr %t113
+  %t115 = zext i8 %t114 to i32
+  %t116 = call i32 @putchar(i32 %t115)
+  %t117 = load i64, ptr %ptr
+  %t118 = getelementptr inbounds i8, ptr @tape, i64 %t117
+  %t119 = load i8, ptr %t118
+  %t120 = add i8 %t119, 3
+  store i8 %t120, ptr %t118
+  %t121 = load i64, ptr %ptr
+  %t122 = getelementptr inbounds i8, ptr @tape, i64 %t121
+  %t123 = load i8, ptr %t122
+  %t124 = zext i8 %t123 to i32
+  %t125 = call i32 @putchar(i32 %t124)
+  %t126 = load i64, ptr %ptr
+  %t127 = call i64 @bf_wrap(i64 %t126, i64 2)
+  store i64 %t127, ptr %ptr
+  %t128 = load i64, ptr %ptr
+  %t129 = getelementptr inbounds i8, ptr @tape, i64 %t128
+  %t130 = load i8, ptr %t129
+  %t131 = zext i8 %t130 to i32
+  %t132 = call i32 @putchar(i32 %t131)
+  %t133 = load i64, ptr %ptr
+  %t134 = call i64 @bf_wrap(i64 %t133, i64 -1)
+  store i64 %t134, ptr %ptr
+  %t135 = load i64, ptr %ptr
+  %t136 = getelementptr inbounds i8, ptr @tape, i64 %t135
+  %t137 = load i8, ptr %t136
+  %t138 = add i8 %t137, 255
+  store i8 %t138, ptr %t136
+  %t139 = load i64, ptr %ptr
+  %t140 = getelementptr inbounds i8, ptr @tape, i64 %t139
+  %t141 = load i8, ptr %t140
+  %t142 = zext i8 %t141 to i32
+  %t143 = call i32 @putchar(i32 %t142)
+  %t144 = load i64, ptr %ptr
+  %t145 = call i64 @bf_wrap(i64 %t144, i64 -1)
+  store i64 %t145, ptr %ptr
+  %t146 = load i64, ptr %ptr
+  %t147 = getelementptr inbounds i8, ptr @tape, i64 %t146
+  %t148 = load i8, ptr %t147
+  %t149 = zext i8 %t148 to i32
+  %t150 = call i32 @putchar(i32 %t149)
+  %t151 = load i64, ptr %ptr
+  %t152 = getelementptr inbounds i8, ptr @tape, i64 %t151
+  %t153 = load i8, ptr %t152
+  %t154 = add i8 %t153, 3
+  store i8 %t154, ptr %t152
+  %t155 = load i64, ptr %ptr
+  %t156 = getelementptr inbounds i8, ptr @tape, i64 %t155
+  %t157 = load i8, ptr %t156
+  %t158 = zext i8 %t157 to i32
+  %t159 = call i32 @putchar(i32 %t158)
+  %t160 = load i64, ptr %ptr
+  %t161 = getelementptr inbounds i8, ptr @tape, i64 %t160
+  %t162 = load i8, ptr %t161
+  %t163 = add i8 %t162, 250
+  store i8 %t163, ptr %t161
+  %t164 = load i64, ptr %ptr
+  %t165 = getelementptr inbounds i8, ptr @tape, i64 %t164
+  %t166 = load i8, ptr %t165
+  %t167 = zext i8 %t166 to i32
+  %t168 = call i32 @putchar(i32 %t167)
+  %t169 = load i64, ptr %ptr
+  %t170 = getelementptr inbounds i8, ptr @tape, i64 %t169
+  %t171 = load i8, ptr %t170
+  %t172 = add i8 %t171, 248
+  store i8 %t172, ptr %t170
+  %t173 = load i64, ptr %ptr
+  %t174 = getelementptr inbounds i8, ptr @tape, i64 %t173
+  %t175 = load i8, ptr %t174
+  %t176 = zext i8 %t175 to i32
+  %t177 = call i32 @putchar(i32 %t176)
+  %t178 = load i64, ptr %ptr
+  %t179 = call i64 @bf_wrap(i64 %t178, i64 2)
+  store i64 %t179, ptr %ptr
+  %t180 = load i64, ptr %ptr
+  %t181 = getelementptr inbounds i8, ptr @tape, i64 %t180
+  %t182 = load i8, ptr %t181
+  %t183 = add i8 %t182, 1
+  store i8 %t183, ptr %t181
+  %t184 = load i64, ptr %ptr
+  %t185 = getelementptr inbounds i8, ptr @tape, i64 %t184
+  %t186 = load i8, ptr %t185
+  %t187 = zext i8 %t186 to i32
+  %t188 = call i32 @putchar(i32 %t187)
+  %t189 = load i64, ptr %ptr
+  %t190 = call i64 @bf_wrap(i64 %t189, i64 1)
+  store i64 %t190, ptr %ptr
+  %t191 = load i64, ptr %ptr
+  %t192 = getelementptr inbounds i8, ptr @tape, i64 %t191
+  %t193 = load i8, ptr %t192
+  %t194 = add i8 %t193, 2
+  store i8 %t194, ptr %t192
+  %t195 = load i64, ptr %ptr
+  %t196 = getelementptr inbounds i8, ptr @tape, i64 %t195
+  %t197 = load i8, ptr %t196
+  %t198 = zext i8 %t197 to i32
+  %t199 = call i32 @putchar(i32 %t198)
+  ret i32 0
+}
@@ -0,0 +1,1391 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementations of a generator for creating `x86-64` assembly for Linux in `GAS` syntax from intermediate compile
+//! results.
+//!
+//! The generated code does not depend on a `C` library, but uses system calls directly. Throughout the program, the
+//! address of the tape is kept in `%rbx` and the index of the current cell in `%r12`. On an unchecked tape with a fixed
+//! size, the indices of other cells are computed in `%r13` and `%r14`, wrapping around at the tape's ends. The same
+//! registers hold the indices of cells before the start of an unchecked tape growing to the right.
+
+use std::convert::TryFrom;
+
+use CellType;
+use EofBehavior;
+use Options;
+use Overflow;
+use TapeGrowth;
+use ir::Instruction;
+use ir::Operation;
+use ir::Program;
+use ir::Step;
+use ir::accessed_range;
+use ir::reserved_range;
+use ir::walk;
+use lexer::MetaData;
+
+/// The buffer collecting the output and the functions `bf_flush`, writing the buffer to the standard output, and
+/// `bf_write`, appending the byte in `%dil` to the buffer.
+static OUTPUT_RUNTIME: &str = "    .lcomm bf_output, 4096
+    .lcomm bf_output_length, 8
+
+    .text
+bf_flush:
+    leaq bf_output(%rip), %rsi
+    movq bf_output_length(%rip), %rdx
+    movq $0, bf_output_length(%rip)
+1:
+    testq %rdx, %rdx
+    jz 2f
+    movl $1, %eax
+    movl $1, %edi
+    syscall
+    testq %rax, %rax
+    jle 2f
+    addq %rax, %rsi
+    subq %rax, %rdx
+    jmp 1b
+2:
+    ret
+
+bf_write:
+    movq bf_output_length(%rip), %rax
+    leaq bf_output(%rip), %rcx
+    movb %dil, (%rcx,%rax)
+    incq %rax
+    movq %rax, bf_output_length(%rip)
+    cmpq $4096, %rax
+    je bf_flush
+    ret
+
+";
+
+/// The function `bf_read`, returning the next character from the standard input in `%rax`. `{eof}` must be replaced by
+/// the value returned at the end of the input.
+static READING_RUNTIME: &str = "    .lcomm bf_input, 1
+
+    .text
+bf_read:
+    call bf_flush
+    xorl %eax, %eax
+    xorl %edi, %edi
+    leaq bf_input(%rip), %rsi
+    movl $1, %edx
+    syscall
+    cmpq $1, %rax
+    jne 1f
+    movzbl bf_input(%rip), %eax
+    ret
+1:
+    movq ${eof}, %rax
+    ret
+
+";
+
+/// The declaration of a tape with a fixed size. `{tape_bytes}` must be replaced by the tape's size in bytes.
+static FIXED_TAPE: &str = "    .local bf_tape
+    .comm bf_tape, {tape_bytes}, 8
+
+";
+
+/// The function `bf_reserve`, moving the pointer by `%rsi` cells on a tape with a fixed size and aborting the program
+/// with the source position `%edi` if the cells from `%rdx` to `%rcx` cells away from the new current cell are not on
+/// the tape. `{tape_size}` must be replaced by the number of cells.
+static CHECKED_FIXED_TAPE: &str = "    .text
+bf_reserve:
+    addq %rsi, %r12
+    leaq (%r12,%rdx), %rax
+    testq %rax, %rax
+    js 1f
+    leaq (%r12,%rcx), %rax
+    movabsq ${tape_size}, %r8
+    cmpq %r8, %rax
+    jge 2f
+    ret
+1:
+    leaq bf_pointer_underflow(%rip), %rsi
+    jmp bf_fail
+2:
+    leaq bf_pointer_overflow(%rip), %rsi
+    jmp bf_fail
+
+";
+
+/// The size of a tape growing when the pointer leaves it, together with the functions `bf_map`, mapping `%rsi` bytes
+/// of zeroed memory, and `bf_allocate`, mapping the initial tape. `{tape_size}` must be replaced by the initial number
+/// of cells, `{cell_size}` by their size in bytes.
+static GROWING_TAPE: &str = "    .data
+bf_tape_size:
+    .quad {tape_size}
+
+    .section .rodata
+bf_out_of_memory:
+    .ascii \"out of memory\\n\"
+
+    .text
+bf_map:
+    movl $9, %eax
+    xorl %edi, %edi
+    movl $3, %edx
+    movl $0x22, %r10d
+    movq $-1, %r8
+    xorl %r9d, %r9d
+    syscall
+    cmpq $-4096, %rax
+    ja 1f
+    ret
+1:
+    movl $1, %eax
+    movl $2, %edi
+    leaq bf_out_of_memory(%rip), %rsi
+    movl $14, %edx
+    syscall
+    movl $60, %eax
+    movl $1, %edi
+    syscall
+
+bf_allocate:
+    movq bf_tape_size(%rip), %rsi
+    imulq ${cell_size}, %rsi, %rsi
+    call bf_map
+    movq %rax, %rbx
+    ret
+
+";
+
+/// The function `bf_reserve`, moving the pointer by `%rsi` cells on a growing tape and growing the tape until the cells
+/// from `%rdx` to `%rcx` cells away from the new current cell are on it. `{cell_size}` must be replaced by the size of
+/// the cells in bytes, and `{grow_left}` by the code computing the number of cells to add before the tape's start in
+/// `%r13`. `{check_left}` must be replaced by the code reporting accesses before the tape's start at the source
+/// position `%edi`, if these are checked, or by the code wrapping the pointer around at the tape's start.
+static GROWING_RESERVE: &str = "    .text
+bf_reserve:
+    addq %rsi, %r12
+    leaq (%r12,%rdx), %rax
+    leaq (%r12,%rcx), %r8
+    movq bf_tape_size(%rip), %r15
+    testq %rax, %rax
+    js 1f
+    cmpq %r15, %r8
+    jge 1f
+    ret
+1:
+{check_left}{grow_left}    xorl %r14d, %r14d
+    cmpq %r15, %r8
+    jl 2f
+    leaq 1(%r8), %r14
+    subq %r15, %r14
+    cmpq %r15, %r14
+    cmovlq %r15, %r14
+2:
+    leaq (%r15,%r13), %rsi
+    addq %r14, %rsi
+    movq %rsi, bf_tape_size(%rip)
+    imulq ${cell_size}, %rsi, %rsi
+    call bf_map
+    movq %rbx, %r14
+    movq %rax, %rbx
+    leaq (%rax,%r13,{cell_size}), %rdi
+    movq %r14, %rsi
+    imulq ${cell_size}, %r15, %rcx
+    movq %rcx, %r15
+    rep movsb
+    movl $11, %eax
+    movq %r14, %rdi
+    movq %r15, %rsi
+    syscall
+    addq %r13, %r12
+    ret
+
+";
+
+/// The code computing the number of cells to add before the start of a tape growing to the left.
+static GROWING_LEFT: &str = "    xorl %r13d, %r13d
+    testq %rax, %rax
+    jns 3f
+    movq %rax, %r13
+    negq %r13
+    cmpq %r15, %r13
+    cmovlq %r15, %r13
+3:
+";
+
+/// The code not adding any cells before the start of a tape growing only to the right.
+static FIXED_LEFT: &str = "    xorl %r13d, %r13d
+";
+
+/// The code wrapping a new position before the start of an unchecked tape growing only to the right around to its
+/// current end, returning if the accessed cells are on the tape afterwards.
+static WRAPPING_LEFT: &str = "    testq %r12, %r12
+    jns 3f
+    movq %r12, %rax
+    cqto
+    idivq %r15
+    leaq (%rdx,%r15), %r12
+    testq %rdx, %rdx
+    cmovnsq %rdx, %r12
+    leaq (%r12,%rcx), %r8
+    cmpq %r15, %r8
+    jge 3f
+    ret
+3:
+";
+
+/// The function `bf_wrap_left`, computing the index of the cell `%r13` cells before the current cell in `%r13` on an
+/// unchecked tape growing only to the right, wrapping around at the tape's start. `%r14` is overwritten.
+static WRAPPING_LEFT_RUNTIME: &str = "    .text
+bf_wrap_left:
+    movq %r12, %r14
+    subq %r13, %r14
+    js 1f
+    movq %r14, %r13
+    ret
+1:
+    pushq %rax
+    pushq %rdx
+    movq %r14, %rax
+    cqto
+    idivq bf_tape_size(%rip)
+    movq bf_tape_size(%rip), %r13
+    addq %rdx, %r13
+    testq %rdx, %rdx
+    cmovnsq %rdx, %r13
+    popq %rdx
+    popq %rax
+    ret
+
+";
+
+/// The code reporting accesses before the start of a tape growing only to the right.
+static CHECKING_LEFT: &str = "    testq %rax, %rax
+    jns 3f
+    leaq bf_pointer_underflow(%rip), %rsi
+    jmp bf_fail
+3:
+";
+
+/// The table of source positions and the function `bf_fail`, aborting the program with the message `%rsi` for the
+/// source position `%edi`. The placeholder `{positions}` must be replaced by the source positions.
+static FAILING_RUNTIME: &str = "    .section .rodata
+bf_positions:
+{positions}bf_cell_overflow:
+    .string \"cell overflow\"
+bf_pointer_underflow:
+    .string \"pointer underflow\"
+bf_pointer_overflow:
+    .string \"pointer overflow\"
+bf_at_line:
+    .string \" at line \"
+bf_at_position:
+    .string \", position \"
+
+    .lcomm bf_error, 128
+
+    .text
+bf_fail:
+    movq %rsi, %rbx
+    movl %edi, %r12d
+    call bf_flush
+    leaq bf_error(%rip), %rdi
+    movq %rbx, %rsi
+    call bf_append_string
+    leaq bf_at_line(%rip), %rsi
+    call bf_append_string
+    leaq bf_positions(%rip), %rax
+    movl (%rax,%r12,8), %eax
+    call bf_append_number
+    leaq bf_at_position(%rip), %rsi
+    call bf_append_string
+    leaq bf_positions(%rip), %rax
+    movl 4(%rax,%r12,8), %eax
+    call bf_append_number
+    movb $10, (%rdi)
+    incq %rdi
+    leaq bf_error(%rip), %rsi
+    movq %rdi, %rdx
+    subq %rsi, %rdx
+    movl $1, %eax
+    movl $2, %edi
+    syscall
+    movl $60, %eax
+    movl $3, %edi
+    syscall
+
+bf_append_string:
+    movb (%rsi), %al
+    testb %al, %al
+    jz 1f
+    movb %al, (%rdi)
+    incq %rsi
+    incq %rdi
+    jmp bf_append_string
+1:
+    ret
+
+bf_append_number:
+    movl $10, %ecx
+    xorl %r8d, %r8d
+1:
+    xorl %edx, %edx
+    divl %ecx
+    addl $48, %edx
+    pushq %rdx
+    incl %r8d
+    testl %eax, %eax
+    jnz 1b
+2:
+    popq %rax
+    movb %al, (%rdi)
+    incq %rdi
+    decl %r8d
+    jnz 2b
+    ret
+
+";
+
+/// The function `bf_add`, adding the 128 bit number in `%rdx:%rax` to the extended cell value in `%rcx` and returning
+/// the new cell value in `%rax`. `{min}` and `{max}` must be replaced by the bounds of the cells, `{bounds}` by the
+/// code handling sums outside them.
+static ARITHMETIC_RUNTIME: &str = "    .text
+bf_add:
+    movq %rcx, %rsi
+    sarq $63, %rsi
+    addq %rcx, %rax
+    adcq %rsi, %rdx
+    movabsq ${min}, %rsi
+    movq %rsi, %r8
+    sarq $63, %r8
+    movq %rdx, %r9
+    cmpq %rsi, %rax
+    sbbq %r8, %r9
+    jl 1f
+    movabsq ${max}, %rsi
+    movq %rsi, %r8
+    sarq $63, %r8
+    cmpq %rax, %rsi
+    sbbq %rdx, %r8
+    jl 2f
+    ret
+{bounds}
+";
+
+/// The code saturating sums outside the bounds of the cells. `{min}` and `{max}` must be replaced by the bounds.
+static SATURATING_BOUNDS: &str = "\
+1:
+    movabsq ${min}, %rax
+    ret
+2:
+    movabsq ${max}, %rax
+    ret
+";
+
+/// The code aborting the program with the source position `%edi` for sums outside the bounds of the cells.
+static CHECKED_BOUNDS: &str = "\
+1:
+2:
+    leaq bf_cell_overflow(%rip), %rsi
+    jmp bf_fail
+";
+
+/// The runtime functions called by the generated assembly.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Runtime {
+
+    /// Cells are changed by calling `bf_add`.
+    add: bool,
+
+    /// Characters are read by calling `bf_read`.
+    read: bool,
+
+    /// The pointer is moved by calling `bf_reserve`.
+    reserve: bool,
+
+    /// The cells before the current cell are accessed by calling `bf_wrap_left`.
+    wrap_left: bool,
+}
+
+/// A generator for creating `x86-64` assembly for Linux in `GAS` syntax from intermediate compile results.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AsmGenerator {
+
+    /// The options controlling the generated code.
+    options: Options,
+
+    /// The number of loops, scans, and conditions for which labels have been created so far.
+    labels: usize,
+
+    /// The source positions reported by the generated code, indexed by the IDs passed to the runtime functions.
+    positions: Vec<MetaData>,
+
+    /// The runtime functions needed by the generated code.
+    runtime: Runtime,
+}
+
+impl AsmGenerator {
+    /// Initialize a new generator with the default options.
+    pub fn new() -> AsmGenerator {
+        AsmGenerator::with_options(Options::default())
+    }
+
+    /// Initialize a new generator with the given `options`.
+    pub fn with_options(options: Options) -> AsmGenerator {
+        AsmGenerator {
+            options,
+            labels: 0,
+            positions: Vec::new(),
+            runtime: Runtime::default(),
+        }
+    }
+
+    /// Generate the assembly from the given `program`, using the specified `template`.
+    ///
+    /// The placeholder `{runtime}` in the `template` is replaced by the declaration of the tape and the functions the
+    /// generated code needs, and `{setup}` by the code loading the tape's address into `%rbx`. The `template` must end
+    /// within the code of the program, after setting the index of the current cell in `%r12` to zero.
+    pub fn generate(&mut self, template: &str, program: &Program) -> String {
+        // The runtime functions depend on the code calling them.
+        self.labels = 0;
+        self.positions.clear();
+        self.runtime = Runtime::default();
+        let mut code = String::new();
+        self.generate_block(&program.instructions, &mut code);
+
+        let setup: &str = match self.options.tape_growth {
+            TapeGrowth::Fixed => "    leaq bf_tape(%rip), %rbx\n",
+            TapeGrowth::Right | TapeGrowth::Both => "    call bf_allocate\n",
+        };
+        let mut output = template.replace("{runtime}", &self.runtime())
+            .replace("{setup}", setup);
+        output.push_str(&code);
+
+        // Flush the output and exit successfully.
+        emit(&mut output, "call bf_flush");
+        emit(&mut output, "movl $60, %eax");
+        emit(&mut output, "xorl %edi, %edi");
+        emit(&mut output, "syscall");
+
+        output
+    }
+
+    /// Generate the assembly for the given list of `instructions` and the bodies of their loops, appending it to the
+    /// `output`.
+    fn generate_block(&mut self, instructions: &[Instruction], output: &mut String) {
+        // The current cell is on the tape when entering a block, but the other accessed cells might not be.
+        self.reserve_segment(instructions, output);
+
+        // The offset of the cell on which the currently generated multiplications depend together with the label
+        // after their condition, and if the current cell is still known not to be zero.
+        let mut guard: Option<(isize, usize)> = None;
+        let mut nonzero: bool = false;
+
+        // The labels of the loops currently generated, innermost last.
+        let mut loops: Vec<String> = Vec::new();
+        for step in walk(instructions) {
+            let (instruction, following): (&Instruction, &[Instruction]) = match step {
+                Step::Instruction(instruction, following) => (instruction, following),
+                Step::LoopEnd(_, following) => {
+                    // Close the loop, which only ends on a cell on the tape, but the cells accessed next might not be.
+                    close_guard(&mut guard, output);
+                    let label: String = loops.pop().expect("The loop has been started.");
+                    emit(output, &format!("jmp {label}", label = label));
+                    output.push_str(&format!("{label}_end:\n", label = label));
+                    nonzero = false;
+                    self.reserve_segment(following, output);
+                    continue;
+                },
+            };
+
+            let source: Option<isize> = match instruction.operation {
+                Operation::MulAdd { .. } => Some(instruction.offset),
+                _ => None,
+            };
+            nonzero = nonzero && source == Some(0);
+            if guard.is_some() && guard.map(|(offset, _)| offset) != source {
+                close_guard(&mut guard, output);
+            }
+
+            // Checked accesses are reported at the accessing instruction. Multiplications depending on the same cell
+            // only need a single check.
+            let offset: isize = instruction.offset;
+            if self.options.is_checked() && guard.is_none() && offset != 0 {
+                let position: Option<usize> = self.position(instruction, true);
+                self.reserve(0, offset.min(0), offset.max(0), position, output);
+            }
+
+            let cell_type: CellType = self.options.cell_type;
+            let cell: String = match instruction.operation {
+                // Loops and scans access their cell anew in each iteration.
+                Operation::Loop(_) | Operation::Scan { .. } => String::new(),
+                _ => self.cell(instruction.offset, output),
+            };
+            match instruction.operation {
+                Operation::Add(delta) if self.options.overflow == Overflow::Wrap => {
+                    // Change the value at the cell, wrapping around at the bounds of the cell type.
+                    emit(output, &format!("add{suffix} ${delta}, {cell}",
+                                          suffix = suffix(cell_type), delta = wrapped(delta, cell_type), cell = cell));
+                },
+                Operation::Add(delta) => {
+                    // Change the value at the cell using the runtime function.
+                    emit(output, &format!("movq ${delta}, %rax", delta = delta));
+                    emit(output, "cqto");
+                    self.add(instruction, &cell, output);
+                },
+                Operation::Move(offset) if self.options.is_reserving() => {
+                    // Go to another cell, making sure that it and the cells accessed next are on the tape.
+                    let (low, high): (isize, isize) = if self.options.is_checked() {
+                        (0, 0)
+                    } else {
+                        accessed_range(following, false)
+                    };
+                    let position: Option<usize> = self.position(instruction, self.options.is_checked());
+                    self.reserve(offset, low, high, position, output);
+                },
+                Operation::Move(offset) => {
+                    // Go to another cell.
+                    self.step(offset, output);
+                },
+                Operation::Read if self.options.eof == EofBehavior::Unchanged => {
+                    // Read a single character into the cell, keeping the cell's value at the end of the input.
+                    self.runtime.read = true;
+                    let label: String = self.label("read");
+                    emit(output, "call bf_read");
+                    emit(output, "testq %rax, %rax");
+                    emit(output, &format!("js {label}_end", label = label));
+                    emit(output, &format!("mov{suffix} {register}, {cell}",
+                                          suffix = suffix(cell_type), register = accumulator(cell_type), cell = cell));
+                    output.push_str(&format!("{label}_end:\n", label = label));
+                },
+                Operation::Read => {
+                    // Read a single character into the cell.
+                    self.runtime.read = true;
+                    emit(output, "call bf_read");
+                    emit(output, &format!("mov{suffix} {register}, {cell}",
+                                          suffix = suffix(cell_type), register = accumulator(cell_type), cell = cell));
+                },
+                Operation::Write => {
+                    // Print the character of the cell, i.e. its lowest byte.
+                    emit(output, &format!("movb {cell}, %dil", cell = cell));
+                    emit(output, "call bf_write");
+                },
+                Operation::Loop(ref body) => {
+                    // Repeat the body as long as the current cell is not zero.
+                    let label: String = self.label("loop");
+                    output.push_str(&format!("{label}:\n", label = label));
+                    let cell: String = self.cell(instruction.offset, output);
+                    emit(output, &format!("cmp{suffix} $0, {cell}", suffix = suffix(cell_type), cell = cell));
+                    emit(output, &format!("je {label}_end", label = label));
+                    self.reserve_segment(body, output);
+                    loops.push(label);
+                    nonzero = true;
+                },
+                Operation::Clear => {
+                    // Set the cell to zero.
+                    emit(output, &format!("mov{suffix} $0, {cell}", suffix = suffix(cell_type), cell = cell));
+                },
+                Operation::MulAdd { offset, factor } if self.options.overflow == Overflow::Wrap => {
+                    // Add a multiple of the cell to another cell, wrapping around at the bounds of the cell type.
+                    self.multiply(instruction, offset, &cell, &mut guard, nonzero, output);
+                    let cell: String = self.reload(instruction.offset, cell, output);
+                    let (register, wide): (&str, bool) = if cell_type == CellType::I64 {
+                        ("%rax", true)
+                    } else {
+                        ("%eax", false)
+                    };
+                    emit(output, &format!("{load} {cell}, {register}",
+                                          load = extension(cell_type, wide), cell = cell, register = register));
+                    if factor != 1 && factor != -1 {
+                        emit(output, &format!("imul{suffix} ${factor}, {register}, {register}",
+                                              suffix = if wide { 'q' } else { 'l' }, factor = factor,
+                                              register = register));
+                    }
+                    let operation: &str = if factor == -1 { "sub" } else { "add" };
+                    let target: String = self.cell(instruction.offset.wrapping_add(offset), output);
+                    emit(output, &format!("{operation}{suffix} {register}, {target}",
+                                          operation = operation, suffix = suffix(cell_type),
+                                          register = accumulator(cell_type), target = target));
+                },
+                Operation::MulAdd { offset, factor } => {
+                    // Add a multiple of the cell to another cell using the runtime function.
+                    self.multiply(instruction, offset, &cell, &mut guard, nonzero, output);
+                    let cell: String = self.reload(instruction.offset, cell, output);
+                    emit(output, &format!("{load} {cell}, {register}",
+                                          load = extension(cell_type, true), cell = cell,
+                                          register = if cell_type == CellType::U32 { "%eax" } else { "%rax" }));
+                    emit(output, &format!("movq ${factor}, %rcx", factor = factor));
+                    emit(output, "imulq %rcx");
+                    let target: String = self.cell(instruction.offset.wrapping_add(offset), output);
+                    self.add(instruction, &target, output);
+                },
+                Operation::Scan { stride: 1 } if self.can_search_memory() => {
+                    // Search the tape to the right for the next zero cell, continuing at the tape's start, and looping
+                    // forever if there is no zero cell at all.
+                    let label: String = self.label("search");
+                    emit(output, "leaq (%rbx,%r12), %rdi");
+                    emit(output, &format!("movabsq ${size}, %rcx", size = self.options.tape_size.max(1)));
+                    emit(output, "subq %r12, %rcx");
+                    emit(output, "xorl %eax, %eax");
+                    emit(output, "repne scasb");
+                    emit(output, &format!("je {label}_end", label = label));
+                    emit(output, "movq %rbx, %rdi");
+                    emit(output, "movq %r12, %rcx");
+                    emit(output, "repne scasb");
+                    emit(output, &format!("je {label}_end", label = label));
+                    output.push_str(&format!("{label}:\n", label = label));
+                    emit(output, &format!("jmp {label}", label = label));
+                    output.push_str(&format!("{label}_end:\n", label = label));
+                    emit(output, "leaq -1(%rdi), %r12");
+                    emit(output, "subq %rbx, %r12");
+                },
+                Operation::Scan { stride: -1 } if self.can_search_memory() => {
+                    // Search the tape to the left for the next zero cell, continuing at the tape's end, and looping
+                    // forever if there is no zero cell at all.
+                    let label: String = self.label("search");
+                    emit(output, "leaq (%rbx,%r12), %rdi");
+                    emit(output, "leaq 1(%r12), %rcx");
+                    emit(output, "xorl %eax, %eax");
+                    emit(output, "std");
+                    emit(output, "repne scasb");
+                    emit(output, &format!("je {label}_end", label = label));
+                    emit(output, &format!("movabsq ${last}, %rcx", last = self.options.tape_size.max(1) - 1));
+                    emit(output, "leaq (%rbx,%rcx), %rdi");
+                    emit(output, "subq %r12, %rcx");
+                    emit(output, "repne scasb");
+                    emit(output, &format!("je {label}_end", label = label));
+                    output.push_str(&format!("{label}:\n", label = label));
+                    emit(output, &format!("jmp {label}", label = label));
+                    output.push_str(&format!("{label}_end:\n", label = label));
+                    emit(output, "cld");
+                    emit(output, "leaq 1(%rdi), %r12");
+                    emit(output, "subq %rbx, %r12");
+                },
+                Operation::Scan { stride } => {
+                    // Step through the cells until reaching a zero cell.
+                    let label: String = self.label("scan");
+                    output.push_str(&format!("{label}:\n", label = label));
+                    let cell: String = self.cell(instruction.offset, output);
+                    emit(output, &format!("cmp{suffix} $0, {cell}", suffix = suffix(cell_type), cell = cell));
+                    emit(output, &format!("je {label}_end", label = label));
+                    if self.options.is_reserving() {
+                        let position: Option<usize> = self.position(instruction, self.options.is_checked());
+                        self.reserve(stride, 0, 0, position, output);
+                    } else {
+                        self.step(stride, output);
+                    }
+                    emit(output, &format!("jmp {label}", label = label));
+                    output.push_str(&format!("{label}_end:\n", label = label));
+                },
+            }
+
+            // Scans only end on a cell on the tape, but the cells accessed next might not be.
+            if let Operation::Scan { .. } = instruction.operation {
+                self.reserve_segment(following, output);
+            }
+        }
+
+        close_guard(&mut guard, output);
+    }
+
+    /// Append the code adding the 128 bit number in `%rdx:%rax` to the `cell` using the runtime function, which reports
+    /// overflows at the `instruction` if the options require it, to the `output`.
+    fn add(&mut self, instruction: &Instruction, cell: &str, output: &mut String) {
+        self.runtime.add = true;
+        let cell_type: CellType = self.options.cell_type;
+        emit(output, &format!("{load} {cell}, {register}",
+                              load = extension(cell_type, true), cell = cell,
+                              register = if cell_type == CellType::U32 { "%ecx" } else { "%rcx" }));
+        if let Some(id) = self.position(instruction, self.options.overflow == Overflow::Error) {
+            emit(output, &format!("movl ${id}, %edi", id = id));
+        }
+        emit(output, "call bf_add");
+        emit(output, &format!("mov{suffix} {register}, {cell}",
+                              suffix = suffix(cell_type), register = accumulator(cell_type), cell = cell));
+    }
+
+    /// Get the memory operand of the `cell` `offset` cells away from the current cell again after growing the tape for
+    /// a multiplication, which might have moved the tape or changed the index of a cell wrapped around at its start,
+    /// appending any code computing the index to the `output`.
+    fn reload(&mut self, offset: isize, cell: String, output: &mut String) -> String {
+        if self.options.is_reserving() {
+            self.cell(offset, output)
+        } else {
+            cell
+        }
+    }
+
+    /// Prepare the multiplication of the `instruction`'s cell with another cell `offset` cells away from it, appending
+    /// the code to the `output`. Like in the replaced loop, the target cell is only accessed if the instruction's cell
+    /// is not zero, so the multiplication is placed within a condition, which is opened unless the previous
+    /// multiplication depends on the same cell and the `guard` is still open, or the instruction's cell is known to be
+    /// `nonzero`. Within the condition, a growing or checked tape is made to contain the target cell. The operand of
+    /// the instruction's `cell` is used for the condition.
+    fn multiply(&mut self, instruction: &Instruction, offset: isize, cell: &str, guard: &mut Option<(isize, usize)>,
+                nonzero: bool, output: &mut String)
+    {
+        if guard.is_none() && !nonzero {
+            let label: String = self.label("multiply");
+            emit(output, &format!("cmp{suffix} $0, {cell}", suffix = suffix(self.options.cell_type), cell = cell));
+            emit(output, &format!("je {label}_end", label = label));
+            *guard = Some((instruction.offset, self.labels));
+        }
+
+        if self.options.is_reserving() {
+            let target: isize = instruction.offset.wrapping_add(offset);
+            let position: Option<usize> = self.position(instruction, self.options.is_checked());
+            self.reserve(0, target.min(0), target.max(0), position, output);
+        }
+    }
+
+    /// Append the call moving the pointer by `offset` cells and making sure that the cells from `low` to `high` cells
+    /// away from the new current cell are on the tape to the `output`. The ID of the source `position` is passed on to
+    /// report errors on checked tapes. The cells before the start of a tape wrapping around do not have to be on the
+    /// tape.
+    fn reserve(&mut self, offset: isize, low: isize, high: isize, position: Option<usize>, output: &mut String) {
+        let low: isize = if self.options.is_wrapping() { low.max(0) } else { low };
+        if offset == 0 && low == 0 && high == 0 {
+            return;
+        }
+
+        self.runtime.reserve = true;
+        emit(output, &format!("movq ${offset}, %rsi", offset = offset));
+        emit(output, &format!("movq ${low}, %rdx", low = low));
+        emit(output, &format!("movq ${high}, %rcx", high = high));
+        if let Some(id) = position {
+            emit(output, &format!("movl ${id}, %edi", id = id));
+        }
+        emit(output, "call bf_reserve");
+    }
+
+    /// Make sure that a growing tape contains all cells accessed by the `instructions` up to the next pointer
+    /// movement, appending the code to the `output`. Checked tapes are handled for each instruction instead.
+    fn reserve_segment(&mut self, instructions: &[Instruction], output: &mut String) {
+        if let Some((low, high)) = reserved_range(instructions, &self.options) {
+            self.reserve(0, low, high, None, output);
+        }
+    }
+
+    /// Append the code moving the pointer by `offset` cells to the `output`, wrapping around at the ends of an
+    /// unchecked tape with a fixed size.
+    fn step(&self, offset: isize, output: &mut String) {
+        let offset: isize = self.wrap(offset);
+        if offset == 0 {
+            return;
+        }
+
+        if self.options.is_reserving() {
+            emit(output, &format!("addq ${offset}, %r12", offset = offset));
+        } else {
+            self.index(offset, output);
+            emit(output, "movq %r13, %r12");
+        }
+    }
+
+    /// Get the memory operand of the cell `offset` cells away from the current cell. On an unchecked tape with a fixed
+    /// size, the code computing the cell's index in `%r13` is appended to the `output` unless it is the current cell.
+    /// The same applies to the cells before the current cell on an unchecked tape growing to the right.
+    fn cell(&mut self, offset: isize, output: &mut String) -> String {
+        let offset: isize = self.wrap(offset);
+        if offset < 0 && self.options.is_reserving() && self.options.is_wrapping() {
+            self.runtime.wrap_left = true;
+            emit(output, &format!("movq ${distance}, %r13", distance = offset.unsigned_abs()));
+            emit(output, "call bf_wrap_left");
+        } else if offset == 0 || self.options.is_reserving() {
+            return memory(offset, self.options.cell_type);
+        } else {
+            self.index(offset, output);
+        }
+
+        format!("(%rbx,%r13,{size})", size = cell_size(self.options.cell_type))
+    }
+
+    /// Append the code computing the index of the cell `offset` cells away from the current cell on an unchecked tape
+    /// with a fixed size in `%r13` to the `output`, wrapping around at the tape's ends. `%r14` is overwritten.
+    fn index(&self, offset: isize, output: &mut String) {
+        let length: isize = isize::try_from(self.options.tape_size.max(1)).unwrap_or(isize::MAX);
+        let distance: isize = if offset < 0 { offset + length } else { offset };
+        emit(output, &format!("movabsq ${distance}, %r13", distance = distance));
+        emit(output, "addq %r12, %r13");
+        emit(output, &format!("movabsq ${distance}, %r14", distance = distance - length));
+        emit(output, "addq %r12, %r14");
+        emit(output, "cmovnsq %r14, %r13");
+    }
+
+    /// Reduce the `offset` to the number of cells on an unchecked tape with a fixed size, on which the pointer wraps
+    /// around at the ends.
+    fn wrap(&self, offset: isize) -> isize {
+        if self.options.is_reserving() {
+            return offset;
+        }
+
+        let length: isize = isize::try_from(self.options.tape_size.max(1)).unwrap_or(isize::MAX);
+        offset % length
+    }
+
+    /// Get a new label of the given `kind`.
+    fn label(&mut self, kind: &str) -> String {
+        self.labels += 1;
+        format!(".L{kind}{number}", kind = kind, number = self.labels)
+    }
+
+    /// Determine if scans with a stride of one can search the tape's memory directly, i.e. if the cells are bytes and
+    /// the tape's size is known at compile time and does not have to be checked.
+    fn can_search_memory(&self) -> bool {
+        self.options.cell_type == CellType::U8 && !self.options.is_reserving()
+    }
+
+    /// Get the declaration of the tape and the functions called by the generated code.
+    fn runtime(&self) -> String {
+        let cell_type: CellType = self.options.cell_type;
+        let tape_size: usize = self.options.tape_size.max(1);
+        let mut runtime: String = self.failing_runtime();
+        match self.options.tape_growth {
+            TapeGrowth::Fixed => {
+                let tape_bytes: String = tape_size.saturating_mul(cell_size(cell_type)).to_string();
+                runtime.push_str(&FIXED_TAPE.replace("{tape_bytes}", &tape_bytes));
+                if self.runtime.reserve {
+                    runtime.push_str(&CHECKED_FIXED_TAPE.replace("{tape_size}", &tape_size.to_string()));
+                }
+            },
+            TapeGrowth::Right | TapeGrowth::Both => {
+                let both: bool = self.options.tape_growth == TapeGrowth::Both;
+                let check_left: &str = if self.options.is_checked() {
+                    CHECKING_LEFT
+                } else if self.options.is_wrapping() {
+                    WRAPPING_LEFT
+                } else {
+                    ""
+                };
+                runtime.push_str(&GROWING_TAPE.replace("{tape_size}", &tape_size.to_string())
+                    .replace("{cell_size}", &cell_size(cell_type).to_string()));
+                if self.runtime.reserve {
+                    runtime.push_str(&GROWING_RESERVE.replace("{cell_size}", &cell_size(cell_type).to_string())
+                        .replace("{check_left}", check_left)
+                        .replace("{grow_left}", if both { GROWING_LEFT } else { FIXED_LEFT }));
+                }
+                if self.runtime.wrap_left {
+                    runtime.push_str(WRAPPING_LEFT_RUNTIME);
+                }
+            },
+        }
+
+        runtime.push_str(OUTPUT_RUNTIME);
+        if self.runtime.read {
+            let eof: &str = if self.options.eof == EofBehavior::Zero { "0" } else { "-1" };
+            runtime.push_str(&READING_RUNTIME.replace("{eof}", eof));
+        }
+
+        if self.runtime.add {
+            let bounds: &str = match self.options.overflow {
+                Overflow::Error => CHECKED_BOUNDS,
+                Overflow::Wrap | Overflow::Saturate => SATURATING_BOUNDS,
+            };
+            runtime.push_str(&ARITHMETIC_RUNTIME.replace("{bounds}", bounds)
+                .replace("{min}", &cell_type.min().to_string())
+                .replace("{max}", &cell_type.max().to_string()));
+        }
+
+        runtime
+    }
+
+    /// Get the table of source positions and the function reporting errors at them, if the generated code reports any
+    /// errors.
+    fn failing_runtime(&self) -> String {
+        if self.positions.is_empty() {
+            return String::new();
+        }
+
+        let positions: String = self.positions.iter()
+            .map(|position| format!("    .long {lineno}, {position}\n",
+                                    lineno = position.lineno, position = position.position))
+            .collect();
+
+        FAILING_RUNTIME.replace("{positions}", &positions)
+    }
+
+    /// Get the ID of the `instruction`'s source position passed to the runtime functions, adding the position to the
+    /// table. Empty unless the position might be `reported`.
+    fn position(&mut self, instruction: &Instruction, reported: bool) -> Option<usize> {
+        if !reported {
+            return None;
+        }
+
+        self.positions.push(instruction.span.start);
+        Some(self.positions.len() - 1)
+    }
+}
+
+/// Append the label after the condition of the multiplications if the `guard` is open to the `output`.
+fn close_guard(guard: &mut Option<(isize, usize)>, output: &mut String) {
+    if let Some((_, label)) = guard.take() {
+        output.push_str(&format!(".Lmultiply{label}_end:\n", label = label));
+    }
+}
+
+/// Append the given assembly `instruction` to the `output`.
+fn emit(output: &mut String, instruction: &str) {
+    output.push_str("    ");
+    output.push_str(instruction);
+    output.push('\n');
+}
+
+/// Get the memory operand of the cell `offset` cells away from the current cell.
+fn memory(offset: isize, cell_type: CellType) -> String {
+    let size: usize = cell_size(cell_type);
+    let displacement: String = match offset {
+        0 => String::new(),
+        offset if offset < 0 => format!("-{distance}", distance = offset.unsigned_abs().saturating_mul(size)),
+        offset => offset.unsigned_abs().saturating_mul(size).to_string(),
+    };
+
+    format!("{displacement}(%rbx,%r12,{size})", displacement = displacement, size = size)
+}
+
+/// Get the size of cells of the given `cell_type` in bytes.
+fn cell_size(cell_type: CellType) -> usize {
+    match cell_type {
+        CellType::U8 => 1,
+        CellType::U16 => 2,
+        CellType::U32 => 4,
+        CellType::I64 => 8,
+    }
+}
+
+/// Get the suffix of instructions operating on cells of the given `cell_type`.
+fn suffix(cell_type: CellType) -> char {
+    match cell_type {
+        CellType::U8 => 'b',
+        CellType::U16 => 'w',
+        CellType::U32 => 'l',
+        CellType::I64 => 'q',
+    }
+}
+
+/// Get the part of `%rax` holding cells of the given `cell_type`.
+fn accumulator(cell_type: CellType) -> &'static str {
+    match cell_type {
+        CellType::U8 => "%al",
+        CellType::U16 => "%ax",
+        CellType::U32 => "%eax",
+        CellType::I64 => "%rax",
+    }
+}
+
+/// Get the instruction loading a cell of the given `cell_type` into a 32 bit register, or into a 64 bit register if
+/// the register is `wide`, extending it according to the cell type's signedness. Cells with 32 bits are always loaded
+/// into a 32 bit register, which clears the upper half of the 64 bit register.
+fn extension(cell_type: CellType, wide: bool) -> &'static str {
+    match cell_type {
+        CellType::U8 if wide => "movzbq",
+        CellType::U8 => "movzbl",
+        CellType::U16 if wide => "movzwq",
+        CellType::U16 => "movzwl",
+        CellType::U32 => "movl",
+        CellType::I64 => "movq",
+    }
+}
+
+/// Get the `value` of a change to a cell of the given `cell_type`, reduced to the cell type's range when wrapping
+/// around at its bounds.
+fn wrapped(value: i32, cell_type: CellType) -> i64 {
+    let value: i64 = i64::from(value);
+    match cell_type {
+        CellType::U8 => value.rem_euclid(0x100),
+        CellType::U16 => value.rem_euclid(0x1_0000),
+        CellType::U32 | CellType::I64 => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use AsmGenerator;
+    use CellType;
+    use EofBehavior;
+    use Instruction;
+    use MetaData;
+    use Operation;
+    use Options;
+    use Overflow;
+    use Program;
+    use Span;
+    use TapeGrowth;
+    use super::Runtime;
+
+    #[test]
+    fn test_new() {
+        let generator = AsmGenerator::new();
+        assert_eq!(generator, AsmGenerator {
+            options: Options::default(),
+            labels: 0,
+            positions: Vec::new(),
+            runtime: Runtime::default(),
+        });
+    }
+
+    #[test]
+    fn test_generate() {
+        let template: &str = "_start:\n{setup}";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(259), span),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::new(Operation::MulAdd { offset: 1, factor: -1 }, span),
+                    Instruction::new(Operation::Clear, span),
+                ]), span),
+                Instruction::at_offset(Operation::Write, -1, span),
+                Instruction::new(Operation::Scan { stride: 2 }, span),
+            ],
+        };
+        let expected: &str = "_start:
+    leaq bf_tape(%rip), %rbx
+    addb $3, (%rbx,%r12,1)
+.Lloop1:
+    cmpb $0, (%rbx,%r12,1)
+    je .Lloop1_end
+    movzbl (%rbx,%r12,1), %eax
+    movabsq $1, %r13
+    addq %r12, %r13
+    movabsq $-29999, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    subb %al, (%rbx,%r13,1)
+    movb $0, (%rbx,%r12,1)
+    jmp .Lloop1
+.Lloop1_end:
+    movabsq $29999, %r13
+    addq %r12, %r13
+    movabsq $-1, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movb (%rbx,%r13,1), %dil
+    call bf_write
+.Lscan2:
+    cmpb $0, (%rbx,%r12,1)
+    je .Lscan2_end
+    movabsq $2, %r13
+    addq %r12, %r13
+    movabsq $-29998, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movq %r13, %r12
+    jmp .Lscan2
+.Lscan2_end:
+    call bf_flush
+    movl $60, %eax
+    xorl %edi, %edi
+    syscall
+";
+
+        let mut generator = AsmGenerator::new();
+        assert_eq!(generator.generate(template, &program), expected);
+
+        // Generating the same program again starts from scratch.
+        assert_eq!(generator.generate(template, &program), expected);
+    }
+
+    #[test]
+    fn test_generate_runtime() {
+        let template: &str = "{runtime}";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![Instruction::new(Operation::Write, span)],
+        };
+
+        let generated: String = AsmGenerator::new().generate(template, &program);
+        assert!(generated.starts_with("    .local bf_tape\n    .comm bf_tape, 30000, 8\n"));
+        assert!(generated.contains("\nbf_flush:\n"));
+        assert!(generated.contains("\nbf_write:\n"));
+        assert!(!generated.contains("bf_read"));
+        assert!(!generated.contains("bf_add"));
+        assert!(!generated.contains("bf_reserve"));
+        assert!(!generated.contains("bf_fail"));
+    }
+
+    #[test]
+    fn test_generate_guard() {
+        let template: &str = "";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::MulAdd { offset: 1, factor: 2 }, span),
+                Instruction::new(Operation::MulAdd { offset: 2, factor: 1 }, span),
+                Instruction::new(Operation::Clear, span),
+            ],
+        };
+
+        let options = Options { tape_size: 4, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("    cmpb $0, (%rbx,%r12,1)
+    je .Lmultiply1_end
+    movzbl (%rbx,%r12,1), %eax
+    imull $2, %eax, %eax
+    movabsq $1, %r13
+    addq %r12, %r13
+    movabsq $-3, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb %al, (%rbx,%r13,1)
+    movzbl (%rbx,%r12,1), %eax
+    movabsq $2, %r13
+    addq %r12, %r13
+    movabsq $-2, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    addb %al, (%rbx,%r13,1)
+.Lmultiply1_end:
+    movb $0, (%rbx,%r12,1)
+"));
+        assert_eq!(generated.matches("je ").count(), 1);
+    }
+
+    #[test]
+    fn test_generate_cell_types() {
+        let template: &str = "{runtime}";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(-70_000), 1, span),
+                Instruction::at_offset(Operation::MulAdd { offset: -2, factor: 3 }, 1, span),
+                Instruction::new(Operation::Write, span),
+            ],
+        };
+
+        let options = Options { cell_type: CellType::U16, tape_size: 16, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("    .local bf_tape\n    .comm bf_tape, 32, 8\n"));
+        assert!(generated.contains("    movabsq $1, %r13\n    addq %r12, %r13\n    movabsq $-15, %r14\n"));
+        assert!(generated.contains("    addw $61072, (%rbx,%r13,2)\n"));
+        assert!(generated.contains("    movzwl (%rbx,%r13,2), %eax\n    imull $3, %eax, %eax\n"));
+        assert!(generated.contains("    movabsq $15, %r13\n    addq %r12, %r13\n    movabsq $-1, %r14\n"));
+        assert!(generated.contains("    addw %ax, (%rbx,%r13,2)\n"));
+        assert!(generated.contains("    movb (%rbx,%r12,2), %dil\n"));
+
+        let options = Options { cell_type: CellType::U32, tape_growth: TapeGrowth::Right, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("    addl $-70000, 4(%rbx,%r12,4)\n"));
+        assert!(generated.contains("    movl 4(%rbx,%r12,4), %eax\n    imull $3, %eax, %eax\n"));
+        assert!(generated.contains("    movq $1, %r13\n    call bf_wrap_left\n    addl %eax, (%rbx,%r13,4)\n"));
+
+        let options = Options { cell_type: CellType::I64, tape_growth: TapeGrowth::Right, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("    addq $-70000, 8(%rbx,%r12,8)\n"));
+        assert!(generated.contains("    movq 8(%rbx,%r12,8), %rax\n    imulq $3, %rax, %rax\n"));
+        assert!(generated.contains("    movq $1, %r13\n    call bf_wrap_left\n    addq %rax, (%rbx,%r13,8)\n"));
+    }
+
+    #[test]
+    fn test_generate_overflow() {
+        let template: &str = "{runtime}";
+        let span = Span::at(MetaData { lineno: 2, position: 3 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(5), span),
+                Instruction::new(Operation::MulAdd { offset: -1, factor: -1 }, span),
+            ],
+        };
+
+        let options = Options { overflow: Overflow::Saturate, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("\nbf_add:\n"));
+        assert!(generated.contains("1:\n    movabsq $0, %rax\n    ret\n2:\n    movabsq $255, %rax\n    ret\n"));
+        assert!(generated.contains("    movq $5, %rax
+    cqto
+    movzbq (%rbx,%r12,1), %rcx
+    call bf_add
+    movb %al, (%rbx,%r12,1)
+"));
+        assert!(generated.contains("    movzbq (%rbx,%r12,1), %rax
+    movq $-1, %rcx
+    imulq %rcx
+    movabsq $29999, %r13
+    addq %r12, %r13
+    movabsq $-1, %r14
+    addq %r12, %r14
+    cmovnsq %r14, %r13
+    movzbq (%rbx,%r13,1), %rcx
+    call bf_add
+    movb %al, (%rbx,%r13,1)
+"));
+        assert!(!generated.contains("bf_fail"));
+
+        let options = Options { overflow: Overflow::Error, cell_type: CellType::I64, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("bf_positions:\n    .long 2, 3\n    .long 2, 3\n"));
+        assert!(generated.contains("    movabsq $-9223372036854775808, %rsi\n"));
+        assert!(generated.contains("1:\n2:\n    leaq bf_cell_overflow(%rip), %rsi\n    jmp bf_fail\n"));
+        assert!(generated.contains("    movq (%rbx,%r12,8), %rcx\n    movl $0, %edi\n    call bf_add\n"));
+        assert!(generated.contains("    movq (%rbx,%r13,8), %rcx\n    movl $1, %edi\n    call bf_add\n"));
+    }
+
+    #[test]
+    fn test_generate_eof() {
+        let template: &str = "{runtime}";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![Instruction::at_offset(Operation::Read, 2, span)],
+        };
+
+        let options = Options { eof: EofBehavior::Zero, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("1:\n    movq $0, %rax\n    ret\n"));
+        assert!(generated.contains("    cmovnsq %r14, %r13\n    call bf_read\n    movb %al, (%rbx,%r13,1)\n"));
+
+        let options = Options { eof: EofBehavior::MinusOne, cell_type: CellType::U16, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("1:\n    movq $-1, %rax\n    ret\n"));
+        assert!(generated.contains("    call bf_read\n    movw %ax, (%rbx,%r13,2)\n"));
+
+        let options = Options { eof: EofBehavior::Unchanged, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("1:\n    movq $-1, %rax\n    ret\n"));
+        assert!(generated.contains("    call bf_read
+    testq %rax, %rax
+    js .Lread1_end
+    movb %al, (%rbx,%r13,1)
+.Lread1_end:
+"));
+    }
+
+    #[test]
+    fn test_generate_search() {
+        let template: &str = "";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Scan { stride: 1 }, span),
+                Instruction::new(Operation::Scan { stride: -1 }, span),
+            ],
+        };
+
+        let options = Options { tape_size: 100, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("    leaq (%rbx,%r12), %rdi
+    movabsq $100, %rcx
+    subq %r12, %rcx
+    xorl %eax, %eax
+    repne scasb
+    je .Lsearch1_end
+    movq %rbx, %rdi
+    movq %r12, %rcx
+    repne scasb
+    je .Lsearch1_end
+.Lsearch1:
+    jmp .Lsearch1
+.Lsearch1_end:
+    leaq -1(%rdi), %r12
+    subq %rbx, %r12
+    leaq (%rbx,%r12), %rdi
+    leaq 1(%r12), %rcx
+    xorl %eax, %eax
+    std
+    repne scasb
+    je .Lsearch2_end
+    movabsq $99, %rcx
+    leaq (%rbx,%rcx), %rdi
+    subq %r12, %rcx
+    repne scasb
+    je .Lsearch2_end
+.Lsearch2:
+    jmp .Lsearch2
+.Lsearch2_end:
+    cld
+"));
+
+        let options = Options { cell_type: CellType::U16, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(!generated.contains("scasb"));
+        assert!(generated.contains("    movabsq $29999, %r13\n"));
+        assert!(generated.contains("    movq %r13, %r12\n    jmp .Lscan2\n"));
+    }
+
+    #[test]
+    fn test_generate_growing_tape() {
+        let template: &str = "{runtime}{setup}";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), -1, span),
+                Instruction::new(Operation::Move(2), span),
+                Instruction::at_offset(Operation::Add(1), 3, span),
+            ],
+        };
+
+        let options = Options { tape_growth: TapeGrowth::Right, cell_type: CellType::U32, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("    .data\nbf_tape_size:\n    .quad 30000\n"));
+        assert!(generated.contains("\nbf_allocate:\n    movq bf_tape_size(%rip), %rsi\n    imulq $4, %rsi, %rsi\n"));
+        assert!(generated.contains("\nbf_reserve:\n"));
+        assert!(generated.contains("    leaq (%rax,%r13,4), %rdi\n"));
+        assert!(generated.contains("1:\n    testq %r12, %r12\n    jns 3f\n    movq %r12, %rax\n    cqto\n"));
+        assert!(generated.contains("    idivq %r15\n    leaq (%rdx,%r15), %r12\n"));
+        assert!(generated.contains("3:\n    xorl %r13d, %r13d\n    xorl %r14d, %r14d\n"));
+        assert!(generated.contains("\nbf_wrap_left:\n"));
+        assert!(generated.contains("    call bf_allocate
+    movq $1, %r13
+    call bf_wrap_left
+    addl $1, (%rbx,%r13,4)
+    movq $2, %rsi
+    movq $0, %rdx
+    movq $3, %rcx
+    call bf_reserve
+    addl $1, 12(%rbx,%r12,4)
+"));
+
+        let options = Options { tape_growth: TapeGrowth::Both, checked: true, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("    negq %r13\n"));
+        assert!(!generated.contains("bf_fail"));
+
+        // Tapes are only grown if the pointer is moved.
+        let program = Program {
+            instructions: vec![Instruction::new(Operation::Add(1), span)],
+        };
+        let options = Options { tape_growth: TapeGrowth::Right, checked: true, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("\nbf_allocate:\n"));
+        assert!(!generated.contains("bf_reserve"));
+    }
+
+    #[test]
+    fn test_generate_checked() {
+        let template: &str = "{runtime}";
+        let position = |lineno: usize, position: usize| Span::at(MetaData { lineno, position });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), -1, position(1, 1)),
+                Instruction::new(Operation::Move(2), position(1, 3)),
+                Instruction::new(Operation::Scan { stride: 1 }, position(2, 5)),
+            ],
+        };
+
+        let options = Options { checked: true, tape_size: 8, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("    .section .rodata
+bf_positions:
+    .long 1, 1
+    .long 1, 3
+    .long 2, 5
+"));
+        assert!(generated.contains("    movabsq $8, %r8\n"));
+        assert!(generated.contains("    movq $0, %rsi
+    movq $-1, %rdx
+    movq $0, %rcx
+    movl $0, %edi
+    call bf_reserve
+"));
+        assert!(generated.contains("    movq $2, %rsi\n    movq $0, %rdx\n    movq $0, %rcx\n    movl $1, %edi\n"));
+        assert!(generated.contains("    movq $1, %rsi\n    movq $0, %rdx\n    movq $0, %rcx\n    movl $2, %edi\n"));
+
+        let options = Options { checked: true, tape_growth: TapeGrowth::Right, ..Options::default() };
+        let generated: String = AsmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("    leaq bf_pointer_underflow(%rip), %rsi
+    jmp bf_fail
+3:
+    xorl %r13d, %r13d
+"));
+    }
+}
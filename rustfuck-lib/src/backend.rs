@@ -0,0 +1,158 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Definitions of the interface of compiler backends, and of a registry collecting them.
+
+use std::io::Write;
+
+use Named;
+use Options;
+use Program;
+use Registry;
+use Result;
+use Target;
+
+/// A backend of the compiler, emitting programs in its target language.
+///
+/// All built-in targets are backends. Other crates can implement further ones, and add them to a `BackendRegistry`.
+/// Backends may write their output while generating it, but do not have to: of the built-in targets, only `C` does,
+/// while the others generate the complete program in memory before writing it.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+///
+/// use rustfuck_lib::Backend;
+/// use rustfuck_lib::BackendRegistry;
+/// use rustfuck_lib::Compiler;
+/// use rustfuck_lib::Named;
+/// use rustfuck_lib::Options;
+/// use rustfuck_lib::Program;
+/// use rustfuck_lib::Result;
+///
+/// /// Emit the number of top-level instructions.
+/// struct Count;
+///
+/// impl Named for Count {
+///     fn name(&self) -> &str {
+///         "count"
+///     }
+/// }
+///
+/// impl Backend for Count {
+///     fn extension(&self) -> &str {
+///         "txt"
+///     }
+///
+///     fn emit(&self, program: &Program, _options: &Options, output: &mut dyn Write) -> Result<()> {
+///         write!(output, "{}", program.instructions.len())?;
+///         Ok(())
+///     }
+/// }
+///
+/// let mut registry = BackendRegistry::new();
+/// registry.register(Box::new(Count));
+///
+/// let backend = registry.get("count").unwrap();
+/// assert_eq!(Compiler::new().compile_with("+>+[-]", backend).unwrap(), b"4");
+/// ```
+pub trait Backend: Named {
+    /// Get the extension of the files emitted by the backend.
+    fn extension(&self) -> &str;
+
+    /// Emit the `program` in the target language using the given `options`, writing the result to the `output`.
+    fn emit(&self, program: &Program, options: &Options, output: &mut dyn Write) -> Result<()>;
+}
+
+/// A collection of backends, identified by their names.
+pub type BackendRegistry = Registry<dyn Backend>;
+
+impl Registry<dyn Backend> {
+    /// Initialize a new registry containing all built-in targets, starting with `C`.
+    pub fn new() -> BackendRegistry {
+        let mut registry = BackendRegistry::empty();
+        for &target in Target::all() {
+            registry.register(Box::new(target));
+        }
+
+        registry
+    }
+}
+
+impl Default for Registry<dyn Backend> {
+    fn default() -> BackendRegistry {
+        BackendRegistry::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Write;
+
+    use Backend;
+    use BackendRegistry;
+    use Named;
+    use Options;
+    use Program;
+    use Result;
+    use Target;
+
+    /// A backend emitting a fixed text.
+    struct Fixed(&'static str);
+
+    impl Named for Fixed {
+        fn name(&self) -> &str {
+            "c"
+        }
+    }
+
+    impl Backend for Fixed {
+        fn extension(&self) -> &str {
+            "txt"
+        }
+
+        fn emit(&self, _program: &Program, _options: &Options, output: &mut dyn Write) -> Result<()> {
+            output.write_all(self.0.as_bytes())?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let registry = BackendRegistry::new();
+        assert_eq!(registry.names(), [
+            "c", "rust", "rust-library", "llvm-ir", "x86_64-asm", "js", "js-cli", "wat", "wasm", "wasi-wat",
+            "wasi-wasm",
+        ]);
+        assert_eq!(registry.get("llvm-ir").map(Backend::extension), Some("ll"));
+        assert!(registry.get("cobol").is_none());
+        assert_eq!(format!("{:?}", BackendRegistry::empty()), "Registry { items: [] }");
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry = BackendRegistry::new();
+        registry.register(Box::new(Fixed("int main;")));
+        assert_eq!(registry.iter().count(), Target::all().len());
+        assert_eq!(registry.names()[0], "c");
+        assert_eq!(registry.get("c").map(Backend::extension), Some("txt"));
+
+        let program = Program { instructions: Vec::new() };
+        let mut output: Vec<u8> = Vec::new();
+        registry.get("c").unwrap().emit(&program, &Options::default(), &mut output).unwrap();
+        assert_eq!(output, b"int main;");
+    }
+
+    #[test]
+    fn test_emit_target() {
+        let program = Program { instructions: Vec::new() };
+        let mut output: Vec<u8> = Vec::new();
+        Target::WasmBinary.emit(&program, &Options::default(), &mut output).unwrap();
+        assert!(output.starts_with(b"\0asm"));
+    }
+}
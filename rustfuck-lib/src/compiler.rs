@@ -6,42 +6,336 @@
 
 //! Implementations of the `Brainfuck` compiler.
 
+use std::io::Error as IOError;
+use std::io::ErrorKind;
+use std::io::Write;
+
+use AsmGenerator;
+use Backend;
+use ElfGenerator;
+use Error;
 use Generator;
-use Lexer;
-use language::Brainfuck;
+use JsGenerator;
+use LlvmGenerator;
+use Named;
+use Optimizer;
+use Options;
+use Program;
+use Result;
+use RustGenerator;
+use WasmGenerator;
+use WasmInterface;
+use generator::PREFACE;
+use language::Dialect;
+use language::Language;
+
+/// The `Rust` preface of the generated tokens, opening the library function running the program.
+static RUST_PREFACE: &str = include_str!("../resources/preface.rs");
+
+/// The `Rust` main function calling the library function of generated programs.
+static RUST_MAIN: &str = include_str!("../resources/main.rs");
+
+/// The `LLVM` preface of the generated IR, opening the main function.
+static LLVM_PREFACE: &str = include_str!("../resources/preface.ll");
+
+/// The assembly preface of the generated code, opening the entry point.
+static ASM_PREFACE: &str = include_str!("../resources/preface.s");
+
+/// The `JavaScript` preface of the generated code, defining the exported function running the program and opening the
+/// function executing it.
+static JS_PREFACE: &str = include_str!("../resources/preface.mjs");
+
+/// The `JavaScript` command-line interface running generated programs on `STDIN` and `STDOUT` in `Node.js`.
+static JS_MAIN: &str = include_str!("../resources/main.mjs");
+
+/// All built-in targets, in the order in which they are listed.
+static TARGETS: [Target; 11] = [
+    Target::C,
+    Target::Rust,
+    Target::RustLibrary,
+    Target::LlvmIr,
+    Target::X86_64Asm,
+    Target::JavaScript,
+    Target::JavaScriptCli,
+    Target::WasmText,
+    Target::WasmBinary,
+    Target::WasiText,
+    Target::WasiBinary,
+];
+
+/// The languages into which the compiler can translate `Brainfuck` programs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Target {
+    /// A `C` program.
+    C,
+
+    /// A `Rust` program.
+    Rust,
+
+    /// A `Rust` library function `run`, executing the program on a given reader and writer.
+    RustLibrary,
+
+    /// A module in textual `LLVM` IR.
+    LlvmIr,
+
+    /// A program in `x86-64` assembly for Linux, in `GAS` syntax.
+    X86_64Asm,
+
+    /// An `ECMAScript` module exporting the function `run`, executing the program on a given `Uint8Array` and returning
+    /// the output as a `Uint8Array`.
+    JavaScript,
+
+    /// An `ECMAScript` module like `JavaScript`, which runs the program on `STDIN` and `STDOUT` when executed in
+    /// `Node.js`.
+    JavaScriptCli,
 
-/// The `C` preface of the generated tokens.
-static PREFACE: &'static str = include_str!("../resources/preface.c");
+    /// A `WebAssembly` module in the text format, importing its input and output functions from the host.
+    WasmText,
+
+    /// A `WebAssembly` module in the binary format, importing its input and output functions from the host.
+    WasmBinary,
+
+    /// A `WebAssembly` module in the text format, running as a `WASI` command.
+    WasiText,
+
+    /// A `WebAssembly` module in the binary format, running as a `WASI` command.
+    WasiBinary,
+}
+
+impl Target {
+    /// Get all built-in targets, starting with `C`.
+    pub fn all() -> &'static [Target] {
+        &TARGETS
+    }
+
+    /// Get the name identifying the target, e.g. on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::C => "c",
+            Target::Rust => "rust",
+            Target::RustLibrary => "rust-library",
+            Target::LlvmIr => "llvm-ir",
+            Target::X86_64Asm => "x86_64-asm",
+            Target::JavaScript => "js",
+            Target::JavaScriptCli => "js-cli",
+            Target::WasmText => "wat",
+            Target::WasmBinary => "wasm",
+            Target::WasiText => "wasi-wat",
+            Target::WasiBinary => "wasi-wasm",
+        }
+    }
+
+    /// Get the file extension of source files in the target language.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Target::C => "c",
+            Target::Rust | Target::RustLibrary => "rs",
+            Target::LlvmIr => "ll",
+            Target::X86_64Asm => "s",
+            Target::JavaScript | Target::JavaScriptCli => "mjs",
+            Target::WasmText | Target::WasiText => "wat",
+            Target::WasmBinary | Target::WasiBinary => "wasm",
+        }
+    }
+
+    /// Determine if the target language is a binary format instead of a text.
+    pub fn is_binary(self) -> bool {
+        matches!(self, Target::WasmBinary | Target::WasiBinary)
+    }
+
+    /// Generate the code for the `program` in the target language using the given `options`. Empty if the target
+    /// language is a binary format.
+    fn generate_text(self, program: &Program, options: Options) -> Option<String> {
+        let code: String = match self {
+            Target::C => Generator::with_options(options).generate(PREFACE, program),
+            Target::Rust => RustGenerator::with_options(options).generate(RUST_PREFACE, program) + RUST_MAIN,
+            Target::RustLibrary => RustGenerator::with_options(options).generate(RUST_PREFACE, program),
+            Target::LlvmIr => LlvmGenerator::with_options(options).generate(LLVM_PREFACE, program),
+            Target::X86_64Asm => AsmGenerator::with_options(options).generate(ASM_PREFACE, program),
+            Target::JavaScript => JsGenerator::with_options(options).generate(JS_PREFACE, program),
+            Target::JavaScriptCli => JsGenerator::with_options(options).generate(JS_PREFACE, program) + JS_MAIN,
+            Target::WasmText => WasmGenerator::with_options(options).generate_text(program, WasmInterface::Host),
+            Target::WasiText => WasmGenerator::with_options(options).generate_text(program, WasmInterface::Wasi),
+            Target::WasmBinary | Target::WasiBinary => return None,
+        };
+
+        Some(code)
+    }
+
+    /// Generate the bytes of the file for the `program` in the target language using the given `options`. Source code
+    /// is encoded in `UTF-8`.
+    fn generate(self, program: &Program, options: Options) -> Vec<u8> {
+        match self {
+            Target::WasmBinary => WasmGenerator::with_options(options).generate_binary(program, WasmInterface::Host),
+            Target::WasiBinary => WasmGenerator::with_options(options).generate_binary(program, WasmInterface::Wasi),
+            _ => self.generate_text(program, options).unwrap_or_default().into_bytes(),
+        }
+    }
+}
+
+impl Named for Target {
+    fn name(&self) -> &str {
+        Target::name(*self)
+    }
+}
+
+impl Backend for Target {
+    fn extension(&self) -> &str {
+        Target::extension(*self)
+    }
+
+    fn emit(&self, program: &Program, options: &Options, output: &mut dyn Write) -> Result<()> {
+        // Only the `C` generator writes its code while generating it. The other targets are generated in memory.
+        if *self == Target::C {
+            return Generator::new().emit(program, options, output);
+        }
+
+        output.write_all(&self.generate(program, *options))?;
+        Ok(())
+    }
+}
 
 /// The `Brainfuck` compiler.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
-pub struct Compiler;
+pub struct Compiler {
+
+    /// The options controlling the compilation.
+    options: Options,
+}
 
 impl Compiler {
-    /// Initialize a `Brainfuck` compiler.
+    /// Initialize a `Brainfuck` compiler with the default options.
     pub fn new() -> Compiler {
-        Compiler{}
+        Compiler::with_options(Options::default())
     }
 
-    /// Compile the given `Brainfuck` `source` code.
-    pub fn compile(&self, source: &str) -> String {
-        let program = Brainfuck::new(source);
-        let tokens = program.tokenize();
+    /// Initialize a `Brainfuck` compiler with the given `options`.
+    pub fn with_options(options: Options) -> Compiler {
+        Compiler {
+            options,
+        }
+    }
+
+    /// Get the options controlling the compilation.
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Compile the given `Brainfuck` `source` code to `C`.
+    ///
+    /// Fails with a syntax error if the loops in the `source` are not balanced.
+    pub fn compile(&self, source: &str) -> Result<String> {
+        self.compile_to(source, Target::C)
+    }
+
+    /// Compile the given `Brainfuck` `source` code to the `target` language.
+    ///
+    /// Fails with a syntax error if the loops in the `source` are not balanced, and with an IO error of the kind
+    /// `InvalidInput` if the `target` language is a binary format. Use `compile_to_bytes` for these targets.
+    pub fn compile_to(&self, source: &str, target: Target) -> Result<String> {
+        let program: Program = self.parse(source)?;
+        target.generate_text(&program, self.options).ok_or_else(|| {
+            let message: String = format!("the target {name} is a binary format", name = target.name());
+            Error::from(IOError::new(ErrorKind::InvalidInput, message))
+        })
+    }
+
+    /// Compile the given `Brainfuck` `source` code to the `target` language, returning the bytes of the generated
+    /// file. Source code is encoded in `UTF-8`.
+    ///
+    /// Fails with a syntax error if the loops in the `source` are not balanced.
+    pub fn compile_to_bytes(&self, source: &str, target: Target) -> Result<Vec<u8>> {
+        self.compile_with(source, &target)
+    }
+
+    /// Compile the given `Brainfuck` `source` code using the `backend`, returning the bytes it emits.
+    ///
+    /// Fails with a syntax error if the loops in the `source` are not balanced, or with the backend's error.
+    pub fn compile_with(&self, source: &str, backend: &dyn Backend) -> Result<Vec<u8>> {
+        self.compile_from(source, &Dialect::Brainfuck, backend)
+    }
+
+    /// Compile the given `source` code written in the `language` using the `backend`, returning the bytes it emits.
+    ///
+    /// Fails with a syntax error if the `source` is not a valid program in the `language` or its loops are not
+    /// balanced, or with the backend's error.
+    pub fn compile_from(&self, source: &str, language: &dyn Language, backend: &dyn Backend) -> Result<Vec<u8>> {
+        let program: Program = self.parse_from(source, language)?;
+        let mut output: Vec<u8> = Vec::new();
+        backend.emit(&program, &self.options, &mut output)?;
+        Ok(output)
+    }
+
+    /// Compile the given `Brainfuck` `source` code to a static `x86-64` Linux executable in the `ELF` format.
+    ///
+    /// Fails with a syntax error if the loops in the `source` are not balanced.
+    pub fn build(&self, source: &str) -> Result<Vec<u8>> {
+        self.build_from(source, &Dialect::Brainfuck)
+    }
 
-        let mut generator = Generator::new();
-        generator.generate(PREFACE, &tokens)
+    /// Compile the given `source` code written in the `language` to a static `x86-64` Linux executable in the `ELF`
+    /// format.
+    ///
+    /// Fails with a syntax error if the `source` is not a valid program in the `language` or its loops are not
+    /// balanced.
+    pub fn build_from(&self, source: &str, language: &dyn Language) -> Result<Vec<u8>> {
+        let program: Program = self.parse_from(source, language)?;
+        Ok(ElfGenerator::with_options(self.options).generate(&program))
+    }
+
+    /// Translate the given `Brainfuck` `source` code into the intermediate representation, optimizing it if enabled
+    /// in the options.
+    ///
+    /// Fails with a syntax error if the loops in the `source` are not balanced.
+    pub fn parse(&self, source: &str) -> Result<Program> {
+        self.parse_from(source, &Dialect::Brainfuck)
+    }
+
+    /// Translate the given `source` code written in the `language` into the intermediate representation, optimizing
+    /// it if enabled in the options.
+    ///
+    /// Fails with a syntax error if the `source` is not a valid program in the `language` or its loops are not
+    /// balanced.
+    pub fn parse_from(&self, source: &str, language: &dyn Language) -> Result<Program> {
+        let tokens = language.tokenize(source)?;
+        let program = Program::new(&tokens)?;
+
+        if self.options.optimize {
+            Ok(Optimizer::with_options(self.options).optimize(program))
+        } else {
+            Ok(program)
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
 
+    use std::io;
+
     use Compiler;
+    use Error;
+    use Interpreter;
+    use MetaData;
+    use Options;
+    use Program;
+    use SyntaxError;
+    use Target;
+    use TapeGrowth;
+    use language::Dialect;
+    use language::SubstitutionLanguage;
 
     #[test]
     fn test_new() {
         let compiler = Compiler::new();
-        assert_eq!(compiler, Compiler{});
+        assert_eq!(compiler, Compiler { options: Options::default() });
+    }
+
+    #[test]
+    fn test_with_options() {
+        let options = Options { optimize: false, ..Options::default() };
+        let compiler = Compiler::with_options(options);
+        assert_eq!(compiler.options(), &options);
     }
 
     #[test]
@@ -50,7 +344,539 @@ mod tests {
         let source: &str = include_str!("../../examples/hello.bf");
         let expected = String::from(include_str!("../resources/tests/hello.c"));
 
-        let compiled: String = compiler.compile(source);
+        let compiled: String = compiler.compile(source).unwrap();
         assert_eq!(compiled, expected);
     }
+
+    #[test]
+    fn test_compile_with() {
+        let compiler = Compiler::new();
+        let source: &str = include_str!("../../examples/hello.bf");
+        let expected: &[u8] = include_bytes!("../resources/tests/hello.c");
+
+        let generated: Vec<u8> = compiler.compile_with(source, &Target::C).unwrap();
+        assert_eq!(&generated[..], expected);
+    }
+
+    #[test]
+    fn test_compile_from() {
+        let compiler = Compiler::new();
+        let source: &str = include_str!("../../examples/hello.bf");
+        let expected: &[u8] = include_bytes!("../resources/tests/hello.c");
+
+        let generated: Vec<u8> = compiler.compile_from(source, &Dialect::Brainfuck, &Target::C).unwrap();
+        assert_eq!(&generated[..], expected);
+        assert_eq!(compiler.build_from(source, &Dialect::Brainfuck).unwrap(), compiler.build(source).unwrap());
+        assert_eq!(compiler.parse_from(source, &Dialect::Brainfuck).unwrap(), compiler.parse(source).unwrap());
+    }
+
+    #[test]
+    fn test_compile_from_ook() {
+        let compiler = Compiler::new();
+        let source: &str = include_str!("../../examples/hello.bf");
+        let ook_source: &str = include_str!("../../examples/hello.ook");
+
+        for target in Target::all() {
+            let generated: Vec<u8> = compiler.compile_from(ook_source, &Dialect::Ook, target).unwrap();
+            assert_eq!(generated, compiler.compile_with(source, target).unwrap(), "Target {:?}", target);
+        }
+        assert_eq!(compiler.build_from(ook_source, &Dialect::Ook).unwrap(), compiler.build(source).unwrap());
+    }
+
+    #[test]
+    fn test_compile_from_substitution() {
+        let compiler = Compiler::new();
+        let source: &str = include_str!("../../examples/hello.bf");
+        let alphuck_source: &str = include_str!("../../examples/hello.alphuck");
+        let alphuck = SubstitutionLanguage::from_mapping(include_str!("../../examples/alphuck.toml")).unwrap();
+
+        for target in Target::all() {
+            let generated: Vec<u8> = compiler.compile_from(alphuck_source, &alphuck, target).unwrap();
+            assert_eq!(generated, compiler.compile_with(source, target).unwrap(), "Target {:?}", target);
+        }
+    }
+
+    #[test]
+    fn test_compile_from_ook_syntax_error() {
+        let result = Compiler::new().compile_from("Ook. Ook.\nOok!", &Dialect::Ook, &Target::C);
+        match result {
+            Err(Error::Syntax(error)) => {
+                assert_eq!(error, SyntaxError::IncompleteCommand(MetaData { lineno: 2, position: 1 }))
+            },
+            _ => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_compile_unoptimized() {
+        let compiler = Compiler::with_options(Options { optimize: false, ..Options::default() });
+        let source: &str = include_str!("../../examples/hello.bf");
+        let expected = String::from(include_str!("../resources/tests/hello_unoptimized.c"));
+
+        let generated: String = compiler.compile(source).unwrap();
+        assert_eq!(generated, expected);
+    }
+
+    #[test]
+    fn test_compile_rust() {
+        let compiler = Compiler::new();
+        let source: &str = include_str!("../../examples/hello.bf");
+        let expected = String::from(include_str!("../resources/tests/hello.rs"));
+
+        let generated: String = compiler.compile_to(source, Target::Rust).unwrap();
+        assert_eq!(generated, expected);
+    }
+
+    #[test]
+    fn test_compile_rust_library() {
+        let compiler = Compiler::new();
+        let source: &str = include_str!("../../examples/hello.bf");
+
+        let generated: String = compiler.compile_to(source, Target::RustLibrary).unwrap();
+        assert!(generated.contains("pub fn run<R: Read, W: Write>(input: &mut R, output: &mut W) -> io::Result<()> {"));
+        assert!(!generated.contains("fn main()"));
+    }
+
+    #[test]
+    fn test_compile_llvm_ir() {
+        let compiler = Compiler::new();
+        let source: &str = include_str!("../../examples/hello.bf");
+        let expected = String::from(include_str!("../resources/tests/hello.ll"));
+
+        let generated: String = compiler.compile_to(source, Target::LlvmIr).unwrap();
+        assert_eq!(generated, expected);
+    }
+
+    #[test]
+    fn test_compile_llvm_ir_unoptimized() {
+        let compiler = Compiler::with_options(Options { optimize: false, ..Options::default() });
+        let source: &str = include_str!("../../examples/hello.bf");
+        let expected = String::from(include_str!("../resources/tests/hello_unoptimized.ll"));
+
+        let generated: String = compiler.compile_to(source, Target::LlvmIr).unwrap();
+        assert_eq!(generated, expected);
+    }
+
+    #[test]
+    fn test_compile_x86_64_asm() {
+        let compiler = Compiler::new();
+        let source: &str = include_str!("../../examples/hello.bf");
+        let expected = String::from(include_str!("../resources/tests/hello.s"));
+
+        let generated: String = compiler.compile_to(source, Target::X86_64Asm).unwrap();
+        assert_eq!(generated, expected);
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn test_run_x86_64_asm() {
+        let compiler = Compiler::new();
+
+        let source: &str = include_str!("../../examples/hello.bf");
+        let assembly: String = compiler.compile_to(source, Target::X86_64Asm).unwrap();
+        if let Some(output) = run_assembly("hello", &assembly) {
+            assert_eq!(output, b"Hello World!\n");
+        }
+
+        let source: &str = include_str!("../../examples/mandelbrot.bf");
+        let assembly: String = compiler.compile_to(source, Target::X86_64Asm).unwrap();
+        if let Some(output) = run_assembly("mandelbrot", &assembly) {
+            assert_eq!(&output[..], &include_bytes!("../resources/tests/mandelbrot.out")[..]);
+        }
+
+        // The pointer wraps around at the ends of the tape.
+        let assembly: String = compiler.compile_to("<+.", Target::X86_64Asm).unwrap();
+        if let Some(output) = run_assembly("left", &assembly) {
+            assert_eq!(output, b"\x01");
+        }
+
+        let assembly: String = compiler.compile_to("-.>+[>+]", Target::X86_64Asm).unwrap();
+        if let Some(output) = run_assembly("right", &assembly) {
+            assert_eq!(output, b"\xff");
+        }
+    }
+
+    /// Assemble and link the `assembly` of the program `name` with `as` and `ld`, run it, and return its output. Empty
+    /// if the tools are not available.
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn run_assembly(name: &str, assembly: &str) -> Option<Vec<u8>> {
+        let file_name: String = format!("{name}.s", name = name);
+        let build: [&[&str]; 2] = [&["as", &file_name, "-o", "program.o"], &["ld", "program.o", "-o", "program"]];
+        let output: ::std::process::Output = run_tool(&file_name, assembly.as_bytes(), &build, &["./program"])?;
+        assert!(output.status.success());
+        Some(output.stdout)
+    }
+
+    #[test]
+    fn test_compile_javascript() {
+        let compiler = Compiler::new();
+        let source: &str = include_str!("../../examples/hello.bf");
+        let expected = String::from(include_str!("../resources/tests/hello.mjs"));
+
+        let generated: String = compiler.compile_to(source, Target::JavaScript).unwrap();
+        assert_eq!(generated, expected);
+
+        let generated: String = compiler.compile_to(source, Target::JavaScriptCli).unwrap();
+        assert!(generated.starts_with(&expected));
+        assert!(generated.contains("process.stdout.write(run(readFileSync(process.stdin.fd)));"));
+    }
+
+    #[test]
+    fn test_run_javascript() {
+        let compiler = Compiler::new();
+
+        let source: &str = include_str!("../../examples/hello.bf");
+        let script: String = compiler.compile_to(source, Target::JavaScriptCli).unwrap();
+        if let Some(output) = run_javascript("hello", &script) {
+            assert!(output.status.success());
+            assert_eq!(output.stdout, b"Hello World!\n");
+        }
+
+        let source: &str = include_str!("../../examples/mandelbrot.bf");
+        let script: String = compiler.compile_to(source, Target::JavaScriptCli).unwrap();
+        if let Some(output) = run_javascript("mandelbrot", &script) {
+            assert!(output.status.success());
+            assert_eq!(&output.stdout[..], &include_bytes!("../resources/tests/mandelbrot.out")[..]);
+        }
+
+        // Runtime errors are reported with their source position.
+        let compiler = Compiler::with_options(Options { checked: true, ..Options::default() });
+        let script: String = compiler.compile_to("+.\n<-", Target::JavaScriptCli).unwrap();
+        if let Some(output) = run_javascript("underflow", &script) {
+            assert_eq!(output.status.code(), Some(3));
+            assert_eq!(output.stdout, b"\x01");
+            assert_eq!(output.stderr, b"pointer underflow at line 2, position 2\n");
+        }
+    }
+
+    /// Write the `script` of the program `name` to a file, run it with `Node.js`, and return its result. Empty if
+    /// `Node.js` is not available.
+    fn run_javascript(name: &str, script: &str) -> Option<::std::process::Output> {
+        let file_name: String = format!("{name}.mjs", name = name);
+        run_tool(&file_name, script.as_bytes(), &[], &["node", &file_name])
+    }
+
+    #[test]
+    fn test_compile_wasm_text() {
+        let compiler = Compiler::new();
+        let source: &str = include_str!("../../examples/hello.bf");
+        let expected = String::from(include_str!("../resources/tests/hello.wat"));
+
+        let generated: String = compiler.compile_to(source, Target::WasmText).unwrap();
+        assert_eq!(generated, expected);
+
+        let generated: Vec<u8> = compiler.compile_to_bytes(source, Target::WasmText).unwrap();
+        assert_eq!(generated, expected.into_bytes());
+    }
+
+    #[test]
+    fn test_compile_wasm_binary_as_text() {
+        let result = Compiler::new().compile_to("+.", Target::WasmBinary);
+        match result {
+            Err(Error::IO(ref error)) => assert_eq!(error.kind(), io::ErrorKind::InvalidInput),
+            _ => panic!("Unexpected result: {:?}", result),
+        }
+
+        let result = Compiler::new().compile_to("+.]", Target::WasiBinary);
+        assert!(matches!(result, Err(Error::Syntax(_))));
+    }
+
+    #[test]
+    fn test_run_wasm_binary() {
+        let compiler = Compiler::new();
+
+        let source: &str = include_str!("../../examples/hello.bf");
+        let module: Vec<u8> = compiler.compile_to_bytes(source, Target::WasmBinary).unwrap();
+        assert_eq!(&module[..4], b"\0asm");
+        if let Some(output) = run_wasm("hello", &module) {
+            assert_eq!(output, b"Hello World!\n");
+        }
+
+        let source: &str = include_str!("../../examples/mandelbrot.bf");
+        let module: Vec<u8> = compiler.compile_to_bytes(source, Target::WasmBinary).unwrap();
+        if let Some(output) = run_wasm("mandelbrot", &module) {
+            assert_eq!(&output[..], &include_bytes!("../resources/tests/mandelbrot.out")[..]);
+        }
+    }
+
+    /// Instantiate the `WebAssembly` `module` of the program `name` with `Node.js`, run it, and return its output.
+    /// Empty if `Node.js` is not available.
+    fn run_wasm(name: &str, module: &[u8]) -> Option<Vec<u8>> {
+        let file_name: String = format!("{name}.wasm", name = name);
+        let host: &str = "\
+            const output = [];
+            const module = require('fs').readFileSync(process.argv[1]);
+            WebAssembly.instantiate(module, { env: {
+                read_byte: () => -1,
+                write_byte: (byte) => output.push(byte),
+                fail: () => process.exit(3),
+            }}).then(({ instance }) => {
+                instance.exports.run();
+                process.stdout.write(Buffer.from(output));
+            });
+        ";
+        let output: ::std::process::Output = run_tool(&file_name, module, &[], &["node", "-e", host, &file_name])?;
+        assert!(output.status.success());
+        Some(output.stdout)
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn test_build() {
+        let compiler = Compiler::new();
+
+        let source: &str = include_str!("../../examples/hello.bf");
+        let executable: Vec<u8> = compiler.build(source).unwrap();
+        let output = run_executable("hello", &executable);
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"Hello World!\n");
+
+        let source: &str = include_str!("../../examples/mandelbrot.bf");
+        let executable: Vec<u8> = compiler.build(source).unwrap();
+        let output = run_executable("mandelbrot", &executable);
+        assert!(output.status.success());
+        assert_eq!(&output.stdout[..], &include_bytes!("../resources/tests/mandelbrot.out")[..]);
+
+        // Runtime errors are reported with their source position.
+        let compiler = Compiler::with_options(Options { checked: true, ..Options::default() });
+        let executable: Vec<u8> = compiler.build("+.\n<-").unwrap();
+        let output = run_executable("underflow", &executable);
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stdout, b"\x01");
+        assert_eq!(output.stderr, b"pointer underflow at line 2, position 2\n");
+    }
+
+    #[test]
+    fn test_build_syntax_error() {
+        let compiler = Compiler::new();
+        assert!(match compiler.build("[") {
+            Err(Error::Syntax(error)) => error == SyntaxError::UnclosedLoop(MetaData { lineno: 1, position: 1 }),
+            _ => false,
+        });
+    }
+
+    /// Write the `executable` of the program `name` to a file, run it, and return its result.
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn run_executable(name: &str, executable: &[u8]) -> ::std::process::Output {
+        let file_name: String = format!("{name}.elf", name = name);
+        let program: String = format!("./{file_name}", file_name = file_name);
+        run_tool(&file_name, executable, &[&["chmod", "+x", &file_name]], &[&program])
+            .expect("The executable can be run.")
+    }
+
+    /// Programs leaving a tape of the given size and growth, which all backends must run like the interpreter,
+    /// wrapping around at the tape's ends or growing the tape.
+    static TAPE_EDGES: [(&str, usize, TapeGrowth); 16] = [
+        ("<+.", 30_000, TapeGrowth::Fixed),
+        ("-.>+[>+]", 30_000, TapeGrowth::Fixed),
+        ("+[<+++>-]<.", 30_000, TapeGrowth::Fixed),
+        ("->>>>>+<<<<<.>>>>>.", 4, TapeGrowth::Fixed),
+        (">+>+<[>]-.", 3, TapeGrowth::Fixed),
+        ("+>+[<]-.", 3, TapeGrowth::Fixed),
+        ("+>>+>>+[>>]-.", 5, TapeGrowth::Fixed),
+        ("+[<<<<<<+>>>>>>-]<<<<<<.", 4, TapeGrowth::Fixed),
+        ("<+.", 4, TapeGrowth::Right),
+        ("<+.", 4, TapeGrowth::Both),
+        ("+[<+>-]<.", 4, TapeGrowth::Right),
+        ("+<<<<<<<+>>>>>>>>>>>>+.<<<<<<.", 4, TapeGrowth::Right),
+        (">>>>>>+<<<<<<<<+.", 4, TapeGrowth::Right),
+        ("+[<]+.", 4, TapeGrowth::Right),
+        ("+>+[<]-<.", 3, TapeGrowth::Right),
+        ("+[<<<+>>>-]<<<.", 3, TapeGrowth::Both),
+    ];
+
+    #[test]
+    fn test_compile_deeply_nested_loops() {
+        let source: String = format!("+{open}-{close}.", open = "[".repeat(1_000), close = "]".repeat(1_000));
+        for &optimize in &[true, false] {
+            let compiler = Compiler::with_options(Options { optimize, ..Options::default() });
+            for &target in Target::all() {
+                assert!(compiler.compile_to_bytes(&source, target).is_ok(), "{target:?}", target = target);
+            }
+
+            #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+            assert!(compiler.build(&source).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_run_tape_edges() {
+        for (index, &(source, tape_size, tape_growth)) in TAPE_EDGES.iter().enumerate() {
+            for &optimize in &[true, false] {
+                let options = Options { optimize, tape_size, tape_growth, ..Options::default() };
+                let compiler = Compiler::with_options(options);
+                let name: String = format!("edge{index}{optimize}", index = index, optimize = optimize);
+                let expected: Vec<u8> = interpret(&compiler, source);
+
+                let code: String = compiler.compile(source).unwrap();
+                if let Some(output) = run_c(&name, &code) {
+                    assert_eq!(output, expected, "C for {source}", source = source);
+                }
+
+                let code: String = compiler.compile_to(source, Target::Rust).unwrap();
+                if let Some(output) = run_rust(&name, &code) {
+                    assert_eq!(output, expected, "Rust for {source}", source = source);
+                }
+
+                let code: String = compiler.compile_to(source, Target::LlvmIr).unwrap();
+                if let Some(output) = run_llvm(&name, &code) {
+                    assert_eq!(output, expected, "LLVM IR for {source}", source = source);
+                }
+
+                let script: String = compiler.compile_to(source, Target::JavaScriptCli).unwrap();
+                if let Some(output) = run_javascript(&name, &script) {
+                    assert!(output.status.success());
+                    assert_eq!(output.stdout, expected, "JavaScript for {source}", source = source);
+                }
+
+                let module: Vec<u8> = compiler.compile_to_bytes(source, Target::WasmBinary).unwrap();
+                if let Some(output) = run_wasm(&name, &module) {
+                    assert_eq!(output, expected, "WebAssembly for {source}", source = source);
+                }
+
+                #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+                {
+                    let code: String = compiler.compile_to(source, Target::X86_64Asm).unwrap();
+                    if let Some(output) = run_assembly(&name, &code) {
+                        assert_eq!(output, expected, "x86-64 assembly for {source}", source = source);
+                    }
+
+                    let executable: Vec<u8> = compiler.build(source).unwrap();
+                    let output = run_executable(&name, &executable);
+                    assert!(output.status.success());
+                    assert_eq!(output.stdout, expected, "ELF executable for {source}", source = source);
+                }
+            }
+        }
+    }
+
+    /// Run the `source` code on the interpreter with the options of the `compiler`, and return its output.
+    fn interpret(compiler: &Compiler, source: &str) -> Vec<u8> {
+        let program: Program = compiler.parse(source).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        Interpreter::with_options(*compiler.options()).run(&program, &mut io::empty(), &mut output).unwrap();
+        output
+    }
+
+    /// Compile the `C` `code` of the program `name` with `cc`, run it, and return its output. Empty if no `C` compiler
+    /// is available.
+    fn run_c(name: &str, code: &str) -> Option<Vec<u8>> {
+        let file_name: String = format!("{name}.c", name = name);
+        let build: [&[&str]; 1] = [&["cc", "-O2", &file_name, "-o", "program"]];
+        let output: ::std::process::Output = run_tool(&file_name, code.as_bytes(), &build, &["./program"])?;
+        assert!(output.status.success());
+        Some(output.stdout)
+    }
+
+    /// Compile the `Rust` `code` of the program `name` with `rustc`, run it, and return its output. Empty if `rustc` is
+    /// not available.
+    fn run_rust(name: &str, code: &str) -> Option<Vec<u8>> {
+        let file_name: String = format!("{name}.rs", name = name);
+        let build: [&[&str]; 1] = [&["rustc", "-O", &file_name, "-o", "program"]];
+        let output: ::std::process::Output = run_tool(&file_name, code.as_bytes(), &build, &["./program"])?;
+        assert!(output.status.success());
+        Some(output.stdout)
+    }
+
+    /// Compile the `LLVM` IR `code` of the program `name` with `llc` and `cc`, run it, and return its output. Empty if
+    /// no `LLVM` compiler is available. Older versions of `llc` need to be told that pointers are opaque.
+    fn run_llvm(name: &str, code: &str) -> Option<Vec<u8>> {
+        use std::process;
+
+        // Newer versions do not know the option, and reject it.
+        let opaque: bool = process::Command::new("llc").args(["-opaque-pointers", "-version"]).output().ok()?
+            .status
+            .success();
+        let flags: &[&str] = if opaque { &["-opaque-pointers"] } else { &[] };
+
+        let file_name: String = format!("{name}.ll", name = name);
+        let compile: Vec<&str> = ["llc", "-O2", "-relocation-model=pic", "-filetype=obj"].iter()
+            .chain(flags)
+            .chain(&[file_name.as_str(), "-o", "program.o"])
+            .cloned()
+            .collect();
+        let build: [&[&str]; 2] = [&compile, &["cc", "program.o", "-o", "program"]];
+        let output: process::Output = run_tool(&file_name, code.as_bytes(), &build, &["./program"])?;
+        assert!(output.status.success());
+        Some(output.stdout)
+    }
+
+    /// Write the `contents` to the file `file_name` in a new temporary directory, run the `build` commands and then the
+    /// `run` command in that directory, and return the result of the latter. Programs with a relative path are looked
+    /// up in the directory, which is removed afterwards. Empty if one of the tools is not available.
+    fn run_tool(file_name: &str, contents: &[u8], build: &[&[&str]], run: &[&str]) -> Option<::std::process::Output> {
+        use std::env;
+        use std::fs;
+        use std::path::PathBuf;
+        use std::process;
+
+        let directory: PathBuf = env::temp_dir().join(format!("rustfuck-{id}", id = process::id())).join(file_name);
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join(file_name), contents).unwrap();
+
+        let command = |arguments: &[&str]| {
+            let program: PathBuf = match arguments[0] {
+                program if program.starts_with("./") => directory.join(program),
+                program => PathBuf::from(program),
+            };
+            let mut command = process::Command::new(program);
+            let _ = command.args(&arguments[1..]).current_dir(&directory).stdin(process::Stdio::null());
+            command
+        };
+        let execute = || {
+            for arguments in build {
+                let built: bool = command(arguments).status().ok()?.success();
+                assert!(built, "{arguments:?} failed", arguments = arguments);
+            }
+            command(run).output().ok()
+        };
+
+        let output: Option<process::Output> = execute();
+        let _ = fs::remove_dir_all(&directory);
+        output
+    }
+
+    #[test]
+    fn test_target_extension() {
+        assert_eq!(Target::C.extension(), "c");
+        assert_eq!(Target::Rust.extension(), "rs");
+        assert_eq!(Target::RustLibrary.extension(), "rs");
+        assert_eq!(Target::LlvmIr.extension(), "ll");
+        assert_eq!(Target::X86_64Asm.extension(), "s");
+        assert_eq!(Target::JavaScript.extension(), "mjs");
+        assert_eq!(Target::JavaScriptCli.extension(), "mjs");
+        assert_eq!(Target::WasmText.extension(), "wat");
+        assert_eq!(Target::WasmBinary.extension(), "wasm");
+        assert_eq!(Target::WasiText.extension(), "wat");
+        assert_eq!(Target::WasiBinary.extension(), "wasm");
+    }
+
+    #[test]
+    fn test_target_name() {
+        let names: Vec<&str> = Target::all().iter().map(|target| target.name()).collect();
+        assert_eq!(names, [
+            "c", "rust", "rust-library", "llvm-ir", "x86_64-asm", "js", "js-cli", "wat", "wasm", "wasi-wat",
+            "wasi-wasm",
+        ]);
+    }
+
+    #[test]
+    fn test_target_is_binary() {
+        assert!(!Target::C.is_binary());
+        assert!(!Target::X86_64Asm.is_binary());
+        assert!(!Target::JavaScript.is_binary());
+        assert!(!Target::WasmText.is_binary());
+        assert!(Target::WasmBinary.is_binary());
+        assert!(!Target::WasiText.is_binary());
+        assert!(Target::WasiBinary.is_binary());
+    }
+
+    #[test]
+    fn test_compile_syntax_error() {
+        let compiler = Compiler::new();
+        assert!(match compiler.compile("+]") {
+            Err(Error::Syntax(error)) => error == SyntaxError::UnopenedLoop(MetaData { lineno: 1, position: 2 }),
+            _ => false,
+        });
+    }
 }
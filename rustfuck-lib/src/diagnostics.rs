@@ -0,0 +1,427 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Rendering of errors against the program source, in the style of `rustc`'s diagnostics.
+
+use DialectError;
+use Error;
+use RuntimeError;
+use SyntaxError;
+use lexer::MetaData;
+
+/// The escape sequence for bold red text.
+const BOLD_RED: &str = "\x1b[1;31m";
+
+/// The escape sequence for bold green text.
+const BOLD_GREEN: &str = "\x1b[1;32m";
+
+/// The escape sequence for bold blue text.
+const BOLD_BLUE: &str = "\x1b[1;34m";
+
+/// The escape sequence for bold text.
+const BOLD: &str = "\x1b[1m";
+
+/// The escape sequence resetting all text attributes.
+const RESET: &str = "\x1b[0m";
+
+/// The number of spaces a tab character is expanded to when showing a source line.
+const TAB_WIDTH: usize = 4;
+
+/// A renderer for errors, showing the affected parts of the program source.
+///
+/// # Examples
+///
+/// ```
+/// use rustfuck_lib::Emitter;
+/// use rustfuck_lib::Error;
+/// use rustfuck_lib::MetaData;
+/// use rustfuck_lib::SyntaxError;
+///
+/// let source: &str = "+-]";
+/// let error = Error::Syntax(SyntaxError::UnopenedLoop(MetaData { lineno: 1, position: 3 }));
+///
+/// let emitter = Emitter::new("program.bf", source, false);
+/// assert_eq!(emitter.render(&error), "\
+/// error: unexpected end of loop
+///  --> program.bf:1:3
+///   |
+/// 1 | +-]
+///   |   ^ no loop to end here
+/// ");
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Emitter<'a> {
+
+    /// The name of the file containing the program.
+    file_name: &'a str,
+
+    /// The program source code.
+    source: &'a str,
+
+    /// Highlight the output with ANSI escape sequences.
+    colored: bool,
+}
+
+/// A message pointing at a position in the program source.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Label {
+
+    /// The position in the program source.
+    position: MetaData,
+
+    /// The text shown next to the marker. May be empty.
+    text: String,
+}
+
+/// An error message with the positions in the program source to show.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Diagnostic {
+
+    /// The main error message.
+    message: String,
+
+    /// The position at which the error occurs, if it is known.
+    primary: Option<Label>,
+
+    /// Additional messages, each with a headline and a position.
+    notes: Vec<(String, Label)>,
+}
+
+impl<'a> Emitter<'a> {
+    /// Initialize a new emitter for the program `source` stored in the file `file_name`. If `colored` is set, the
+    /// rendered messages are highlighted using ANSI escape sequences.
+    pub fn new(file_name: &'a str, source: &'a str, colored: bool) -> Emitter<'a> {
+        Emitter {
+            file_name,
+            source,
+            colored,
+        }
+    }
+
+    /// Render the given `error`, including the affected lines of the source if the `error` has a position.
+    pub fn render(&self, error: &Error) -> String {
+        let diagnostic: Diagnostic = self.diagnose(error);
+
+        // Line numbers of all labels must fit into the gutter.
+        let gutter_width: usize = diagnostic.primary.iter()
+            .chain(diagnostic.notes.iter().map(|(_, label)| label))
+            .map(|label| label.position.lineno.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        let mut output: String = self.headline("error", BOLD_RED, &diagnostic.message);
+        if let Some(ref label) = diagnostic.primary {
+            output.push_str(&self.snippet(label, gutter_width, BOLD_RED));
+        }
+
+        for (message, label) in &diagnostic.notes {
+            output.push_str(&self.headline("note", BOLD_GREEN, message));
+            output.push_str(&self.snippet(label, gutter_width, BOLD_BLUE));
+        }
+
+        output
+    }
+
+    /// Describe the given `error` in terms of positions in the source.
+    fn diagnose(&self, error: &Error) -> Diagnostic {
+        match *error {
+            Error::IO(ref error) => Diagnostic {
+                message: error.to_string(),
+                primary: None,
+                notes: Vec::new(),
+            },
+            Error::Syntax(SyntaxError::UnclosedLoop(position)) => Diagnostic {
+                message: String::from("unclosed loop"),
+                primary: Some(Label { position: self.end_of_source(), text: String::from("expected `]`") }),
+                notes: vec![
+                    (String::from("loop opened here"), Label { position, text: String::new() }),
+                ],
+            },
+            Error::Syntax(SyntaxError::UnopenedLoop(position)) => Diagnostic {
+                message: String::from("unexpected end of loop"),
+                primary: Some(Label { position, text: String::from("no loop to end here") }),
+                notes: Vec::new(),
+            },
+            Error::Syntax(SyntaxError::IncompleteCommand(position)) => Diagnostic {
+                message: String::from("incomplete command"),
+                primary: Some(Label {
+                    position: self.end_of_source(),
+                    text: String::from("expected the rest of the command"),
+                }),
+                notes: vec![
+                    (String::from("command begun here"), Label { position, text: String::new() }),
+                ],
+            },
+            Error::Syntax(SyntaxError::UnknownCommand(position)) => Diagnostic {
+                message: String::from("unknown command"),
+                primary: Some(Label { position, text: String::from("not a command of the language") }),
+                notes: Vec::new(),
+            },
+            Error::Dialect(DialectError::MalformedEntry(position)) => Diagnostic {
+                message: String::from("malformed entry"),
+                primary: Some(Label { position, text: String::from("expected `key = value`") }),
+                notes: Vec::new(),
+            },
+            Error::Dialect(DialectError::UnknownKey(position)) => Diagnostic {
+                message: String::from("unknown key"),
+                primary: Some(Label { position, text: String::from("not a command, `name`, or `extensions`") }),
+                notes: Vec::new(),
+            },
+            Error::Dialect(DialectError::DuplicateKey(position)) => Diagnostic {
+                message: String::from("duplicate key"),
+                primary: Some(Label { position, text: String::from("already defined before") }),
+                notes: Vec::new(),
+            },
+            Error::Dialect(ref error) => Diagnostic {
+                message: error.to_string(),
+                primary: None,
+                notes: Vec::new(),
+            },
+            Error::Runtime(RuntimeError::CellOverflow(position)) => Diagnostic {
+                message: String::from("cell overflow"),
+                primary: Some(Label { position, text: String::from("value out of the cell's range") }),
+                notes: Vec::new(),
+            },
+            Error::Runtime(RuntimeError::PointerUnderflow(position)) => Diagnostic {
+                message: String::from("pointer underflow"),
+                primary: Some(Label { position, text: String::from("cell before the start of the tape") }),
+                notes: Vec::new(),
+            },
+            Error::Runtime(RuntimeError::PointerOverflow(position)) => Diagnostic {
+                message: String::from("pointer overflow"),
+                primary: Some(Label { position, text: String::from("cell past the end of the tape") }),
+                notes: Vec::new(),
+            },
+        }
+    }
+
+    /// Render a headline of the form `<kind>: <message>`.
+    fn headline(&self, kind: &str, color: &'static str, message: &str) -> String {
+        format!("{color}{kind}{reset}{bold}: {message}{reset}\n",
+                kind = kind, message = message,
+                color = self.paint(color), bold = self.paint(BOLD), reset = self.paint(RESET))
+    }
+
+    /// Render the source line of the `label`'s position, marking the position with a `^`.
+    fn snippet(&self, label: &Label, gutter_width: usize, color: &'static str) -> String {
+        let position: MetaData = label.position;
+        let line: &str = self.source.lines().nth(position.lineno.saturating_sub(1)).unwrap_or("");
+
+        // Expand tabs so that the marker lines up with the shown source line.
+        let expand = |character: char| -> String {
+            if character == '\t' {
+                " ".repeat(TAB_WIDTH)
+            } else {
+                character.to_string()
+            }
+        };
+        let shown_line: String = line.chars().map(expand).collect();
+        let indentation: usize = line.chars()
+            .take(position.position.saturating_sub(1))
+            .map(|character| expand(character).len())
+            .sum();
+
+        let padding: String = " ".repeat(gutter_width);
+        let marker: String = if label.text.is_empty() {
+            String::from("^")
+        } else {
+            format!("^ {text}", text = label.text)
+        };
+
+        format!("{padding}{blue}-->{reset} {file}:{lineno}:{column}\n\
+                 {padding} {blue}|{reset}\n\
+                 {blue}{lineno:>width$} |{reset} {line}\n\
+                 {padding} {blue}|{reset} {indentation}{color}{marker}{reset}\n",
+                padding = padding, file = self.file_name, lineno = position.lineno, column = position.position,
+                width = gutter_width, line = shown_line, indentation = " ".repeat(indentation), marker = marker,
+                color = self.paint(color), blue = self.paint(BOLD_BLUE), reset = self.paint(RESET))
+    }
+
+    /// The position directly after the last character of the source.
+    fn end_of_source(&self) -> MetaData {
+        let lineno: usize = self.source.lines().count().max(1);
+        let position: usize = self.source.lines().last().map_or(0, |line| line.chars().count()) + 1;
+        MetaData { lineno, position }
+    }
+
+    /// Return the escape sequence `code` if the output is colored, an empty string otherwise.
+    fn paint(&self, code: &'static str) -> &'static str {
+        if self.colored {
+            code
+        } else {
+            ""
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Error as IOError;
+    use std::io::ErrorKind;
+
+    use DialectError;
+    use Emitter;
+    use Error;
+    use MetaData;
+    use RuntimeError;
+    use SyntaxError;
+
+    #[test]
+    fn test_render_io() {
+        let error = Error::IO(IOError::new(ErrorKind::NotFound, "file not found"));
+        let emitter = Emitter::new("hello.bf", "", false);
+
+        assert_eq!(emitter.render(&error), "error: file not found\n");
+    }
+
+    #[test]
+    fn test_render_unopened_loop() {
+        let source: &str = "+[-]\n\t-]\n";
+        let error = Error::Syntax(SyntaxError::UnopenedLoop(MetaData { lineno: 2, position: 3 }));
+        let emitter = Emitter::new("hello.bf", source, false);
+
+        let expected: &str = "\
+error: unexpected end of loop
+ --> hello.bf:2:3
+  |
+2 |     -]
+  |      ^ no loop to end here
+";
+        assert_eq!(emitter.render(&error), expected);
+    }
+
+    #[test]
+    fn test_render_unclosed_loop() {
+        let source: &str = "+[\n-\n-\n-\n-\n-\n-\n-\n-\n.";
+        let error = Error::Syntax(SyntaxError::UnclosedLoop(MetaData { lineno: 1, position: 2 }));
+        let emitter = Emitter::new("hello.bf", source, false);
+
+        let expected: &str = "\
+error: unclosed loop
+  --> hello.bf:10:2
+   |
+10 | .
+   |  ^ expected `]`
+note: loop opened here
+  --> hello.bf:1:2
+   |
+ 1 | +[
+   |  ^
+";
+        assert_eq!(emitter.render(&error), expected);
+    }
+
+    #[test]
+    fn test_render_incomplete_command() {
+        let source: &str = "Ook. Ook.\nOook! Ook!";
+        let error = Error::Syntax(SyntaxError::IncompleteCommand(MetaData { lineno: 2, position: 7 }));
+        let emitter = Emitter::new("hello.ook", source, false);
+
+        let expected: &str = "\
+error: incomplete command
+ --> hello.ook:2:11
+  |
+2 | Oook! Ook!
+  |           ^ expected the rest of the command
+note: command begun here
+ --> hello.ook:2:7
+  |
+2 | Oook! Ook!
+  |       ^
+";
+        assert_eq!(emitter.render(&error), expected);
+    }
+
+    #[test]
+    fn test_render_unknown_command() {
+        let source: &str = "Ook. Ook? Ook? Ook?";
+        let error = Error::Syntax(SyntaxError::UnknownCommand(MetaData { lineno: 1, position: 11 }));
+        let emitter = Emitter::new("hello.ook", source, false);
+
+        let expected: &str = "\
+error: unknown command
+ --> hello.ook:1:11
+  |
+1 | Ook. Ook? Ook? Ook?
+  |           ^ not a command of the language
+";
+        assert_eq!(emitter.render(&error), expected);
+    }
+
+    #[test]
+    fn test_render_dialect() {
+        let source: &str = "add = \"+\"\nsub = -";
+        let error = Error::Dialect(DialectError::MalformedEntry(MetaData { lineno: 2, position: 7 }));
+        let emitter = Emitter::new("dialect.toml", source, false);
+
+        let expected: &str = "\
+error: malformed entry
+ --> dialect.toml:2:7
+  |
+2 | sub = -
+  |       ^ expected `key = value`
+";
+        assert_eq!(emitter.render(&error), expected);
+
+        let error = Error::Dialect(DialectError::MissingCommand("right"));
+        assert_eq!(emitter.render(&error), "error: missing command `right`\n");
+    }
+
+    #[test]
+    fn test_render_cell_overflow() {
+        let source: &str = "-[--\n+++]";
+        let error = Error::Runtime(RuntimeError::CellOverflow(MetaData { lineno: 2, position: 1 }));
+        let emitter = Emitter::new("hello.bf", source, false);
+
+        let expected: &str = "\
+error: cell overflow
+ --> hello.bf:2:1
+  |
+2 | +++]
+  | ^ value out of the cell's range
+";
+        assert_eq!(emitter.render(&error), expected);
+    }
+
+    #[test]
+    fn test_render_pointer_underflow() {
+        let source: &str = "+[<]";
+        let error = Error::Runtime(RuntimeError::PointerUnderflow(MetaData { lineno: 1, position: 2 }));
+        let emitter = Emitter::new("hello.bf", source, false);
+
+        let expected: &str = "\
+error: pointer underflow
+ --> hello.bf:1:2
+  |
+1 | +[<]
+  |  ^ cell before the start of the tape
+";
+        assert_eq!(emitter.render(&error), expected);
+    }
+
+    #[test]
+    fn test_render_colored() {
+        let error = Error::Syntax(SyntaxError::UnopenedLoop(MetaData { lineno: 1, position: 1 }));
+        let emitter = Emitter::new("hello.bf", "]", true);
+
+        let expected: &str = "\
+\x1b[1;31merror\x1b[0m\x1b[1m: unexpected end of loop\x1b[0m
+ \x1b[1;34m-->\x1b[0m hello.bf:1:1
+  \x1b[1;34m|\x1b[0m
+\x1b[1;34m1 |\x1b[0m ]
+  \x1b[1;34m|\x1b[0m \x1b[1;31m^ no loop to end here\x1b[0m
+";
+        assert_eq!(emitter.render(&error), expected);
+    }
+
+    #[test]
+    fn test_end_of_source() {
+        assert_eq!(Emitter::new("", "", false).end_of_source(), MetaData { lineno: 1, position: 1 });
+        assert_eq!(Emitter::new("", "+[\n-", false).end_of_source(), MetaData { lineno: 2, position: 2 });
+        assert_eq!(Emitter::new("", "+[\n-\n", false).end_of_source(), MetaData { lineno: 2, position: 2 });
+    }
+}
@@ -0,0 +1,1315 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementations of a generator for creating static `x86-64` Linux executables in the `ELF` format from intermediate
+//! compile results.
+//!
+//! The executables consist of a read-only segment with the headers, the machine code, and constant data, followed by
+//! a zero-initialized segment with the buffers and, for tapes with a fixed size, the tape. The machine code is the
+//! same as the one created by the assembly generator; it does not depend on any library, but uses system calls
+//! directly.
+
+use std::convert::TryFrom;
+use std::mem;
+
+use CellType;
+use EofBehavior;
+use Options;
+use Overflow;
+use TapeGrowth;
+use ir::Instruction;
+use ir::Operation;
+use ir::Program;
+use ir::Step;
+use ir::accessed_range;
+use ir::reserved_range;
+use ir::walk;
+use lexer::MetaData;
+use x86_64::Arithmetic;
+use x86_64::Assembler;
+use x86_64::Condition;
+use x86_64::Label;
+use x86_64::Memory;
+use x86_64::Register;
+use x86_64::Size;
+
+/// The address at which the executable is loaded.
+const BASE_ADDRESS: usize = 0x40_0000;
+
+/// The size of the `ELF` header in bytes.
+const ELF_HEADER_SIZE: usize = 64;
+
+/// The size of a program header in bytes.
+const PROGRAM_HEADER_SIZE: usize = 56;
+
+/// The size of all headers in bytes, after which the machine code starts.
+const HEADERS_SIZE: usize = ELF_HEADER_SIZE + 2 * PROGRAM_HEADER_SIZE;
+
+/// The alignment of the segments in memory.
+const PAGE_SIZE: usize = 0x1000;
+
+/// The size of the buffer collecting the output in bytes.
+const OUTPUT_SIZE: usize = 4096;
+
+/// The size of the buffer for error messages in bytes.
+const ERROR_SIZE: usize = 128;
+
+/// The functions, variables, and constants in the executable.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Symbols {
+
+    /// The function writing the output buffer to the standard output.
+    flush: Label,
+
+    /// The function appending the byte in `%dil` to the output buffer.
+    write: Label,
+
+    /// The function returning the next character from the standard input in `%rax`.
+    read: Label,
+
+    /// The function adding the 128 bit number in `%rdx:%rax` to the extended cell value in `%rcx`.
+    add: Label,
+
+    /// The function moving the pointer by `%rsi` cells and making sure that the cells from `%rdx` to `%rcx` cells away
+    /// from the new current cell are on the tape.
+    reserve: Label,
+
+    /// The function computing the index of the cell `%r13` cells before the current cell in `%r13`, wrapping around
+    /// at the tape's start.
+    wrap_left: Label,
+
+    /// The function mapping the initial tape.
+    allocate: Label,
+
+    /// The function mapping `%rsi` bytes of zeroed memory.
+    map: Label,
+
+    /// The function aborting the program with the message `%rsi` for the source position `%edi`.
+    fail: Label,
+
+    /// The function appending the string at `%rsi` to the buffer at `%rdi`.
+    append_string: Label,
+
+    /// The function appending the decimal number `%eax` to the buffer at `%rdi`.
+    append_number: Label,
+
+    /// The buffer collecting the output.
+    output: Label,
+
+    /// The number of bytes in the output buffer.
+    output_length: Label,
+
+    /// The buffer for the character read from the input.
+    input: Label,
+
+    /// The number of cells on a growing tape.
+    tape_size: Label,
+
+    /// The buffer for error messages.
+    error: Label,
+
+    /// The tape with a fixed size.
+    tape: Label,
+
+    /// The table of source positions reported in error messages.
+    positions: Label,
+
+    /// The message for cell overflows.
+    cell_overflow: Label,
+
+    /// The message for accesses before the start of the tape.
+    pointer_underflow: Label,
+
+    /// The message for accesses past the end of the tape.
+    pointer_overflow: Label,
+
+    /// The text before the line of a source position.
+    at_line: Label,
+
+    /// The text before the position within the line of a source position.
+    at_position: Label,
+
+    /// The message for failed memory allocations.
+    out_of_memory: Label,
+}
+
+impl Symbols {
+    /// Create the labels of all symbols in the `assembler`.
+    fn new(assembler: &mut Assembler) -> Symbols {
+        Symbols {
+            flush: assembler.label(),
+            write: assembler.label(),
+            read: assembler.label(),
+            add: assembler.label(),
+            reserve: assembler.label(),
+            wrap_left: assembler.label(),
+            allocate: assembler.label(),
+            map: assembler.label(),
+            fail: assembler.label(),
+            append_string: assembler.label(),
+            append_number: assembler.label(),
+            output: assembler.label(),
+            output_length: assembler.label(),
+            input: assembler.label(),
+            tape_size: assembler.label(),
+            error: assembler.label(),
+            tape: assembler.label(),
+            positions: assembler.label(),
+            cell_overflow: assembler.label(),
+            pointer_underflow: assembler.label(),
+            pointer_overflow: assembler.label(),
+            at_line: assembler.label(),
+            at_position: assembler.label(),
+            out_of_memory: assembler.label(),
+        }
+    }
+}
+
+/// The runtime functions called by the generated code.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Runtime {
+
+    /// Cells are changed by calling `add`.
+    add: bool,
+
+    /// Characters are read by calling `read`.
+    read: bool,
+
+    /// The pointer is moved by calling `reserve`.
+    reserve: bool,
+
+    /// The cells before the current cell are accessed by calling `wrap_left`.
+    wrap_left: bool,
+}
+
+/// A generator for creating static `x86-64` Linux executables in the `ELF` format from intermediate compile results.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ElfGenerator {
+
+    /// The options controlling the generated code.
+    options: Options,
+
+    /// The assembler encoding the generated code.
+    assembler: Assembler,
+
+    /// The labels of the functions, variables, and constants in the executable.
+    symbols: Symbols,
+
+    /// The source positions reported by the generated code, indexed by the IDs passed to the runtime functions.
+    positions: Vec<MetaData>,
+
+    /// The runtime functions needed by the generated code.
+    runtime: Runtime,
+}
+
+impl Default for ElfGenerator {
+    fn default() -> ElfGenerator {
+        ElfGenerator::new()
+    }
+}
+
+impl ElfGenerator {
+    /// Initialize a new generator with the default options.
+    pub fn new() -> ElfGenerator {
+        ElfGenerator::with_options(Options::default())
+    }
+
+    /// Initialize a new generator with the given `options`.
+    pub fn with_options(options: Options) -> ElfGenerator {
+        let mut assembler = Assembler::new();
+        let symbols = Symbols::new(&mut assembler);
+        ElfGenerator {
+            options,
+            assembler,
+            symbols,
+            positions: Vec::new(),
+            runtime: Runtime::default(),
+        }
+    }
+
+    /// Generate the executable from the given `program`.
+    pub fn generate(&mut self, program: &Program) -> Vec<u8> {
+        self.assembler = Assembler::new();
+        self.symbols = Symbols::new(&mut self.assembler);
+        self.positions.clear();
+        self.runtime = Runtime::default();
+
+        // Load the tape's address into `%rbx` and start at its first cell.
+        match self.options.tape_growth {
+            TapeGrowth::Fixed => self.assembler.mov_address(self.symbols.tape, Register::Rbx),
+            TapeGrowth::Right | TapeGrowth::Both => self.assembler.call(self.symbols.allocate),
+        }
+        self.assembler.arithmetic(Arithmetic::Xor, Size::Double, Register::R12, Register::R12);
+
+        self.generate_block(&program.instructions);
+
+        // Flush the output and exit successfully.
+        self.assembler.call(self.symbols.flush);
+        self.assembler.mov(Size::Double, 60, Register::Rax);
+        self.assembler.arithmetic(Arithmetic::Xor, Size::Double, Register::Rdi, Register::Rdi);
+        self.assembler.syscall();
+
+        self.generate_runtime();
+        self.generate_constants();
+
+        // The variables follow the code on the next page.
+        let code_size: usize = self.assembler.len();
+        let variables: usize = align(HEADERS_SIZE + code_size, PAGE_SIZE);
+        let mut offset: usize = variables - HEADERS_SIZE;
+        let layout: [(Label, usize); 6] = [
+            (self.symbols.output, OUTPUT_SIZE),
+            (self.symbols.output_length, 8),
+            (self.symbols.input, 8),
+            (self.symbols.tape_size, 8),
+            (self.symbols.error, ERROR_SIZE),
+            (self.symbols.tape, 0),
+        ];
+        for &(label, size) in layout.iter() {
+            self.assembler.bind_to(label, offset);
+            offset += size;
+        }
+        let mut variables_size: usize = offset + HEADERS_SIZE - variables;
+        if self.options.tape_growth == TapeGrowth::Fixed {
+            variables_size = variables_size.saturating_add(self.tape_size().saturating_mul(self.cell_size().bytes()));
+        }
+
+        let assembler: Assembler = mem::replace(&mut self.assembler, Assembler::new());
+        let code: Vec<u8> = assembler.finish(to_u64(BASE_ADDRESS + HEADERS_SIZE));
+        let mut executable: Vec<u8> = headers(code.len(), variables, variables_size);
+        executable.extend_from_slice(&code);
+        executable
+    }
+
+    /// Generate the code for the given list of `instructions` and the bodies of their loops.
+    fn generate_block(&mut self, instructions: &[Instruction]) {
+        // The current cell is on the tape when entering a block, but the other accessed cells might not be.
+        self.reserve_segment(instructions);
+
+        // The offset of the cell on which the currently generated multiplications depend together with the label
+        // after their condition, and if the current cell is still known not to be zero.
+        let mut guard: Option<(isize, Label)> = None;
+        let mut nonzero: bool = false;
+
+        // The labels at the start and after the end of the loops currently generated, innermost last.
+        let mut loops: Vec<(Label, Label)> = Vec::new();
+        for step in walk(instructions) {
+            let (instruction, following): (&Instruction, &[Instruction]) = match step {
+                Step::Instruction(instruction, following) => (instruction, following),
+                Step::LoopEnd(_, following) => {
+                    // Close the loop, which only ends on a cell on the tape, but the cells accessed next might not be.
+                    self.close_guard(&mut guard);
+                    let (start, end): (Label, Label) = loops.pop().expect("The loop has been started.");
+                    self.assembler.jmp(start);
+                    self.assembler.bind(end);
+                    nonzero = false;
+                    self.reserve_segment(following);
+                    continue;
+                },
+            };
+
+            let source: Option<isize> = match instruction.operation {
+                Operation::MulAdd { .. } => Some(instruction.offset),
+                _ => None,
+            };
+            nonzero = nonzero && source == Some(0);
+            if guard.is_some() && guard.map(|(offset, _)| offset) != source {
+                self.close_guard(&mut guard);
+            }
+
+            // Checked accesses are reported at the accessing instruction. Multiplications depending on the same cell
+            // only need a single check.
+            let offset: isize = instruction.offset;
+            if self.options.is_checked() && guard.is_none() && offset != 0 {
+                let position: Option<usize> = self.position(instruction, true);
+                self.reserve(0, offset.min(0), offset.max(0), position);
+            }
+
+            let size: Size = self.cell_size();
+            let cell: Memory = match instruction.operation {
+                // Loops and scans access their cell anew in each iteration.
+                Operation::Loop(_) | Operation::Scan { .. } => self.cell(0),
+                _ => self.cell(instruction.offset),
+            };
+            match instruction.operation {
+                Operation::Add(delta) if self.options.overflow == Overflow::Wrap => {
+                    // Change the value at the cell, wrapping around at the bounds of the cell type.
+                    self.assembler.arithmetic(Arithmetic::Add, size, wrapped(delta, self.options.cell_type), cell);
+                },
+                Operation::Add(delta) => {
+                    // Change the value at the cell using the runtime function.
+                    self.assembler.mov(Size::Quad, delta, Register::Rax);
+                    self.assembler.cqto();
+                    self.add(instruction, cell);
+                },
+                Operation::Move(offset) if self.options.is_reserving() => {
+                    // Go to another cell, making sure that it and the cells accessed next are on the tape.
+                    let (low, high): (isize, isize) = if self.options.is_checked() {
+                        (0, 0)
+                    } else {
+                        accessed_range(following, false)
+                    };
+                    let position: Option<usize> = self.position(instruction, self.options.is_checked());
+                    self.reserve(offset, low, high, position);
+                },
+                Operation::Move(offset) => {
+                    // Go to another cell.
+                    self.step(offset);
+                },
+                Operation::Read => {
+                    // Read a single character into the cell, keeping the cell's value at the end of the input if
+                    // required.
+                    self.runtime.read = true;
+                    let end: Label = self.assembler.label();
+                    self.assembler.call(self.symbols.read);
+                    if self.options.eof == EofBehavior::Unchanged {
+                        self.assembler.test(Size::Quad, Register::Rax, Register::Rax);
+                        self.assembler.jcc(Condition::Sign, end);
+                    }
+                    self.assembler.mov(size, Register::Rax, cell);
+                    self.assembler.bind(end);
+                },
+                Operation::Write => {
+                    // Print the character of the cell, i.e. its lowest byte.
+                    self.assembler.mov(Size::Byte, cell, Register::Rdi);
+                    self.assembler.call(self.symbols.write);
+                },
+                Operation::Loop(ref body) => {
+                    // Repeat the body as long as the current cell is not zero.
+                    let start: Label = self.assembler.label();
+                    let end: Label = self.assembler.label();
+                    self.assembler.bind(start);
+                    let cell: Memory = self.cell(instruction.offset);
+                    self.assembler.arithmetic(Arithmetic::Cmp, size, 0, cell);
+                    self.assembler.jcc(Condition::Equal, end);
+                    self.reserve_segment(body);
+                    loops.push((start, end));
+                    nonzero = true;
+                },
+                Operation::Clear => {
+                    // Set the cell to zero.
+                    self.assembler.mov(size, 0, cell);
+                },
+                Operation::MulAdd { offset, factor } if self.options.overflow == Overflow::Wrap => {
+                    // Add a multiple of the cell to another cell, wrapping around at the bounds of the cell type.
+                    self.multiply(instruction, offset, cell, &mut guard, nonzero);
+                    let cell: Memory = self.reload(instruction.offset, cell);
+                    self.load(cell, Register::Rax);
+                    if factor != 1 && factor != -1 {
+                        let wide: Size = if size == Size::Quad { Size::Quad } else { Size::Double };
+                        self.assembler.imul(wide, factor, Register::Rax, Register::Rax);
+                    }
+                    let operation: Arithmetic = if factor == -1 { Arithmetic::Sub } else { Arithmetic::Add };
+                    let target: Memory = self.cell(instruction.offset.wrapping_add(offset));
+                    self.assembler.arithmetic(operation, size, Register::Rax, target);
+                },
+                Operation::MulAdd { offset, factor } => {
+                    // Add a multiple of the cell to another cell using the runtime function.
+                    self.multiply(instruction, offset, cell, &mut guard, nonzero);
+                    let cell: Memory = self.reload(instruction.offset, cell);
+                    self.load(cell, Register::Rax);
+                    self.assembler.mov(Size::Quad, factor, Register::Rcx);
+                    self.assembler.imul_wide(Register::Rcx);
+                    let target: Memory = self.cell(instruction.offset.wrapping_add(offset));
+                    self.add(instruction, target);
+                },
+                Operation::Scan { stride: 1 } if self.can_search_memory() => {
+                    // Search the tape to the right for the next zero cell, continuing at the tape's start, and looping
+                    // forever if there is no zero cell at all.
+                    let forever: Label = self.assembler.label();
+                    let end: Label = self.assembler.label();
+                    self.assembler.lea(Memory::indexed(Register::Rbx, Register::R12, Size::Byte, 0), Register::Rdi);
+                    self.assembler.mov(Size::Quad, to_i64(self.tape_size()), Register::Rcx);
+                    self.assembler.arithmetic(Arithmetic::Sub, Size::Quad, Register::R12, Register::Rcx);
+                    self.assembler.arithmetic(Arithmetic::Xor, Size::Double, Register::Rax, Register::Rax);
+                    self.assembler.repne_scasb();
+                    self.assembler.jcc(Condition::Equal, end);
+                    self.assembler.mov(Size::Quad, Register::Rbx, Register::Rdi);
+                    self.assembler.mov(Size::Quad, Register::R12, Register::Rcx);
+                    self.assembler.repne_scasb();
+                    self.assembler.jcc(Condition::Equal, end);
+                    self.assembler.bind(forever);
+                    self.assembler.jmp(forever);
+                    self.assembler.bind(end);
+                    self.assembler.lea(Memory::at(Register::Rdi, -1), Register::R12);
+                    self.assembler.arithmetic(Arithmetic::Sub, Size::Quad, Register::Rbx, Register::R12);
+                },
+                Operation::Scan { stride: -1 } if self.can_search_memory() => {
+                    // Search the tape to the left for the next zero cell, continuing at the tape's end, and looping
+                    // forever if there is no zero cell at all.
+                    let forever: Label = self.assembler.label();
+                    let end: Label = self.assembler.label();
+                    self.assembler.lea(Memory::indexed(Register::Rbx, Register::R12, Size::Byte, 0), Register::Rdi);
+                    self.assembler.lea(Memory::at(Register::R12, 1), Register::Rcx);
+                    self.assembler.arithmetic(Arithmetic::Xor, Size::Double, Register::Rax, Register::Rax);
+                    self.assembler.std();
+                    self.assembler.repne_scasb();
+                    self.assembler.jcc(Condition::Equal, end);
+                    self.assembler.mov(Size::Quad, to_i64(self.tape_size() - 1), Register::Rcx);
+                    self.assembler.lea(Memory::indexed(Register::Rbx, Register::Rcx, Size::Byte, 0), Register::Rdi);
+                    self.assembler.arithmetic(Arithmetic::Sub, Size::Quad, Register::R12, Register::Rcx);
+                    self.assembler.repne_scasb();
+                    self.assembler.jcc(Condition::Equal, end);
+                    self.assembler.bind(forever);
+                    self.assembler.jmp(forever);
+                    self.assembler.bind(end);
+                    self.assembler.cld();
+                    self.assembler.lea(Memory::at(Register::Rdi, 1), Register::R12);
+                    self.assembler.arithmetic(Arithmetic::Sub, Size::Quad, Register::Rbx, Register::R12);
+                },
+                Operation::Scan { stride } => {
+                    // Step through the cells until reaching a zero cell.
+                    let start: Label = self.assembler.label();
+                    let end: Label = self.assembler.label();
+                    self.assembler.bind(start);
+                    let cell: Memory = self.cell(instruction.offset);
+                    self.assembler.arithmetic(Arithmetic::Cmp, size, 0, cell);
+                    self.assembler.jcc(Condition::Equal, end);
+                    if self.options.is_reserving() {
+                        let position: Option<usize> = self.position(instruction, self.options.is_checked());
+                        self.reserve(stride, 0, 0, position);
+                    } else {
+                        self.step(stride);
+                    }
+                    self.assembler.jmp(start);
+                    self.assembler.bind(end);
+                },
+            }
+
+            // Scans only end on a cell on the tape, but the cells accessed next might not be.
+            if let Operation::Scan { .. } = instruction.operation {
+                self.reserve_segment(following);
+            }
+        }
+
+        self.close_guard(&mut guard);
+    }
+
+    /// Add the 128 bit number in `%rdx:%rax` to the `cell` using the runtime function, which reports overflows at the
+    /// `instruction` if the options require it.
+    fn add(&mut self, instruction: &Instruction, cell: Memory) {
+        self.runtime.add = true;
+        self.load(cell, Register::Rcx);
+        if let Some(id) = self.position(instruction, self.options.overflow == Overflow::Error) {
+            self.assembler.mov(Size::Double, to_i64(id), Register::Rdi);
+        }
+        self.assembler.call(self.symbols.add);
+        self.assembler.mov(self.cell_size(), Register::Rax, cell);
+    }
+
+    /// Prepare the multiplication of the `instruction`'s cell with another cell `offset` cells away from it. Like in
+    /// the replaced loop, the target cell is only accessed if the instruction's cell is not zero, so the
+    /// multiplication is placed within a condition, which is opened unless the previous multiplication depends on the
+    /// same cell and the `guard` is still open, or the instruction's cell is known to be `nonzero`. Within the
+    /// condition, a growing or checked tape is made to contain the target cell. The instruction's `cell` is used for
+    /// the condition.
+    fn multiply(&mut self, instruction: &Instruction, offset: isize, cell: Memory, guard: &mut Option<(isize, Label)>,
+                nonzero: bool)
+    {
+        if guard.is_none() && !nonzero {
+            let end: Label = self.assembler.label();
+            self.assembler.arithmetic(Arithmetic::Cmp, self.cell_size(), 0, cell);
+            self.assembler.jcc(Condition::Equal, end);
+            *guard = Some((instruction.offset, end));
+        }
+
+        if self.options.is_reserving() {
+            let target: isize = instruction.offset.wrapping_add(offset);
+            let position: Option<usize> = self.position(instruction, self.options.is_checked());
+            self.reserve(0, target.min(0), target.max(0), position);
+        }
+    }
+
+    /// Get the address of the `cell` `offset` cells away from the current cell again after growing the tape for a
+    /// multiplication, which might have moved the tape or changed the index of a cell wrapped around at its start.
+    fn reload(&mut self, offset: isize, cell: Memory) -> Memory {
+        if self.options.is_reserving() {
+            self.cell(offset)
+        } else {
+            cell
+        }
+    }
+
+    /// Bind the label after the condition of the multiplications if the `guard` is open.
+    fn close_guard(&mut self, guard: &mut Option<(isize, Label)>) {
+        if let Some((_, end)) = guard.take() {
+            self.assembler.bind(end);
+        }
+    }
+
+    /// Call the runtime function moving the pointer by `offset` cells and making sure that the cells from `low` to
+    /// `high` cells away from the new current cell are on the tape. The ID of the source `position` is passed on to
+    /// report errors on checked tapes. The cells before the start of a tape wrapping around do not have to be on the
+    /// tape.
+    fn reserve(&mut self, offset: isize, low: isize, high: isize, position: Option<usize>) {
+        let low: isize = if self.options.is_wrapping() { low.max(0) } else { low };
+        if offset == 0 && low == 0 && high == 0 {
+            return;
+        }
+
+        self.runtime.reserve = true;
+        self.assembler.mov(Size::Quad, to_i64(offset), Register::Rsi);
+        self.assembler.mov(Size::Quad, to_i64(low), Register::Rdx);
+        self.assembler.mov(Size::Quad, to_i64(high), Register::Rcx);
+        if let Some(id) = position {
+            self.assembler.mov(Size::Double, to_i64(id), Register::Rdi);
+        }
+        self.assembler.call(self.symbols.reserve);
+    }
+
+    /// Make sure that a growing tape contains all cells accessed by the `instructions` up to the next pointer
+    /// movement. Checked tapes are handled for each instruction instead.
+    fn reserve_segment(&mut self, instructions: &[Instruction]) {
+        if let Some((low, high)) = reserved_range(instructions, &self.options) {
+            self.reserve(0, low, high, None);
+        }
+    }
+
+    /// Load the value of the `cell` into the `register`, extending it to 64 bits.
+    fn load(&mut self, cell: Memory, register: Register) {
+        match self.cell_size() {
+            Size::Byte => self.assembler.movzx(Size::Byte, cell, register),
+            Size::Word => self.assembler.movzx(Size::Word, cell, register),
+            Size::Double => self.assembler.mov(Size::Double, cell, register),
+            Size::Quad => self.assembler.mov(Size::Quad, cell, register),
+        }
+    }
+
+    /// Move the pointer by `offset` cells, wrapping around at the ends of an unchecked tape with a fixed size.
+    fn step(&mut self, offset: isize) {
+        let offset: isize = self.wrap(offset);
+        if offset == 0 {
+            return;
+        }
+
+        if self.options.is_reserving() {
+            self.assembler.arithmetic(Arithmetic::Add, Size::Quad, to_i64(offset), Register::R12);
+        } else {
+            self.index(offset);
+            self.assembler.mov(Size::Quad, Register::R13, Register::R12);
+        }
+    }
+
+    /// Get the address of the cell `offset` cells away from the current cell. On an unchecked tape with a fixed size,
+    /// the cell's index is computed in `%r13` unless it is the current cell. The same applies to the cells before the
+    /// current cell on an unchecked tape growing to the right.
+    fn cell(&mut self, offset: isize) -> Memory {
+        let size: Size = self.cell_size();
+        let offset: isize = self.wrap(offset);
+        if offset < 0 && self.options.is_reserving() && self.options.is_wrapping() {
+            self.runtime.wrap_left = true;
+            self.assembler.mov(Size::Quad, to_i64(offset.unsigned_abs()), Register::R13);
+            self.assembler.call(self.symbols.wrap_left);
+            return Memory::indexed(Register::Rbx, Register::R13, size, 0);
+        }
+        if offset != 0 && !self.options.is_reserving() {
+            self.index(offset);
+            return Memory::indexed(Register::Rbx, Register::R13, size, 0);
+        }
+
+        let displacement: isize = offset.saturating_mul(to_isize(size.bytes()));
+        let displacement: i32 = i32::try_from(displacement).expect("The offset is within the range of addresses.");
+        Memory::indexed(Register::Rbx, Register::R12, size, displacement)
+    }
+
+    /// Compute the index of the cell `offset` cells away from the current cell on an unchecked tape with a fixed size
+    /// in `%r13`, wrapping around at the tape's ends. `%r14` is overwritten.
+    fn index(&mut self, offset: isize) {
+        let length: isize = to_isize(self.tape_size());
+        let distance: isize = if offset < 0 { offset + length } else { offset };
+        self.assembler.mov(Size::Quad, to_i64(distance), Register::R13);
+        self.assembler.arithmetic(Arithmetic::Add, Size::Quad, Register::R12, Register::R13);
+        self.assembler.mov(Size::Quad, to_i64(distance - length), Register::R14);
+        self.assembler.arithmetic(Arithmetic::Add, Size::Quad, Register::R12, Register::R14);
+        self.assembler.cmov(Condition::NotSign, Size::Quad, Register::R14, Register::R13);
+    }
+
+    /// Reduce the `offset` to the number of cells on an unchecked tape with a fixed size, on which the pointer wraps
+    /// around at the ends.
+    fn wrap(&self, offset: isize) -> isize {
+        if self.options.is_reserving() {
+            offset
+        } else {
+            offset % to_isize(self.tape_size())
+        }
+    }
+
+    /// Get the size of the cells.
+    fn cell_size(&self) -> Size {
+        match self.options.cell_type {
+            CellType::U8 => Size::Byte,
+            CellType::U16 => Size::Word,
+            CellType::U32 => Size::Double,
+            CellType::I64 => Size::Quad,
+        }
+    }
+
+    /// Get the initial number of cells on the tape.
+    fn tape_size(&self) -> usize {
+        self.options.tape_size.max(1)
+    }
+
+    /// Determine if scans with a stride of one can search the tape's memory directly, i.e. if the cells are bytes and
+    /// the tape's size is known at compile time and does not have to be checked.
+    fn can_search_memory(&self) -> bool {
+        self.options.cell_type == CellType::U8 && !self.options.is_reserving()
+    }
+
+    /// Generate the functions called by the generated code.
+    fn generate_runtime(&mut self) {
+        if !self.positions.is_empty() {
+            self.generate_failing_runtime();
+        }
+
+        match self.options.tape_growth {
+            TapeGrowth::Fixed if self.runtime.reserve => self.generate_checked_fixed_tape(),
+            TapeGrowth::Fixed => {},
+            TapeGrowth::Right | TapeGrowth::Both => {
+                self.generate_growing_tape();
+                if self.runtime.reserve {
+                    self.generate_growing_reserve();
+                }
+                if self.runtime.wrap_left {
+                    self.generate_wrapping_left();
+                }
+            },
+        }
+
+        self.generate_output_runtime();
+        if self.runtime.read {
+            self.generate_reading_runtime();
+        }
+
+        if self.runtime.add {
+            self.generate_arithmetic_runtime();
+        }
+    }
+
+    /// Generate the functions `flush`, writing the output buffer to the standard output, and `write`, appending the
+    /// byte in `%dil` to the buffer.
+    fn generate_output_runtime(&mut self) {
+        let symbols: Symbols = self.symbols;
+        let asm: &mut Assembler = &mut self.assembler;
+        let written: Label = asm.label();
+        let done: Label = asm.label();
+
+        asm.bind(symbols.flush);
+        asm.mov_address(symbols.output, Register::Rsi);
+        asm.mov(Size::Quad, Memory::Absolute(symbols.output_length), Register::Rdx);
+        asm.mov(Size::Quad, 0, Memory::Absolute(symbols.output_length));
+        asm.bind(written);
+        asm.test(Size::Quad, Register::Rdx, Register::Rdx);
+        asm.jcc(Condition::Equal, done);
+        asm.mov(Size::Double, 1, Register::Rax);
+        asm.mov(Size::Double, 1, Register::Rdi);
+        asm.syscall();
+        asm.test(Size::Quad, Register::Rax, Register::Rax);
+        asm.jcc(Condition::LessEqual, done);
+        asm.arithmetic(Arithmetic::Add, Size::Quad, Register::Rax, Register::Rsi);
+        asm.arithmetic(Arithmetic::Sub, Size::Quad, Register::Rax, Register::Rdx);
+        asm.jmp(written);
+        asm.bind(done);
+        asm.ret();
+
+        asm.bind(symbols.write);
+        asm.mov(Size::Quad, Memory::Absolute(symbols.output_length), Register::Rax);
+        asm.mov_address(symbols.output, Register::Rcx);
+        asm.mov(Size::Byte, Register::Rdi, Memory::indexed(Register::Rcx, Register::Rax, Size::Byte, 0));
+        asm.arithmetic(Arithmetic::Add, Size::Quad, 1, Register::Rax);
+        asm.mov(Size::Quad, Register::Rax, Memory::Absolute(symbols.output_length));
+        asm.arithmetic(Arithmetic::Cmp, Size::Quad, to_i64(OUTPUT_SIZE), Register::Rax);
+        asm.jcc(Condition::Equal, symbols.flush);
+        asm.ret();
+    }
+
+    /// Generate the function `read`, returning the next character from the standard input in `%rax`, or the value
+    /// for the end of the input.
+    fn generate_reading_runtime(&mut self) {
+        let eof: i32 = if self.options.eof == EofBehavior::Zero { 0 } else { -1 };
+        let symbols: Symbols = self.symbols;
+        let asm: &mut Assembler = &mut self.assembler;
+        let end: Label = asm.label();
+
+        asm.bind(symbols.read);
+        asm.call(symbols.flush);
+        asm.arithmetic(Arithmetic::Xor, Size::Double, Register::Rax, Register::Rax);
+        asm.arithmetic(Arithmetic::Xor, Size::Double, Register::Rdi, Register::Rdi);
+        asm.mov_address(symbols.input, Register::Rsi);
+        asm.mov(Size::Double, 1, Register::Rdx);
+        asm.syscall();
+        asm.arithmetic(Arithmetic::Cmp, Size::Quad, 1, Register::Rax);
+        asm.jcc(Condition::NotEqual, end);
+        asm.movzx(Size::Byte, Memory::Absolute(symbols.input), Register::Rax);
+        asm.ret();
+        asm.bind(end);
+        asm.mov(Size::Quad, eof, Register::Rax);
+        asm.ret();
+    }
+
+    /// Generate the function `reserve`, moving the pointer by `%rsi` cells on a tape with a fixed size and aborting
+    /// the program with the source position `%edi` if the cells from `%rdx` to `%rcx` cells away from the new current
+    /// cell are not on the tape.
+    fn generate_checked_fixed_tape(&mut self) {
+        let tape_size: i64 = to_i64(self.tape_size());
+        let symbols: Symbols = self.symbols;
+        let asm: &mut Assembler = &mut self.assembler;
+        let underflow: Label = asm.label();
+        let overflow: Label = asm.label();
+
+        asm.bind(symbols.reserve);
+        asm.arithmetic(Arithmetic::Add, Size::Quad, Register::Rsi, Register::R12);
+        asm.lea(Memory::indexed(Register::R12, Register::Rdx, Size::Byte, 0), Register::Rax);
+        asm.test(Size::Quad, Register::Rax, Register::Rax);
+        asm.jcc(Condition::Sign, underflow);
+        asm.lea(Memory::indexed(Register::R12, Register::Rcx, Size::Byte, 0), Register::Rax);
+        asm.mov(Size::Quad, tape_size, Register::R8);
+        asm.arithmetic(Arithmetic::Cmp, Size::Quad, Register::R8, Register::Rax);
+        asm.jcc(Condition::GreaterEqual, overflow);
+        asm.ret();
+        asm.bind(underflow);
+        asm.mov_address(symbols.pointer_underflow, Register::Rsi);
+        asm.jmp(symbols.fail);
+        asm.bind(overflow);
+        asm.mov_address(symbols.pointer_overflow, Register::Rsi);
+        asm.jmp(symbols.fail);
+    }
+
+    /// Generate the functions `map`, mapping `%rsi` bytes of zeroed memory, and `allocate`, mapping the initial tape.
+    fn generate_growing_tape(&mut self) {
+        let tape_size: i64 = to_i64(self.tape_size());
+        let cell_size: i32 = to_i32(self.cell_size().bytes());
+        let symbols: Symbols = self.symbols;
+        let asm: &mut Assembler = &mut self.assembler;
+        let failed: Label = asm.label();
+
+        asm.bind(symbols.map);
+        asm.mov(Size::Double, 9, Register::Rax);
+        asm.arithmetic(Arithmetic::Xor, Size::Double, Register::Rdi, Register::Rdi);
+        asm.mov(Size::Double, 3, Register::Rdx);
+        asm.mov(Size::Double, 0x22, Register::R10);
+        asm.mov(Size::Quad, -1, Register::R8);
+        asm.arithmetic(Arithmetic::Xor, Size::Double, Register::R9, Register::R9);
+        asm.syscall();
+        asm.arithmetic(Arithmetic::Cmp, Size::Quad, -4096, Register::Rax);
+        asm.jcc(Condition::Above, failed);
+        asm.ret();
+        asm.bind(failed);
+        asm.mov(Size::Double, 1, Register::Rax);
+        asm.mov(Size::Double, 2, Register::Rdi);
+        asm.mov_address(symbols.out_of_memory, Register::Rsi);
+        asm.mov(Size::Double, 14, Register::Rdx);
+        asm.syscall();
+        asm.mov(Size::Double, 60, Register::Rax);
+        asm.mov(Size::Double, 1, Register::Rdi);
+        asm.syscall();
+
+        asm.bind(symbols.allocate);
+        asm.mov(Size::Quad, tape_size, Register::Rsi);
+        asm.mov(Size::Quad, Register::Rsi, Memory::Absolute(symbols.tape_size));
+        asm.imul(Size::Quad, cell_size, Register::Rsi, Register::Rsi);
+        asm.call(symbols.map);
+        asm.mov(Size::Quad, Register::Rax, Register::Rbx);
+        asm.ret();
+    }
+
+    /// Generate the function `reserve`, moving the pointer by `%rsi` cells on a growing tape and growing the tape
+    /// until the cells from `%rdx` to `%rcx` cells away from the new current cell are on it. Accesses before the
+    /// tape's start are reported at the source position `%edi` if they are checked. On a tape wrapping around, a new
+    /// position before the tape's start continues at its current end.
+    fn generate_growing_reserve(&mut self) {
+        let cell_size: Size = self.cell_size();
+        let checked: bool = self.options.is_checked();
+        let wrapping: bool = self.options.is_wrapping();
+        let both: bool = self.options.tape_growth == TapeGrowth::Both;
+        let symbols: Symbols = self.symbols;
+        let asm: &mut Assembler = &mut self.assembler;
+        let grow: Label = asm.label();
+        let left: Label = asm.label();
+        let right: Label = asm.label();
+
+        asm.bind(symbols.reserve);
+        asm.arithmetic(Arithmetic::Add, Size::Quad, Register::Rsi, Register::R12);
+        asm.lea(Memory::indexed(Register::R12, Register::Rdx, Size::Byte, 0), Register::Rax);
+        asm.lea(Memory::indexed(Register::R12, Register::Rcx, Size::Byte, 0), Register::R8);
+        asm.mov(Size::Quad, Memory::Absolute(symbols.tape_size), Register::R15);
+        asm.test(Size::Quad, Register::Rax, Register::Rax);
+        asm.jcc(Condition::Sign, grow);
+        asm.arithmetic(Arithmetic::Cmp, Size::Quad, Register::R15, Register::R8);
+        asm.jcc(Condition::GreaterEqual, grow);
+        asm.ret();
+        asm.bind(grow);
+
+        // Compute the number of cells to add before the tape's start in `%r13`, at least doubling the tape's size.
+        if checked {
+            let valid: Label = asm.label();
+            asm.test(Size::Quad, Register::Rax, Register::Rax);
+            asm.jcc(Condition::NotSign, valid);
+            asm.mov_address(symbols.pointer_underflow, Register::Rsi);
+            asm.jmp(symbols.fail);
+            asm.bind(valid);
+        }
+        if wrapping {
+            let wrapped: Label = asm.label();
+            asm.test(Size::Quad, Register::R12, Register::R12);
+            asm.jcc(Condition::NotSign, wrapped);
+            asm.mov(Size::Quad, Register::R12, Register::Rax);
+            asm.cqto();
+            asm.idiv(Register::R15);
+            asm.lea(Memory::indexed(Register::Rdx, Register::R15, Size::Byte, 0), Register::R12);
+            asm.test(Size::Quad, Register::Rdx, Register::Rdx);
+            asm.cmov(Condition::NotSign, Size::Quad, Register::Rdx, Register::R12);
+            asm.lea(Memory::indexed(Register::R12, Register::Rcx, Size::Byte, 0), Register::R8);
+            asm.arithmetic(Arithmetic::Cmp, Size::Quad, Register::R15, Register::R8);
+            asm.jcc(Condition::GreaterEqual, wrapped);
+            asm.ret();
+            asm.bind(wrapped);
+        }
+        asm.arithmetic(Arithmetic::Xor, Size::Double, Register::R13, Register::R13);
+        if both {
+            asm.test(Size::Quad, Register::Rax, Register::Rax);
+            asm.jcc(Condition::NotSign, left);
+            asm.mov(Size::Quad, Register::Rax, Register::R13);
+            asm.neg(Size::Quad, Register::R13);
+            asm.arithmetic(Arithmetic::Cmp, Size::Quad, Register::R15, Register::R13);
+            asm.cmov(Condition::Less, Size::Quad, Register::R15, Register::R13);
+        }
+        asm.bind(left);
+
+        // Compute the number of cells to add after the tape's end in `%r14`.
+        asm.arithmetic(Arithmetic::Xor, Size::Double, Register::R14, Register::R14);
+        asm.arithmetic(Arithmetic::Cmp, Size::Quad, Register::R15, Register::R8);
+        asm.jcc(Condition::Less, right);
+        asm.lea(Memory::at(Register::R8, 1), Register::R14);
+        asm.arithmetic(Arithmetic::Sub, Size::Quad, Register::R15, Register::R14);
+        asm.arithmetic(Arithmetic::Cmp, Size::Quad, Register::R15, Register::R14);
+        asm.cmov(Condition::Less, Size::Quad, Register::R15, Register::R14);
+        asm.bind(right);
+
+        // Map the grown tape, copy the old tape into it, and unmap the old tape.
+        let scale: i32 = to_i32(cell_size.bytes());
+        asm.lea(Memory::indexed(Register::R15, Register::R13, Size::Byte, 0), Register::Rsi);
+        asm.arithmetic(Arithmetic::Add, Size::Quad, Register::R14, Register::Rsi);
+        asm.mov(Size::Quad, Register::Rsi, Memory::Absolute(symbols.tape_size));
+        asm.imul(Size::Quad, scale, Register::Rsi, Register::Rsi);
+        asm.call(symbols.map);
+        asm.mov(Size::Quad, Register::Rbx, Register::R14);
+        asm.mov(Size::Quad, Register::Rax, Register::Rbx);
+        asm.lea(Memory::indexed(Register::Rax, Register::R13, cell_size, 0), Register::Rdi);
+        asm.mov(Size::Quad, Register::R14, Register::Rsi);
+        asm.imul(Size::Quad, scale, Register::R15, Register::Rcx);
+        asm.mov(Size::Quad, Register::Rcx, Register::R15);
+        asm.rep_movsb();
+        asm.mov(Size::Double, 11, Register::Rax);
+        asm.mov(Size::Quad, Register::R14, Register::Rdi);
+        asm.mov(Size::Quad, Register::R15, Register::Rsi);
+        asm.syscall();
+        asm.arithmetic(Arithmetic::Add, Size::Quad, Register::R13, Register::R12);
+        asm.ret();
+    }
+
+    /// Generate the function `wrap_left`, computing the index of the cell `%r13` cells before the current cell in
+    /// `%r13` on an unchecked tape growing only to the right, wrapping around at the tape's start. `%r14` is
+    /// overwritten.
+    fn generate_wrapping_left(&mut self) {
+        let symbols: Symbols = self.symbols;
+        let asm: &mut Assembler = &mut self.assembler;
+        let wrap: Label = asm.label();
+
+        asm.bind(symbols.wrap_left);
+        asm.mov(Size::Quad, Register::R12, Register::R14);
+        asm.arithmetic(Arithmetic::Sub, Size::Quad, Register::R13, Register::R14);
+        asm.jcc(Condition::Sign, wrap);
+        asm.mov(Size::Quad, Register::R14, Register::R13);
+        asm.ret();
+        asm.bind(wrap);
+        asm.push(Register::Rax);
+        asm.push(Register::Rdx);
+        asm.mov(Size::Quad, Register::R14, Register::Rax);
+        asm.cqto();
+        asm.idiv(Memory::Absolute(symbols.tape_size));
+        asm.mov(Size::Quad, Memory::Absolute(symbols.tape_size), Register::R13);
+        asm.arithmetic(Arithmetic::Add, Size::Quad, Register::Rdx, Register::R13);
+        asm.test(Size::Quad, Register::Rdx, Register::Rdx);
+        asm.cmov(Condition::NotSign, Size::Quad, Register::Rdx, Register::R13);
+        asm.pop(Register::Rdx);
+        asm.pop(Register::Rax);
+        asm.ret();
+    }
+
+    /// Generate the function `fail`, aborting the program with the message `%rsi` for the source position `%edi`, and
+    /// its helper functions.
+    fn generate_failing_runtime(&mut self) {
+        let symbols: Symbols = self.symbols;
+        let asm: &mut Assembler = &mut self.assembler;
+
+        asm.bind(symbols.fail);
+        asm.mov(Size::Quad, Register::Rsi, Register::Rbx);
+        asm.mov(Size::Double, Register::Rdi, Register::R12);
+        asm.call(symbols.flush);
+        asm.mov_address(symbols.error, Register::Rdi);
+        asm.mov(Size::Quad, Register::Rbx, Register::Rsi);
+        asm.call(symbols.append_string);
+        asm.mov_address(symbols.at_line, Register::Rsi);
+        asm.call(symbols.append_string);
+        asm.mov_address(symbols.positions, Register::Rax);
+        asm.mov(Size::Double, Memory::indexed(Register::Rax, Register::R12, Size::Quad, 0), Register::Rax);
+        asm.call(symbols.append_number);
+        asm.mov_address(symbols.at_position, Register::Rsi);
+        asm.call(symbols.append_string);
+        asm.mov_address(symbols.positions, Register::Rax);
+        asm.mov(Size::Double, Memory::indexed(Register::Rax, Register::R12, Size::Quad, 4), Register::Rax);
+        asm.call(symbols.append_number);
+        asm.mov(Size::Byte, 10, Memory::at(Register::Rdi, 0));
+        asm.arithmetic(Arithmetic::Add, Size::Quad, 1, Register::Rdi);
+        asm.mov_address(symbols.error, Register::Rsi);
+        asm.mov(Size::Quad, Register::Rdi, Register::Rdx);
+        asm.arithmetic(Arithmetic::Sub, Size::Quad, Register::Rsi, Register::Rdx);
+        asm.mov(Size::Double, 1, Register::Rax);
+        asm.mov(Size::Double, 2, Register::Rdi);
+        asm.syscall();
+        asm.mov(Size::Double, 60, Register::Rax);
+        asm.mov(Size::Double, 3, Register::Rdi);
+        asm.syscall();
+
+        let end: Label = asm.label();
+        asm.bind(symbols.append_string);
+        asm.mov(Size::Byte, Memory::at(Register::Rsi, 0), Register::Rax);
+        asm.test(Size::Byte, Register::Rax, Register::Rax);
+        asm.jcc(Condition::Equal, end);
+        asm.mov(Size::Byte, Register::Rax, Memory::at(Register::Rdi, 0));
+        asm.arithmetic(Arithmetic::Add, Size::Quad, 1, Register::Rsi);
+        asm.arithmetic(Arithmetic::Add, Size::Quad, 1, Register::Rdi);
+        asm.jmp(symbols.append_string);
+        asm.bind(end);
+        asm.ret();
+
+        let divide: Label = asm.label();
+        let append: Label = asm.label();
+        asm.bind(symbols.append_number);
+        asm.mov(Size::Double, 10, Register::Rcx);
+        asm.arithmetic(Arithmetic::Xor, Size::Double, Register::R8, Register::R8);
+        asm.bind(divide);
+        asm.arithmetic(Arithmetic::Xor, Size::Double, Register::Rdx, Register::Rdx);
+        asm.div(Register::Rcx);
+        asm.arithmetic(Arithmetic::Add, Size::Double, 48, Register::Rdx);
+        asm.push(Register::Rdx);
+        asm.arithmetic(Arithmetic::Add, Size::Double, 1, Register::R8);
+        asm.test(Size::Double, Register::Rax, Register::Rax);
+        asm.jcc(Condition::NotEqual, divide);
+        asm.bind(append);
+        asm.pop(Register::Rax);
+        asm.mov(Size::Byte, Register::Rax, Memory::at(Register::Rdi, 0));
+        asm.arithmetic(Arithmetic::Add, Size::Quad, 1, Register::Rdi);
+        asm.arithmetic(Arithmetic::Sub, Size::Double, 1, Register::R8);
+        asm.jcc(Condition::NotEqual, append);
+        asm.ret();
+    }
+
+    /// Generate the function `add`, adding the 128 bit number in `%rdx:%rax` to the extended cell value in `%rcx` and
+    /// returning the new cell value in `%rax`. Sums outside the bounds of the cells are saturated, or abort the
+    /// program with the source position `%edi`.
+    fn generate_arithmetic_runtime(&mut self) {
+        let min: i64 = self.options.cell_type.min();
+        let max: i64 = self.options.cell_type.max();
+        let overflow: Overflow = self.options.overflow;
+        let symbols: Symbols = self.symbols;
+        let asm: &mut Assembler = &mut self.assembler;
+        let low: Label = asm.label();
+        let high: Label = asm.label();
+
+        asm.bind(symbols.add);
+        asm.mov(Size::Quad, Register::Rcx, Register::Rsi);
+        asm.sar(Size::Quad, 63, Register::Rsi);
+        asm.arithmetic(Arithmetic::Add, Size::Quad, Register::Rcx, Register::Rax);
+        asm.arithmetic(Arithmetic::Adc, Size::Quad, Register::Rsi, Register::Rdx);
+        for &(bound, label, below) in [(min, low, true), (max, high, false)].iter() {
+            // Compare the 128 bit sum with the sign-extended bound.
+            asm.mov(Size::Quad, bound, Register::Rsi);
+            asm.mov(Size::Quad, Register::Rsi, Register::R8);
+            asm.sar(Size::Quad, 63, Register::R8);
+            if below {
+                asm.mov(Size::Quad, Register::Rdx, Register::R9);
+                asm.arithmetic(Arithmetic::Cmp, Size::Quad, Register::Rsi, Register::Rax);
+                asm.arithmetic(Arithmetic::Sbb, Size::Quad, Register::R8, Register::R9);
+            } else {
+                asm.arithmetic(Arithmetic::Cmp, Size::Quad, Register::Rax, Register::Rsi);
+                asm.arithmetic(Arithmetic::Sbb, Size::Quad, Register::Rdx, Register::R8);
+            }
+            asm.jcc(Condition::Less, label);
+        }
+        asm.ret();
+
+        match overflow {
+            Overflow::Error => {
+                asm.bind(low);
+                asm.bind(high);
+                asm.mov_address(symbols.cell_overflow, Register::Rsi);
+                asm.jmp(symbols.fail);
+            },
+            Overflow::Wrap | Overflow::Saturate => {
+                asm.bind(low);
+                asm.mov(Size::Quad, min, Register::Rax);
+                asm.ret();
+                asm.bind(high);
+                asm.mov(Size::Quad, max, Register::Rax);
+                asm.ret();
+            },
+        }
+    }
+
+    /// Append the table of source positions and the messages to the code.
+    fn generate_constants(&mut self) {
+        let symbols: Symbols = self.symbols;
+        let asm: &mut Assembler = &mut self.assembler;
+
+        asm.bind(symbols.positions);
+        for position in &self.positions {
+            let lineno: u32 = u32::try_from(position.lineno).unwrap_or(u32::MAX);
+            let position: u32 = u32::try_from(position.position).unwrap_or(u32::MAX);
+            asm.data(&lineno.to_le_bytes());
+            asm.data(&position.to_le_bytes());
+        }
+
+        let messages: [(Label, &[u8]); 6] = [
+            (symbols.cell_overflow, b"cell overflow\0"),
+            (symbols.pointer_underflow, b"pointer underflow\0"),
+            (symbols.pointer_overflow, b"pointer overflow\0"),
+            (symbols.at_line, b" at line \0"),
+            (symbols.at_position, b", position \0"),
+            (symbols.out_of_memory, b"out of memory\n"),
+        ];
+        for &(label, message) in messages.iter() {
+            asm.bind(label);
+            asm.data(message);
+        }
+    }
+
+    /// Get the ID of the `instruction`'s source position passed to the runtime functions, adding the position to the
+    /// table. Empty unless the position might be `reported`.
+    fn position(&mut self, instruction: &Instruction, reported: bool) -> Option<usize> {
+        if !reported {
+            return None;
+        }
+
+        self.positions.push(instruction.span.start);
+        Some(self.positions.len() - 1)
+    }
+}
+
+/// Get the `ELF` header and the program headers of an executable with `code_size` bytes of machine code, and
+/// `variables_size` bytes of zero-initialized memory at the offset `variables` from the start of the executable.
+fn headers(code_size: usize, variables: usize, variables_size: usize) -> Vec<u8> {
+    let mut headers: Vec<u8> = Vec::with_capacity(HEADERS_SIZE);
+
+    // The identification of a little-endian 64 bit executable for the System V ABI, and the remaining fields of the
+    // `ELF` header: the type of the file, the machine, the version, the entry point, the offsets of the program
+    // headers and of the non-existent section headers, the flags, and the sizes and numbers of the headers.
+    headers.extend_from_slice(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0");
+    headers.extend_from_slice(&2_u16.to_le_bytes());
+    headers.extend_from_slice(&0x3E_u16.to_le_bytes());
+    headers.extend_from_slice(&1_u32.to_le_bytes());
+    headers.extend_from_slice(&to_u64(BASE_ADDRESS + HEADERS_SIZE).to_le_bytes());
+    headers.extend_from_slice(&to_u64(ELF_HEADER_SIZE).to_le_bytes());
+    headers.extend_from_slice(&0_u64.to_le_bytes());
+    headers.extend_from_slice(&0_u32.to_le_bytes());
+    for &value in [ELF_HEADER_SIZE, PROGRAM_HEADER_SIZE, 2, 64, 0, 0].iter() {
+        headers.extend_from_slice(&u16::try_from(value).expect("The header field fits into 16 bits.").to_le_bytes());
+    }
+
+    // The loadable segments: the readable and executable headers and code, and the readable and writable variables,
+    // which are not stored in the file.
+    let segments: [(u32, usize, usize, usize); 2] = [
+        (5, BASE_ADDRESS, HEADERS_SIZE + code_size, HEADERS_SIZE + code_size),
+        (6, BASE_ADDRESS + variables, 0, variables_size),
+    ];
+    for &(flags, address, file_size, memory_size) in segments.iter() {
+        headers.extend_from_slice(&1_u32.to_le_bytes());
+        headers.extend_from_slice(&flags.to_le_bytes());
+        headers.extend_from_slice(&0_u64.to_le_bytes());
+        headers.extend_from_slice(&to_u64(address).to_le_bytes());
+        headers.extend_from_slice(&to_u64(address).to_le_bytes());
+        headers.extend_from_slice(&to_u64(file_size).to_le_bytes());
+        headers.extend_from_slice(&to_u64(memory_size).to_le_bytes());
+        headers.extend_from_slice(&to_u64(PAGE_SIZE).to_le_bytes());
+    }
+
+    headers
+}
+
+/// Round the `value` up to the next multiple of the `alignment`.
+fn align(value: usize, alignment: usize) -> usize {
+    value.div_ceil(alignment) * alignment
+}
+
+/// Get the `value` of a change to a cell of the given `cell_type`, reduced to the cell type's range when wrapping
+/// around at its bounds.
+fn wrapped(value: i32, cell_type: CellType) -> i64 {
+    let value: i64 = i64::from(value);
+    match cell_type {
+        CellType::U8 => value.rem_euclid(0x100),
+        CellType::U16 => value.rem_euclid(0x1_0000),
+        CellType::U32 | CellType::I64 => value,
+    }
+}
+
+/// Convert the `value` to a 32 bit number.
+fn to_i32(value: usize) -> i32 {
+    i32::try_from(value).expect("The value fits into 32 bits.")
+}
+
+/// Convert the `value` to a signed 64 bit number.
+fn to_i64<T>(value: T) -> i64 where i64: TryFrom<T> {
+    i64::try_from(value).unwrap_or_else(|_| panic!("The value fits into 64 bits."))
+}
+
+/// Convert the `value` to a signed number.
+fn to_isize(value: usize) -> isize {
+    isize::try_from(value).expect("The value fits into a signed number.")
+}
+
+/// Convert the `value` to an unsigned 64 bit number.
+fn to_u64(value: usize) -> u64 {
+    u64::try_from(value).expect("The value fits into 64 bits.")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::convert::TryFrom;
+
+    use CellType;
+    use ElfGenerator;
+    use Instruction;
+    use MetaData;
+    use Operation;
+    use Options;
+    use Overflow;
+    use Program;
+    use Span;
+    use TapeGrowth;
+
+    /// Read the little-endian 64 bit number at the `offset` in the `executable`.
+    fn read_u64(executable: &[u8], offset: usize) -> u64 {
+        let mut bytes: [u8; 8] = [0; 8];
+        bytes.copy_from_slice(&executable[offset..offset + 8]);
+        u64::from_le_bytes(bytes)
+    }
+
+    #[test]
+    fn test_generate_headers() {
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![Instruction::new(Operation::Write, span)],
+        };
+
+        let executable: Vec<u8> = ElfGenerator::new().generate(&program);
+        assert_eq!(&executable[..20], b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0\x02\0\x3e\0");
+        assert_eq!(read_u64(&executable, 24), 0x40_00B0);
+        assert_eq!(&executable[54..58], &[56, 0, 2, 0]);
+
+        // The code segment contains the entire file, the variables follow on the next page.
+        let file_size: u64 = u64::try_from(executable.len()).unwrap();
+        assert_eq!(&executable[64..72], &[1, 0, 0, 0, 5, 0, 0, 0]);
+        assert_eq!(read_u64(&executable, 80), 0x40_0000);
+        assert_eq!(read_u64(&executable, 96), file_size);
+        assert_eq!(read_u64(&executable, 104), file_size);
+        assert_eq!(&executable[120..128], &[1, 0, 0, 0, 6, 0, 0, 0]);
+        assert_eq!(read_u64(&executable, 136), 0x40_1000);
+        assert_eq!(read_u64(&executable, 152), 0);
+        assert_eq!(read_u64(&executable, 160), 4248 + 30_000);
+    }
+
+    #[test]
+    fn test_generate_code() {
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(259), span),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::new(Operation::MulAdd { offset: 1, factor: -1 }, span),
+                    Instruction::new(Operation::Clear, span),
+                ]), span),
+                Instruction::at_offset(Operation::Write, -1, span),
+            ],
+        };
+
+        let executable: Vec<u8> = ElfGenerator::new().generate(&program);
+        assert_eq!(&executable[176..291], &[
+            // Load the tape's address at the end of the variables, and start at the first cell.
+            0xBB, 0x98, 0x20, 0x40, 0x00,
+            0x45, 0x31, 0xE4,
+            // Add.
+            0x42, 0x80, 0x04, 0x23, 0x03,
+            // Loop, wrapping the index of the multiplication's target around at the tape's end.
+            0x42, 0x80, 0x3C, 0x23, 0x00,
+            0x0F, 0x84, 0x2B, 0x00, 0x00, 0x00,
+            0x42, 0x0F, 0xB6, 0x04, 0x23,
+            0x49, 0xC7, 0xC5, 0x01, 0x00, 0x00, 0x00,
+            0x4D, 0x01, 0xE5,
+            0x49, 0xC7, 0xC6, 0xD1, 0x8A, 0xFF, 0xFF,
+            0x4D, 0x01, 0xE6,
+            0x4D, 0x0F, 0x49, 0xEE,
+            0x42, 0x28, 0x04, 0x2B,
+            0x42, 0xC6, 0x04, 0x23, 0x00,
+            0xE9, 0xCA, 0xFF, 0xFF, 0xFF,
+            // Write, wrapping the index around at the tape's start.
+            0x49, 0xC7, 0xC5, 0x2F, 0x75, 0x00, 0x00,
+            0x4D, 0x01, 0xE5,
+            0x49, 0xC7, 0xC6, 0xFF, 0xFF, 0xFF, 0xFF,
+            0x4D, 0x01, 0xE6,
+            0x4D, 0x0F, 0x49, 0xEE,
+            0x42, 0x8A, 0x3C, 0x2B,
+            0xE8, 0x54, 0x00, 0x00, 0x00,
+            // Flush the output and exit.
+            0xE8, 0x0A, 0x00, 0x00, 0x00,
+            0xC7, 0xC0, 0x3C, 0x00, 0x00, 0x00,
+            0x31, 0xFF,
+            0x0F, 0x05,
+        ][..]);
+
+        // The output buffer starts the variables.
+        assert_eq!(&executable[291..296], &[0xBE, 0x00, 0x10, 0x40, 0x00]);
+    }
+
+    #[test]
+    fn test_generate_runtime() {
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(1), span),
+                Instruction::new(Operation::Move(1), span),
+            ],
+        };
+
+        // Only the functions needed by the program are included.
+        let wrapping: usize = ElfGenerator::new().generate(&program).len();
+        let options = Options { overflow: Overflow::Error, ..Options::default() };
+        let failing: Vec<u8> = ElfGenerator::with_options(options).generate(&program);
+        assert!(failing.len() > wrapping);
+        assert!(failing.windows(14).any(|window| window == b"cell overflow\0"));
+        assert!(failing.windows(8).any(|window| window == [1, 0, 0, 0, 1, 0, 0, 0]));
+
+        // Growing tapes are not part of the variables.
+        let options = Options { cell_type: CellType::U16, tape_growth: TapeGrowth::Right, ..Options::default() };
+        let executable: Vec<u8> = ElfGenerator::with_options(options).generate(&program);
+        assert_eq!(read_u64(&executable, 160), 4248);
+        assert!(executable.windows(14).any(|window| window == b"out of memory\n"));
+
+        let options = Options { cell_type: CellType::U16, tape_size: 100, ..Options::default() };
+        let executable: Vec<u8> = ElfGenerator::with_options(options).generate(&program);
+        assert_eq!(read_u64(&executable, 160), 4248 + 200);
+
+        // Cells before the start of an unchecked tape growing to the right are wrapped around at its current end.
+        let program = Program {
+            instructions: vec![Instruction::at_offset(Operation::Add(1), -1, span)],
+        };
+        let cqto: [u8; 2] = [0x48, 0x99];
+        let options = Options { tape_growth: TapeGrowth::Right, ..Options::default() };
+        let executable: Vec<u8> = ElfGenerator::with_options(options).generate(&program);
+        assert!(executable.windows(2).any(|window| window == cqto));
+        let executable: Vec<u8> = ElfGenerator::new().generate(&program);
+        assert!(!executable.windows(2).any(|window| window == cqto));
+    }
+}
@@ -11,6 +11,8 @@ use std::fmt;
 use std::io::Error as IOError;
 use std::result::Result as StdResult;
 
+use lexer::MetaData;
+
 /// A specialized `Result` type for `rustfuck`.
 pub type Result<T> = StdResult<T, Error>;
 
@@ -20,26 +22,99 @@ pub enum Error {
 
     /// IO errors caused by file handling failures.
     IO(IOError),
+
+    /// Syntax errors caused by a malformed program.
+    Syntax(SyntaxError),
+
+    /// Runtime errors caused by an invalid operation during the program's execution.
+    Runtime(RuntimeError),
+
+    /// Errors caused by a malformed definition of a dialect.
+    Dialect(DialectError),
+}
+
+/// The syntax errors a program can contain.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SyntaxError {
+
+    /// A loop is begun at the given position, but never ended.
+    UnclosedLoop(MetaData),
+
+    /// A loop is ended at the given position, but has never been begun.
+    UnopenedLoop(MetaData),
+
+    /// A command begins at the given position, but the source ends before the command is complete.
+    IncompleteCommand(MetaData),
+
+    /// The lexemes at the given position do not form any command of the language.
+    UnknownCommand(MetaData),
+}
+
+/// The errors a definition of a dialect can contain.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DialectError {
+
+    /// The entry at the given position is not of the form `key = value`, or its value has the wrong type.
+    MalformedEntry(MetaData),
+
+    /// The key at the given position is not known.
+    UnknownKey(MetaData),
+
+    /// The key at the given position has already been defined before.
+    DuplicateKey(MetaData),
+
+    /// The command with the given name has not been defined.
+    MissingCommand(&'static str),
+
+    /// The command with the given name is defined as the empty string.
+    EmptyCommand(&'static str),
+
+    /// The commands with the given names are defined as the same string.
+    AmbiguousCommands(&'static str, &'static str),
+}
+
+/// The errors that can occur while executing a program.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RuntimeError {
+
+    /// The operation at the given position changes a cell to a value outside the range of its type.
+    CellOverflow(MetaData),
+
+    /// The operation at the given position accesses a cell before the start of the tape.
+    PointerUnderflow(MetaData),
+
+    /// The operation at the given position accesses a cell past the end of the tape.
+    PointerOverflow(MetaData),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Error::IO(ref error) => error.fmt(formatter),
+            Error::Syntax(ref error) => error.fmt(formatter),
+            Error::Runtime(ref error) => error.fmt(formatter),
+            Error::Dialect(ref error) => error.fmt(formatter),
         }
     }
 }
 
 impl StdError for Error {
+    #[allow(deprecated)]
     fn description(&self) -> &str {
         match *self {
             Error::IO(ref error) => error.description(),
+            Error::Syntax(ref error) => error.description(),
+            Error::Runtime(ref error) => error.description(),
+            Error::Dialect(ref error) => error.description(),
         }
     }
 
-    fn cause(&self) -> Option<&StdError> {
+    fn cause(&self) -> Option<&dyn StdError> {
         match *self {
             Error::IO(ref error) => Some(error),
+            Error::Syntax(ref error) => Some(error),
+            Error::Runtime(ref error) => Some(error),
+            Error::Dialect(ref error) => Some(error),
         }
     }
 }
@@ -50,22 +125,166 @@ impl From<IOError> for Error {
     }
 }
 
+impl From<SyntaxError> for Error {
+    fn from(error: SyntaxError) -> Error {
+        Error::Syntax(error)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(error: RuntimeError) -> Error {
+        Error::Runtime(error)
+    }
+}
+
+impl From<DialectError> for Error {
+    fn from(error: DialectError) -> Error {
+        Error::Dialect(error)
+    }
+}
+
+impl SyntaxError {
+    /// The position in the source code at which the error occurs.
+    pub fn position(&self) -> MetaData {
+        match *self {
+            SyntaxError::UnclosedLoop(position) |
+            SyntaxError::UnopenedLoop(position) |
+            SyntaxError::IncompleteCommand(position) |
+            SyntaxError::UnknownCommand(position) => position,
+        }
+    }
+
+    /// A short message describing the error.
+    fn message(&self) -> &'static str {
+        match *self {
+            SyntaxError::UnclosedLoop(_) => "unclosed loop",
+            SyntaxError::UnopenedLoop(_) => "unexpected end of loop",
+            SyntaxError::IncompleteCommand(_) => "incomplete command",
+            SyntaxError::UnknownCommand(_) => "unknown command",
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let position: MetaData = self.position();
+        write!(formatter, "{description} at line {lineno}, position {position}",
+               description = self.message(), lineno = position.lineno, position = position.position)
+    }
+}
+
+impl StdError for SyntaxError {
+    fn description(&self) -> &str {
+        self.message()
+    }
+}
+
+impl RuntimeError {
+    /// The position in the source code of the operation causing the error.
+    pub fn position(&self) -> MetaData {
+        match *self {
+            RuntimeError::CellOverflow(position) |
+            RuntimeError::PointerUnderflow(position) |
+            RuntimeError::PointerOverflow(position) => position,
+        }
+    }
+
+    /// A short message describing the error.
+    fn message(&self) -> &'static str {
+        match *self {
+            RuntimeError::CellOverflow(_) => "cell overflow",
+            RuntimeError::PointerUnderflow(_) => "pointer underflow",
+            RuntimeError::PointerOverflow(_) => "pointer overflow",
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let position: MetaData = self.position();
+        write!(formatter, "{description} at line {lineno}, position {position}",
+               description = self.message(), lineno = position.lineno, position = position.position)
+    }
+}
+
+impl StdError for RuntimeError {
+    fn description(&self) -> &str {
+        self.message()
+    }
+}
+
+impl DialectError {
+    /// The position in the definition at which the error occurs, if it is caused by a single entry.
+    pub fn position(&self) -> Option<MetaData> {
+        match *self {
+            DialectError::MalformedEntry(position) |
+            DialectError::UnknownKey(position) |
+            DialectError::DuplicateKey(position) => Some(position),
+            DialectError::MissingCommand(_) |
+            DialectError::EmptyCommand(_) |
+            DialectError::AmbiguousCommands(_, _) => None,
+        }
+    }
+
+    /// A short message describing the error.
+    fn message(&self) -> &'static str {
+        match *self {
+            DialectError::MalformedEntry(_) => "malformed entry",
+            DialectError::UnknownKey(_) => "unknown key",
+            DialectError::DuplicateKey(_) => "duplicate key",
+            DialectError::MissingCommand(_) => "missing command",
+            DialectError::EmptyCommand(_) => "empty command",
+            DialectError::AmbiguousCommands(_, _) => "ambiguous commands",
+        }
+    }
+}
+
+impl fmt::Display for DialectError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DialectError::MissingCommand(command) | DialectError::EmptyCommand(command) => {
+                write!(formatter, "{description} `{command}`", description = self.message(), command = command)
+            },
+            DialectError::AmbiguousCommands(first, second) => {
+                write!(formatter, "{description} `{first}` and `{second}`",
+                       description = self.message(), first = first, second = second)
+            },
+            DialectError::MalformedEntry(position) | DialectError::UnknownKey(position) |
+            DialectError::DuplicateKey(position) => {
+                write!(formatter, "{description} at line {lineno}, position {position}",
+                       description = self.message(), lineno = position.lineno, position = position.position)
+            },
+        }
+    }
+}
+
+impl StdError for DialectError {
+    fn description(&self) -> &str {
+        self.message()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::error::Error as StdError;
     use std::io::Error as IOError;
 
+    use DialectError;
     use Error;
+    use MetaData;
+    use RuntimeError;
+    use SyntaxError;
 
     #[test]
     fn fmt_io() {
         let io_error = IOError::from_raw_os_error(42);
-        let fmt = String::from(format!("{}", io_error));
+        let fmt = format!("{}", io_error);
         let error = Error::IO(io_error);
         assert_eq!(format!("{}", error), fmt);
     }
 
     #[test]
+    #[allow(deprecated)]
     fn description_io() {
         let io_error = IOError::from_raw_os_error(42);
         let description = String::from(io_error.description());
@@ -74,6 +293,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn cause_io() {
         let io_error = IOError::from_raw_os_error(42);
         let io_error_debug: String = format!("{:?}", io_error);
@@ -85,11 +305,125 @@ mod tests {
     }
 
     #[test]
+    fn fmt_syntax() {
+        let error = Error::Syntax(SyntaxError::UnclosedLoop(MetaData { lineno: 2, position: 5 }));
+        assert_eq!(format!("{}", error), "unclosed loop at line 2, position 5");
+
+        let error = Error::Syntax(SyntaxError::UnopenedLoop(MetaData { lineno: 3, position: 1 }));
+        assert_eq!(format!("{}", error), "unexpected end of loop at line 3, position 1");
+
+        let error = Error::Syntax(SyntaxError::IncompleteCommand(MetaData { lineno: 1, position: 4 }));
+        assert_eq!(format!("{}", error), "incomplete command at line 1, position 4");
+
+        let error = Error::Syntax(SyntaxError::UnknownCommand(MetaData { lineno: 2, position: 1 }));
+        assert_eq!(format!("{}", error), "unknown command at line 2, position 1");
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn description_syntax() {
+        let error = Error::Syntax(SyntaxError::UnclosedLoop(MetaData { lineno: 2, position: 5 }));
+        assert_eq!(error.description(), "unclosed loop");
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn cause_syntax() {
+        let syntax_error = SyntaxError::UnopenedLoop(MetaData { lineno: 3, position: 1 });
+        let error = Error::Syntax(syntax_error);
+
+        assert!(error.cause().is_some());
+        assert_eq!(format!("{:?}", error.cause().unwrap()), format!("{:?}", syntax_error));
+    }
+
+    #[test]
+    #[allow(clippy::match_like_matches_macro)]
     fn from_io() {
         let io_error = IOError::from_raw_os_error(42);
         assert!(match Error::from(io_error) {
             Error::IO(_) => true,
-            // _ => false,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn from_syntax() {
+        let syntax_error = SyntaxError::UnclosedLoop(MetaData { lineno: 1, position: 1 });
+        assert!(match Error::from(syntax_error) {
+            Error::Syntax(error) => error == syntax_error,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn fmt_runtime() {
+        let error = Error::Runtime(RuntimeError::CellOverflow(MetaData { lineno: 4, position: 2 }));
+        assert_eq!(format!("{}", error), "cell overflow at line 4, position 2");
+
+        let error = Error::Runtime(RuntimeError::PointerUnderflow(MetaData { lineno: 1, position: 7 }));
+        assert_eq!(format!("{}", error), "pointer underflow at line 1, position 7");
+
+        let error = Error::Runtime(RuntimeError::PointerOverflow(MetaData { lineno: 3, position: 9 }));
+        assert_eq!(format!("{}", error), "pointer overflow at line 3, position 9");
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn description_runtime() {
+        let error = Error::Runtime(RuntimeError::CellOverflow(MetaData { lineno: 4, position: 2 }));
+        assert_eq!(error.description(), "cell overflow");
+    }
+
+    #[test]
+    fn from_runtime() {
+        let runtime_error = RuntimeError::CellOverflow(MetaData { lineno: 1, position: 1 });
+        assert!(match Error::from(runtime_error) {
+            Error::Runtime(error) => error == runtime_error,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn fmt_dialect() {
+        let error = Error::Dialect(DialectError::MalformedEntry(MetaData { lineno: 2, position: 7 }));
+        assert_eq!(format!("{}", error), "malformed entry at line 2, position 7");
+
+        let error = Error::Dialect(DialectError::UnknownKey(MetaData { lineno: 1, position: 1 }));
+        assert_eq!(format!("{}", error), "unknown key at line 1, position 1");
+
+        let error = Error::Dialect(DialectError::DuplicateKey(MetaData { lineno: 4, position: 1 }));
+        assert_eq!(format!("{}", error), "duplicate key at line 4, position 1");
+
+        let error = Error::Dialect(DialectError::MissingCommand("add"));
+        assert_eq!(format!("{}", error), "missing command `add`");
+
+        let error = Error::Dialect(DialectError::EmptyCommand("sub"));
+        assert_eq!(format!("{}", error), "empty command `sub`");
+
+        let error = Error::Dialect(DialectError::AmbiguousCommands("read", "write"));
+        assert_eq!(format!("{}", error), "ambiguous commands `read` and `write`");
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn description_dialect() {
+        let error = Error::Dialect(DialectError::MissingCommand("add"));
+        assert_eq!(error.description(), "missing command");
+    }
+
+    #[test]
+    fn position_dialect() {
+        let position = MetaData { lineno: 3, position: 2 };
+        assert_eq!(DialectError::UnknownKey(position).position(), Some(position));
+        assert_eq!(DialectError::EmptyCommand("left").position(), None);
+    }
+
+    #[test]
+    fn from_dialect() {
+        let dialect_error = DialectError::MissingCommand("end_loop");
+        assert!(match Error::from(dialect_error) {
+            Error::Dialect(error) => error == dialect_error,
+            _ => false,
         });
     }
 }
@@ -6,71 +6,730 @@
 
 //! Implementations of a generator for creating `C` code from intermediate compile results.
 
-use lexer::Token;
+use std::convert::TryFrom;
+use std::io;
+use std::io::Write;
+use std::slice;
+
+use Backend;
+use CellType;
+use EofBehavior;
+use Named;
+use Options;
+use Overflow;
+use Result;
+use TapeGrowth;
+use ir::Instruction;
+use ir::Operation;
+use ir::Program;
+use ir::Step;
+use ir::accessed_range;
+use ir::walk;
+use lexer::MetaData;
+
+/// The `C` preface of the generated tokens.
+pub static PREFACE: &str = include_str!("../resources/preface.c");
+
+/// The `C` declaration of a tape with a fixed size. `{tape_size}` must be replaced by the number of cells.
+static FIXED_TAPE: &str = "\
+static cell tape[{tape_size}];
+
+";
+
+/// The `C` function aborting the program if cells outside a tape with a fixed size are accessed.
+static CHECKED_FIXED_TAPE: &str = "\
+static inline cell *bf_reserve(cell *ptr, ptrdiff_t low, ptrdiff_t high, int id) {
+    ptrdiff_t position = ptr - tape;
+    if (position + low < 0) {
+        bf_fail(\"pointer underflow\", id);
+    }
+    if (position + high >= (ptrdiff_t) (sizeof(tape) / sizeof(cell))) {
+        bf_fail(\"pointer overflow\", id);
+    }
+    return ptr;
+}
+
+";
+
+/// The `C` function getting the cell `offset` cells away from the current cell on the tape, wrapping around at the
+/// tape's ends. `{length}` must be replaced by the expression for the tape's current number of cells.
+static WRAPPING_TAPE: &str = "\
+static inline cell *bf_wrap(cell *ptr, ptrdiff_t offset) {
+    size_t length = {length};
+    size_t distance = (size_t) (offset < 0 ? -offset : offset) % length;
+    size_t position = (size_t) (ptr - tape) + (offset < 0 ? length - distance : distance);
+    return tape + position % length;
+}
+
+";
+
+/// The `C` functions searching a tape with a fixed size for the next zero cell to the right and to the left of the
+/// current cell. Like the interpreter, they continue at the tape's other end if there is no zero cell before reaching
+/// the end, and never return if there is no zero cell at all.
+static SEARCHING_RUNTIME: &str = "\
+static inline cell *bf_search_right(cell *ptr) {
+    cell *zero = memchr(ptr, 0, sizeof(tape) - (ptr - tape));
+    if (!zero) {
+        zero = memchr(tape, 0, ptr - tape);
+    }
+    if (!zero) {
+        for (;;) {}
+    }
+    return zero;
+}
+
+static inline cell *bf_search_left(cell *ptr) {
+    cell *zero = memrchr(tape, 0, ptr - tape + 1);
+    if (!zero) {
+        zero = memrchr(ptr + 1, 0, sizeof(tape) - (ptr - tape) - 1);
+    }
+    if (!zero) {
+        for (;;) {}
+    }
+    return zero;
+}
+
+";
+
+/// The `C` declaration of a tape growing when the pointer leaves it, together with the functions for allocating and
+/// growing it. `{tape_size}` must be replaced by the initial number of cells, `{grow_left}` by the code computing the
+/// number of cells to add before the tape's start, if the tape grows to the left at all. `{id_parameter}` must be
+/// replaced by the parameter for reporting accesses before the tape's start, if these are checked, and `{check_left}`
+/// by the code handling them on a tape growing only to the right.
+static GROWING_TAPE: &str = "\
+static cell *tape;
+static size_t tape_size = {tape_size};
+
+static void *bf_check_allocation(void *memory) {
+    if (!memory) {
+        fputs(\"out of memory\\n\", stderr);
+        exit(1);
+    }
+    return memory;
+}
+
+static cell *bf_allocate(void) {
+    tape = bf_check_allocation(calloc(tape_size, sizeof(cell)));
+    return tape;
+}
+
+static cell *bf_grow(ptrdiff_t position, ptrdiff_t low, ptrdiff_t high) {
+    size_t before = 0;
+    size_t after = 0;
+{grow_left}    if (position + high >= (ptrdiff_t) tape_size) {
+        after = (size_t) (position + high) + 1 - tape_size;
+        after = after < tape_size ? tape_size : after;
+    }
+
+    tape = bf_check_allocation(realloc(tape, (before + tape_size + after) * sizeof(cell)));
+    memmove(tape + before, tape, tape_size * sizeof(cell));
+    memset(tape, 0, before * sizeof(cell));
+    memset(tape + before + tape_size, 0, after * sizeof(cell));
+    tape_size += before + after;
+    return tape + before + position;
+}
+
+static inline cell *bf_reserve(cell *ptr, ptrdiff_t low, ptrdiff_t high{id_parameter}) {
+    ptrdiff_t position = ptr - tape;
+    if (position + low >= 0 && position + high < (ptrdiff_t) tape_size) {
+        return ptr;
+    }
+{check_left}    return bf_grow(position, low, high);
+}
+
+";
+
+/// The `C` code computing the number of cells to add before the start of a tape growing to the left.
+static GROWING_LEFT: &str = "    if (position + low < 0) {
+        before = (size_t) -(position + low);
+        before = before < tape_size ? tape_size : before;
+    }
+";
+
+/// The `C` code reporting accesses before the start of a tape growing only to the right.
+static CHECKING_LEFT: &str = "    if (position + low < 0) {
+        bf_fail(\"pointer underflow\", id);
+    }
+";
+
+/// The `C` code wrapping a pointer before the start of a tape growing only to the right around to the tape's current
+/// end, like the interpreter. The other accessed cells before the start are wrapped when accessing them.
+static WRAPPING_LEFT: &str = "    if (position < 0) {
+        position %= (ptrdiff_t) tape_size;
+        position += position < 0 ? (ptrdiff_t) tape_size : 0;
+        if (position + high < (ptrdiff_t) tape_size) {
+            return tape + position;
+        }
+    }
+";
+
+/// The `C` declaration of the function aborting the program with an error message, which is defined after the program.
+static FAILING_DECLARATION: &str = "\
+static void bf_fail(const char *message, int id);
+
+";
+
+/// The `C` function aborting the program with an error message for the source position with the given `id`, following
+/// the program. The placeholder `{positions}` must be replaced by the initializers of all source positions.
+static FAILING_RUNTIME: &str = "\n\
+static const int bf_positions[][2] = {
+{positions}};
+
+static void bf_fail(const char *message, int id) {
+    fflush(stdout);
+    fprintf(stderr, \"%s at line %d, position %d\\n\", message, bf_positions[id][0], bf_positions[id][1]);
+    exit(3);
+}
+";
+
+/// The `C` function reading a character into a cell. `{eof}` must be replaced by the cell's new value at the end of the
+/// input.
+static READING_RUNTIME: &str = "\
+static inline cell bf_read(cell value) {
+    int character = getchar();
+    return character == EOF ? {eof} : (cell) character;
+}
+
+";
+
+/// The `C` functions changing cells with 64 bits that wrap around at their bounds.
+static WRAPPING_RUNTIME: &str = "\
+static inline cell bf_add(cell value, int64_t delta) {
+    return (cell) ((uint64_t) value + (uint64_t) delta);
+}
+
+static inline int64_t bf_mul(cell value, int64_t factor) {
+    return (int64_t) ((uint64_t) value * (uint64_t) factor);
+}
+
+";
+
+/// The `C` functions changing cells that saturate at their bounds. `{min}` and `{max}` must be replaced by the bounds.
+static SATURATING_RUNTIME: &str = "\
+static inline cell bf_add(cell value, int64_t delta) {
+    cell result;
+    if (__builtin_add_overflow(value, delta, &result)) {
+        return delta < 0 ? {min} : {max};
+    }
+    return result;
+}
+
+static inline int64_t bf_mul(cell value, int64_t factor) {
+    int64_t result;
+    if (__builtin_mul_overflow(value, factor, &result)) {
+        return (value < 0) == (factor < 0) ? INT64_MAX : INT64_MIN;
+    }
+    return result;
+}
+
+";
+
+/// The `C` functions changing cells that abort the program when leaving their bounds.
+static CHECKED_RUNTIME: &str = "\
+static inline cell bf_add(cell value, int64_t delta, int id) {
+    cell result;
+    if (__builtin_add_overflow(value, delta, &result)) {
+        bf_fail(\"cell overflow\", id);
+    }
+    return result;
+}
+
+static inline int64_t bf_mul(cell value, int64_t factor, int id) {
+    int64_t result;
+    if (__builtin_mul_overflow(value, factor, &result)) {
+        bf_fail(\"cell overflow\", id);
+    }
+    return result;
+}
+
+";
 
 /// A generator for creating `C` code from intermediate compile results.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct Generator {
 
+    /// The options controlling the generated code.
+    options: Options,
+
     /// The current level of indentation.
     indentation_level: u32,
+
+    /// The cells around the current cell are accessed through the runtime function wrapping around at the tape's ends.
+    wrapping: bool,
+}
+
+/// The `C` code generated for a program so far.
+struct Code<'a> {
+
+    /// The writer receiving the text of the code.
+    output: &'a mut dyn Write,
+
+    /// The result of writing the text, keeping the first error.
+    result: io::Result<()>,
+
+    /// The source positions reported by the code, indexed by the IDs passed to the runtime functions.
+    positions: Vec<MetaData>,
 }
 
 impl Generator {
-    /// Initialize a new generator.
+    /// Initialize a new generator with the default options.
     pub fn new() -> Generator {
+        Generator::with_options(Options::default())
+    }
+
+    /// Initialize a new generator with the given `options`.
+    pub fn with_options(options: Options) -> Generator {
         Generator {
+            options,
             indentation_level: 1,
+            wrapping: false,
         }
     }
 
-    /// Generate the `C` code from the given list of `tokens`, using the specified `template`.
-    pub fn generate(&mut self, template: &str, tokens: &[Token]) -> String {
-        let mut output = String::from(template);
+    /// Generate the `C` code from the given `program`, using the specified `template`.
+    ///
+    /// The placeholder `{cell_type}` in the `template` is replaced by the `C` type of the cells, `{runtime}` by the
+    /// declaration of the tape and the functions the generated code needs for working with it, and `{tape}` by the
+    /// expression for the tape's first cell.
+    pub fn generate(&mut self, template: &str, program: &Program) -> String {
+        let mut output: Vec<u8> = Vec::new();
+        self.generate_into(template, program, &mut output).expect("Writing to a vector does not fail.");
+        String::from_utf8(output).expect("The generated code is valid UTF-8.")
+    }
+
+    /// Generate the `C` code from the given `program`, using the specified `template`, and write it to the `output`
+    /// while generating it.
+    ///
+    /// The placeholders in the `template` are replaced as by `generate`. The code reporting errors refers to a table of
+    /// their source positions, which is written after the code.
+    pub fn generate_into(&mut self, template: &str, program: &Program, output: &mut dyn Write) -> io::Result<()> {
+        // The runtime functions precede the code, so they are chosen before generating it.
+        let searches: bool = self.can_search_memory() && walk(&program.instructions).any(|step| match step {
+            Step::Instruction(instruction, _) => match instruction.operation {
+                Operation::Scan { stride } => stride == 1 || stride == -1,
+                _ => false,
+            },
+            Step::LoopEnd(..) => false,
+        });
+        let tape: &str = match self.options.tape_growth {
+            TapeGrowth::Fixed => "tape",
+            TapeGrowth::Right | TapeGrowth::Both => "bf_allocate()",
+        };
+        let header = template.replace("{cell_type}", c_type(self.options.cell_type))
+            .replace("{runtime}", &self.runtime(searches))
+            .replace("{tape}", tape);
+        output.write_all(header.as_bytes())?;
 
-        for token in tokens {
-            match *token {
-                Token::Add(_, _) => {
-                    // Increment the value at the current cell.
-                    output.push_str(&self.indent("(*ptr)++;\n"));
-                },
-                Token::Sub(_, _) => {
-                    // Decrement the value at the current cell.
-                    output.push_str(&self.indent("(*ptr)--;\n"));
-                },
-                Token::Right(_, _) => {
-                    // Go to the right neighbor of the current cell.
-                    output.push_str(&self.indent("ptr++;\n"));
-                },
-                Token::Left(_, _) => {
-                    // Go to the left neighbor of the current cell.
-                    output.push_str(&self.indent("ptr--;\n"));
+        let mut code = Code { output, result: Ok(()), positions: Vec::new() };
+        self.generate_block(&program.instructions, &mut code);
+
+        // Add a closing bracket and a newline to the end of the code, followed by the reported source positions.
+        code.push_str("}\n");
+        let failing: String = self.failing_runtime(&code.positions);
+        code.push_str(&failing);
+
+        code.result
+    }
+
+    /// Generate the `C` code for the given list of `instructions` and the bodies of their loops, appending it to the
+    /// `output`.
+    fn generate_block(&mut self, instructions: &[Instruction], output: &mut Code) {
+        // The current cell is on the tape when entering a block, but the other accessed cells might not be.
+        let mut generated: usize = self.begin_segment(instructions, false, output);
+
+        for step in walk(instructions) {
+            let (instruction, following): (&Instruction, &[Instruction]) = match step {
+                Step::Instruction(..) if generated > 0 => {
+                    // The instruction has been generated with its segment.
+                    generated -= 1;
+                    continue;
                 },
-                Token::Read(_, _) => {
-                    // Read a single character into the current cell.
-                    output.push_str(&self.indent("*ptr = getchar();\n"));
+                Step::Instruction(instruction, following) => (instruction, following),
+                Step::LoopEnd(_, following) => {
+                    // Close the loop, which only ends on a cell on the tape.
+                    self.indentation_level -= 1;
+                    output.push_str(&self.indent("}\n"));
+                    generated = self.begin_segment(following, false, output);
+                    continue;
                 },
-                Token::Write(_, _) => {
-                    // Print the character of the current cell.
-                    output.push_str(&self.indent("putchar(*ptr);\n"));
+            };
+
+            // Checked accesses are reported at the accessing instruction. The target of a multiplication is checked
+            // separately, as the replaced loop only accesses it if the current cell is not zero.
+            let (low, high): (isize, isize) = match instruction.operation {
+                Operation::MulAdd { .. } => (instruction.offset.min(0), instruction.offset.max(0)),
+                _ => accessed_range(slice::from_ref(instruction), true),
+            };
+            if self.options.checked && (low != 0 || high != 0) {
+                self.check(low, high, instruction, output);
+            }
+
+            match instruction.operation {
+                Operation::Move(offset) => {
+                    // Go to another cell.
+                    output.push_str(&self.indent(&format!("{step};\n", step = self.step(offset))));
                 },
-                Token::BeginLoop(_, _) => {
-                    // Begin a loop at the current cell.
+                Operation::Loop(_) => {
+                    // Repeat the body as long as the current cell is not zero.
                     output.push_str(&self.indent("while (*ptr) {\n"));
                     self.indentation_level += 1;
                 },
-                Token::EndLoop(_, _) => {
-                    // Close the current loop.
-                    self.indentation_level -= 1;
-                    output.push_str(&self.indent("}\n"));
+                Operation::Scan { stride: 1 } if self.can_search_memory() => {
+                    // Search the tape to the right for the next zero cell.
+                    output.push_str(&self.indent("ptr = bf_search_right(ptr);\n"));
                 },
+                Operation::Scan { stride: -1 } if self.can_search_memory() => {
+                    // Search the tape to the left for the next zero cell.
+                    output.push_str(&self.indent("ptr = bf_search_left(ptr);\n"));
+                },
+                Operation::Scan { stride } => {
+                    // Step through the cells until reaching a zero cell.
+                    let step: String = self.step(stride);
+                    if self.options.tape_growth == TapeGrowth::Fixed && !self.options.checked {
+                        output.push_str(&self.indent(&format!("while (*ptr) {step};\n", step = step)));
+                    } else {
+                        output.push_str(&self.indent("while (*ptr) {\n"));
+                        self.indentation_level += 1;
+                        output.push_str(&self.indent(&format!("{step};\n", step = step)));
+                        if self.options.checked {
+                            self.check(0, 0, instruction, output);
+                        } else {
+                            output.push_str(&self.indent("ptr = bf_reserve(ptr, 0, 0);\n"));
+                        }
+                        self.indentation_level -= 1;
+                        output.push_str(&self.indent("}\n"));
+                    }
+                },
+                _ => self.operation(instruction, output),
+            }
+
+            // After moving the pointer, neither the current cell nor the other accessed cells might be on the tape.
+            // Scans only end on a cell on the tape.
+            match instruction.operation {
+                Operation::Loop(ref body) => generated = self.begin_segment(body, false, output),
+                Operation::Move(_) if self.options.checked => self.check(0, 0, instruction, output),
+                Operation::Move(_) => generated = self.begin_segment(following, true, output),
+                Operation::Scan { .. } => generated = self.begin_segment(following, false, output),
+                _ => {},
             }
         }
+    }
 
-        // Add a closing bracket and a newline to the end of the output.
-        output.push_str("}\n");
+    /// Generate the `C` code for the `instruction`, which neither moves the pointer nor is a loop, appending it to the
+    /// `output`.
+    fn operation(&mut self, instruction: &Instruction, output: &mut Code) {
+        let cell: String = self.cell(instruction.offset);
+        match instruction.operation {
+            Operation::Add(delta) if !self.has_native_arithmetic() => {
+                // Change the value at the cell using the runtime functions.
+                let position: String = output.position(instruction, self.options.overflow == Overflow::Error);
+                let line: String = format!("{cell} = bf_add({cell}, {delta}{position});\n",
+                                           cell = cell, delta = delta, position = position);
+                output.push_str(&self.indent(&line));
+            },
+            Operation::Add(1) => {
+                // Increment the value at the cell.
+                output.push_str(&self.indent(&format!("{cell}++;\n", cell = parenthesize(&cell))));
+            },
+            Operation::Add(-1) => {
+                // Decrement the value at the cell.
+                output.push_str(&self.indent(&format!("{cell}--;\n", cell = parenthesize(&cell))));
+            },
+            Operation::Add(delta) => {
+                // Change the value at the cell by more than one.
+                let sign: char = if delta < 0 { '-' } else { '+' };
+                let line: String = format!("{cell} {sign}= {value};\n",
+                                           cell = cell, sign = sign, value = delta.unsigned_abs());
+                output.push_str(&self.indent(&line));
+            },
+            Operation::Read => {
+                // Read a single character into the cell.
+                output.push_str(&self.indent(&format!("{cell} = bf_read({cell});\n", cell = cell)));
+            },
+            Operation::Write => {
+                // Print the character of the cell.
+                output.push_str(&self.indent(&format!("putchar({cell});\n", cell = cell)));
+            },
+            Operation::Clear => {
+                // Set the cell to zero.
+                output.push_str(&self.indent(&format!("{cell} = 0;\n", cell = cell)));
+            },
+            Operation::MulAdd { offset, factor } if !self.has_native_arithmetic() => {
+                // Add a multiple of the cell to another cell using the runtime functions.
+                let target: String = self.cell(instruction.offset.wrapping_add(offset));
+                let position: String = output.position(instruction, self.options.overflow == Overflow::Error);
+                let product: String = format!("bf_mul({cell}, {factor}{position})",
+                                              cell = cell, factor = factor, position = position);
+                let line: String = format!("{target} = bf_add({target}, {product}{position});\n",
+                                           target = target, product = product, position = position);
+                self.multiply(instruction, offset, &line, output);
+            },
+            Operation::MulAdd { offset, factor } => {
+                // Add a multiple of the cell to another cell.
+                let target: String = self.cell(instruction.offset.wrapping_add(offset));
+                let sign: char = if factor < 0 { '-' } else { '+' };
+                let product: String = match factor.unsigned_abs() {
+                    1 => cell,
+                    factor => format!("{cell} * {factor}", cell = cell, factor = factor),
+                };
+                let line: String = format!("{target} {sign}= {product};\n",
+                                           target = target, sign = sign, product = product);
+                self.multiply(instruction, offset, &line, output);
+            },
+            Operation::Move(_) | Operation::Loop(_) | Operation::Scan { .. } => {
+                unreachable!("Pointer movements and loops are generated by the block.");
+            },
+        }
+    }
+
+    /// Make sure that a growing tape contains all cells accessed by the `instructions` up to the next pointer
+    /// movement, appending the code to the `output`. If the pointer has not `moved`, the current cell is known to be
+    /// on the tape. Checked tapes are handled for each instruction instead. A tape growing only to the right wraps the
+    /// cells before its start around instead of reserving them.
+    fn reserve(&self, instructions: &[Instruction], moved: bool, output: &mut Code) {
+        if self.options.tape_growth == TapeGrowth::Fixed || self.options.checked {
+            return;
+        }
 
-        output
+        let (low, high): (isize, isize) = accessed_range(instructions, true);
+        let low: isize = if self.options.tape_growth == TapeGrowth::Right { 0 } else { low };
+        if moved || low != 0 || high != 0 {
+            let line: String = format!("ptr = bf_reserve(ptr, {low}, {high});\n", low = low, high = high);
+            output.push_str(&self.indent(&line));
+        }
+    }
+
+    /// Begin a segment of the `instructions` up to the next pointer movement, appending the code to the `output`. On a
+    /// growing tape, the accessed cells are reserved, where the current cell might not be on the tape if the pointer
+    /// has `moved`. On a tape wrapping around, the segment is generated right away, accessing the cells directly unless
+    /// they lie beyond the tape's ends. Returns the number of instructions generated this way.
+    fn begin_segment(&mut self, instructions: &[Instruction], moved: bool, output: &mut Code) -> usize {
+        self.reserve(instructions, moved, output);
+        if !self.options.is_wrapping() {
+            return 0;
+        }
+
+        // A tape growing to the right contains the cells after the current one once they are reserved.
+        let fixed: bool = self.options.tape_growth == TapeGrowth::Fixed;
+        let (low, high): (isize, isize) = accessed_range(instructions, true);
+        let high: isize = if fixed { high } else { 0 };
+        if low == 0 && high == 0 {
+            return 0;
+        }
+
+        let length: usize = instructions.iter()
+            .position(|instruction| {
+                matches!(instruction.operation, Operation::Move(_) | Operation::Loop(_) | Operation::Scan { .. })
+            })
+            .unwrap_or(instructions.len());
+        let segment: &[Instruction] = &instructions[..length];
+
+        // If the accessed cells do not fit on the tape at once, their offsets always wrap around its ends.
+        if fixed && high.saturating_sub(low) >= self.length() {
+            self.wrapping = true;
+            for instruction in segment {
+                self.operation(instruction, output);
+            }
+            self.wrapping = false;
+            return length;
+        }
+
+        let mut conditions: Vec<String> = Vec::new();
+        if low != 0 {
+            conditions.push(format!("ptr - tape >= {distance}", distance = low.unsigned_abs()));
+        }
+        if high != 0 {
+            conditions.push(format!("ptr - tape < {rest}", rest = self.length() - high));
+        }
+
+        // Access the cells directly if all of them are on the tape, and wrap their offsets around its ends otherwise.
+        output.push_str(&self.indent(&format!("if ({conditions}) {{\n", conditions = conditions.join(" && "))));
+        self.indentation_level += 1;
+        for instruction in segment {
+            self.operation(instruction, output);
+        }
+        self.indentation_level -= 1;
+        output.push_str(&self.indent("} else {\n"));
+        self.indentation_level += 1;
+        self.wrapping = true;
+        for instruction in segment {
+            self.operation(instruction, output);
+        }
+        self.wrapping = false;
+        self.indentation_level -= 1;
+        output.push_str(&self.indent("}\n"));
+
+        length
+    }
+
+    /// Make sure that the cells from `low` to `high` around the current cell are on a checked tape, growing it if
+    /// possible, and reporting the `instruction`'s position otherwise. The code is appended to the `output`.
+    fn check(&mut self, low: isize, high: isize, instruction: &Instruction, output: &mut Code) {
+        let position: String = output.position(instruction, true);
+        let line: String = format!("ptr = bf_reserve(ptr, {low}, {high}{position});\n",
+                                   low = low, high = high, position = position);
+        output.push_str(&self.indent(&line));
+    }
+
+    /// Append the `line` adding a multiple of the `instruction`'s cell to the cell `offset` cells away from it to the
+    /// `output`. On checked tapes, the target cell is only checked and changed if the instruction's cell is not zero.
+    fn multiply(&mut self, instruction: &Instruction, offset: isize, line: &str, output: &mut Code) {
+        if !self.options.checked {
+            output.push_str(&self.indent(line));
+            return;
+        }
+
+        let target: isize = instruction.offset.wrapping_add(offset);
+        output.push_str(&self.indent(&format!("if ({cell}) {{\n", cell = self.cell(instruction.offset))));
+        self.indentation_level += 1;
+        self.check(target.min(0), target.max(0), instruction, output);
+        output.push_str(&self.indent(line));
+        self.indentation_level -= 1;
+        output.push_str(&self.indent("}\n"));
+    }
+
+    /// Get the `C` expression for the cell `offset` cells away from the current cell. While `wrapping`, the offset
+    /// wraps around at the tape's ends, of which a tape growing to the right only has its start.
+    fn cell(&self, offset: isize) -> String {
+        let wrapping: bool = self.wrapping && (offset < 0 || self.options.tape_growth == TapeGrowth::Fixed);
+        match offset {
+            0 => String::from("*ptr"),
+            _ if wrapping => match self.wrap(offset) {
+                0 => String::from("*ptr"),
+                offset => format!("*bf_wrap(ptr, {offset})", offset = offset),
+            },
+            _ => format!("ptr[{offset}]", offset = offset),
+        }
+    }
+
+    /// Get the `C` expression moving the pointer by `offset` cells, wrapping around at the ends of a tape with a fixed
+    /// size that is not checked.
+    fn step(&self, offset: isize) -> String {
+        match offset {
+            _ if !self.options.is_reserving() => match self.wrap(offset) {
+                offset if offset < 0 => format!("ptr = ptr - tape >= {distance} ? ptr - {distance} : ptr + {rest}",
+                                                distance = offset.unsigned_abs(), rest = self.length() + offset),
+                offset => format!("ptr = ptr - tape < {rest} ? ptr + {offset} : ptr - {rest}",
+                                  rest = self.length() - offset, offset = offset),
+            },
+            1 => String::from("ptr++"),
+            -1 => String::from("ptr--"),
+            _ => {
+                let sign: char = if offset < 0 { '-' } else { '+' };
+                format!("ptr {sign}= {value}", sign = sign, value = offset.unsigned_abs())
+            },
+        }
+    }
+
+    /// Reduce the `offset` to less than the size of a tape with a fixed size that is not checked, keeping its sign.
+    /// Other offsets are returned unchanged.
+    fn wrap(&self, offset: isize) -> isize {
+        if self.options.is_reserving() {
+            return offset;
+        }
+
+        offset % self.length()
+    }
+
+    /// Get the initial size of the tape as an offset.
+    fn length(&self) -> isize {
+        isize::try_from(self.options.tape_size.max(1)).unwrap_or(isize::MAX)
+    }
+
+    /// Determine if scans with a stride of one can search the tape's memory directly, i.e. if the cells are bytes and
+    /// the tape's size is known at compile time and does not have to be checked.
+    fn can_search_memory(&self) -> bool {
+        self.options.cell_type == CellType::U8 && !self.options.is_reserving()
+    }
+
+    /// Determine if the arithmetic operators of `C` handle overflows as required by the options, i.e. if the cells are
+    /// unsigned and wrap around.
+    fn has_native_arithmetic(&self) -> bool {
+        self.options.overflow == Overflow::Wrap && !self.options.cell_type.is_signed()
+    }
+
+    /// Get the declaration of the tape and the `C` functions needed by the code for working with it and for reading
+    /// input. If the code `searches` the tape's memory for zero cells, the functions doing so are included.
+    fn runtime(&self, searches: bool) -> String {
+        let checked: bool = self.options.checked;
+        let tape_size: String = self.options.tape_size.max(1).to_string();
+        let id_parameter: &str = if checked { ", int id" } else { "" };
+        let fixed_wrapping: String = WRAPPING_TAPE.replace("{length}", "sizeof(tape) / sizeof(cell)");
+        let tape: String = match self.options.tape_growth {
+            TapeGrowth::Fixed if checked => FIXED_TAPE.replace("{tape_size}", &tape_size) + CHECKED_FIXED_TAPE,
+            TapeGrowth::Fixed if searches => {
+                FIXED_TAPE.replace("{tape_size}", &tape_size) + &fixed_wrapping + SEARCHING_RUNTIME
+            },
+            TapeGrowth::Fixed => FIXED_TAPE.replace("{tape_size}", &tape_size) + &fixed_wrapping,
+            TapeGrowth::Right if checked => GROWING_TAPE.replace("{tape_size}", &tape_size)
+                .replace("{grow_left}", "")
+                .replace("{id_parameter}", id_parameter)
+                .replace("{check_left}", CHECKING_LEFT),
+            TapeGrowth::Right => GROWING_TAPE.replace("{tape_size}", &tape_size)
+                .replace("{grow_left}", "")
+                .replace("{id_parameter}", "")
+                .replace("{check_left}", WRAPPING_LEFT) + &WRAPPING_TAPE.replace("{length}", "tape_size"),
+            TapeGrowth::Both => GROWING_TAPE.replace("{tape_size}", &tape_size)
+                .replace("{grow_left}", GROWING_LEFT)
+                .replace("{id_parameter}", id_parameter)
+                .replace("{check_left}", ""),
+        };
+
+        let eof: &str = match self.options.eof {
+            EofBehavior::Zero => "0",
+            EofBehavior::MinusOne => "(cell) -1",
+            EofBehavior::Unchanged => "value",
+        };
+
+        let reading: String = READING_RUNTIME.replace("{eof}", eof);
+        let failing: &str = if self.reports_errors() { FAILING_DECLARATION } else { "" };
+        String::from(failing) + &tape + &reading + &self.arithmetic_runtime()
+    }
+
+    /// Get the table of source positions and the `C` function reporting errors at them, if the generated code reports
+    /// any errors.
+    fn failing_runtime(&self, positions: &[MetaData]) -> String {
+        if !self.reports_errors() {
+            return String::new();
+        }
+
+        // Empty initializers are not allowed in `C`.
+        let positions: String = if positions.is_empty() {
+            String::from("    {0, 0},\n")
+        } else {
+            positions.iter()
+                .map(|position| format!("    {{{lineno}, {position}}},\n",
+                                        lineno = position.lineno, position = position.position))
+                .collect()
+        };
+
+        FAILING_RUNTIME.replace("{positions}", &positions)
+    }
+
+    /// Determine if the generated code aborts the program with errors reported at source positions.
+    fn reports_errors(&self) -> bool {
+        self.options.is_checked() || self.options.overflow == Overflow::Error
+    }
+
+    /// Get the `C` functions needed for changing cells if `C`'s arithmetic operators cannot be used.
+    fn arithmetic_runtime(&self) -> String {
+        if self.has_native_arithmetic() {
+            return String::new();
+        }
+
+        match self.options.overflow {
+            Overflow::Wrap => String::from(WRAPPING_RUNTIME),
+            Overflow::Saturate => {
+                let (min, max): (&str, &str) = match self.options.cell_type {
+                    CellType::U8 => ("0", "UINT8_MAX"),
+                    CellType::U16 => ("0", "UINT16_MAX"),
+                    CellType::U32 => ("0", "UINT32_MAX"),
+                    CellType::I64 => ("INT64_MIN", "INT64_MAX"),
+                };
+                SATURATING_RUNTIME.replace("{min}", min).replace("{max}", max)
+            },
+            Overflow::Error => String::from(CHECKED_RUNTIME),
+        }
     }
 
     /// Indent the given `line` respective to the current indentation level.
@@ -85,17 +744,85 @@ impl Generator {
     }
 }
 
+impl Named for Generator {
+    fn name(&self) -> &str {
+        "c"
+    }
+}
+
+impl Backend for Generator {
+    fn extension(&self) -> &str {
+        "c"
+    }
+
+    fn emit(&self, program: &Program, options: &Options, output: &mut dyn Write) -> Result<()> {
+        Generator::with_options(*options).generate_into(PREFACE, program, output)?;
+        Ok(())
+    }
+}
+
+impl<'a> Code<'a> {
+    /// Append the `text` to the code, unless writing has already failed.
+    fn push_str(&mut self, text: &str) {
+        if self.result.is_ok() {
+            self.result = self.output.write_all(text.as_bytes());
+        }
+    }
+
+    /// Get the additional argument passing the ID of the `instruction`'s source position to the runtime functions,
+    /// adding the position to the table. Empty unless the position might be `reported`.
+    fn position(&mut self, instruction: &Instruction, reported: bool) -> String {
+        if !reported {
+            return String::new();
+        }
+
+        self.positions.push(instruction.span.start);
+        format!(", {id}", id = self.positions.len() - 1)
+    }
+}
+
+/// Get the `C` type of cells of the given `cell_type`.
+fn c_type(cell_type: CellType) -> &'static str {
+    match cell_type {
+        CellType::U8 => "uint8_t",
+        CellType::U16 => "uint16_t",
+        CellType::U32 => "uint32_t",
+        CellType::I64 => "int64_t",
+    }
+}
+
+/// Wrap the `C` expression for a `cell` in parentheses if it is a dereferenced pointer.
+fn parenthesize(cell: &str) -> String {
+    if cell.starts_with('*') {
+        format!("({cell})", cell = cell)
+    } else {
+        String::from(cell)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
+    use Backend;
+    use CellType;
+    use EofBehavior;
     use Generator;
+    use Instruction;
     use MetaData;
+    use Named;
+    use Operation;
+    use Options;
+    use Overflow;
+    use Program;
+    use Span;
+    use TapeGrowth;
     use Token;
+    use generator::PREFACE;
 
     #[test]
     fn test_new() {
         let generator = Generator::new();
-        assert_eq!(generator, Generator { indentation_level: 1 })
+        assert_eq!(generator, Generator { options: Options::default(), indentation_level: 1, wrapping: false })
     }
 
     #[test]
@@ -104,20 +831,438 @@ mod tests {
         let tokens: Vec<Token> = vec![
             Token::Add(String::from("+"), MetaData{ lineno: 1, position: 1 }),
             Token::Right(String::from(">"), MetaData{ lineno: 1, position: 2 }),
-            Token::Left(String::from("<"), MetaData{ lineno: 1, position: 3 }),
-            Token::BeginLoop(String::from("["), MetaData{ lineno: 1, position: 4 }),
-            Token::Read(String::from(","), MetaData{ lineno: 1, position: 5 }),
-            Token::Sub(String::from("-"), MetaData{ lineno: 1, position: 6 }),
-            Token::EndLoop(String::from("]"), MetaData{ lineno: 1, position: 7 }),
-            Token::Write(String::from("."), MetaData{ lineno: 1, position: 8 }),
+            Token::Right(String::from(">"), MetaData{ lineno: 1, position: 3 }),
+            Token::Left(String::from("<"), MetaData{ lineno: 1, position: 4 }),
+            Token::BeginLoop(String::from("["), MetaData{ lineno: 1, position: 5 }),
+            Token::Read(String::from(","), MetaData{ lineno: 1, position: 6 }),
+            Token::Sub(String::from("-"), MetaData{ lineno: 1, position: 7 }),
+            Token::Sub(String::from("-"), MetaData{ lineno: 1, position: 8 }),
+            Token::Left(String::from("<"), MetaData{ lineno: 1, position: 9 }),
+            Token::Left(String::from("<"), MetaData{ lineno: 1, position: 10 }),
+            Token::Add(String::from("+"), MetaData{ lineno: 1, position: 11 }),
+            Token::Add(String::from("+"), MetaData{ lineno: 1, position: 12 }),
+            Token::Add(String::from("+"), MetaData{ lineno: 1, position: 13 }),
+            Token::Right(String::from(">"), MetaData{ lineno: 1, position: 14 }),
+            Token::Right(String::from(">"), MetaData{ lineno: 1, position: 15 }),
+            Token::Right(String::from(">"), MetaData{ lineno: 1, position: 16 }),
+            Token::EndLoop(String::from("]"), MetaData{ lineno: 1, position: 17 }),
+            Token::Write(String::from("."), MetaData{ lineno: 1, position: 18 }),
         ];
+        let program = Program::new(&tokens).unwrap();
         let expected = String::from(include_str!("../resources/tests/generator_test_generate.c"));
 
         let mut generator = Generator::new();
-        let generated: String = generator.generate(template, &tokens);
+        let generated: String = generator.generate(template, &program);
         assert_eq!(generated, expected);
     }
 
+    #[test]
+    fn test_generate_optimized() {
+        let template: &str = "{\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::MulAdd { offset: 1, factor: 1 }, span),
+                Instruction::new(Operation::MulAdd { offset: -2, factor: -1 }, span),
+                Instruction::new(Operation::MulAdd { offset: 3, factor: 4 }, span),
+                Instruction::new(Operation::MulAdd { offset: 4, factor: -5 }, span),
+                Instruction::new(Operation::Clear, span),
+                Instruction::at_offset(Operation::Add(1), 2, span),
+                Instruction::at_offset(Operation::Add(-3), -1, span),
+                Instruction::at_offset(Operation::MulAdd { offset: -2, factor: 2 }, 2, span),
+                Instruction::at_offset(Operation::Clear, 2, span),
+                Instruction::new(Operation::Move(3), span),
+                Instruction::new(Operation::Scan { stride: 1 }, span),
+                Instruction::new(Operation::Scan { stride: -1 }, span),
+                Instruction::new(Operation::Scan { stride: 4 }, span),
+                Instruction::new(Operation::Scan { stride: -2 }, span),
+            ],
+        };
+        let expected: &str = "{
+    if (ptr - tape >= 2 && ptr - tape < 29996) {
+        ptr[1] += *ptr;
+        ptr[-2] -= *ptr;
+        ptr[3] += *ptr * 4;
+        ptr[4] -= *ptr * 5;
+        *ptr = 0;
+        ptr[2]++;
+        ptr[-1] -= 3;
+        *ptr += ptr[2] * 2;
+        ptr[2] = 0;
+    } else {
+        *bf_wrap(ptr, 1) += *ptr;
+        *bf_wrap(ptr, -2) -= *ptr;
+        *bf_wrap(ptr, 3) += *ptr * 4;
+        *bf_wrap(ptr, 4) -= *ptr * 5;
+        *ptr = 0;
+        (*bf_wrap(ptr, 2))++;
+        *bf_wrap(ptr, -1) -= 3;
+        *ptr += *bf_wrap(ptr, 2) * 2;
+        *bf_wrap(ptr, 2) = 0;
+    }
+    ptr = ptr - tape < 29997 ? ptr + 3 : ptr - 29997;
+    ptr = bf_search_right(ptr);
+    ptr = bf_search_left(ptr);
+    while (*ptr) ptr = ptr - tape < 29996 ? ptr + 4 : ptr - 29996;
+    while (*ptr) ptr = ptr - tape >= 2 ? ptr - 2 : ptr + 29998;
+}
+";
+
+        let mut generator = Generator::new();
+        assert_eq!(generator.generate(template, &program), expected);
+    }
+
+    #[test]
+    fn test_generate_cell_types() {
+        let template: &str = "typedef {cell_type} cell;\n{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 2, position: 3 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(-2), 1, span),
+                Instruction::new(Operation::MulAdd { offset: 1, factor: 3 }, span),
+                Instruction::new(Operation::Scan { stride: 1 }, span),
+            ],
+        };
+
+        let options = Options { cell_type: CellType::U16, ..Options::default() };
+        let generated: String = Generator::with_options(options).generate(template, &program);
+        assert_eq!(generated, "typedef uint16_t cell;
+static cell tape[30000];
+
+static inline cell *bf_wrap(cell *ptr, ptrdiff_t offset) {
+    size_t length = sizeof(tape) / sizeof(cell);
+    size_t distance = (size_t) (offset < 0 ? -offset : offset) % length;
+    size_t position = (size_t) (ptr - tape) + (offset < 0 ? length - distance : distance);
+    return tape + position % length;
+}
+
+static inline cell bf_read(cell value) {
+    int character = getchar();
+    return character == EOF ? value : (cell) character;
+}
+
+{
+    if (ptr - tape < 29999) {
+        ptr[1] -= 2;
+        ptr[1] += *ptr * 3;
+    } else {
+        *bf_wrap(ptr, 1) -= 2;
+        *bf_wrap(ptr, 1) += *ptr * 3;
+    }
+    while (*ptr) ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
+}
+");
+
+        let options = Options { cell_type: CellType::I64, ..Options::default() };
+        let generated: String = Generator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("typedef int64_t cell;\n"));
+        assert!(generated.contains("static inline cell bf_add(cell value, int64_t delta) {"));
+        assert!(generated.ends_with("{
+    if (ptr - tape < 29999) {
+        ptr[1] = bf_add(ptr[1], -2);
+        ptr[1] = bf_add(ptr[1], bf_mul(*ptr, 3));
+    } else {
+        *bf_wrap(ptr, 1) = bf_add(*bf_wrap(ptr, 1), -2);
+        *bf_wrap(ptr, 1) = bf_add(*bf_wrap(ptr, 1), bf_mul(*ptr, 3));
+    }
+    while (*ptr) ptr = ptr - tape < 29999 ? ptr + 1 : ptr - 29999;
+}
+"));
+    }
+
+    #[test]
+    fn test_generate_search() {
+        let template: &str = "{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Scan { stride: 1 }, span),
+                Instruction::new(Operation::Scan { stride: -1 }, span),
+            ],
+        };
+
+        // Searches continue at the tape's other end instead of using the null pointer of a failed search.
+        let generated: String = Generator::new().generate(template, &program);
+        assert!(generated.contains("    if (!zero) {\n        zero = memchr(tape, 0, ptr - tape);\n    }\n"));
+        assert!(generated.contains("zero = memrchr(ptr + 1, 0, sizeof(tape) - (ptr - tape) - 1);\n"));
+        assert!(generated.ends_with("{\n    ptr = bf_search_right(ptr);\n    ptr = bf_search_left(ptr);\n}\n"));
+
+        let program = Program { instructions: vec![Instruction::new(Operation::Scan { stride: 2 }, span)] };
+        let generated: String = Generator::new().generate(template, &program);
+        assert!(!generated.contains("bf_search"));
+    }
+
+    #[test]
+    fn test_generate_overflow() {
+        let template: &str = "{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 2, position: 3 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(5), span),
+                Instruction::new(Operation::MulAdd { offset: -1, factor: -1 }, span),
+            ],
+        };
+
+        let options = Options { overflow: Overflow::Saturate, ..Options::default() };
+        let generated: String = Generator::with_options(options).generate(template, &program);
+        assert!(generated.contains("return delta < 0 ? 0 : UINT8_MAX;"));
+        assert!(generated.ends_with("{
+    if (ptr - tape >= 1) {
+        *ptr = bf_add(*ptr, 5);
+        ptr[-1] = bf_add(ptr[-1], bf_mul(*ptr, -1));
+    } else {
+        *ptr = bf_add(*ptr, 5);
+        *bf_wrap(ptr, -1) = bf_add(*bf_wrap(ptr, -1), bf_mul(*ptr, -1));
+    }
+}
+"));
+
+        let options = Options { overflow: Overflow::Error, ..Options::default() };
+        let generated: String = Generator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("static void bf_fail(const char *message, int id);\n\n"));
+        assert!(generated.contains("{
+    if (ptr - tape >= 1) {
+        *ptr = bf_add(*ptr, 5, 0);
+        ptr[-1] = bf_add(ptr[-1], bf_mul(*ptr, -1, 1), 1);
+    } else {
+        *ptr = bf_add(*ptr, 5, 2);
+        *bf_wrap(ptr, -1) = bf_add(*bf_wrap(ptr, -1), bf_mul(*ptr, -1, 3), 3);
+    }
+}
+
+static const int bf_positions[][2] = {
+    {2, 3},
+    {2, 3},
+    {2, 3},
+    {2, 3},
+};
+"));
+    }
+
+    #[test]
+    fn test_generate_eof() {
+        let template: &str = "{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![Instruction::at_offset(Operation::Read, 2, span)],
+        };
+
+        let expectations: [(EofBehavior, &str); 3] = [
+            (EofBehavior::Zero, "return character == EOF ? 0 : (cell) character;"),
+            (EofBehavior::MinusOne, "return character == EOF ? (cell) -1 : (cell) character;"),
+            (EofBehavior::Unchanged, "return character == EOF ? value : (cell) character;"),
+        ];
+        for &(eof, expected) in expectations.iter() {
+            let options = Options { eof, ..Options::default() };
+            let generated: String = Generator::with_options(options).generate(template, &program);
+            assert!(generated.contains(expected));
+            assert!(generated.ends_with("{
+    if (ptr - tape < 29998) {
+        ptr[2] = bf_read(ptr[2]);
+    } else {
+        *bf_wrap(ptr, 2) = bf_read(*bf_wrap(ptr, 2));
+    }
+}
+"));
+        }
+    }
+
+    #[test]
+    fn test_generate_wrapping_tape() {
+        let template: &str = "{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), 5, span),
+                Instruction::at_offset(Operation::MulAdd { offset: -3, factor: 2 }, -1, span),
+                Instruction::at_offset(Operation::Write, 4, span),
+                Instruction::new(Operation::Move(-6), span),
+                Instruction::new(Operation::Scan { stride: 9 }, span),
+            ],
+        };
+
+        // Offsets are reduced to less than the tape's size, and the runtime function wraps the remaining distance. The
+        // accessed cells do not fit on the tape at once, so there is no direct access.
+        let options = Options { tape_size: 4, ..Options::default() };
+        let generated: String = Generator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("static cell tape[4];\n\nstatic inline cell *bf_wrap("));
+        assert!(generated.ends_with("{
+    (*bf_wrap(ptr, 1))++;
+    *ptr += *bf_wrap(ptr, -1) * 2;
+    putchar(*ptr);
+    ptr = ptr - tape >= 2 ? ptr - 2 : ptr + 2;
+    while (*ptr) ptr = ptr - tape < 3 ? ptr + 1 : ptr - 3;
+}
+"));
+
+        // Checked tapes are accessed directly.
+        let options = Options { tape_size: 4, checked: true, ..Options::default() };
+        let generated: String = Generator::with_options(options).generate(template, &program);
+        assert!(!generated.contains("bf_wrap"));
+    }
+
+    #[test]
+    fn test_generate_growing_tape() {
+        let template: &str = "{runtime}{\n    cell *ptr = {tape};\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), -1, span),
+                Instruction::new(Operation::Move(2), span),
+                Instruction::at_offset(Operation::Add(1), 3, span),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::at_offset(Operation::MulAdd { offset: -3, factor: 2 }, 1, span),
+                    Instruction::new(Operation::Move(1), span),
+                ]), span),
+                Instruction::new(Operation::Scan { stride: 1 }, span),
+                Instruction::at_offset(Operation::Write, -2, span),
+            ],
+        };
+
+        let options = Options { tape_size: 16, tape_growth: TapeGrowth::Right, ..Options::default() };
+        let generated: String = Generator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("static cell *tape;\nstatic size_t tape_size = 16;\n"));
+        assert!(!generated.contains("before = (size_t) -(position + low);"));
+
+        // Like the interpreter, the cells before the tape's start wrap around to its current end.
+        assert!(generated.contains("    if (position < 0) {\n        position %= (ptrdiff_t) tape_size;\n"));
+        assert!(generated.contains("    size_t length = tape_size;\n"));
+        assert!(generated.ends_with("{
+    cell *ptr = bf_allocate();
+    if (ptr - tape >= 1) {
+        ptr[-1]++;
+    } else {
+        (*bf_wrap(ptr, -1))++;
+    }
+    ptr += 2;
+    ptr = bf_reserve(ptr, 0, 3);
+    ptr[3]++;
+    while (*ptr) {
+        ptr = bf_reserve(ptr, 0, 1);
+        if (ptr - tape >= 2) {
+            ptr[-2] += ptr[1] * 2;
+        } else {
+            *bf_wrap(ptr, -2) += ptr[1] * 2;
+        }
+        ptr++;
+        ptr = bf_reserve(ptr, 0, 0);
+    }
+    while (*ptr) {
+        ptr++;
+        ptr = bf_reserve(ptr, 0, 0);
+    }
+    if (ptr - tape >= 2) {
+        putchar(ptr[-2]);
+    } else {
+        putchar(*bf_wrap(ptr, -2));
+    }
+}
+"));
+
+        let options = Options { tape_growth: TapeGrowth::Both, ..Options::default() };
+        let generated: String = Generator::with_options(options).generate(template, &program);
+        assert!(generated.contains("    if (position + low < 0) {\n        before = (size_t) -(position + low);\n"));
+        assert!(!generated.contains("bf_wrap"));
+        assert!(generated.ends_with("{
+    cell *ptr = bf_allocate();
+    ptr = bf_reserve(ptr, -1, 0);
+    ptr[-1]++;
+    ptr += 2;
+    ptr = bf_reserve(ptr, 0, 3);
+    ptr[3]++;
+    while (*ptr) {
+        ptr = bf_reserve(ptr, -2, 1);
+        ptr[-2] += ptr[1] * 2;
+        ptr++;
+        ptr = bf_reserve(ptr, 0, 0);
+    }
+    while (*ptr) {
+        ptr++;
+        ptr = bf_reserve(ptr, 0, 0);
+    }
+    ptr = bf_reserve(ptr, -2, 0);
+    putchar(ptr[-2]);
+}
+"));
+    }
+
+    #[test]
+    fn test_generate_checked() {
+        let template: &str = "{runtime}{\n";
+        let position = |lineno: usize, position: usize| Span::at(MetaData { lineno, position });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), -1, position(1, 1)),
+                Instruction::new(Operation::Write, position(1, 2)),
+                Instruction::new(Operation::Move(2), position(1, 3)),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::at_offset(Operation::MulAdd { offset: -3, factor: 2 }, 1, position(2, 1)),
+                    Instruction::new(Operation::Scan { stride: 1 }, position(2, 5)),
+                ]), position(1, 4)),
+            ],
+        };
+
+        let options = Options { checked: true, ..Options::default() };
+        let generated: String = Generator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("static void bf_fail(const char *message, int id);\n\nstatic cell tape["));
+        assert!(generated.contains("static cell tape[30000];\n\nstatic inline cell *bf_reserve("));
+        assert!(generated.contains("{
+    ptr = bf_reserve(ptr, -1, 0, 0);
+    ptr[-1]++;
+    putchar(*ptr);
+    ptr += 2;
+    ptr = bf_reserve(ptr, 0, 0, 1);
+    while (*ptr) {
+        ptr = bf_reserve(ptr, 0, 1, 2);
+        if (ptr[1]) {
+            ptr = bf_reserve(ptr, -2, 0, 3);
+            ptr[-2] += ptr[1] * 2;
+        }
+        while (*ptr) {
+            ptr++;
+            ptr = bf_reserve(ptr, 0, 0, 4);
+        }
+    }
+}
+
+static const int bf_positions[][2] = {
+    {1, 1},
+    {1, 3},
+    {2, 1},
+    {2, 1},
+    {2, 5},
+};
+"));
+        assert!(generated.ends_with("    exit(3);\n}\n"));
+
+        let options = Options { checked: true, tape_growth: TapeGrowth::Right, ..Options::default() };
+        let generated: String = Generator::with_options(options).generate(template, &program);
+        assert!(generated.contains("cell *bf_reserve(cell *ptr, ptrdiff_t low, ptrdiff_t high, int id) {"));
+        assert!(generated.contains("bf_fail(\"pointer underflow\", id);"));
+        assert!(!generated.contains("bf_fail(\"pointer overflow\", id);"));
+    }
+
+    #[test]
+    fn test_emit() {
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(1), span),
+                Instruction::new(Operation::Loop(vec![Instruction::new(Operation::Write, span)]), span),
+            ],
+        };
+
+        let generator = Generator::new();
+        assert_eq!(generator.name(), "c");
+        assert_eq!(generator.extension(), "c");
+
+        // The backend uses the given options instead of the generator's.
+        let options = Options { overflow: Overflow::Error, ..Options::default() };
+        let mut output: Vec<u8> = Vec::new();
+        generator.emit(&program, &options, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), Generator::with_options(options).generate(PREFACE, &program));
+    }
+
     #[test]
     fn test_indent_level_0() {
         let line: &str = "fn test_indent_level_0() {}";
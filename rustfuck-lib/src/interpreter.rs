@@ -0,0 +1,558 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementations of an interpreter for directly executing intermediate compile results.
+
+use std::convert::TryFrom;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::iter;
+
+use CellType;
+use EofBehavior;
+use Error;
+use Options;
+use Overflow;
+use Result;
+use RuntimeError;
+use TapeGrowth;
+use ir::Instruction;
+use ir::Operation;
+use ir::Program;
+use lexer::MetaData;
+
+/// An interpreter for directly executing programs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Interpreter {
+
+    /// The options controlling the execution.
+    options: Options,
+
+    /// The cells of the memory tape. Each cell's value is kept within the range of the cell type.
+    tape: Vec<i64>,
+
+    /// The index of the current cell.
+    pointer: usize,
+}
+
+impl Interpreter {
+    /// Initialize a new interpreter with an empty tape and the default options.
+    pub fn new() -> Interpreter {
+        Interpreter::with_options(Options::default())
+    }
+
+    /// Initialize a new interpreter with an empty tape and the given `options`.
+    pub fn with_options(options: Options) -> Interpreter {
+        Interpreter {
+            options,
+            tape: vec![0; options.tape_size.max(1)],
+            pointer: 0,
+        }
+    }
+
+    /// Execute the given `program`, reading from `input` and writing to `output`.
+    ///
+    /// Moving the pointer past an end of the tape grows the tape if the options allow it, and wraps around to the other
+    /// end otherwise. Reading at the end of the `input` changes the current cell as specified in the options. Printing
+    /// a cell writes the lowest byte of its value. Fails with a runtime error if a cell overflows and the options
+    /// require overflows to be reported, or if a cell outside the tape is accessed in checked mode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustfuck_lib::Interpreter;
+    /// use rustfuck_lib::Lexer;
+    /// use rustfuck_lib::Program;
+    /// use rustfuck_lib::language::Brainfuck;
+    ///
+    /// let tokens = Brainfuck::new("++++++++[>++++++++<-]>+.").tokenize();
+    /// let program = Program::new(&tokens).unwrap();
+    /// let mut output: Vec<u8> = Vec::new();
+    ///
+    /// let mut interpreter = Interpreter::new();
+    /// interpreter.run(&program, &mut "".as_bytes(), &mut output).unwrap();
+    /// assert_eq!(output, b"A");
+    /// ```
+    pub fn run<R: Read, W: Write>(&mut self, program: &Program, input: &mut R, output: &mut W) -> Result<()> {
+        self.execute(&program.instructions, input, output)?;
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Execute the given list of `instructions`, reading from `input` and writing to `output`. The bodies of the loops
+    /// currently executed are kept on a stack, so deeply nested loops cannot overflow the call stack.
+    fn execute<R: Read, W: Write>(&mut self, instructions: &[Instruction], input: &mut R, output: &mut W)
+        -> Result<()>
+    {
+        // The blocks currently executed, innermost last, and the index of the next instruction in each of them. The
+        // first entry holds the top-level instructions, the others the bodies of loops.
+        let mut blocks: Vec<(&[Instruction], usize)> = vec![(instructions, 0)];
+        loop {
+            let depth: usize = blocks.len();
+            let (block, index): (&[Instruction], &mut usize) = match blocks.last_mut() {
+                Some(&mut (block, ref mut index)) => (block, index),
+                None => break,
+            };
+            let instruction: &Instruction = match block.get(*index) {
+                Some(instruction) => {
+                    *index += 1;
+                    instruction
+                },
+                None if depth > 1 && self.tape[self.pointer] != 0 => {
+                    // Repeat the body of the loop as long as the current cell is not zero.
+                    *index = 0;
+                    continue;
+                },
+                None => {
+                    let _ = blocks.pop();
+                    continue;
+                },
+            };
+
+            let position: MetaData = instruction.span.start;
+            let cell: usize = self.index(instruction.offset, position)?;
+            match instruction.operation {
+                Operation::Add(delta) => {
+                    // Change the value at the cell.
+                    self.tape[cell] = self.add(self.tape[cell], i128::from(delta), position)?;
+                },
+                Operation::Move(offset) => {
+                    // Go to another cell.
+                    self.pointer = self.index(offset, position)?;
+                },
+                Operation::Read => {
+                    // Read a single character into the cell.
+                    let mut buffer = [0_u8; 1];
+                    match input.read_exact(&mut buffer) {
+                        Ok(()) => self.tape[cell] = i64::from(buffer[0]),
+                        Err(ref error) if error.kind() == ErrorKind::UnexpectedEof => {
+                            let cell_type: CellType = self.options.cell_type;
+                            match self.options.eof {
+                                EofBehavior::Zero => self.tape[cell] = 0,
+                                EofBehavior::MinusOne if cell_type.is_signed() => self.tape[cell] = -1,
+                                EofBehavior::MinusOne => self.tape[cell] = cell_type.max(),
+                                EofBehavior::Unchanged => {},
+                            }
+                        },
+                        Err(error) => return Err(Error::from(error)),
+                    }
+                },
+                Operation::Write => {
+                    // Print the character of the cell.
+                    output.write_all(&self.tape[cell].to_le_bytes()[..1])?;
+                },
+                Operation::Loop(ref body) => {
+                    // Execute the body if the current cell is not zero.
+                    if self.tape[self.pointer] != 0 {
+                        blocks.push((body, 0));
+                    }
+                },
+                Operation::Clear => {
+                    // Set the cell to zero.
+                    self.tape[cell] = 0;
+                },
+                Operation::MulAdd { .. } if self.tape[cell] == 0 => {
+                    // Like the replaced loop, do not access the target cell if there is nothing to add.
+                },
+                Operation::MulAdd { offset, factor } => {
+                    // Add a multiple of the cell to another cell.
+                    // Growing the tape to reach the target may shift the indices of all cells.
+                    let target: usize = self.index(instruction.offset.wrapping_add(offset), position)?;
+                    let cell: usize = self.index(instruction.offset, position)?;
+                    let product: i128 = i128::from(self.tape[cell]) * i128::from(factor);
+                    self.tape[target] = self.add(self.tape[target], product, position)?;
+                },
+                Operation::Scan { stride } => {
+                    // Go to the next zero cell in steps of `stride` cells.
+                    self.scan(stride, position)?;
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add `delta` to the `value` of a cell, handling overflows according to the options. The `position` is reported if
+    /// the overflow is an error.
+    fn add(&self, value: i64, delta: i128, position: MetaData) -> Result<i64> {
+        let min: i128 = i128::from(self.options.cell_type.min());
+        let max: i128 = i128::from(self.options.cell_type.max());
+
+        let sum: i128 = i128::from(value) + delta;
+        let result: i128 = if sum < min || sum > max {
+            match self.options.overflow {
+                Overflow::Wrap => (sum - min).rem_euclid(max - min + 1) + min,
+                Overflow::Saturate => sum.clamp(min, max),
+                Overflow::Error => return Err(RuntimeError::CellOverflow(position).into()),
+            }
+        } else {
+            sum
+        };
+
+        Ok(i64::try_from(result).expect("The result is within the range of the cell type."))
+    }
+
+    /// Get the index of the cell `offset` cells away from the current cell.
+    ///
+    /// If the cell lies beyond an end of the tape, the tape grows in that direction if the options allow it, at least
+    /// doubling its size. Otherwise, the index wraps around to the other end, or, in checked mode, the instruction at
+    /// `position` fails with a runtime error. Growing the tape to the left shifts the indices of all cells, including
+    /// the pointer.
+    fn index(&mut self, offset: isize, position: MetaData) -> Result<usize> {
+        let length: usize = self.tape.len();
+        let distance: usize = offset.unsigned_abs();
+        let growth: TapeGrowth = self.options.tape_growth;
+
+        if offset >= 0 && self.pointer + distance >= length && growth != TapeGrowth::Fixed {
+            let index: usize = self.pointer + distance;
+            self.tape.resize((index + 1).max(length * 2), 0);
+            return Ok(index);
+        }
+
+        if offset < 0 && distance > self.pointer && growth == TapeGrowth::Both {
+            let extension: usize = (distance - self.pointer).max(length);
+            let _ = self.tape.splice(0..0, iter::repeat_n(0, extension));
+            self.pointer += extension;
+            return Ok(self.pointer - distance);
+        }
+
+        if self.options.checked {
+            if offset >= 0 && self.pointer + distance >= length {
+                return Err(RuntimeError::PointerOverflow(position).into());
+            }
+
+            if offset < 0 && distance > self.pointer {
+                return Err(RuntimeError::PointerUnderflow(position).into());
+            }
+        }
+
+        Ok(wrap(self.pointer, offset, length))
+    }
+
+    /// Move the pointer to the first zero cell reached from the current cell by moving in steps of `stride` cells.
+    ///
+    /// The tape is searched up to the end in the direction of the `stride` at once. Only if there is no zero cell on
+    /// the way, the search continues cell by cell beyond that end. The `position` is reported if this leaves the tape
+    /// in checked mode.
+    fn scan(&mut self, stride: isize, position: MetaData) -> Result<()> {
+        let step: usize = stride.unsigned_abs();
+        let found: Option<usize> = if stride < 0 {
+            self.tape[..=self.pointer].iter()
+                .rev()
+                .step_by(step)
+                .position(|&cell| cell == 0)
+                .map(|steps| self.pointer - steps * step)
+        } else {
+            self.tape[self.pointer..].iter()
+                .step_by(step)
+                .position(|&cell| cell == 0)
+                .map(|steps| self.pointer + steps * step)
+        };
+
+        match found {
+            Some(index) => self.pointer = index,
+            None => {
+                while self.tape[self.pointer] != 0 {
+                    self.pointer = self.index(stride, position)?;
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the index `offset` cells away from the `index` on a tape of the given `length`, wrapping around at the tape's
+/// ends.
+fn wrap(index: usize, offset: isize, length: usize) -> usize {
+    let distance: usize = offset.unsigned_abs() % length;
+    if offset < 0 {
+        (index + length - distance) % length
+    } else {
+        (index + distance) % length
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use CellType;
+    use EofBehavior;
+    use Error;
+    use Interpreter;
+    use Lexer;
+    use MetaData;
+    use Optimizer;
+    use Options;
+    use Overflow;
+    use Program;
+    use RuntimeError;
+    use TapeGrowth;
+    use language::Brainfuck;
+
+
+    /// Run the `Brainfuck` `source` with the given `input` on a new interpreter and return the output.
+    fn run(source: &str, input: &str) -> Vec<u8> {
+        let tokens = Brainfuck::new(source).tokenize();
+        let program = Program::new(&tokens).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.run(&program, &mut input.as_bytes(), &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_new() {
+        let interpreter = Interpreter::new();
+        assert_eq!(interpreter, Interpreter { options: Options::default(), tape: vec![0; 30_000], pointer: 0 });
+    }
+
+    #[test]
+    fn test_run_hello_world() {
+        let source: &str = include_str!("../../examples/hello.bf");
+        assert_eq!(run(source, ""), b"Hello World!\n");
+    }
+
+    #[test]
+    fn test_run_optimized() {
+        let tokens = Brainfuck::new("+++++[->++>---<<]>.>.<<+++[+]>>[-<+>]<<.>.").tokenize();
+        let program = Optimizer::new().optimize(Program::new(&tokens).unwrap());
+        let mut output: Vec<u8> = Vec::new();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.run(&program, &mut "".as_bytes(), &mut output).unwrap();
+        assert_eq!(output, [10, 241, 0, 251]);
+    }
+
+    #[test]
+    fn test_run_read() {
+        // Echo the input, incrementing each character.
+        assert_eq!(run(",+.,+.,+.", "HAL"), b"IBM");
+    }
+
+    #[test]
+    fn test_run_read_eof_unchanged() {
+        assert_eq!(run("+++++,.", ""), [5]);
+    }
+
+    #[test]
+    fn test_run_read_eof() {
+        let run = |eof: EofBehavior, cell_type: CellType| -> Vec<i64> {
+            let options = Options { eof, cell_type, ..Options::default() };
+            let tokens = Brainfuck::new(",>+++,").tokenize();
+            let program = Program::new(&tokens).unwrap();
+
+            let mut interpreter = Interpreter::with_options(options);
+            interpreter.run(&program, &mut "a".as_bytes(), &mut Vec::new()).unwrap();
+            interpreter.tape[..2].to_vec()
+        };
+
+        assert_eq!(run(EofBehavior::Zero, CellType::U8), [97, 0]);
+        assert_eq!(run(EofBehavior::MinusOne, CellType::U8), [97, 255]);
+        assert_eq!(run(EofBehavior::MinusOne, CellType::U16), [97, 65_535]);
+        assert_eq!(run(EofBehavior::MinusOne, CellType::I64), [97, -1]);
+        assert_eq!(run(EofBehavior::Unchanged, CellType::U8), [97, 3]);
+    }
+
+    #[test]
+    fn test_run_wrapping_cells() {
+        assert_eq!(run("-.+.", ""), [255, 0]);
+        assert_eq!(run(&("+".repeat(300) + "."), ""), [44]);
+    }
+
+    #[test]
+    fn test_run_cell_types() {
+        let run = |source: &str, cell_type: CellType| -> Vec<i64> {
+            let options = Options { cell_type, ..Options::default() };
+            let tokens = Brainfuck::new(source).tokenize();
+            let program = Optimizer::with_options(options).optimize(Program::new(&tokens).unwrap());
+
+            let mut interpreter = Interpreter::with_options(options);
+            interpreter.run(&program, &mut "".as_bytes(), &mut Vec::new()).unwrap();
+            interpreter.tape[..3].to_vec()
+        };
+
+        let source: &str = "-> +++++++++++++++++[->+++++++++++++++<]> [-<+++++++++++++++++>] <-";
+        assert_eq!(run(source, CellType::U8), [255, 238, 0]);
+        assert_eq!(run(source, CellType::U16), [65_535, 4_334, 0]);
+        assert_eq!(run(source, CellType::U32), [4_294_967_295, 4_334, 0]);
+        assert_eq!(run(source, CellType::I64), [-1, 4_334, 0]);
+    }
+
+    #[test]
+    fn test_run_overflow_saturate() {
+        let options = Options { overflow: Overflow::Saturate, ..Options::default() };
+        let tokens = Brainfuck::new("--.+.>++++++++++++++++[->++++++++++++++++<]>+.--.").tokenize();
+        let program = Optimizer::with_options(options).optimize(Program::new(&tokens).unwrap());
+        let mut output: Vec<u8> = Vec::new();
+
+        let mut interpreter = Interpreter::with_options(options);
+        interpreter.run(&program, &mut "".as_bytes(), &mut output).unwrap();
+        assert_eq!(output, [0, 1, 255, 253]);
+    }
+
+    #[test]
+    fn test_run_overflow_error() {
+        let options = Options { cell_type: CellType::U16, overflow: Overflow::Error, ..Options::default() };
+        let tokens = Brainfuck::new("+++.\n>---").tokenize();
+        let program = Program::new(&tokens).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+
+        let mut interpreter = Interpreter::with_options(options);
+        let result = interpreter.run(&program, &mut "".as_bytes(), &mut output);
+        assert!(match result {
+            Err(Error::Runtime(error)) => error == RuntimeError::CellOverflow(MetaData { lineno: 2, position: 2 }),
+            _ => false,
+        });
+        assert_eq!(output, [3]);
+    }
+
+    #[test]
+    fn test_run_wrapping_tape() {
+        let mut interpreter = Interpreter::new();
+        let tokens = Brainfuck::new("<+").tokenize();
+        let program = Program::new(&tokens).unwrap();
+        interpreter.run(&program, &mut "".as_bytes(), &mut Vec::new()).unwrap();
+
+        assert_eq!(interpreter.pointer, 29_999);
+        assert_eq!(interpreter.tape[29_999], 1);
+    }
+
+    #[test]
+    fn test_run_checked_tape() {
+        let run = |source: &str, tape_growth: TapeGrowth| -> Option<RuntimeError> {
+            let options = Options { checked: true, tape_size: 4, tape_growth, ..Options::default() };
+            let tokens = Brainfuck::new(source).tokenize();
+            let program = Optimizer::with_options(options).optimize(Program::new(&tokens).unwrap());
+
+            let mut interpreter = Interpreter::with_options(options);
+            match interpreter.run(&program, &mut "".as_bytes(), &mut Vec::new()) {
+                Ok(()) => None,
+                Err(Error::Runtime(error)) => Some(error),
+                Err(error) => panic!("Unexpected error: {}", error),
+            }
+        };
+
+        let position = |lineno: usize, position: usize| MetaData { lineno, position };
+        assert_eq!(run(">>>+<<<", TapeGrowth::Fixed), None);
+        assert_eq!(run("+\n<+", TapeGrowth::Fixed), Some(RuntimeError::PointerUnderflow(position(2, 2))));
+        assert_eq!(run(">>>>+", TapeGrowth::Fixed), Some(RuntimeError::PointerOverflow(position(1, 5))));
+        assert_eq!(run("+[>+]", TapeGrowth::Fixed), Some(RuntimeError::PointerOverflow(position(1, 4))));
+        assert_eq!(run("+>+>+>+[>]", TapeGrowth::Fixed), Some(RuntimeError::PointerOverflow(position(1, 8))));
+        assert_eq!(run(">>>+[<]", TapeGrowth::Fixed), None);
+        assert_eq!(run("+[+<]", TapeGrowth::Fixed), Some(RuntimeError::PointerUnderflow(position(1, 4))));
+        assert_eq!(run(">>>>+", TapeGrowth::Right), None);
+        assert_eq!(run("-[<-]", TapeGrowth::Right), Some(RuntimeError::PointerUnderflow(position(1, 4))));
+        assert_eq!(run("+>[-<<+>>]", TapeGrowth::Fixed), None);
+        assert_eq!(run("+[-<<+>>]", TapeGrowth::Fixed), Some(RuntimeError::PointerUnderflow(position(1, 2))));
+        assert_eq!(run(">>+[<+]", TapeGrowth::Right), Some(RuntimeError::PointerUnderflow(position(1, 6))));
+        assert_eq!(run("<<+>>>>>>+", TapeGrowth::Both), None);
+    }
+
+    #[test]
+    fn test_run_scan() {
+        // Mark the cells 1, 2, 4, and 6, leaving the cells 3 and 5 empty, then scan in both directions.
+        let source: &str = ">+>+>>+>>+<<<<<<[>]<.>>+[>>]<.<[<]>.<<<[<<]>[>>>>]<<.";
+        let tokens = Brainfuck::new(source).tokenize();
+        let program = Optimizer::new().optimize(Program::new(&tokens).unwrap());
+        let mut output: Vec<u8> = Vec::new();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.run(&program, &mut "".as_bytes(), &mut output).unwrap();
+        assert_eq!(output, run(source, ""));
+    }
+
+    #[test]
+    fn test_run_scan_wrapping_tape() {
+        let tokens = Brainfuck::new("+>+[<]+>.").tokenize();
+        let program = Optimizer::new().optimize(Program::new(&tokens).unwrap());
+        let mut output: Vec<u8> = Vec::new();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.run(&program, &mut "".as_bytes(), &mut output).unwrap();
+        assert_eq!(output, [1]);
+        assert_eq!(interpreter.pointer, 0);
+        assert_eq!(interpreter.tape[29_999], 1);
+    }
+
+    #[test]
+    fn test_run_tape_size() {
+        let options = Options { tape_size: 3, ..Options::default() };
+        let tokens = Brainfuck::new("+>++>+++>.").tokenize();
+        let program = Program::new(&tokens).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+
+        let mut interpreter = Interpreter::with_options(options);
+        interpreter.run(&program, &mut "".as_bytes(), &mut output).unwrap();
+        assert_eq!(output, [1]);
+        assert_eq!(interpreter.tape, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_run_growing_tape_right() {
+        let options = Options { tape_size: 2, tape_growth: TapeGrowth::Right, ..Options::default() };
+        let tokens = Brainfuck::new("+>++>+++>>>>+<<<<<<.").tokenize();
+        let program = Optimizer::with_options(options).optimize(Program::new(&tokens).unwrap());
+        let mut output: Vec<u8> = Vec::new();
+
+        let mut interpreter = Interpreter::with_options(options);
+        interpreter.run(&program, &mut "".as_bytes(), &mut output).unwrap();
+        assert_eq!(output, [1]);
+        assert_eq!(interpreter.tape, [1, 2, 3, 0, 0, 0, 1, 0]);
+        assert_eq!(interpreter.pointer, 0);
+    }
+
+    #[test]
+    fn test_run_growing_tape_both() {
+        let options = Options { tape_size: 2, tape_growth: TapeGrowth::Both, ..Options::default() };
+        let tokens = Brainfuck::new("+<++<+++<<<+>>>>>.").tokenize();
+        let program = Optimizer::with_options(options).optimize(Program::new(&tokens).unwrap());
+        let mut output: Vec<u8> = Vec::new();
+
+        let mut interpreter = Interpreter::with_options(options);
+        interpreter.run(&program, &mut "".as_bytes(), &mut output).unwrap();
+        assert_eq!(output, [1]);
+        assert_eq!(interpreter.tape, [0, 1, 0, 0, 3, 2, 1, 0]);
+        assert_eq!(interpreter.pointer, 6);
+    }
+
+    #[test]
+    fn test_run_growing_tape_scan() {
+        let options = Options { tape_size: 4, tape_growth: TapeGrowth::Both, ..Options::default() };
+        let tokens = Brainfuck::new("+>+>+>+[>>]+<<<<<[<]+").tokenize();
+        let program = Optimizer::with_options(options).optimize(Program::new(&tokens).unwrap());
+
+        let mut interpreter = Interpreter::with_options(options);
+        interpreter.run(&program, &mut "".as_bytes(), &mut Vec::new()).unwrap();
+        assert_eq!(interpreter.tape, [0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 1, 0, 0]);
+        assert_eq!(interpreter.pointer, 7);
+    }
+
+    #[test]
+    fn test_run_skip_loop() {
+        assert_eq!(run("[.]+.", ""), [1]);
+    }
+
+    #[test]
+    fn test_run_deeply_nested_loops() {
+        let source: String = format!("+{open}-{close}+.", open = "[".repeat(20_000), close = "]".repeat(20_000));
+        assert_eq!(run(&source, ""), [1]);
+
+        let tokens = Brainfuck::new(&source).tokenize();
+        let program = Optimizer::new().optimize(Program::new(&tokens).unwrap());
+        let mut output: Vec<u8> = Vec::new();
+        Interpreter::new().run(&program, &mut "".as_bytes(), &mut output).unwrap();
+        assert_eq!(output, [1]);
+    }
+}
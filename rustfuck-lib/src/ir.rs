@@ -0,0 +1,494 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The intermediate representation of programs, built from tokens and consumed by all backends.
+
+use std::mem;
+use std::slice;
+
+use Options;
+use Result;
+use lexer::MetaData;
+use lexer::Token;
+use validator::validate;
+
+/// A program in the intermediate representation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Program {
+
+    /// The top-level instructions of the program.
+    pub instructions: Vec<Instruction>,
+}
+
+/// A single operation of a program, together with the part of the source code it was built from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Instruction {
+
+    /// The operation to execute.
+    pub operation: Operation,
+
+    /// The distance from the current cell to the cell on which the operation is executed. Always `0` for `Move` and
+    /// `Loop` operations.
+    pub offset: isize,
+
+    /// The part of the source code from which the operation was built.
+    pub span: Span,
+}
+
+/// The operations understood by the backends.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Operation {
+
+    /// Add the given value to the cell. Negative values decrement the cell.
+    Add(i32),
+
+    /// Move the pointer by the given number of cells. Negative values move the pointer to the left.
+    Move(isize),
+
+    /// Read a single character into the cell.
+    Read,
+
+    /// Print the content of the cell.
+    Write,
+
+    /// Repeat the given instructions as long as the current cell is not zero.
+    Loop(Vec<Instruction>),
+
+    /// Set the cell to zero.
+    Clear,
+
+    /// Add the value of the cell, multiplied by `factor`, to the cell `offset` cells away from it.
+    MulAdd {
+        /// The distance from the cell to the changed cell.
+        offset: isize,
+
+        /// The factor by which the value of the current cell is multiplied.
+        factor: i32,
+    },
+
+    /// Move the pointer by `stride` cells until the current cell is zero. Does not move the pointer if the current cell
+    /// is already zero.
+    Scan {
+        /// The number of cells to move the pointer in each step. Negative values move the pointer to the left.
+        stride: isize,
+    },
+}
+
+/// A step of a walk through nested instructions, together with the instructions following the visited one in its
+/// block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Step<'a> {
+
+    /// Visit an instruction. The body of a loop is walked directly afterwards.
+    Instruction(&'a Instruction, &'a [Instruction]),
+
+    /// Leave the body of a loop, after walking it.
+    LoopEnd(&'a Instruction, &'a [Instruction]),
+}
+
+/// An iterator walking through nested instructions in the order of the source code, without recursion.
+#[derive(Clone, Debug)]
+pub struct Walk<'a> {
+
+    /// The loops currently walked, innermost last, and the remaining instructions in their bodies. The first entry
+    /// holds the remaining top-level instructions.
+    blocks: Vec<(Option<&'a Instruction>, slice::Iter<'a, Instruction>)>,
+}
+
+/// A range within the source code, given by the positions of its first and its last token.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Span {
+
+    /// The position of the first token.
+    pub start: MetaData,
+
+    /// The position of the last token.
+    pub end: MetaData,
+}
+
+impl Program {
+    /// Build the program from the given list of `tokens`.
+    ///
+    /// Consecutive increments and decrements are folded into a single `Add`, consecutive pointer movements into a
+    /// single `Move`. Folded operations cancelling each other out are removed entirely. Fails with a syntax error if
+    /// the loops in the `tokens` are not balanced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustfuck_lib::Lexer;
+    /// use rustfuck_lib::Operation;
+    /// use rustfuck_lib::Program;
+    /// use rustfuck_lib::language::Brainfuck;
+    ///
+    /// let tokens = Brainfuck::new("+++>>-<").tokenize();
+    /// let program = Program::new(&tokens).unwrap();
+    ///
+    /// let operations: Vec<Operation> = program.instructions.iter()
+    ///     .map(|instruction| instruction.operation.clone())
+    ///     .collect();
+    /// assert_eq!(operations, vec![Operation::Add(3), Operation::Move(2), Operation::Add(-1), Operation::Move(-1)]);
+    /// ```
+    pub fn new(tokens: &[Token]) -> Result<Program> {
+        validate(tokens)?;
+
+        Ok(Program { instructions: build(tokens) })
+    }
+}
+
+impl Drop for Program {
+    /// Drop the instructions one after another, instead of dropping the bodies of deeply nested loops recursively.
+    fn drop(&mut self) {
+        let mut instructions: Vec<Instruction> = mem::take(&mut self.instructions);
+        while let Some(instruction) = instructions.pop() {
+            if let Operation::Loop(body) = instruction.operation {
+                instructions.extend(body);
+            }
+        }
+    }
+}
+
+impl Instruction {
+    /// Initialize a new instruction executing the `operation` on the current cell, built from the source code in
+    /// `span`.
+    pub fn new(operation: Operation, span: Span) -> Instruction {
+        Instruction::at_offset(operation, 0, span)
+    }
+
+    /// Initialize a new instruction executing the `operation` on the cell `offset` cells away from the current cell,
+    /// built from the source code in `span`.
+    pub fn at_offset(operation: Operation, offset: isize, span: Span) -> Instruction {
+        Instruction {
+            operation,
+            offset,
+            span,
+        }
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = Step<'a>;
+
+    fn next(&mut self) -> Option<Step<'a>> {
+        let (_, ref mut remaining) = *self.blocks.last_mut()?;
+        if let Some(instruction) = remaining.next() {
+            let following: &'a [Instruction] = remaining.as_slice();
+            if let Operation::Loop(ref body) = instruction.operation {
+                self.blocks.push((Some(instruction), body.iter()));
+            }
+
+            return Some(Step::Instruction(instruction, following));
+        }
+
+        // Leave the loop whose body has been walked, if any.
+        let (instruction, _) = self.blocks.pop()?;
+        let following: &'a [Instruction] = self.blocks.last()?.1.as_slice();
+        instruction.map(|instruction| Step::LoopEnd(instruction, following))
+    }
+}
+
+impl Span {
+    /// Initialize a span covering only the token at the given `position`.
+    pub fn at(position: MetaData) -> Span {
+        Span {
+            start: position,
+            end: position,
+        }
+    }
+}
+
+/// Get the smallest and the largest offset from the current cell accessed by the `instructions` before the pointer is
+/// moved next. The target cells of multiplications are only included if `targets` are requested, as the replaced
+/// loops only access them if the multiplied cell is not zero.
+pub fn accessed_range(instructions: &[Instruction], targets: bool) -> (isize, isize) {
+    let mut range: (isize, isize) = (0, 0);
+    for instruction in instructions {
+        let offsets: [isize; 2] = match instruction.operation {
+            Operation::Move(_) | Operation::Loop(_) | Operation::Scan { .. } => break,
+            Operation::MulAdd { offset, .. } if targets => {
+                [instruction.offset, instruction.offset.wrapping_add(offset)]
+            },
+            _ => [instruction.offset; 2],
+        };
+
+        for &offset in offsets.iter() {
+            range = (range.0.min(offset), range.1.max(offset));
+        }
+    }
+
+    range
+}
+
+/// Get the range of cells around the current cell that a growing tape must contain before executing the
+/// `instructions` up to the next pointer movement under the given `options`, excluding the targets of
+/// multiplications. `None` if the tape does not have to be prepared, i.e. if it has a fixed size, if the accesses are
+/// checked for each instruction instead, or if only the current cell is accessed.
+pub fn reserved_range(instructions: &[Instruction], options: &Options) -> Option<(isize, isize)> {
+    if !options.is_reserving() || options.is_checked() {
+        return None;
+    }
+
+    match accessed_range(instructions, false) {
+        (0, 0) => None,
+        range => Some(range),
+    }
+}
+
+/// Walk through the `instructions` and the bodies of their loops, visiting each instruction before its body, and each
+/// loop again after its body. Deeply nested loops are walked without recursion.
+pub fn walk<'a>(instructions: &'a [Instruction]) -> Walk<'a> {
+    Walk {
+        blocks: vec![(None, instructions.iter())],
+    }
+}
+
+/// Build the instructions from the balanced `tokens`. The enclosing blocks of the current loop are kept on a stack
+/// instead of building the loop bodies recursively, so deeply nested loops cannot overflow the call stack.
+fn build(tokens: &[Token]) -> Vec<Instruction> {
+    // The instructions of the enclosing blocks, innermost last, with the position of the token starting the loop.
+    let mut enclosing: Vec<(Vec<Instruction>, MetaData)> = Vec::new();
+    let mut instructions: Vec<Instruction> = Vec::new();
+
+    for token in tokens {
+        match *token {
+            Token::Add(_, position) => fold_add(&mut instructions, 1, position),
+            Token::Sub(_, position) => fold_add(&mut instructions, -1, position),
+            Token::Right(_, position) => fold_move(&mut instructions, 1, position),
+            Token::Left(_, position) => fold_move(&mut instructions, -1, position),
+            Token::Read(_, position) => {
+                push(&mut instructions, Instruction::new(Operation::Read, Span::at(position)));
+            },
+            Token::Write(_, position) => {
+                push(&mut instructions, Instruction::new(Operation::Write, Span::at(position)));
+            },
+            Token::BeginLoop(_, start) => {
+                enclosing.push((mem::take(&mut instructions), start));
+            },
+            Token::EndLoop(_, end) => {
+                // The tokens have been validated, thus there must be a start to the loop.
+                if let Some((outer, start)) = enclosing.pop() {
+                    remove_noop(&mut instructions);
+                    let body: Vec<Instruction> = mem::replace(&mut instructions, outer);
+                    push(&mut instructions, Instruction::new(Operation::Loop(body), Span { start, end }));
+                }
+            },
+        }
+    }
+
+    remove_noop(&mut instructions);
+    instructions
+}
+
+/// Add `delta` to the current cell, merging it into the last of the `instructions` if that is an `Add`, too.
+fn fold_add(instructions: &mut Vec<Instruction>, delta: i32, position: MetaData) {
+    let last: Option<&mut Instruction> = instructions.last_mut();
+    if let Some(&mut Instruction { operation: Operation::Add(ref mut value), ref mut span, .. }) = last {
+        *value = value.wrapping_add(delta);
+        span.end = position;
+        return;
+    }
+
+    push(instructions, Instruction::new(Operation::Add(delta), Span::at(position)));
+}
+
+/// Move the pointer by `offset`, merging it into the last of the `instructions` if that is a `Move`, too.
+fn fold_move(instructions: &mut Vec<Instruction>, offset: isize, position: MetaData) {
+    let last: Option<&mut Instruction> = instructions.last_mut();
+    if let Some(&mut Instruction { operation: Operation::Move(ref mut value), ref mut span, .. }) = last {
+        *value = value.wrapping_add(offset);
+        span.end = position;
+        return;
+    }
+
+    push(instructions, Instruction::new(Operation::Move(offset), Span::at(position)));
+}
+
+/// Append the `instruction` to the `instructions`, replacing the last one if that does not have any effect.
+fn push(instructions: &mut Vec<Instruction>, instruction: Instruction) {
+    remove_noop(instructions);
+
+    // Removing an instruction might have made the new one foldable into its predecessor.
+    match (instruction.operation, instructions.last().map(|last| &last.operation)) {
+        (Operation::Add(delta), Some(&Operation::Add(_))) => fold_add(instructions, delta, instruction.span.end),
+        (Operation::Move(offset), Some(&Operation::Move(_))) => fold_move(instructions, offset, instruction.span.end),
+        (operation, _) => instructions.push(Instruction::new(operation, instruction.span)),
+    }
+}
+
+/// Remove the last of the `instructions` if it does not have any effect.
+fn remove_noop(instructions: &mut Vec<Instruction>) {
+    let is_noop: bool = matches!(instructions.last(),
+                                 Some(&Instruction { operation: Operation::Add(0), .. }) |
+                                 Some(&Instruction { operation: Operation::Move(0), .. }));
+
+    if is_noop {
+        let _ = instructions.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Error;
+    use Instruction;
+    use Lexer;
+    use MetaData;
+    use Operation;
+    use Options;
+    use Program;
+    use Span;
+    use SyntaxError;
+    use TapeGrowth;
+    use language::Brainfuck;
+    use super::Step;
+    use super::accessed_range;
+    use super::reserved_range;
+    use super::walk;
+
+    /// Build the span from `(lineno, position)` of the `start` to `(lineno, position)` of the `end`.
+    fn span(start: (usize, usize), end: (usize, usize)) -> Span {
+        Span {
+            start: MetaData { lineno: start.0, position: start.1 },
+            end: MetaData { lineno: end.0, position: end.1 },
+        }
+    }
+
+    #[test]
+    fn test_new_empty() {
+        let program = Program::new(&[]).unwrap();
+        assert_eq!(program, Program { instructions: vec![] });
+    }
+
+    #[test]
+    fn test_new_folding() {
+        let tokens = Brainfuck::new("+++--\n>><<<.,").tokenize();
+        let program = Program::new(&tokens).unwrap();
+
+        assert_eq!(program.instructions, vec![
+            Instruction::new(Operation::Add(1), span((1, 1), (1, 5))),
+            Instruction::new(Operation::Move(-1), span((2, 1), (2, 5))),
+            Instruction::new(Operation::Write, span((2, 6), (2, 6))),
+            Instruction::new(Operation::Read, span((2, 7), (2, 7))),
+        ]);
+    }
+
+    #[test]
+    fn test_new_noop_removal() {
+        let tokens = Brainfuck::new(">+-<.+><+").tokenize();
+        let program = Program::new(&tokens).unwrap();
+
+        assert_eq!(program.instructions, vec![
+            Instruction::new(Operation::Write, span((1, 5), (1, 5))),
+            Instruction::new(Operation::Add(2), span((1, 6), (1, 9))),
+        ]);
+    }
+
+    #[test]
+    fn test_new_loops() {
+        let tokens = Brainfuck::new("++[>+[-]\n<-]").tokenize();
+        let program = Program::new(&tokens).unwrap();
+
+        assert_eq!(program.instructions, vec![
+            Instruction::new(Operation::Add(2), span((1, 1), (1, 2))),
+            Instruction::new(Operation::Loop(vec![
+                Instruction::new(Operation::Move(1), span((1, 4), (1, 4))),
+                Instruction::new(Operation::Add(1), span((1, 5), (1, 5))),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::new(Operation::Add(-1), span((1, 7), (1, 7))),
+                ]), span((1, 6), (1, 8))),
+                Instruction::new(Operation::Move(-1), span((2, 1), (2, 1))),
+                Instruction::new(Operation::Add(-1), span((2, 2), (2, 2))),
+            ]), span((1, 3), (2, 3))),
+        ]);
+    }
+
+    #[test]
+    fn test_new_no_folding_across_loops() {
+        let tokens = Brainfuck::new("+[]+").tokenize();
+        let program = Program::new(&tokens).unwrap();
+
+        assert_eq!(program.instructions, vec![
+            Instruction::new(Operation::Add(1), span((1, 1), (1, 1))),
+            Instruction::new(Operation::Loop(vec![]), span((1, 2), (1, 3))),
+            Instruction::new(Operation::Add(1), span((1, 4), (1, 4))),
+        ]);
+    }
+
+    #[test]
+    fn test_new_deeply_nested_loops() {
+        let source: String = format!("{open}+{close}", open = "[".repeat(20_000), close = "]".repeat(20_000));
+        let tokens = Brainfuck::new(&source).tokenize();
+        let program = Program::new(&tokens).unwrap();
+
+        let loops: usize = walk(&program.instructions)
+            .filter(|step| matches!(*step, Step::LoopEnd(..)))
+            .count();
+        assert_eq!(loops, 20_000);
+    }
+
+    #[test]
+    fn test_new_syntax_error() {
+        let tokens = Brainfuck::new("+]").tokenize();
+        assert!(match Program::new(&tokens) {
+            Err(Error::Syntax(error)) => error == SyntaxError::UnopenedLoop(MetaData { lineno: 1, position: 2 }),
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn test_walk() {
+        let tokens = Brainfuck::new("+[>[-]<]-").tokenize();
+        let program = Program::new(&tokens).unwrap();
+
+        // The visited instructions and loop ends, given by their first source position and their number of following
+        // instructions.
+        let steps: Vec<(bool, usize, usize)> = walk(&program.instructions)
+            .map(|step| match step {
+                Step::Instruction(instruction, following) => (true, instruction.span.start.position, following.len()),
+                Step::LoopEnd(instruction, following) => (false, instruction.span.start.position, following.len()),
+            })
+            .collect();
+        assert_eq!(steps, vec![
+            (true, 1, 2),
+            (true, 2, 1),
+            (true, 3, 2),
+            (true, 4, 1),
+            (true, 5, 0),
+            (false, 4, 1),
+            (true, 7, 0),
+            (false, 2, 1),
+            (true, 9, 0),
+        ]);
+    }
+
+    #[test]
+    fn test_accessed_range() {
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let instructions = vec![
+            Instruction::at_offset(Operation::Add(1), 2, span),
+            Instruction::at_offset(Operation::MulAdd { offset: -5, factor: 2 }, 1, span),
+            Instruction::new(Operation::Move(3), span),
+            Instruction::at_offset(Operation::Write, 7, span),
+        ];
+        assert_eq!(accessed_range(&instructions, false), (0, 2));
+        assert_eq!(accessed_range(&instructions, true), (-4, 2));
+        assert_eq!(accessed_range(&instructions[2..], true), (0, 0));
+    }
+
+    #[test]
+    fn test_reserved_range() {
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let instructions = vec![
+            Instruction::at_offset(Operation::Add(1), -2, span),
+            Instruction::at_offset(Operation::MulAdd { offset: 5, factor: 2 }, 1, span),
+        ];
+        let growing = Options { tape_growth: TapeGrowth::Right, ..Options::default() };
+        assert_eq!(reserved_range(&instructions, &growing), Some((-2, 1)));
+        assert_eq!(reserved_range(&instructions[..0], &growing), None);
+        assert_eq!(reserved_range(&instructions, &Options::default()), None);
+        assert_eq!(reserved_range(&instructions, &Options { checked: true, ..growing }), None);
+    }
+}
@@ -0,0 +1,1024 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementations of a generator for creating `JavaScript` code from intermediate compile results.
+//!
+//! The tape is a typed array matching the cell type. Typed arrays wrap around at the bounds of their element type when
+//! storing values, so wrapping arithmetic needs no explicit reduction. Cells with 64 bits hold `BigInt`s.
+
+use std::convert::TryFrom;
+use std::fmt::Display;
+
+use CellType;
+use EofBehavior;
+use Options;
+use Overflow;
+use TapeGrowth;
+use ir::Instruction;
+use ir::Operation;
+use ir::Program;
+use ir::Step;
+use ir::accessed_range;
+use ir::reserved_range;
+use ir::walk;
+use lexer::MetaData;
+
+/// The `JavaScript` function making sure that the cells from `low` to `high` cells away from the cell at `position`
+/// are on the tape, returning the (possibly changed) position. It is defined within the generated function to replace
+/// the tape when growing it. `{id_parameter}` must be replaced by the parameter for reporting errors, and `{left}` and
+/// `{right}` by the code handling the tape's start and end.
+static RESERVING_RUNTIME: &str = "    function reserve(position, low, high{id_parameter}) {
+{left}{right}        return position;
+    }
+
+";
+
+/// The `JavaScript` code growing the tape when cells before its start are accessed.
+static GROWING_LEFT: &str = "        if (position + low < 0) {
+            const before = Math.max(-(position + low), tape.length);
+            const grown = new tape.constructor(tape.length + before);
+            grown.set(tape, before);
+            tape = grown;
+            position += before;
+        }
+";
+
+/// The `JavaScript` code growing the tape when cells past its end are accessed.
+static GROWING_RIGHT: &str = "        if (position + high >= tape.length) {
+            const grown = new tape.constructor(Math.max(position + high + 1, 2 * tape.length));
+            grown.set(tape);
+            tape = grown;
+        }
+";
+
+/// The `JavaScript` code continuing at the current end of a tape growing only to the right when the pointer leaves its
+/// start. The cells before the new current cell do not have to be on the tape.
+static WRAPPING_LEFT: &str = "        if (position < 0) {
+            position = (position % tape.length + tape.length) % tape.length;
+        }
+";
+
+/// The `JavaScript` function getting the index `distance` cells before the index `ptr` on a tape growing only to the
+/// right, continuing at the tape's current end when passing its start. It is defined within the generated function to
+/// access the current tape.
+static WRAPPING_LEFT_RUNTIME: &str = "    function wrapLeft(ptr, distance) {
+        const position = ptr - distance;
+        return position < 0 ? (position % tape.length + tape.length) % tape.length : position;
+    }
+
+";
+
+/// The `JavaScript` code reporting accesses before the tape's start.
+static CHECKING_LEFT: &str = "        if (position + low < 0) {
+            throw fail(\"pointer underflow\", id);
+        }
+";
+
+/// The `JavaScript` code reporting accesses past the tape's end.
+static CHECKING_RIGHT: &str = "        if (position + high >= tape.length) {
+            throw fail(\"pointer overflow\", id);
+        }
+";
+
+/// The `JavaScript` function getting the index `offset` cells away from the index `ptr` on a tape with a fixed size,
+/// wrapping around at the tape's ends. The distance must be smaller than the tape's size, which must replace
+/// `{tape_size}`.
+static WRAPPING_RUNTIME: &str = "\
+function wrap(ptr, offset) {
+    const position = ptr + (offset < 0 ? offset + {tape_size} : offset);
+    return position < {tape_size} ? position : position - {tape_size};
+}
+
+";
+
+/// The `JavaScript` function searching a tape with a fixed size for the next zero cell to the right of the current
+/// cell. Like the interpreter, it continues at the tape's start if there is no zero cell before reaching the end, and
+/// never returns if there is no zero cell at all. The placeholder `{zero}` must be replaced by the literal zero.
+static SEARCHING_RIGHT: &str = "\
+function searchRight(tape, ptr) {
+    let position = tape.indexOf({zero}, ptr);
+    if (position < 0) {
+        position = tape.indexOf({zero});
+    }
+    while (position < 0) {
+    }
+    return position;
+}
+
+";
+
+/// The `JavaScript` function searching a tape with a fixed size for the next zero cell to the left of the current
+/// cell, continuing at the tape's end like `searchRight` continues at its start.
+static SEARCHING_LEFT: &str = "\
+function searchLeft(tape, ptr) {
+    let position = tape.lastIndexOf({zero}, ptr);
+    if (position < 0) {
+        position = tape.lastIndexOf({zero});
+    }
+    while (position < 0) {
+    }
+    return position;
+}
+
+";
+
+/// The `JavaScript` function creating an error with a message for the source position with the given `id`. The
+/// placeholder `{positions}` must be replaced by the initializers of the source positions.
+static FAILING_RUNTIME: &str = "\
+const POSITIONS = [
+{positions}];
+
+function fail(message, id) {
+    const [lineno, position] = POSITIONS[id];
+    return new RuntimeError(`${message} at line ${lineno}, position ${position}`);
+}
+
+";
+
+/// The `JavaScript` function changing cells that saturate at their bounds. The placeholders `{min}` and `{max}` must
+/// be replaced by the bounds of the cell type.
+static SATURATING_RUNTIME: &str = "\
+function add(value, delta) {
+    const sum = BigInt(value) + delta;
+    const bounded = sum < {min}n ? {min}n : sum > {max}n ? {max}n : sum;
+    return typeof value === \"bigint\" ? bounded : Number(bounded);
+}
+
+";
+
+/// The `JavaScript` function changing cells that fails when leaving their bounds. The placeholders `{min}` and `{max}`
+/// must be replaced by the bounds of the cell type.
+static CHECKED_RUNTIME: &str = "\
+function add(value, delta, id) {
+    const sum = BigInt(value) + delta;
+    if (sum < {min}n || sum > {max}n) {
+        throw fail(\"cell overflow\", id);
+    }
+    return typeof value === \"bigint\" ? sum : Number(sum);
+}
+
+";
+
+/// The runtime functions called by the generated `JavaScript` code.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Runtime {
+
+    /// Cells are changed by calling `add`.
+    add: bool,
+
+    /// The pointer is moved by calling `reserve`.
+    reserve: bool,
+
+    /// Zero cells to the right are searched by calling `searchRight`.
+    search_right: bool,
+
+    /// Zero cells to the left are searched by calling `searchLeft`.
+    search_left: bool,
+
+    /// The cells are accessed by calling `wrap`.
+    wrap: bool,
+
+    /// The cells before the current cell on a tape growing to the right are accessed by calling `wrapLeft`.
+    wrap_left: bool,
+}
+
+/// A generator for creating `JavaScript` code from intermediate compile results.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct JsGenerator {
+
+    /// The options controlling the generated code.
+    options: Options,
+
+    /// The current level of indentation.
+    indentation_level: u32,
+
+    /// The source positions reported by the generated code, indexed by the IDs passed to the runtime functions.
+    positions: Vec<MetaData>,
+
+    /// The runtime functions called by the generated code.
+    runtime: Runtime,
+}
+
+impl JsGenerator {
+    /// Initialize a new generator with the default options.
+    pub fn new() -> JsGenerator {
+        JsGenerator::with_options(Options::default())
+    }
+
+    /// Initialize a new generator with the given `options`.
+    pub fn with_options(options: Options) -> JsGenerator {
+        JsGenerator {
+            options,
+            indentation_level: 1,
+            positions: Vec::new(),
+            runtime: Runtime::default(),
+        }
+    }
+
+    /// Generate the `JavaScript` code from the given `program`, using the specified `template`.
+    ///
+    /// The placeholder `{array}` in the `template` is replaced by the typed array holding the cells, `{runtime}` by
+    /// the functions the generated code needs, and `{tape_size}` by the initial number of cells. The `template` must
+    /// end within a function, in which the tape `tape`, the index `ptr` of the current cell, the iterator `bytes` over
+    /// the input, and the array `output` collecting the output bytes are defined. Runtime errors are thrown as
+    /// instances of the class `RuntimeError`, which the `template` must define.
+    pub fn generate(&mut self, template: &str, program: &Program) -> String {
+        // The runtime functions depend on the code calling them.
+        self.positions.clear();
+        self.runtime = Runtime::default();
+        let mut code = String::new();
+        self.generate_block(&program.instructions, &mut code);
+
+        let mut output = template.replace("{array}", typed_array(self.options.cell_type))
+            .replace("{runtime}", &self.runtime())
+            .replace("{tape_size}", &self.options.tape_size.max(1).to_string());
+        if self.runtime.reserve {
+            output.push_str(&self.reserving_runtime());
+        }
+        if self.runtime.wrap_left {
+            output.push_str(WRAPPING_LEFT_RUNTIME);
+        }
+        output.push_str(&code);
+
+        // Close the function.
+        output.push_str("}\n");
+
+        output
+    }
+
+    /// Generate the `JavaScript` code for the given list of `instructions` and the bodies of their loops, appending it
+    /// to the `output`.
+    fn generate_block(&mut self, instructions: &[Instruction], output: &mut String) {
+        // The current cell is on the tape when entering a block, but the other accessed cells might not be.
+        self.reserve_segment(instructions, output);
+
+        // The offset of the cell on which the currently generated multiplications depend, and if the current cell
+        // is still known not to be zero.
+        let mut guard: Option<isize> = None;
+        let mut nonzero: bool = false;
+        for step in walk(instructions) {
+            let (instruction, following): (&Instruction, &[Instruction]) = match step {
+                Step::Instruction(instruction, following) => (instruction, following),
+                Step::LoopEnd(_, following) => {
+                    // Close the loop, which only ends on a cell on the tape, but the cells accessed next might not be.
+                    self.close_guard(&mut guard, output);
+                    self.indentation_level -= 1;
+                    output.push_str(&self.indent("}\n"));
+                    nonzero = false;
+                    self.reserve_segment(following, output);
+                    continue;
+                },
+            };
+
+            let source: Option<isize> = match instruction.operation {
+                Operation::MulAdd { .. } => Some(instruction.offset),
+                _ => None,
+            };
+            nonzero = nonzero && source == Some(0);
+            if guard.is_some() && guard != source {
+                self.close_guard(&mut guard, output);
+            }
+
+            // Checked accesses are reported at the accessing instruction. Multiplications depending on the same cell
+            // only need a single check.
+            let offset: isize = instruction.offset;
+            if self.options.is_checked() && guard.is_none() && offset != 0 {
+                let position: String = self.position(instruction, true);
+                self.reserve(0, offset.min(0), offset.max(0), &position, output);
+            }
+
+            let cell: String = self.cell(instruction.offset);
+            match instruction.operation {
+                Operation::Add(delta) if self.options.overflow == Overflow::Wrap => {
+                    // Change the value at the cell, wrapping around at the bounds of the cell type.
+                    let operator: &str = if delta < 0 { "-=" } else { "+=" };
+                    let line: String = format!("{cell} {operator} {value};\n",
+                                               cell = cell, operator = operator,
+                                               value = self.literal(magnitude(delta, self.options.cell_type)));
+                    output.push_str(&self.indent(&line));
+                },
+                Operation::Add(delta) => {
+                    // Change the value at the cell using the runtime function.
+                    let line: String = self.add(instruction, &cell, &format!("{delta}n", delta = delta));
+                    output.push_str(&self.indent(&line));
+                },
+                Operation::Move(offset) if self.options.is_reserving() => {
+                    // Go to another cell, making sure that it and the cells accessed next are on the tape.
+                    let (low, high): (isize, isize) = if self.options.is_checked() {
+                        (0, 0)
+                    } else {
+                        accessed_range(following, false)
+                    };
+                    let position: String = self.position(instruction, self.options.is_checked());
+                    self.reserve(offset, low, high, &position, output);
+                },
+                Operation::Move(offset) => {
+                    // Go to another cell, wrapping around at the tape's ends.
+                    self.advance(offset, output);
+                },
+                Operation::Read => {
+                    // Read a single character into the cell. Unsigned typed arrays store -1 as their maximum.
+                    let eof: String = match self.options.eof {
+                        EofBehavior::Zero => String::from("0"),
+                        EofBehavior::MinusOne => String::from("-1"),
+                        EofBehavior::Unchanged => cell.clone(),
+                    };
+                    let character: String = format!("bytes.next().value ?? {eof}", eof = eof);
+                    let line: String = match self.options.cell_type {
+                        CellType::I64 => format!("{cell} = BigInt({character});\n", cell = cell, character = character),
+                        _ => format!("{cell} = {character};\n", cell = cell, character = character),
+                    };
+                    output.push_str(&self.indent(&line));
+                },
+                Operation::Write => {
+                    // Print the character of the cell, i.e. its lowest byte.
+                    let byte: String = match self.options.cell_type {
+                        CellType::U8 => cell,
+                        CellType::U16 | CellType::U32 => format!("{cell} & 255", cell = cell),
+                        CellType::I64 => format!("Number({cell} & 255n)", cell = cell),
+                    };
+                    output.push_str(&self.indent(&format!("output.push({byte});\n", byte = byte)));
+                },
+                Operation::Loop(ref body) => {
+                    // Repeat the body as long as the current cell is not zero.
+                    let line: String = format!("while (tape[ptr] !== {zero}) {{\n", zero = self.literal(0));
+                    output.push_str(&self.indent(&line));
+                    self.indentation_level += 1;
+                    self.reserve_segment(body, output);
+                    nonzero = true;
+                },
+                Operation::Clear => {
+                    // Set the cell to zero.
+                    output.push_str(&self.indent(&format!("{cell} = {zero};\n", cell = cell, zero = self.literal(0))));
+                },
+                Operation::MulAdd { offset, factor } if self.options.overflow == Overflow::Wrap => {
+                    // Add a multiple of the cell to another cell, wrapping around at the bounds of the cell type.
+                    // Products of 32 bit cells might not be exact as floating-point numbers.
+                    let target: String = self.cell(instruction.offset.wrapping_add(offset));
+                    let operator: &str = if factor < 0 { "-=" } else { "+=" };
+                    let product: String = match magnitude(factor, self.options.cell_type) {
+                        1 => cell,
+                        factor if self.options.cell_type == CellType::U32 => {
+                            format!("Math.imul({cell}, {factor})", cell = cell, factor = factor)
+                        },
+                        factor => format!("{cell} * {factor}", cell = cell, factor = self.literal(factor)),
+                    };
+                    let line: String = format!("{target} {operator} {product};\n",
+                                               target = target, operator = operator, product = product);
+                    self.multiply(instruction, offset, &line, &mut guard, nonzero, output);
+                },
+                Operation::MulAdd { offset, factor } => {
+                    // Add a multiple of the cell to another cell using the runtime function.
+                    let target: String = self.cell(instruction.offset.wrapping_add(offset));
+                    let product: String = format!("BigInt({cell}) * {factor}n", cell = cell, factor = factor);
+                    let line: String = self.add(instruction, &target, &product);
+                    self.multiply(instruction, offset, &line, &mut guard, nonzero, output);
+                },
+                Operation::Scan { stride: 1 } if !self.options.is_reserving() => {
+                    // Search the tape to the right for the next zero cell.
+                    self.runtime.search_right = true;
+                    output.push_str(&self.indent("ptr = searchRight(tape, ptr);\n"));
+                },
+                Operation::Scan { stride: -1 } if !self.options.is_reserving() => {
+                    // Search the tape to the left for the next zero cell.
+                    self.runtime.search_left = true;
+                    output.push_str(&self.indent("ptr = searchLeft(tape, ptr);\n"));
+                },
+                Operation::Scan { stride } => {
+                    // Step through the cells until reaching a zero cell.
+                    let line: String = format!("while (tape[ptr] !== {zero}) {{\n", zero = self.literal(0));
+                    output.push_str(&self.indent(&line));
+                    self.indentation_level += 1;
+                    if self.options.is_reserving() {
+                        let position: String = self.position(instruction, self.options.is_checked());
+                        self.reserve(stride, 0, 0, &position, output);
+                    } else {
+                        self.advance(stride, output);
+                    }
+                    self.indentation_level -= 1;
+                    output.push_str(&self.indent("}\n"));
+                },
+            }
+
+            // Scans only end on a cell on the tape, but the cells accessed next might not be.
+            if let Operation::Scan { .. } = instruction.operation {
+                self.reserve_segment(following, output);
+            }
+        }
+
+        self.close_guard(&mut guard, output);
+    }
+
+    /// Get the line adding the `BigInt` `delta` expression to the `cell` using the runtime function, which reports
+    /// overflows at the `instruction` if the options require it.
+    fn add(&mut self, instruction: &Instruction, cell: &str, delta: &str) -> String {
+        self.runtime.add = true;
+        let position: String = self.position(instruction, self.options.overflow == Overflow::Error);
+        format!("{cell} = add({cell}, {delta}{position});\n", cell = cell, delta = delta, position = position)
+    }
+
+    /// Append the `line` adding a multiple of the `instruction`'s cell to the cell `offset` cells away from it to the
+    /// `output`. Like in the replaced loop, the target cell is only accessed if the instruction's cell is not zero, so
+    /// the line is placed within a condition, which is opened unless the previous multiplication depends on the same
+    /// cell and the `guard` is still open, or the instruction's cell is known to be `nonzero`. Within the condition, a
+    /// growing or checked tape is made to contain the target cell.
+    fn multiply(&mut self, instruction: &Instruction, offset: isize, line: &str, guard: &mut Option<isize>,
+                nonzero: bool, output: &mut String)
+    {
+        if guard.is_none() && !nonzero {
+            let condition: String = format!("if ({cell} !== {zero}) {{\n",
+                                            cell = self.cell(instruction.offset), zero = self.literal(0));
+            output.push_str(&self.indent(&condition));
+            self.indentation_level += 1;
+            *guard = Some(instruction.offset);
+        }
+
+        if self.options.is_reserving() {
+            let target: isize = instruction.offset.wrapping_add(offset);
+            let position: String = self.position(instruction, self.options.is_checked());
+            self.reserve(0, target.min(0), target.max(0), &position, output);
+        }
+
+        output.push_str(&self.indent(line));
+    }
+
+    /// Close the condition of the multiplications if the `guard` is open, appending the code to the `output`.
+    fn close_guard(&mut self, guard: &mut Option<isize>, output: &mut String) {
+        if guard.take().is_some() {
+            self.indentation_level -= 1;
+            output.push_str(&self.indent("}\n"));
+        }
+    }
+
+    /// Append the call moving the pointer by `offset` cells and making sure that the cells from `low` to `high` cells
+    /// away from the new current cell are on the tape to the `output`. The `position` argument is passed on to report
+    /// errors on checked tapes. The cells before the start of a tape wrapping around do not have to be on the tape.
+    fn reserve(&mut self, offset: isize, low: isize, high: isize, position: &str, output: &mut String) {
+        let low: isize = if self.options.is_wrapping() { low.max(0) } else { low };
+        if offset == 0 && low == 0 && high == 0 {
+            return;
+        }
+
+        self.runtime.reserve = true;
+        let pointer: String = match offset {
+            0 => String::from("ptr"),
+            offset if offset < 0 => format!("ptr - {value}", value = offset.unsigned_abs()),
+            offset => format!("ptr + {value}", value = offset),
+        };
+        let line: String = format!("ptr = reserve({pointer}, {low}, {high}{position});\n",
+                                   pointer = pointer, low = low, high = high, position = position);
+        output.push_str(&self.indent(&line));
+    }
+
+    /// Make sure that a growing tape contains all cells accessed by the `instructions` up to the next pointer
+    /// movement, appending the code to the `output`. Checked tapes are handled for each instruction instead.
+    fn reserve_segment(&mut self, instructions: &[Instruction], output: &mut String) {
+        if let Some((low, high)) = reserved_range(instructions, &self.options) {
+            self.reserve(0, low, high, "", output);
+        }
+    }
+
+    /// Append the code moving the pointer by `offset` cells on a tape with a fixed size that is not checked, wrapping
+    /// around at its ends, to the `output`.
+    fn advance(&mut self, offset: isize, output: &mut String) {
+        self.runtime.wrap = true;
+        let line: String = format!("ptr = wrap(ptr, {offset});\n", offset = self.wrap(offset));
+        output.push_str(&self.indent(&line));
+    }
+
+    /// Get the `JavaScript` expression for the cell `offset` cells away from the current cell. On a tape with a fixed
+    /// size that is not checked, the offset wraps around at the tape's ends, and so do negative offsets on a tape
+    /// growing to the right that is not checked.
+    fn cell(&mut self, offset: isize) -> String {
+        match self.wrap(offset) {
+            0 => String::from("tape[ptr]"),
+            offset if self.options.is_reserving() && self.options.is_wrapping() && offset < 0 => {
+                self.runtime.wrap_left = true;
+                format!("tape[wrapLeft(ptr, {distance})]", distance = offset.unsigned_abs())
+            },
+            offset if self.options.is_reserving() && offset < 0 => {
+                format!("tape[ptr - {distance}]", distance = offset.unsigned_abs())
+            },
+            offset if self.options.is_reserving() => format!("tape[ptr + {distance}]", distance = offset),
+            offset => {
+                self.runtime.wrap = true;
+                format!("tape[wrap(ptr, {offset})]", offset = offset)
+            },
+        }
+    }
+
+    /// Reduce the `offset` to less than the size of a tape with a fixed size that is not checked, keeping its sign.
+    /// Other offsets are returned unchanged.
+    fn wrap(&self, offset: isize) -> isize {
+        if self.options.is_reserving() {
+            return offset;
+        }
+
+        let length: isize = isize::try_from(self.options.tape_size.max(1)).unwrap_or(isize::MAX);
+        offset % length
+    }
+
+    /// Get the `JavaScript` functions called by the generated code, except for the function moving the pointer on a
+    /// growing or checked tape.
+    fn runtime(&self) -> String {
+        let mut runtime: String = self.failing_runtime();
+        if self.runtime.wrap {
+            let tape_size: String = self.options.tape_size.max(1).to_string();
+            runtime.push_str(&WRAPPING_RUNTIME.replace("{tape_size}", &tape_size));
+        }
+
+        if self.runtime.search_right {
+            runtime.push_str(&SEARCHING_RIGHT.replace("{zero}", &self.literal(0)));
+        }
+
+        if self.runtime.search_left {
+            runtime.push_str(&SEARCHING_LEFT.replace("{zero}", &self.literal(0)));
+        }
+
+        if self.runtime.add {
+            let template: &str = match self.options.overflow {
+                Overflow::Error => CHECKED_RUNTIME,
+                Overflow::Wrap | Overflow::Saturate => SATURATING_RUNTIME,
+            };
+            runtime.push_str(&template.replace("{min}", &self.options.cell_type.min().to_string())
+                .replace("{max}", &self.options.cell_type.max().to_string()));
+        }
+
+        runtime
+    }
+
+    /// Get the table of source positions and the `JavaScript` function creating errors at them, if the generated code
+    /// reports any errors.
+    fn failing_runtime(&self) -> String {
+        if self.positions.is_empty() {
+            return String::new();
+        }
+
+        let positions: String = self.positions.iter()
+            .map(|position| format!("    [{lineno}, {position}],\n",
+                                    lineno = position.lineno, position = position.position))
+            .collect();
+
+        FAILING_RUNTIME.replace("{positions}", &positions)
+    }
+
+    /// Get the `JavaScript` function moving the pointer on the tape, which is defined at the start of the generated
+    /// function.
+    fn reserving_runtime(&self) -> String {
+        let checked: bool = self.options.is_checked();
+        let (left, right): (&str, &str) = match self.options.tape_growth {
+            TapeGrowth::Fixed => (CHECKING_LEFT, CHECKING_RIGHT),
+            TapeGrowth::Right if checked => (CHECKING_LEFT, GROWING_RIGHT),
+            TapeGrowth::Right => (WRAPPING_LEFT, GROWING_RIGHT),
+            TapeGrowth::Both => (GROWING_LEFT, GROWING_RIGHT),
+        };
+
+        RESERVING_RUNTIME.replace("{id_parameter}", if checked { ", id" } else { "" })
+            .replace("{left}", left)
+            .replace("{right}", right)
+    }
+
+    /// Get the additional argument passing the ID of the `instruction`'s source position to the runtime functions,
+    /// adding the position to the table. Empty unless the position might be `reported`.
+    fn position(&mut self, instruction: &Instruction, reported: bool) -> String {
+        if !reported {
+            return String::new();
+        }
+
+        self.positions.push(instruction.span.start);
+        format!(", {id}", id = self.positions.len() - 1)
+    }
+
+    /// Get the `JavaScript` literal of the given `value` for the cells, i.e. a `BigInt` for 64 bit cells.
+    fn literal<T: Display>(&self, value: T) -> String {
+        match self.options.cell_type {
+            CellType::I64 => format!("{value}n", value = value),
+            _ => value.to_string(),
+        }
+    }
+
+    /// Indent the given `line` respective to the current indentation level.
+    fn indent(&self, line: &str) -> String {
+        let mut indentation = String::new();
+        for _ in 0..self.indentation_level {
+            indentation.push_str("    ");
+        }
+
+        indentation.push_str(line);
+        indentation
+    }
+}
+
+/// Get the typed array holding cells of the given `cell_type`.
+fn typed_array(cell_type: CellType) -> &'static str {
+    match cell_type {
+        CellType::U8 => "Uint8Array",
+        CellType::U16 => "Uint16Array",
+        CellType::U32 => "Uint32Array",
+        CellType::I64 => "BigInt64Array",
+    }
+}
+
+/// Get the absolute `value` of a change to a cell of the given `cell_type`, reduced to the cell type's range when
+/// wrapping around at its bounds.
+fn magnitude(value: i32, cell_type: CellType) -> u32 {
+    let magnitude: u32 = value.unsigned_abs();
+    match cell_type {
+        CellType::U8 => magnitude % 0x100,
+        CellType::U16 => magnitude % 0x1_0000,
+        CellType::U32 | CellType::I64 => magnitude,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use CellType;
+    use EofBehavior;
+    use Instruction;
+    use JsGenerator;
+    use MetaData;
+    use Operation;
+    use Options;
+    use Overflow;
+    use Program;
+    use Span;
+    use TapeGrowth;
+    use super::Runtime;
+
+    #[test]
+    fn test_new() {
+        let generator = JsGenerator::new();
+        assert_eq!(generator, JsGenerator {
+            options: Options::default(),
+            indentation_level: 1,
+            positions: Vec::new(),
+            runtime: Runtime::default(),
+        });
+    }
+
+    #[test]
+    fn test_generate() {
+        let template: &str = "{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(3), span),
+                Instruction::at_offset(Operation::Add(-300), 2, span),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::new(Operation::MulAdd { offset: 1, factor: 1 }, span),
+                    Instruction::new(Operation::MulAdd { offset: -2, factor: -1 }, span),
+                    Instruction::new(Operation::MulAdd { offset: 3, factor: 4 }, span),
+                    Instruction::new(Operation::Clear, span),
+                ]), span),
+                Instruction::at_offset(Operation::Read, -1, span),
+                Instruction::at_offset(Operation::Write, -1, span),
+                Instruction::new(Operation::Move(3), span),
+                Instruction::new(Operation::Move(-1), span),
+                Instruction::new(Operation::Scan { stride: 1 }, span),
+                Instruction::new(Operation::Scan { stride: -1 }, span),
+                Instruction::new(Operation::Scan { stride: 4 }, span),
+            ],
+        };
+        let expected: &str = "\
+function wrap(ptr, offset) {
+    const position = ptr + (offset < 0 ? offset + 30000 : offset);
+    return position < 30000 ? position : position - 30000;
+}
+
+function searchRight(tape, ptr) {
+    let position = tape.indexOf(0, ptr);
+    if (position < 0) {
+        position = tape.indexOf(0);
+    }
+    while (position < 0) {
+    }
+    return position;
+}
+
+function searchLeft(tape, ptr) {
+    let position = tape.lastIndexOf(0, ptr);
+    if (position < 0) {
+        position = tape.lastIndexOf(0);
+    }
+    while (position < 0) {
+    }
+    return position;
+}
+
+{
+    tape[ptr] += 3;
+    tape[wrap(ptr, 2)] -= 44;
+    while (tape[ptr] !== 0) {
+        tape[wrap(ptr, 1)] += tape[ptr];
+        tape[wrap(ptr, -2)] -= tape[ptr];
+        tape[wrap(ptr, 3)] += tape[ptr] * 4;
+        tape[ptr] = 0;
+    }
+    tape[wrap(ptr, -1)] = bytes.next().value ?? tape[wrap(ptr, -1)];
+    output.push(tape[wrap(ptr, -1)]);
+    ptr = wrap(ptr, 3);
+    ptr = wrap(ptr, -1);
+    ptr = searchRight(tape, ptr);
+    ptr = searchLeft(tape, ptr);
+    while (tape[ptr] !== 0) {
+        ptr = wrap(ptr, 4);
+    }
+}
+";
+
+        let mut generator = JsGenerator::new();
+        assert_eq!(generator.generate(template, &program), expected);
+    }
+
+    #[test]
+    fn test_generate_cell_types() {
+        let template: &str = "{runtime}{\n    let tape = new {array}({tape_size});\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(-70_000), 1, span),
+                Instruction::new(Operation::MulAdd { offset: 1, factor: 300 }, span),
+                Instruction::new(Operation::Write, span),
+            ],
+        };
+
+        let options = Options { cell_type: CellType::U16, tape_size: 16, ..Options::default() };
+        let generated: String = JsGenerator::with_options(options).generate(template, &program);
+        assert_eq!(generated, "function wrap(ptr, offset) {
+    const position = ptr + (offset < 0 ? offset + 16 : offset);
+    return position < 16 ? position : position - 16;
+}
+
+{
+    let tape = new Uint16Array(16);
+    tape[wrap(ptr, 1)] -= 4464;
+    if (tape[ptr] !== 0) {
+        tape[wrap(ptr, 1)] += tape[ptr] * 300;
+    }
+    output.push(tape[ptr] & 255);
+}
+");
+
+        // Products of 32 bit cells are computed exactly.
+        let options = Options { cell_type: CellType::U32, ..Options::default() };
+        let generated: String = JsGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("    let tape = new Uint32Array(30000);\n"));
+        assert!(generated.contains("        tape[wrap(ptr, 1)] += Math.imul(tape[ptr], 300);\n"));
+
+        let options = Options { cell_type: CellType::I64, ..Options::default() };
+        let generated: String = JsGenerator::with_options(options).generate(template, &program);
+        assert!(generated.ends_with("{
+    let tape = new BigInt64Array(30000);
+    tape[wrap(ptr, 1)] -= 70000n;
+    if (tape[ptr] !== 0n) {
+        tape[wrap(ptr, 1)] += tape[ptr] * 300n;
+    }
+    output.push(Number(tape[ptr] & 255n));
+}
+"));
+    }
+
+    #[test]
+    fn test_generate_overflow() {
+        let template: &str = "{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 2, position: 3 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(5), span),
+                Instruction::new(Operation::MulAdd { offset: -1, factor: -1 }, span),
+            ],
+        };
+
+        let options = Options { overflow: Overflow::Saturate, ..Options::default() };
+        let generated: String = JsGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("function wrap(ptr, offset) {\n"));
+        assert!(generated.contains("\n\nfunction add(value, delta) {\n"));
+        assert!(generated.contains("\
+function add(value, delta) {
+    const sum = BigInt(value) + delta;
+    const bounded = sum < 0n ? 0n : sum > 255n ? 255n : sum;
+"));
+        assert!(generated.ends_with("{
+    tape[ptr] = add(tape[ptr], 5n);
+    if (tape[ptr] !== 0) {
+        tape[wrap(ptr, -1)] = add(tape[wrap(ptr, -1)], BigInt(tape[ptr]) * -1n);
+    }
+}
+"));
+
+        let options = Options { overflow: Overflow::Error, cell_type: CellType::I64, ..Options::default() };
+        let generated: String = JsGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("const POSITIONS = [\n    [2, 3],\n    [2, 3],\n];\n"));
+        assert!(generated.contains("\
+function add(value, delta, id) {
+    const sum = BigInt(value) + delta;
+    if (sum < -9223372036854775808n || sum > 9223372036854775807n) {
+        throw fail(\"cell overflow\", id);
+    }
+"));
+        assert!(generated.ends_with("{
+    tape[ptr] = add(tape[ptr], 5n, 0);
+    if (tape[ptr] !== 0n) {
+        tape[wrap(ptr, -1)] = add(tape[wrap(ptr, -1)], BigInt(tape[ptr]) * -1n, 1);
+    }
+}
+"));
+    }
+
+    #[test]
+    fn test_generate_eof() {
+        let template: &str = "{\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![Instruction::at_offset(Operation::Read, 2, span)],
+        };
+
+        let expectations: [(EofBehavior, CellType, &str); 4] = [
+            (EofBehavior::Zero, CellType::U8, "bytes.next().value ?? 0"),
+            (EofBehavior::MinusOne, CellType::U8, "bytes.next().value ?? -1"),
+            (EofBehavior::MinusOne, CellType::I64, "BigInt(bytes.next().value ?? -1)"),
+            (EofBehavior::Unchanged, CellType::U8, "bytes.next().value ?? tape[wrap(ptr, 2)]"),
+        ];
+        for &(eof, cell_type, expected) in expectations.iter() {
+            let options = Options { eof, cell_type, ..Options::default() };
+            let generated: String = JsGenerator::with_options(options).generate(template, &program);
+            let line: String = format!("    tape[wrap(ptr, 2)] = {expected};\n", expected = expected);
+            assert!(generated.contains(&line));
+        }
+    }
+
+    #[test]
+    fn test_generate_wrapping_tape() {
+        let template: &str = "{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), 5, span),
+                Instruction::at_offset(Operation::MulAdd { offset: -3, factor: 2 }, -1, span),
+                Instruction::new(Operation::Move(-6), span),
+                Instruction::new(Operation::Scan { stride: 9 }, span),
+            ],
+        };
+
+        // Offsets are reduced to less than the tape's size, and the runtime function wraps the remaining distance.
+        let options = Options { tape_size: 4, ..Options::default() };
+        let generated: String = JsGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("function wrap(ptr, offset) {\n"));
+        assert!(generated.contains("    return position < 4 ? position : position - 4;\n"));
+        assert!(generated.ends_with("{
+    tape[wrap(ptr, 1)] += 1;
+    if (tape[wrap(ptr, -1)] !== 0) {
+        tape[ptr] += tape[wrap(ptr, -1)] * 2;
+    }
+    ptr = wrap(ptr, -2);
+    while (tape[ptr] !== 0) {
+        ptr = wrap(ptr, 1);
+    }
+}
+"));
+
+        // Checked tapes are accessed directly.
+        let options = Options { tape_size: 4, checked: true, ..Options::default() };
+        let generated: String = JsGenerator::with_options(options).generate(template, &program);
+        assert!(!generated.contains("wrap("));
+    }
+
+    #[test]
+    fn test_generate_growing_tape() {
+        let template: &str = "{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), -1, span),
+                Instruction::new(Operation::Move(2), span),
+                Instruction::at_offset(Operation::Add(1), 3, span),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::at_offset(Operation::MulAdd { offset: -3, factor: 2 }, 1, span),
+                    Instruction::new(Operation::Move(1), span),
+                ]), span),
+                Instruction::new(Operation::Scan { stride: 1 }, span),
+                Instruction::at_offset(Operation::Write, -2, span),
+            ],
+        };
+
+        let options = Options { tape_growth: TapeGrowth::Right, ..Options::default() };
+        let generated: String = JsGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("{
+    function reserve(position, low, high) {
+        if (position < 0) {
+            position = (position % tape.length + tape.length) % tape.length;
+        }
+        if (position + high >= tape.length) {
+"));
+        assert!(generated.ends_with("
+        return position;
+    }
+
+    function wrapLeft(ptr, distance) {
+        const position = ptr - distance;
+        return position < 0 ? (position % tape.length + tape.length) % tape.length : position;
+    }
+
+    tape[wrapLeft(ptr, 1)] += 1;
+    ptr = reserve(ptr + 2, 0, 3);
+    tape[ptr + 3] += 1;
+    while (tape[ptr] !== 0) {
+        ptr = reserve(ptr, 0, 1);
+        if (tape[ptr + 1] !== 0) {
+            tape[wrapLeft(ptr, 2)] += tape[ptr + 1] * 2;
+        }
+        ptr = reserve(ptr + 1, 0, 0);
+    }
+    while (tape[ptr] !== 0) {
+        ptr = reserve(ptr + 1, 0, 0);
+    }
+    output.push(tape[wrapLeft(ptr, 2)]);
+}
+"));
+
+        let options = Options { tape_growth: TapeGrowth::Both, checked: true, ..Options::default() };
+        let generated: String = JsGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("{
+    function reserve(position, low, high) {
+        if (position + low < 0) {
+            const before = Math.max(-(position + low), tape.length);
+"));
+        assert!(generated.contains("    ptr = reserve(ptr + 2, 0, 3);\n"));
+    }
+
+    #[test]
+    fn test_generate_checked() {
+        let template: &str = "{runtime}{\n";
+        let position = |lineno: usize, position: usize| Span::at(MetaData { lineno, position });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), -1, position(1, 1)),
+                Instruction::new(Operation::Move(2), position(1, 3)),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::at_offset(Operation::MulAdd { offset: -3, factor: 2 }, 1, position(2, 1)),
+                    Instruction::new(Operation::Scan { stride: 1 }, position(2, 5)),
+                ]), position(1, 4)),
+            ],
+        };
+
+        let options = Options { checked: true, ..Options::default() };
+        let generated: String = JsGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("const POSITIONS = [
+    [1, 1],
+    [1, 3],
+    [2, 1],
+    [2, 1],
+    [2, 5],
+];
+"));
+        assert!(generated.ends_with("{
+    function reserve(position, low, high, id) {
+        if (position + low < 0) {
+            throw fail(\"pointer underflow\", id);
+        }
+        if (position + high >= tape.length) {
+            throw fail(\"pointer overflow\", id);
+        }
+        return position;
+    }
+
+    ptr = reserve(ptr, -1, 0, 0);
+    tape[ptr - 1] += 1;
+    ptr = reserve(ptr + 2, 0, 0, 1);
+    while (tape[ptr] !== 0) {
+        ptr = reserve(ptr, 0, 1, 2);
+        if (tape[ptr + 1] !== 0) {
+            ptr = reserve(ptr, -2, 0, 3);
+            tape[ptr - 2] += tape[ptr + 1] * 2;
+        }
+        while (tape[ptr] !== 0) {
+            ptr = reserve(ptr + 1, 0, 0, 4);
+        }
+    }
+}
+"));
+
+        let options = Options { checked: true, tape_growth: TapeGrowth::Right, ..Options::default() };
+        let generated: String = JsGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("            throw fail(\"pointer underflow\", id);\n"));
+        assert!(!generated.contains("            throw fail(\"pointer overflow\", id);\n"));
+    }
+
+    #[test]
+    fn test_indent_level_0() {
+        let mut generator = JsGenerator::new();
+        generator.indentation_level = 0;
+        assert_eq!(generator.indent("ptr += 1;"), String::from("ptr += 1;"));
+    }
+
+    #[test]
+    fn test_indent_level_2() {
+        let mut generator = JsGenerator::new();
+        generator.indentation_level = 2;
+        assert_eq!(generator.indent("ptr += 1;"), String::from("        ptr += 1;"));
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Definitions of the languages built into `rustfuck`.
+
+use Lexer;
+use Named;
+use Token;
+use super::Brainfuck;
+use super::Language;
+use super::Ook;
+
+/// All built-in dialects, in the order in which they are registered.
+static DIALECTS: [Dialect; 2] = [
+    Dialect::Brainfuck,
+    Dialect::Ook,
+];
+
+/// The languages from which `rustfuck` can compile programs without further configuration.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Dialect {
+    /// `Brainfuck` itself.
+    Brainfuck,
+
+    /// `Ook!`, spelling each `Brainfuck` command as a pair of words.
+    Ook,
+}
+
+impl Dialect {
+    /// Get all built-in dialects, starting with `Brainfuck`.
+    pub fn all() -> &'static [Dialect] {
+        &DIALECTS
+    }
+
+    /// Get the name identifying the dialect, e.g. on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Dialect::Brainfuck => "brainfuck",
+            Dialect::Ook => "ook",
+        }
+    }
+
+    /// Get the extensions of source files written in the dialect, starting with the preferred one.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Dialect::Brainfuck => &["bf", "b"],
+            Dialect::Ook => &["ook"],
+        }
+    }
+}
+
+impl Named for Dialect {
+    fn name(&self) -> &str {
+        Dialect::name(*self)
+    }
+}
+
+impl Language for Dialect {
+    fn extensions(&self) -> Vec<&str> {
+        Dialect::extensions(*self).to_vec()
+    }
+
+    fn lexer(&self, source: &str) -> Box<dyn Lexer> {
+        match *self {
+            Dialect::Brainfuck => Box::new(Brainfuck::new(source)),
+            Dialect::Ook => Box::new(Ook::new(source)),
+        }
+    }
+
+    fn spell(&self, token: &Token) -> &str {
+        match (*self, token) {
+            (Dialect::Brainfuck, &Token::Add(..)) => "+",
+            (Dialect::Brainfuck, &Token::Sub(..)) => "-",
+            (Dialect::Brainfuck, &Token::Right(..)) => ">",
+            (Dialect::Brainfuck, &Token::Left(..)) => "<",
+            (Dialect::Brainfuck, &Token::Read(..)) => ",",
+            (Dialect::Brainfuck, &Token::Write(..)) => ".",
+            (Dialect::Brainfuck, &Token::BeginLoop(..)) => "[",
+            (Dialect::Brainfuck, &Token::EndLoop(..)) => "]",
+            (Dialect::Ook, &Token::Add(..)) => "Ook. Ook.",
+            (Dialect::Ook, &Token::Sub(..)) => "Ook! Ook!",
+            (Dialect::Ook, &Token::Right(..)) => "Ook. Ook?",
+            (Dialect::Ook, &Token::Left(..)) => "Ook? Ook.",
+            (Dialect::Ook, &Token::Read(..)) => "Ook. Ook!",
+            (Dialect::Ook, &Token::Write(..)) => "Ook! Ook.",
+            (Dialect::Ook, &Token::BeginLoop(..)) => "Ook! Ook?",
+            (Dialect::Ook, &Token::EndLoop(..)) => "Ook? Ook!",
+        }
+    }
+
+    fn separator(&self) -> &str {
+        match *self {
+            Dialect::Brainfuck => "",
+            Dialect::Ook => " ",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Error;
+    use MetaData;
+    use Named;
+    use SyntaxError;
+    use Token;
+    use language::Dialect;
+    use language::Language;
+
+    #[test]
+    fn test_all() {
+        assert_eq!(Dialect::all(), [Dialect::Brainfuck, Dialect::Ook]);
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!(Dialect::Brainfuck.name(), "brainfuck");
+        assert_eq!(Dialect::Ook.name(), "ook");
+        assert_eq!(Named::name(&Dialect::Brainfuck), "brainfuck");
+    }
+
+    #[test]
+    fn test_extensions() {
+        assert_eq!(Dialect::Brainfuck.extensions(), ["bf", "b"]);
+        assert_eq!(Dialect::Ook.extensions(), ["ook"]);
+        assert_eq!(Language::extensions(&Dialect::Brainfuck), vec!["bf", "b"]);
+    }
+
+    #[test]
+    fn test_spell() {
+        let source: &str = "+-><,.[]";
+        let tokens: Vec<Token> = Dialect::Brainfuck.tokenize(source).unwrap();
+        let spelled: Vec<&str> = tokens.iter().map(|token| Dialect::Brainfuck.spell(token)).collect();
+        assert_eq!(spelled.concat(), source);
+
+        let spelled: Vec<&str> = tokens.iter().map(|token| Dialect::Ook.spell(token)).collect();
+        let tokens: Vec<Token> = Dialect::Ook.tokenize(&spelled.join(" ")).unwrap();
+        let spelled: Vec<&str> = tokens.iter().map(|token| Dialect::Brainfuck.spell(token)).collect();
+        assert_eq!(spelled.concat(), source);
+    }
+
+    #[test]
+    fn test_separator() {
+        assert_eq!(Dialect::Brainfuck.separator(), "");
+        assert_eq!(Dialect::Ook.separator(), " ");
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens: Vec<Token> = Dialect::Brainfuck.tokenize("+\n[-]").unwrap();
+        let expected: Vec<Token> = vec![
+            Token::Add(String::from("+"), MetaData { lineno: 1, position: 1 }),
+            Token::BeginLoop(String::from("["), MetaData { lineno: 2, position: 1 }),
+            Token::Sub(String::from("-"), MetaData { lineno: 2, position: 2 }),
+            Token::EndLoop(String::from("]"), MetaData { lineno: 2, position: 3 }),
+        ];
+        assert_eq!(tokens, expected);
+
+        let tokens: Vec<Token> = Dialect::Ook.tokenize("Ook. Ook.\nOok! Ook? Ook! Ook! Ook? Ook!").unwrap();
+        let expected: Vec<Token> = vec![
+            Token::Add(String::from("Ook. Ook."), MetaData { lineno: 1, position: 1 }),
+            Token::BeginLoop(String::from("Ook! Ook?"), MetaData { lineno: 2, position: 1 }),
+            Token::Sub(String::from("Ook! Ook!"), MetaData { lineno: 2, position: 11 }),
+            Token::EndLoop(String::from("Ook? Ook!"), MetaData { lineno: 2, position: 21 }),
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_tokenize_syntax_error() {
+        let result = Dialect::Ook.tokenize("Ook. Ook. Ook!");
+        let position = MetaData { lineno: 1, position: 11 };
+        match result {
+            Err(Error::Syntax(error)) => assert_eq!(error, SyntaxError::IncompleteCommand(position)),
+            _ => panic!("Unexpected result: {:?}", result),
+        }
+    }
+}
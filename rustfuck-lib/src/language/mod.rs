@@ -4,8 +4,210 @@
 // MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
 // modified, or distributed except according to those terms.
 
-//! Module defining all languages understood by `rustfuck`.
+//! Module defining all languages understood by `rustfuck`, and a registry collecting them.
 
 mod brainfuck;
+mod dialect;
+mod ook;
+mod substitution;
 
 pub use self::brainfuck::Brainfuck;
+pub use self::dialect::Dialect;
+pub use self::ook::Ook;
+// Substitution languages are named after their kind of language, which repeats the module's name.
+#[allow(clippy::module_name_repetitions)]
+pub use self::substitution::SubstitutionLanguage;
+
+use std::path::Path;
+
+use Lexer;
+use Named;
+use Registry;
+use Result;
+use Token;
+
+/// A language from the `Brainfuck` family, whose programs the compiler can read.
+///
+/// All built-in dialects are languages. Other crates can implement further ones, and add them to a
+/// `LanguageRegistry`.
+///
+/// # Examples
+///
+/// ```
+/// use rustfuck_lib::Lexer;
+/// use rustfuck_lib::Named;
+/// use rustfuck_lib::Token;
+/// use rustfuck_lib::language::Brainfuck;
+/// use rustfuck_lib::language::Dialect;
+/// use rustfuck_lib::language::Language;
+/// use rustfuck_lib::language::LanguageRegistry;
+///
+/// /// `Brainfuck` written in capital letters.
+/// struct Shouting;
+///
+/// impl Named for Shouting {
+///     fn name(&self) -> &str {
+///         "shouting"
+///     }
+/// }
+///
+/// impl Language for Shouting {
+///     fn extensions(&self) -> Vec<&str> {
+///         vec!["BF"]
+///     }
+///
+///     fn lexer(&self, source: &str) -> Box<dyn Lexer> {
+///         Box::new(Brainfuck::new(&source.to_lowercase()))
+///     }
+///
+///     fn spell(&self, token: &Token) -> &str {
+///         Dialect::Brainfuck.spell(token)
+///     }
+/// }
+///
+/// let mut registry = LanguageRegistry::new();
+/// registry.register(Box::new(Shouting));
+///
+/// let language = registry.get("shouting").unwrap();
+/// assert_eq!(language.tokenize("+[-]").unwrap().len(), 4);
+/// ```
+pub trait Language: Named {
+    /// Get the extensions of source files written in the language, starting with the preferred one.
+    fn extensions(&self) -> Vec<&str>;
+
+    /// Get a lexer converting the given `source` into tokens.
+    fn lexer(&self, source: &str) -> Box<dyn Lexer>;
+
+    /// Get the string spelling the command of the `token` in the language, ignoring the token's own lexeme.
+    fn spell(&self, token: &Token) -> &str;
+
+    /// Get the string placed between adjacent commands in generated source code. Tokenizing generated code must
+    /// result in the same commands whether they are separated by this string, by spaces, or by line breaks.
+    fn separator(&self) -> &str {
+        " "
+    }
+
+    /// Convert the given `source` into tokens.
+    ///
+    /// Fails with a syntax error if the `source` is not a valid program in the language.
+    fn tokenize(&self, source: &str) -> Result<Vec<Token>> {
+        self.lexer(source).try_tokenize()
+    }
+}
+
+/// A collection of languages, identified by their names and file extensions.
+// The registry of languages is named after its kind of extension, which repeats the module's name.
+#[allow(clippy::module_name_repetitions)]
+pub type LanguageRegistry = Registry<dyn Language>;
+
+impl Registry<dyn Language> {
+    /// Initialize a new registry containing all built-in dialects, starting with `Brainfuck`.
+    pub fn new() -> LanguageRegistry {
+        let mut registry = LanguageRegistry::empty();
+        for &dialect in Dialect::all() {
+            registry.register(Box::new(dialect));
+        }
+
+        registry
+    }
+
+    /// Get the first registered language whose files have the given `extension`, ignoring the case.
+    pub fn for_extension(&self, extension: &str) -> Option<&dyn Language> {
+        self.iter()
+            .find(|language| language.extensions().iter().any(|known| known.eq_ignore_ascii_case(extension)))
+    }
+
+    /// Get the first registered language whose files have the extension of the file at `path`.
+    pub fn for_path(&self, path: &Path) -> Option<&dyn Language> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| self.for_extension(extension))
+    }
+}
+
+impl Default for Registry<dyn Language> {
+    fn default() -> LanguageRegistry {
+        LanguageRegistry::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::path::Path;
+
+    use Lexer;
+    use MetaData;
+    use Named;
+    use Token;
+    use language::Brainfuck;
+    use language::Dialect;
+    use language::Language;
+    use language::LanguageRegistry;
+
+    /// `Brainfuck` in files with a different extension.
+    struct Renamed;
+
+    impl Named for Renamed {
+        fn name(&self) -> &str {
+            "brainfuck"
+        }
+    }
+
+    impl Language for Renamed {
+        fn extensions(&self) -> Vec<&str> {
+            vec!["bfk"]
+        }
+
+        fn lexer(&self, source: &str) -> Box<dyn Lexer> {
+            Box::new(Brainfuck::new(source))
+        }
+
+        fn spell(&self, token: &Token) -> &str {
+            Dialect::Brainfuck.spell(token)
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let registry = LanguageRegistry::new();
+        assert_eq!(registry.names(), ["brainfuck", "ook"]);
+        assert_eq!(registry.get("brainfuck").map(Named::name), Some("brainfuck"));
+        assert!(registry.get("cobol").is_none());
+        assert_eq!(format!("{:?}", LanguageRegistry::empty()), "Registry { items: [] }");
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry = LanguageRegistry::new();
+        registry.register(Box::new(Renamed));
+        assert_eq!(registry.iter().count(), 2);
+        assert_eq!(registry.get("brainfuck").map(Language::extensions), Some(vec!["bfk"]));
+        assert!(registry.for_extension("bf").is_none());
+    }
+
+    #[test]
+    fn test_separator() {
+        let token = Token::Add(String::from("+"), MetaData { lineno: 1, position: 1 });
+        assert_eq!(Renamed.spell(&token), "+");
+        assert_eq!(Renamed.separator(), " ");
+    }
+
+    #[test]
+    fn test_for_extension() {
+        let registry = LanguageRegistry::new();
+        assert_eq!(registry.for_extension("bf").map(Named::name), Some("brainfuck"));
+        assert_eq!(registry.for_extension("B").map(Named::name), Some("brainfuck"));
+        assert_eq!(registry.for_extension("ook").map(Named::name), Some("ook"));
+        assert!(registry.for_extension("c").is_none());
+    }
+
+    #[test]
+    fn test_for_path() {
+        let registry = LanguageRegistry::new();
+        assert_eq!(registry.for_path(Path::new("examples/hello.bf")).map(Named::name), Some("brainfuck"));
+        assert_eq!(registry.for_path(Path::new("hello.b")).map(Named::name), Some("brainfuck"));
+        assert!(registry.for_path(Path::new("hello.txt")).is_none());
+        assert!(registry.for_path(Path::new("hello")).is_none());
+    }
+}
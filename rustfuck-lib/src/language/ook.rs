@@ -0,0 +1,186 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementation of the `Ook!` programming language.
+
+use Lexer;
+use MetaData;
+use Result;
+use SyntaxError;
+use Token;
+
+/// The esoteric programming language `Ook!`, created by David Morgan-Mar, which spells each `Brainfuck` command as
+/// a pair of the words `Ook.`, `Ook?`, and `Ook!`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ook {
+
+    /// The program source code.
+    source: String,
+}
+
+impl Ook {
+    /// Initialize a new `Ook!` program from the given `source`.
+    pub fn new(source: &str) -> Ook {
+        Ook {
+            source: String::from(source),
+        }
+    }
+
+    /// Find all words in the source, returning their punctuation marks with the positions at which they start.
+    fn words(&self) -> Vec<(char, MetaData)> {
+        let mut words = Vec::<(char, MetaData)>::new();
+
+        // Lines and cursor positions start counting at 1, so use `zip(1..)` instead of enumerate which would start
+        // counting at 0.
+        for (line, lineno) in self.source.lines().zip(1..) {
+            let characters: Vec<char> = line.chars().collect();
+            for (index, position) in (0..characters.len()).zip(1..) {
+                if let ['O', 'o', 'k', mark @ ('.' | '?' | '!'), ..] = characters[index..] {
+                    words.push((mark, MetaData { lineno, position }));
+                }
+            }
+        }
+
+        words
+    }
+
+    /// Get the token of the command spelled by the words with the punctuation marks `first` and `second`, starting at
+    /// the given `position`, if the words form a command.
+    fn command(first: char, second: char, position: MetaData) -> Option<Token> {
+        let lexeme: String = format!("Ook{first} Ook{second}", first = first, second = second);
+        match (first, second) {
+            ('.', '.') => Some(Token::Add(lexeme, position)),
+            ('!', '!') => Some(Token::Sub(lexeme, position)),
+            ('.', '?') => Some(Token::Right(lexeme, position)),
+            ('?', '.') => Some(Token::Left(lexeme, position)),
+            ('.', '!') => Some(Token::Read(lexeme, position)),
+            ('!', '.') => Some(Token::Write(lexeme, position)),
+            ('!', '?') => Some(Token::BeginLoop(lexeme, position)),
+            ('?', '!') => Some(Token::EndLoop(lexeme, position)),
+            _ => None,
+        }
+    }
+}
+
+impl Lexer for Ook {
+    /// Convert the source into tokens, skipping the pair `Ook? Ook?` and a final single word, as they do not form any
+    /// command.
+    fn tokenize(&self) -> Vec<Token> {
+        self.words()
+            .chunks(2)
+            .filter_map(|pair| match *pair {
+                [(first, position), (second, _)] => Ook::command(first, second, position),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn try_tokenize(&self) -> Result<Vec<Token>> {
+        let mut tokens = Vec::<Token>::new();
+        for pair in self.words().chunks(2) {
+            match *pair {
+                [(first, position), (second, _)] => match Ook::command(first, second, position) {
+                    Some(token) => tokens.push(token),
+                    None => return Err(SyntaxError::UnknownCommand(position).into()),
+                },
+                [(_, position)] => return Err(SyntaxError::IncompleteCommand(position).into()),
+                _ => unreachable!("Chunks contain either one or two words."),
+            }
+        }
+
+        Ok(tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Error;
+    use Lexer;
+    use MetaData;
+    use SyntaxError;
+    use Token;
+    use language::Ook;
+
+    /// Tokenize the given `Ook!` `source`, expecting it to be valid.
+    fn tokenize(source: &str) -> Vec<Token> {
+        let tokens: Vec<Token> = Ook::new(source).try_tokenize().unwrap();
+        assert_eq!(tokens, Ook::new(source).tokenize());
+        tokens
+    }
+
+    #[test]
+    fn test_new() {
+        let source: &str = "Ook. Ook.";
+        let ook = Ook::new(source);
+
+        assert_eq!(ook, Ook { source: String::from(source) });
+    }
+
+    #[test]
+    fn test_tokenize_commands() {
+        let position = MetaData { lineno: 1, position: 1 };
+        assert_eq!(tokenize("Ook. Ook."), vec![Token::Add(String::from("Ook. Ook."), position)]);
+        assert_eq!(tokenize("Ook! Ook!"), vec![Token::Sub(String::from("Ook! Ook!"), position)]);
+        assert_eq!(tokenize("Ook. Ook?"), vec![Token::Right(String::from("Ook. Ook?"), position)]);
+        assert_eq!(tokenize("Ook? Ook."), vec![Token::Left(String::from("Ook? Ook."), position)]);
+        assert_eq!(tokenize("Ook. Ook!"), vec![Token::Read(String::from("Ook. Ook!"), position)]);
+        assert_eq!(tokenize("Ook! Ook."), vec![Token::Write(String::from("Ook! Ook."), position)]);
+        assert_eq!(tokenize("Ook! Ook?"), vec![Token::BeginLoop(String::from("Ook! Ook?"), position)]);
+        assert_eq!(tokenize("Ook? Ook!"), vec![Token::EndLoop(String::from("Ook? Ook!"), position)]);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let source: &str = "Ook. Ook. Ook! Ook?Ook. Ook?  the\n\tape says Ook!\nOok. Ook! Ook!";
+        let expected: Vec<Token> = vec![
+            Token::Add(String::from("Ook. Ook."), MetaData { lineno: 1, position: 1 }),
+            Token::BeginLoop(String::from("Ook! Ook?"), MetaData { lineno: 1, position: 11 }),
+            Token::Right(String::from("Ook. Ook?"), MetaData { lineno: 1, position: 20 }),
+            Token::Write(String::from("Ook! Ook."), MetaData { lineno: 2, position: 11 }),
+            Token::Sub(String::from("Ook! Ook!"), MetaData { lineno: 3, position: 6 }),
+        ];
+        assert_eq!(tokenize(source), expected);
+    }
+
+    #[test]
+    fn test_tokenize_skips_invalid_commands() {
+        let tokens: Vec<Token> = Ook::new("Ook? Ook? Ook. Ook. Ook!").tokenize();
+        let expected: Vec<Token> = vec![
+            Token::Add(String::from("Ook. Ook."), MetaData { lineno: 1, position: 11 }),
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_tokenize_ignores_other_words() {
+        assert_eq!(tokenize("Ook Book. ook. OOK! Ook"), Vec::new());
+        assert_eq!(tokenize("BookOok. Ook.k"), vec![Token::Add(String::from("Ook. Ook."), MetaData {
+            lineno: 1,
+            position: 5,
+        })]);
+    }
+
+    #[test]
+    fn test_try_tokenize_incomplete_command() {
+        let result = Ook::new("Ook. Ook.\nOok! Ook? Ook.").try_tokenize();
+        let position = MetaData { lineno: 2, position: 11 };
+        match result {
+            Err(Error::Syntax(error)) => assert_eq!(error, SyntaxError::IncompleteCommand(position)),
+            _ => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_try_tokenize_unknown_command() {
+        let result = Ook::new("Ook. Ook. Ook? Ook?").try_tokenize();
+        let position = MetaData { lineno: 1, position: 11 };
+        match result {
+            Err(Error::Syntax(error)) => assert_eq!(error, SyntaxError::UnknownCommand(position)),
+            _ => panic!("Unexpected result: {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,637 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementation of trivial substitutions of `Brainfuck`, defined by mapping files.
+
+use DialectError;
+use Error;
+use Lexer;
+use MetaData;
+use Named;
+use Result;
+use Token;
+use super::Language;
+
+/// A function creating a token from its lexeme and position.
+type Constructor = fn(String, MetaData) -> Token;
+
+/// The keys of the commands in mapping files, with the tokens they stand for, in the order of `Brainfuck`'s commands
+/// `+-><,.[]`.
+static COMMANDS: [(&str, Constructor); 8] = [
+    ("add", Token::Add),
+    ("sub", Token::Sub),
+    ("right", Token::Right),
+    ("left", Token::Left),
+    ("read", Token::Read),
+    ("write", Token::Write),
+    ("begin_loop", Token::BeginLoop),
+    ("end_loop", Token::EndLoop),
+];
+
+/// The name of a substitution language whose mapping file does not define one.
+const DEFAULT_NAME: &str = "substitution";
+
+/// A language replacing each `Brainfuck` command by an arbitrary string. Everything else in a program is a comment.
+///
+/// Lexing takes the longest string at each position, thus one command's string may start with another one's. Generated
+/// source code is only tokenized into the same commands if no command begins or ends with whitespace.
+///
+/// # Examples
+///
+/// ```
+/// use rustfuck_lib::Named;
+/// use rustfuck_lib::language::Language;
+/// use rustfuck_lib::language::SubstitutionLanguage;
+///
+/// let mapping: &str = r#"
+/// ## ReverseFuck swaps each pair of related commands.
+/// name = "reversefuck"
+/// extensions = ["rbf"]
+///
+/// add = "-"
+/// sub = "+"
+/// right = "<"
+/// left = ">"
+/// read = "."
+/// write = ","
+/// begin_loop = "]"
+/// end_loop = "["
+/// "#;
+///
+/// let language = SubstitutionLanguage::from_mapping(mapping).unwrap();
+/// assert_eq!(language.name(), "reversefuck");
+/// assert_eq!(language.tokenize("-]+[").unwrap().len(), 4);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubstitutionLanguage {
+
+    /// The name identifying the language.
+    name: String,
+
+    /// The extensions of source files written in the language.
+    extensions: Vec<String>,
+
+    /// The strings of the commands, in the order of `COMMANDS`.
+    commands: [String; 8],
+}
+
+impl SubstitutionLanguage {
+    /// Initialize a new substitution language with the given `name` and file `extensions`, spelling `Brainfuck`'s
+    /// commands `+-><,.[]` as the given `commands`.
+    ///
+    /// Fails with a dialect error if a command is the empty string, or two commands are the same string.
+    pub fn new(name: &str, extensions: &[&str], commands: [&str; 8]) -> Result<SubstitutionLanguage> {
+        for (index, command) in commands.iter().enumerate() {
+            if command.is_empty() {
+                return Err(DialectError::EmptyCommand(COMMANDS[index].0).into());
+            }
+
+            if let Some(other) = commands[..index].iter().position(|other| other == command) {
+                return Err(DialectError::AmbiguousCommands(COMMANDS[other].0, COMMANDS[index].0).into());
+            }
+        }
+
+        Ok(SubstitutionLanguage {
+            name: String::from(name),
+            extensions: extensions.iter().map(|&extension| String::from(extension)).collect(),
+            commands: commands.map(String::from),
+        })
+    }
+
+    /// Initialize a new substitution language from the `mapping` file, written in a subset of `TOML`.
+    ///
+    /// Each entry `key = value` starts on a line of its own, between empty lines and comments starting with `#`. Arrays
+    /// may continue on the following lines, which may contain comments as well. The keys `add`, `sub`,
+    /// `right`, `left`, `read`, `write`, `begin_loop`, and `end_loop` define the commands as strings. Optionally, the
+    /// key `name` defines the name as a string, and the key `extensions` defines the file extensions as an array of
+    /// strings.
+    ///
+    /// Fails with a dialect error if the file is malformed, or does not define a valid language.
+    pub fn from_mapping(mapping: &str) -> Result<SubstitutionLanguage> {
+        let mut name: Option<String> = None;
+        let mut extensions: Option<Vec<String>> = None;
+        let mut commands: [Option<String>; 8] = Default::default();
+
+        let mut parser = EntryParser::new(mapping);
+        while let Some((key, key_position, value, value_position)) = parser.entry()? {
+            let duplicate: bool = match (key.as_str(), value) {
+                ("name", Value::String(value)) => name.replace(value).is_some(),
+                ("extensions", Value::Array(value)) => extensions.replace(value).is_some(),
+                ("name", _) | ("extensions", _) => return Err(DialectError::MalformedEntry(value_position).into()),
+                (key, value) => {
+                    let index: usize = match COMMANDS.iter().position(|&(command, _)| command == key) {
+                        Some(index) => index,
+                        None => return Err(DialectError::UnknownKey(key_position).into()),
+                    };
+                    match value {
+                        Value::String(value) => commands[index].replace(value).is_some(),
+                        Value::Array(_) => return Err(DialectError::MalformedEntry(value_position).into()),
+                    }
+                },
+            };
+
+            if duplicate {
+                return Err(DialectError::DuplicateKey(key_position).into());
+            }
+        }
+
+        let name: String = name.unwrap_or_else(|| String::from(DEFAULT_NAME));
+        let extensions: Vec<String> = extensions.unwrap_or_default();
+        let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+        let mut defined: [&str; 8] = [""; 8];
+        for (index, command) in commands.iter().enumerate() {
+            match *command {
+                Some(ref command) => defined[index] = command,
+                None => return Err(DialectError::MissingCommand(COMMANDS[index].0).into()),
+            }
+        }
+
+        SubstitutionLanguage::new(&name, &extensions, defined)
+    }
+}
+
+impl Named for SubstitutionLanguage {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Language for SubstitutionLanguage {
+    fn extensions(&self) -> Vec<&str> {
+        self.extensions.iter().map(String::as_str).collect()
+    }
+
+    fn lexer(&self, source: &str) -> Box<dyn Lexer> {
+        Box::new(Substitution {
+            source: String::from(source),
+            commands: self.commands.clone(),
+        })
+    }
+
+    fn spell(&self, token: &Token) -> &str {
+        let index: usize = match *token {
+            Token::Add(..) => 0,
+            Token::Sub(..) => 1,
+            Token::Right(..) => 2,
+            Token::Left(..) => 3,
+            Token::Read(..) => 4,
+            Token::Write(..) => 5,
+            Token::BeginLoop(..) => 6,
+            Token::EndLoop(..) => 7,
+        };
+
+        &self.commands[index]
+    }
+
+    /// Get the empty string if all commands are single characters, as they cannot be confused then, a space otherwise.
+    fn separator(&self) -> &str {
+        if self.commands.iter().all(|command| command.chars().count() == 1) {
+            ""
+        } else {
+            " "
+        }
+    }
+}
+
+/// A program in a substitution language.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Substitution {
+
+    /// The program source code.
+    source: String,
+
+    /// The strings of the commands, in the order of `COMMANDS`.
+    commands: [String; 8],
+}
+
+impl Lexer for Substitution {
+    fn tokenize(&self) -> Vec<Token> {
+        let mut tokens = Vec::<Token>::new();
+
+        // Lines and cursor positions start counting at 1.
+        let mut position = MetaData { lineno: 1, position: 1 };
+        let mut rest: &str = &self.source;
+        while let Some(character) = rest.chars().next() {
+            let longest = self.commands.iter()
+                .enumerate()
+                .filter(|&(_, command)| rest.starts_with(command.as_str()))
+                .max_by_key(|&(_, command)| command.len());
+
+            let lexeme: &str = match longest {
+                Some((index, command)) => {
+                    tokens.push(COMMANDS[index].1(command.clone(), position));
+                    command
+                },
+                None => &rest[..character.len_utf8()],
+            };
+
+            for character in lexeme.chars() {
+                if character == '\n' {
+                    position = MetaData { lineno: position.lineno + 1, position: 1 };
+                } else {
+                    position.position += 1;
+                }
+            }
+            rest = &rest[lexeme.len()..];
+        }
+
+        tokens
+    }
+}
+
+/// The values of entries in mapping files.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Value {
+    /// A single string.
+    String(String),
+
+    /// An array of strings.
+    Array(Vec<String>),
+}
+
+/// A parser for the entries of a mapping file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct EntryParser {
+
+    /// The characters of each line of the file.
+    lines: Vec<Vec<char>>,
+
+    /// The index of the line containing the next character to parse.
+    line: usize,
+
+    /// The index of the next character to parse within its line.
+    index: usize,
+}
+
+impl EntryParser {
+    /// Initialize a new parser for the given `mapping` file.
+    fn new(mapping: &str) -> EntryParser {
+        EntryParser {
+            lines: mapping.lines().map(|line| line.chars().collect()).collect(),
+            line: 0,
+            index: 0,
+        }
+    }
+
+    /// Parse the next entry, returning its key and value with their positions, or nothing if only empty lines and
+    /// comments are left.
+    fn entry(&mut self) -> Result<Option<(String, MetaData, Value, MetaData)>> {
+        self.skip_whitespace();
+        while self.is_at_end() {
+            if !self.next_line() {
+                return Ok(None);
+            }
+            self.skip_whitespace();
+        }
+
+        let key_position: MetaData = self.position();
+        let key: String = self.key()?;
+        self.skip_whitespace();
+        self.expect('=')?;
+        self.skip_whitespace();
+        let value_position: MetaData = self.position();
+        let value: Value = self.value()?;
+        self.skip_whitespace();
+        if !self.is_at_end() {
+            return Err(self.error());
+        }
+
+        Ok(Some((key, key_position, value, value_position)))
+    }
+
+    /// Parse a bare key of letters, digits, underscores, and dashes, or a quoted key.
+    fn key(&mut self) -> Result<String> {
+        if let Some('"') | Some('\'') = self.peek() {
+            return self.string();
+        }
+
+        let start: usize = self.index;
+        while let Some(character) = self.peek() {
+            if !(character.is_ascii_alphanumeric() || character == '_' || character == '-') {
+                break;
+            }
+            self.index += 1;
+        }
+
+        if self.index == start {
+            return Err(self.error());
+        }
+
+        Ok(self.characters()[start..self.index].iter().collect())
+    }
+
+    /// Parse a string or an array of strings, which may span several lines.
+    fn value(&mut self) -> Result<Value> {
+        if self.peek() != Some('[') {
+            return Ok(Value::String(self.string()?));
+        }
+
+        self.index += 1;
+        let mut strings: Vec<String> = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                self.index += 1;
+                return Ok(Value::Array(strings));
+            }
+
+            strings.push(self.string()?);
+            self.skip_blank();
+            match self.peek() {
+                Some(',') => self.index += 1,
+                Some(']') => {},
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    /// Parse a basic string in double quotes, which may contain escape sequences, or a literal string in single
+    /// quotes.
+    fn string(&mut self) -> Result<String> {
+        let quote: char = match self.peek() {
+            Some(quote @ '"') | Some(quote @ '\'') => quote,
+            _ => return Err(self.error()),
+        };
+        self.index += 1;
+
+        let mut string = String::new();
+        loop {
+            let character: char = self.next().ok_or_else(|| self.error())?;
+            match character {
+                _ if character == quote => return Ok(string),
+                '\\' if quote == '"' => string.push(self.escape()?),
+                _ => string.push(character),
+            }
+        }
+    }
+
+    /// Parse the escape sequence following a backslash in a basic string.
+    fn escape(&mut self) -> Result<char> {
+        let escaped: char = match self.next() {
+            Some('b') => '\u{8}',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('f') => '\u{c}',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('u') => return self.unicode(4),
+            Some('U') => return self.unicode(8),
+            _ => return Err(self.error()),
+        };
+
+        Ok(escaped)
+    }
+
+    /// Parse the `digits` hexadecimal digits of a unicode escape sequence.
+    fn unicode(&mut self, digits: usize) -> Result<char> {
+        let end: usize = self.index + digits;
+        if end > self.characters().len() {
+            return Err(self.error());
+        }
+
+        let code: String = self.characters()[self.index..end].iter().collect();
+        self.index = end;
+        u32::from_str_radix(&code, 16).ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error())
+    }
+
+    /// Consume the `expected` character.
+    fn expect(&mut self, expected: char) -> Result<()> {
+        if self.peek() != Some(expected) {
+            return Err(self.error());
+        }
+
+        self.index += 1;
+        Ok(())
+    }
+
+    /// Skip spaces and tabs.
+    fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.index += 1;
+        }
+    }
+
+    /// Skip spaces, tabs, comments, and line breaks.
+    fn skip_blank(&mut self) {
+        self.skip_whitespace();
+        while self.is_at_end() && self.next_line() {
+            self.skip_whitespace();
+        }
+    }
+
+    /// Continue at the start of the next line, if there is one.
+    fn next_line(&mut self) -> bool {
+        if self.line + 1 >= self.lines.len() {
+            return false;
+        }
+
+        self.line += 1;
+        self.index = 0;
+        true
+    }
+
+    /// Determine if the rest of the line is empty or a comment.
+    fn is_at_end(&self) -> bool {
+        matches!(self.peek(), None | Some('#'))
+    }
+
+    /// Get the characters of the current line.
+    fn characters(&self) -> &[char] {
+        self.lines.get(self.line).map_or(&[], Vec::as_slice)
+    }
+
+    /// Get the next character without consuming it.
+    fn peek(&self) -> Option<char> {
+        self.characters().get(self.index).cloned()
+    }
+
+    /// Consume the next character.
+    fn next(&mut self) -> Option<char> {
+        let character: Option<char> = self.peek();
+        if character.is_some() {
+            self.index += 1;
+        }
+
+        character
+    }
+
+    /// Get the position of the next character.
+    fn position(&self) -> MetaData {
+        MetaData { lineno: self.line + 1, position: self.index + 1 }
+    }
+
+    /// Get the error for a malformed entry at the position of the next character.
+    fn error(&self) -> Error {
+        DialectError::MalformedEntry(self.position()).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use DialectError;
+    use Error;
+    use MetaData;
+    use Named;
+    use Result;
+    use Token;
+    use language::Language;
+    use language::SubstitutionLanguage;
+
+    /// A mapping file defining `Alphuck`.
+    const ALPHUCK: &str = include_str!("../../../examples/alphuck.toml");
+
+    /// Assert that the `result` is the dialect `error`.
+    fn assert_error(result: Result<SubstitutionLanguage>, error: DialectError) {
+        match result {
+            Err(Error::Dialect(actual)) => assert_eq!(actual, error),
+            _ => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let language = SubstitutionLanguage::new("blub", &["blub"], ["a", "b", "c", "d", "e", "f", "g", "h"]).unwrap();
+        assert_eq!(language.name(), "blub");
+        assert_eq!(language.extensions(), vec!["blub"]);
+
+        let result = SubstitutionLanguage::new("blub", &[], ["a", "b", "c", "d", "", "f", "g", "h"]);
+        assert_error(result, DialectError::EmptyCommand("read"));
+
+        let result = SubstitutionLanguage::new("blub", &[], ["a", "b", "c", "d", "e", "f", "b", "h"]);
+        assert_error(result, DialectError::AmbiguousCommands("sub", "begin_loop"));
+    }
+
+    #[test]
+    fn test_from_mapping() {
+        let language = SubstitutionLanguage::from_mapping(ALPHUCK).unwrap();
+        let commands: [&str; 8] = ["e", "p", "a", "c", "s", "o", "i", "j"];
+        let expected = SubstitutionLanguage::new("alphuck", &["alphuck", "a"], commands).unwrap();
+        assert_eq!(language, expected);
+    }
+
+    #[test]
+    fn test_from_mapping_strings() {
+        let mapping: &str = "\
+            add = \"\\u00e4\\t\\\"\" # A comment.\n\
+            sub = 'C:\\path'\n\
+            \"right\"= \"\\U0001F600\"\n\
+            left='\"'\n\
+            read = \"\\\\\"\n\
+            write = \"\\n\"\n\
+            'begin_loop' = \"[\"\n\
+            end_loop = \"]\"\n\
+            extensions = [ ]";
+        let language = SubstitutionLanguage::from_mapping(mapping).unwrap();
+        let expected = SubstitutionLanguage::new("substitution", &[], [
+            "\u{e4}\t\"", "C:\\path", "\u{1F600}", "\"", "\\", "\n", "[", "]",
+        ]);
+        assert_eq!(language, expected.unwrap());
+    }
+
+    #[test]
+    fn test_from_mapping_multiline_array() {
+        let mapping: String = ALPHUCK.replace("extensions = [\"alphuck\", \"a\"]", "\
+            extensions = [\n\
+                # The preferred extension.\n\
+                \"alphuck\",\n\
+            \n\
+                \"a\", # A short one.\n\
+            ]");
+        assert_ne!(mapping, ALPHUCK);
+        let language = SubstitutionLanguage::from_mapping(&mapping).unwrap();
+        assert_eq!(language, SubstitutionLanguage::from_mapping(ALPHUCK).unwrap());
+    }
+
+    #[test]
+    fn test_from_mapping_errors() {
+        let position = |lineno: usize, position: usize| MetaData { lineno, position };
+
+        assert_error(SubstitutionLanguage::from_mapping("add = +"), DialectError::MalformedEntry(position(1, 7)));
+        assert_error(SubstitutionLanguage::from_mapping("add \"+\""), DialectError::MalformedEntry(position(1, 5)));
+        assert_error(SubstitutionLanguage::from_mapping("add = \"+"), DialectError::MalformedEntry(position(1, 9)));
+        assert_error(SubstitutionLanguage::from_mapping("add = \"+\" -"),
+                     DialectError::MalformedEntry(position(1, 11)));
+        assert_error(SubstitutionLanguage::from_mapping("add = \"\\q\""),
+                     DialectError::MalformedEntry(position(1, 10)));
+        assert_error(SubstitutionLanguage::from_mapping("add = \"\\uD800\""),
+                     DialectError::MalformedEntry(position(1, 14)));
+        assert_error(SubstitutionLanguage::from_mapping("= \"+\""), DialectError::MalformedEntry(position(1, 1)));
+        assert_error(SubstitutionLanguage::from_mapping("add = [\"+\"]"), DialectError::MalformedEntry(position(1, 7)));
+        assert_error(SubstitutionLanguage::from_mapping("name = [\"+\"]"),
+                     DialectError::MalformedEntry(position(1, 8)));
+        assert_error(SubstitutionLanguage::from_mapping("extensions = [\"a\" \"b\"]"),
+                     DialectError::MalformedEntry(position(1, 19)));
+        assert_error(SubstitutionLanguage::from_mapping("extensions = [\n  \"a\",\n  b\n]"),
+                     DialectError::MalformedEntry(position(3, 3)));
+        assert_error(SubstitutionLanguage::from_mapping("extensions = [\n  \"a\",\n"),
+                     DialectError::MalformedEntry(position(2, 7)));
+        assert_error(SubstitutionLanguage::from_mapping("extensions = [\"a\"] add = \"+\""),
+                     DialectError::MalformedEntry(position(1, 20)));
+        assert_error(SubstitutionLanguage::from_mapping("\n  mul = \"*\""), DialectError::UnknownKey(position(2, 3)));
+        assert_error(SubstitutionLanguage::from_mapping(&format!("{}\nadd = \"+\"", ALPHUCK)),
+                     DialectError::DuplicateKey(position(15, 1)));
+        assert_error(SubstitutionLanguage::from_mapping(&ALPHUCK.replace("end_loop", "# end_loop")),
+                     DialectError::MissingCommand("end_loop"));
+        assert_error(SubstitutionLanguage::from_mapping(&ALPHUCK.replace("\"j\"", "\"e\"")),
+                     DialectError::AmbiguousCommands("add", "end_loop"));
+    }
+
+    #[test]
+    fn test_spell() {
+        let language = SubstitutionLanguage::from_mapping(ALPHUCK).unwrap();
+        let tokens: Vec<Token> = language.tokenize("eparoij").unwrap();
+        let spelled: Vec<&str> = tokens.iter().map(|token| language.spell(token)).collect();
+        assert_eq!(spelled, ["e", "p", "a", "o", "i", "j"]);
+    }
+
+    #[test]
+    fn test_separator() {
+        let language = SubstitutionLanguage::from_mapping(ALPHUCK).unwrap();
+        assert_eq!(language.separator(), "");
+
+        let language = SubstitutionLanguage::new("blub", &[], ["a", "b", "c", "d", "e", "f", "g", "\u{1F600}"]);
+        assert_eq!(language.unwrap().separator(), "");
+
+        let language = SubstitutionLanguage::new("blub", &[], ["a", "b", "c", "d", "e", "f", "g", "hh"]);
+        assert_eq!(language.unwrap().separator(), " ");
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let language = SubstitutionLanguage::from_mapping(ALPHUCK).unwrap();
+        let tokens: Vec<Token> = language.tokenize("eE\nii\u{e4}pj Xo").unwrap();
+        let expected: Vec<Token> = vec![
+            Token::Add(String::from("e"), MetaData { lineno: 1, position: 1 }),
+            Token::BeginLoop(String::from("i"), MetaData { lineno: 2, position: 1 }),
+            Token::BeginLoop(String::from("i"), MetaData { lineno: 2, position: 2 }),
+            Token::Sub(String::from("p"), MetaData { lineno: 2, position: 4 }),
+            Token::EndLoop(String::from("j"), MetaData { lineno: 2, position: 5 }),
+            Token::Write(String::from("o"), MetaData { lineno: 2, position: 8 }),
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_tokenize_longest_match() {
+        let commands: [&str; 8] = ["Blub.", "Blub!", "Blub. Blub?", "Blub? Blub.", "?", "Blub?", "\n[", "]\n"];
+        let language = SubstitutionLanguage::new("blub", &[], commands).unwrap();
+        let tokens: Vec<Token> = language.tokenize("Blub. Blub?Blub? Blub.Blub.\n[?]\nBlub?").unwrap();
+        let expected: Vec<Token> = vec![
+            Token::Right(String::from("Blub. Blub?"), MetaData { lineno: 1, position: 1 }),
+            Token::Left(String::from("Blub? Blub."), MetaData { lineno: 1, position: 12 }),
+            Token::Add(String::from("Blub."), MetaData { lineno: 1, position: 23 }),
+            Token::BeginLoop(String::from("\n["), MetaData { lineno: 1, position: 28 }),
+            Token::Read(String::from("?"), MetaData { lineno: 2, position: 2 }),
+            Token::EndLoop(String::from("]\n"), MetaData { lineno: 2, position: 3 }),
+            Token::Write(String::from("Blub?"), MetaData { lineno: 3, position: 1 }),
+        ];
+        assert_eq!(tokens, expected);
+    }
+}
@@ -6,6 +6,8 @@
 
 //! Implementations and trait definitions for lexers, analyzing a program source to specific tokens.
 
+use Result;
+
 /// The tokens representing the commands understood by rustfuck.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Token {
@@ -50,4 +52,10 @@ pub trait Lexer {
 
     /// Convert the `input` string into a list of tokens.
     fn tokenize(&self) -> Vec<Token>;
+
+    /// Convert the `input` string into a list of tokens, failing with a syntax error if it contains lexemes which do
+    /// not form a token. By default, lexers skip everything that is not a token, and never fail.
+    fn try_tokenize(&self) -> Result<Vec<Token>> {
+        Ok(self.tokenize())
+    }
 }
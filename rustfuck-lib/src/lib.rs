@@ -4,7 +4,9 @@
 // MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
 // modified, or distributed except according to those terms.
 
-//! The `rustfuck` library, defining the actual `Brainfuck` to `C` translator.
+//! The `rustfuck` library, defining the actual translator from `Brainfuck` to `C`, `Rust`, `LLVM` IR, `x86-64`
+//! assembly, static Linux executables, `JavaScript`, or `WebAssembly`, and the `Brainfuck` interpreter, virtual
+//! machine, and just-in-time compiler.
 
 #![warn(missing_docs,
         missing_debug_implementations, missing_copy_implementations,
@@ -17,16 +19,68 @@
                                       stutter, used_underscore_binding, use_debug, wrong_self_convention,
                                       wrong_pub_self_convention))]
 
+mod asm_generator;
+mod backend;
 mod compiler;
+mod diagnostics;
+mod elf_generator;
 mod error;
 mod generator;
+mod interpreter;
+mod ir;
+mod js_generator;
 pub mod language;
 mod lexer;
+mod llvm_generator;
+mod optimizer;
+mod options;
+mod registry;
+mod rust_generator;
+mod source_generator;
+mod validator;
+mod vm;
+mod wasm;
+mod wasm_generator;
+mod x86_64;
 
+pub use asm_generator::AsmGenerator;
+pub use backend::Backend;
+pub use backend::BackendRegistry;
 pub use compiler::Compiler;
+pub use compiler::Target;
+pub use diagnostics::Emitter;
+pub use elf_generator::ElfGenerator;
+pub use error::DialectError;
 pub use error::Error;
 pub use error::Result;
+pub use error::RuntimeError;
+pub use error::SyntaxError;
 pub use generator::Generator;
+pub use interpreter::Interpreter;
+pub use ir::Instruction;
+pub use ir::Operation;
+pub use ir::Program;
+pub use ir::Span;
+pub use js_generator::JsGenerator;
 pub use lexer::Lexer;
 pub use lexer::MetaData;
 pub use lexer::Token;
+pub use llvm_generator::LlvmGenerator;
+pub use optimizer::Optimizer;
+pub use options::CellType;
+pub use options::EofBehavior;
+pub use options::Options;
+pub use options::Overflow;
+pub use options::TapeGrowth;
+pub use registry::Named;
+pub use registry::Registry;
+pub use rust_generator::RustGenerator;
+pub use source_generator::Layout;
+pub use source_generator::SourceGenerator;
+pub use validator::validate;
+pub use vm::Bytecode;
+pub use vm::Jit;
+pub use vm::Opcode;
+pub use vm::VirtualMachine;
+pub use wasm_generator::WasmGenerator;
+pub use wasm_generator::WasmInterface;
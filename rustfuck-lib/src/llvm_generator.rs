@@ -0,0 +1,1310 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementations of a generator for creating textual `LLVM` IR from intermediate compile results.
+
+use std::convert::TryFrom;
+
+use CellType;
+use EofBehavior;
+use Options;
+use Overflow;
+use TapeGrowth;
+use ir::Instruction;
+use ir::Operation;
+use ir::Program;
+use ir::Step;
+use ir::accessed_range;
+use ir::reserved_range;
+use ir::walk;
+use lexer::MetaData;
+
+/// The `LLVM` declaration of a tape with a fixed size. `{tape_size}` must be replaced by the number of cells, `{cell}`
+/// by the type of the cells.
+static FIXED_TAPE: &str = "\
+@tape = internal global [{tape_size} x {cell}] zeroinitializer
+
+";
+
+/// The `LLVM` function moving the pointer by `offset` cells on a tape with a fixed size, aborting the program if the
+/// cells from `low` to `high` cells away from the new current cell are not on the tape. `{tape_size}` must be
+/// replaced by the number of cells.
+static CHECKED_FIXED_TAPE: &str = "\
+define internal i64 @bf_reserve(i64 %ptr, i64 %offset, i64 %low, i64 %high, i32 %id) {
+entry:
+  %position = add i64 %ptr, %offset
+  %first = add i64 %position, %low
+  %last = add i64 %position, %high
+  %underflow = icmp slt i64 %first, 0
+  br i1 %underflow, label %underflow.fail, label %right
+
+underflow.fail:
+  call void @bf_fail(ptr @bf_pointer_underflow, i32 %id)
+  unreachable
+
+right:
+  %overflow = icmp sge i64 %last, {tape_size}
+  br i1 %overflow, label %overflow.fail, label %done
+
+overflow.fail:
+  call void @bf_fail(ptr @bf_pointer_overflow, i32 %id)
+  unreachable
+
+done:
+  ret i64 %position
+}
+
+";
+
+/// The `LLVM` declaration of a tape growing when the pointer leaves it, together with the functions for allocating it
+/// and for moving the pointer by `offset` cells, growing the tape until the cells from `low` to `high` cells away from
+/// the new current cell are on it. `{tape_size}` must be replaced by the initial number of cells, `{cell}` by the type
+/// of the cells, and `{cell_size}` by their size in bytes. `{grow_left}` must be replaced by the code computing the
+/// number `%before` of cells to add before the tape's start and `{before}` by this number, if the tape grows to the
+/// left at all. `{id_parameter}` and `{check_left}` must be replaced by the parameter and the code reporting accesses
+/// before the tape's start, if these are checked, and `{position}` by the code computing the new index `%position`.
+static GROWING_TAPE: &str = "\
+@tape = internal global ptr null
+@tape_size = internal global i64 {tape_size}
+@bf_out_of_memory = private unnamed_addr constant [14 x i8] c\"out of memory\\0A\"
+
+declare ptr @calloc(i64, i64)
+declare ptr @realloc(ptr, i64)
+declare ptr @memmove(ptr, ptr, i64)
+declare ptr @memset(ptr, i32, i64)
+declare i64 @write(i32, ptr, i64)
+
+define internal ptr @bf_check_allocation(ptr %memory) {
+entry:
+  %failed = icmp eq ptr %memory, null
+  br i1 %failed, label %fail, label %done
+
+fail:
+  %written = call i64 @write(i32 2, ptr @bf_out_of_memory, i64 14)
+  call void @exit(i32 1)
+  unreachable
+
+done:
+  ret ptr %memory
+}
+
+define internal void @bf_allocate() {
+entry:
+  %size = load i64, ptr @tape_size
+  %memory = call ptr @calloc(i64 %size, i64 {cell_size})
+  %tape = call ptr @bf_check_allocation(ptr %memory)
+  store ptr %tape, ptr @tape
+  ret void
+}
+
+define internal i64 @bf_reserve(i64 %ptr, i64 %offset, i64 %low, i64 %high{id_parameter}) {
+entry:
+{position}  %first = add i64 %position, %low
+  %last = add i64 %position, %high
+  %size = load i64, ptr @tape_size
+  %underflow = icmp slt i64 %first, 0
+  %overflow = icmp sge i64 %last, %size
+  %outside = or i1 %underflow, %overflow
+  br i1 %outside, label %grow, label %done
+
+done:
+  ret i64 %position
+
+grow:
+{check_left}{grow_left}  %missing.after = sub i64 %last, %size
+  %needed.after = add i64 %missing.after, 1
+  %small.after = icmp slt i64 %needed.after, %size
+  %grown.after = select i1 %small.after, i64 %size, i64 %needed.after
+  %after = select i1 %overflow, i64 %grown.after, i64 0
+  %added = add i64 {before}, %after
+  %new.size = add i64 %size, %added
+  %new.bytes = mul i64 %new.size, {cell_size}
+  %old.tape = load ptr, ptr @tape
+  %memory = call ptr @realloc(ptr %old.tape, i64 %new.bytes)
+  %tape = call ptr @bf_check_allocation(ptr %memory)
+  %start = getelementptr inbounds {cell}, ptr %tape, i64 {before}
+  %old.bytes = mul i64 %size, {cell_size}
+  %moved = call ptr @memmove(ptr %start, ptr %tape, i64 %old.bytes)
+  %before.bytes = mul i64 {before}, {cell_size}
+  %cleared.before = call ptr @memset(ptr %tape, i32 0, i64 %before.bytes)
+  %end = getelementptr inbounds {cell}, ptr %start, i64 %size
+  %after.bytes = mul i64 %after, {cell_size}
+  %cleared.after = call ptr @memset(ptr %end, i32 0, i64 %after.bytes)
+  store ptr %tape, ptr @tape
+  store i64 %new.size, ptr @tape_size
+  %new.position = add i64 %position, {before}
+  ret i64 %new.position
+}
+
+";
+
+/// The `LLVM` code computing the number of cells to add before the start of a tape growing to the left.
+static GROWING_LEFT: &str = "  %missing.before = sub i64 0, %first
+  %small.before = icmp slt i64 %missing.before, %size
+  %grown.before = select i1 %small.before, i64 %size, i64 %missing.before
+  %before = select i1 %underflow, i64 %grown.before, i64 0
+";
+
+/// The `LLVM` code computing the new index of the current cell.
+static MOVING_POSITION: &str = "  %position = add i64 %ptr, %offset
+";
+
+/// The `LLVM` code computing the new index of the current cell on a tape growing only to the right, wrapping an index
+/// before the tape's start around to its current end like the interpreter. The other accessed cells before the start
+/// are wrapped when accessing them.
+static WRAPPING_POSITION: &str = "  %unwrapped = add i64 %ptr, %offset
+  %before.start = icmp slt i64 %unwrapped, 0
+  br i1 %before.start, label %wrap, label %reserve
+
+wrap:
+  %wrap.size = load i64, ptr @tape_size
+  %remainder = srem i64 %unwrapped, %wrap.size
+  %raised = add i64 %remainder, %wrap.size
+  %wrapped = srem i64 %raised, %wrap.size
+  br label %reserve
+
+reserve:
+  %position = phi i64 [ %unwrapped, %entry ], [ %wrapped, %wrap ]
+";
+
+/// The `LLVM` function getting the index `distance` cells to the left of the cell at `ptr` on a tape growing only to
+/// the right, wrapping around at the tape's start.
+static WRAPPING_LEFT: &str = "\
+define internal i64 @bf_wrap_left(i64 %ptr, i64 %distance) {
+entry:
+  %inside = icmp uge i64 %ptr, %distance
+  br i1 %inside, label %done, label %wrap
+
+done:
+  %index = sub i64 %ptr, %distance
+  ret i64 %index
+
+wrap:
+  %size = load i64, ptr @tape_size
+  %reduced = urem i64 %distance, %size
+  %raised = add i64 %ptr, %size
+  %moved = sub i64 %raised, %reduced
+  %wrapped = urem i64 %moved, %size
+  ret i64 %wrapped
+}
+
+";
+
+/// The `LLVM` code reporting accesses before the start of a tape growing only to the right.
+static CHECKING_LEFT: &str = "  br i1 %underflow, label %underflow.fail, label %grow.right
+
+underflow.fail:
+  call void @bf_fail(ptr @bf_pointer_underflow, i32 %id)
+  unreachable
+
+grow.right:
+";
+
+/// The `LLVM` function aborting the program with an error message for the source position with the given `id`. The
+/// placeholder `{count}` must be replaced by the number of source positions, `{positions}` by their initializers.
+static FAILING_RUNTIME: &str = "\
+@bf_positions = private unnamed_addr constant [{count} x [2 x i32]] [
+{positions}]
+@bf_format = private unnamed_addr constant [28 x i8] c\"%s at line %d, position %d\\0A\\00\"
+@bf_cell_overflow = private unnamed_addr constant [14 x i8] c\"cell overflow\\00\"
+@bf_pointer_underflow = private unnamed_addr constant [18 x i8] c\"pointer underflow\\00\"
+@bf_pointer_overflow = private unnamed_addr constant [17 x i8] c\"pointer overflow\\00\"
+
+declare i32 @fflush(ptr)
+declare i32 @dprintf(i32, ptr, ...)
+
+define internal void @bf_fail(ptr %message, i32 %id) noreturn cold {
+entry:
+  %lineno.address = getelementptr inbounds [{count} x [2 x i32]], ptr @bf_positions, i64 0, i32 %id, i32 0
+  %lineno = load i32, ptr %lineno.address
+  %position.address = getelementptr inbounds [{count} x [2 x i32]], ptr @bf_positions, i64 0, i32 %id, i32 1
+  %position = load i32, ptr %position.address
+  %flushed = call i32 @fflush(ptr null)
+  %printed = call i32 (i32, ptr, ...) @dprintf(i32 2, ptr @bf_format, ptr %message, i32 %lineno, i32 %position)
+  call void @exit(i32 3)
+  unreachable
+}
+
+";
+
+/// The `LLVM` function reading a character into a cell. `{cell}` must be replaced by the type of the cells,
+/// `{conversion}` by the instruction converting an `i32` to a cell, and `{eof}` by the cell's new value at the end of
+/// the input.
+static READING_RUNTIME: &str = "\
+define internal {cell} @bf_read({cell} %value) {
+entry:
+  %character = call i32 @getchar()
+  %eof = icmp eq i32 %character, -1
+  %cell = {conversion} i32 %character to {cell}
+  %result = select i1 %eof, {cell} {eof}, {cell} %cell
+  ret {cell} %result
+}
+
+";
+
+/// The `LLVM` function moving the pointer by `offset` cells on an unchecked tape with a fixed size, wrapping around
+/// at its ends. The `offset` must be smaller than the number of cells, by which `{tape_size}` must be replaced.
+static WRAPPING_TAPE: &str = "\
+define internal i64 @bf_wrap(i64 %ptr, i64 %offset) {
+entry:
+  %negative = icmp slt i64 %offset, 0
+  %raised = add i64 %offset, {tape_size}
+  %distance = select i1 %negative, i64 %raised, i64 %offset
+  %position = add i64 %ptr, %distance
+  %inside = icmp ult i64 %position, {tape_size}
+  %reduced = sub i64 %position, {tape_size}
+  %result = select i1 %inside, i64 %position, i64 %reduced
+  ret i64 %result
+}
+
+";
+
+/// The `LLVM` functions searching the tape's memory for the next zero cell to the right or to the left of the cell at
+/// `ptr`, continuing at the tape's other end and looping forever if there is no zero cell at all. `{tape_size}` must
+/// be replaced by the number of cells.
+static SEARCHING_RUNTIME: &str = "\
+declare ptr @memchr(ptr, i32, i64)
+declare ptr @memrchr(ptr, i32, i64)
+
+define internal i64 @bf_search_right(i64 %ptr) {
+entry:
+  %start = getelementptr inbounds i8, ptr @tape, i64 %ptr
+  %length = sub i64 {tape_size}, %ptr
+  %found = call ptr @memchr(ptr %start, i32 0, i64 %length)
+  %missing = icmp eq ptr %found, null
+  br i1 %missing, label %wrap, label %done
+
+wrap:
+  %wrapped = call ptr @memchr(ptr @tape, i32 0, i64 %ptr)
+  %endless = icmp eq ptr %wrapped, null
+  br i1 %endless, label %forever, label %done
+
+forever:
+  br label %forever
+
+done:
+  %result = phi ptr [ %found, %entry ], [ %wrapped, %wrap ]
+  %address = ptrtoint ptr %result to i64
+  %tape = ptrtoint ptr @tape to i64
+  %index = sub i64 %address, %tape
+  ret i64 %index
+}
+
+define internal i64 @bf_search_left(i64 %ptr) {
+entry:
+  %length = add i64 %ptr, 1
+  %found = call ptr @memrchr(ptr @tape, i32 0, i64 %length)
+  %missing = icmp eq ptr %found, null
+  br i1 %missing, label %wrap, label %done
+
+wrap:
+  %start = getelementptr inbounds i8, ptr @tape, i64 %length
+  %rest = sub i64 {tape_size}, %length
+  %wrapped = call ptr @memrchr(ptr %start, i32 0, i64 %rest)
+  %endless = icmp eq ptr %wrapped, null
+  br i1 %endless, label %forever, label %done
+
+forever:
+  br label %forever
+
+done:
+  %result = phi ptr [ %found, %entry ], [ %wrapped, %wrap ]
+  %address = ptrtoint ptr %result to i64
+  %tape = ptrtoint ptr @tape to i64
+  %index = sub i64 %address, %tape
+  ret i64 %index
+}
+
+";
+
+/// The `LLVM` function changing cells that saturate at their bounds. `{cell}` must be replaced by the type of the
+/// cells, `{extension}` by the instruction extending a cell to an `i128`, and `{min}` and `{max}` by the bounds.
+static SATURATING_RUNTIME: &str = "\
+define internal {cell} @bf_add({cell} %value, i128 %delta) {
+entry:
+  %wide = {extension} {cell} %value to i128
+  %sum = add i128 %wide, %delta
+  %low = icmp slt i128 %sum, {min}
+  %high = icmp sgt i128 %sum, {max}
+  %bounded = select i1 %low, i128 {min}, i128 %sum
+  %clamped = select i1 %high, i128 {max}, i128 %bounded
+  %result = trunc i128 %clamped to {cell}
+  ret {cell} %result
+}
+
+";
+
+/// The `LLVM` function changing cells that aborts the program when leaving their bounds. `{cell}` must be replaced by
+/// the type of the cells, `{extension}` by the instruction extending a cell to an `i128`, and `{min}` and `{max}` by
+/// the bounds.
+static CHECKED_RUNTIME: &str = "\
+define internal {cell} @bf_add({cell} %value, i128 %delta, i32 %id) {
+entry:
+  %wide = {extension} {cell} %value to i128
+  %sum = add i128 %wide, %delta
+  %low = icmp slt i128 %sum, {min}
+  %high = icmp sgt i128 %sum, {max}
+  %outside = or i1 %low, %high
+  br i1 %outside, label %overflow, label %inside
+
+overflow:
+  call void @bf_fail(ptr @bf_cell_overflow, i32 %id)
+  unreachable
+
+inside:
+  %result = trunc i128 %sum to {cell}
+  ret {cell} %result
+}
+
+";
+
+/// The runtime functions called by the generated `LLVM` IR.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Runtime {
+
+    /// Cells are changed by calling `@bf_add`.
+    add: bool,
+
+    /// Characters are read by calling `@bf_read`.
+    read: bool,
+
+    /// The pointer is moved by calling `@bf_reserve`.
+    reserve: bool,
+
+    /// Zero cells are searched by calling `@bf_search_right` and `@bf_search_left`.
+    search: bool,
+
+    /// The pointer is moved around the ends of a fixed tape by calling `@bf_wrap`.
+    wrap: bool,
+
+    /// The cells before the start of a tape growing to the right are accessed by calling `@bf_wrap_left`.
+    wrap_left: bool,
+}
+
+/// A generator for creating textual `LLVM` IR from intermediate compile results.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LlvmGenerator {
+
+    /// The options controlling the generated code.
+    options: Options,
+
+    /// The number of virtual registers defined so far.
+    registers: usize,
+
+    /// The number of loops, scans, and conditions for which basic blocks have been labeled so far.
+    labels: usize,
+
+    /// The source positions reported by the generated code, indexed by the IDs passed to the runtime functions.
+    positions: Vec<MetaData>,
+
+    /// The runtime functions needed by the generated code.
+    runtime: Runtime,
+}
+
+impl LlvmGenerator {
+    /// Initialize a new generator with the default options.
+    pub fn new() -> LlvmGenerator {
+        LlvmGenerator::with_options(Options::default())
+    }
+
+    /// Initialize a new generator with the given `options`.
+    pub fn with_options(options: Options) -> LlvmGenerator {
+        LlvmGenerator {
+            options,
+            registers: 0,
+            labels: 0,
+            positions: Vec::new(),
+            runtime: Runtime::default(),
+        }
+    }
+
+    /// Generate the `LLVM` IR from the given `program`, using the specified `template`.
+    ///
+    /// The placeholder `{runtime}` in the `template` is replaced by the declaration of the tape and the functions the
+    /// generated code needs, and `{setup}` by the code preparing the tape. The `template` must end within the entry
+    /// block of a function returning an `i32`, in which the index of the current cell is stored at `%ptr`.
+    pub fn generate(&mut self, template: &str, program: &Program) -> String {
+        // The runtime functions depend on the code calling them.
+        self.registers = 0;
+        self.labels = 0;
+        self.positions.clear();
+        self.runtime = Runtime::default();
+        let mut code = String::new();
+        self.generate_block(&program.instructions, &mut code);
+
+        let setup: &str = match self.options.tape_growth {
+            TapeGrowth::Fixed => "",
+            TapeGrowth::Right | TapeGrowth::Both => "  call void @bf_allocate()\n",
+        };
+        let mut output = template.replace("{runtime}", &self.runtime())
+            .replace("{setup}", setup);
+        output.push_str(&code);
+
+        // Return from the function and close it.
+        output.push_str("  ret i32 0\n");
+        output.push_str("}\n");
+
+        output
+    }
+
+    /// Generate the `LLVM` IR for the given list of `instructions` and the bodies of their loops, appending it to the
+    /// `output`.
+    fn generate_block(&mut self, instructions: &[Instruction], output: &mut String) {
+        // The current cell is on the tape when entering a block, but the other accessed cells might not be.
+        self.reserve_segment(instructions, output);
+
+        // The offset of the cell on which the currently generated multiplications depend together with the label of
+        // their condition, and if the current cell is still known not to be zero.
+        let mut guard: Option<(isize, usize)> = None;
+        let mut nonzero: bool = false;
+
+        // The labels of the loops currently generated, innermost last.
+        let mut loops: Vec<String> = Vec::new();
+        for step in walk(instructions) {
+            let (instruction, following): (&Instruction, &[Instruction]) = match step {
+                Step::Instruction(instruction, following) => (instruction, following),
+                Step::LoopEnd(_, following) => {
+                    // Close the loop, which only ends on a cell on the tape, but the cells accessed next might not be.
+                    close_guard(&mut guard, output);
+                    let label: String = loops.pop().expect("The loop has been started.");
+                    output.push_str(&format!("  br label %{label}\n\n{label}.end:\n", label = label));
+                    nonzero = false;
+                    self.reserve_segment(following, output);
+                    continue;
+                },
+            };
+
+            let source: Option<isize> = match instruction.operation {
+                Operation::MulAdd { .. } => Some(instruction.offset),
+                _ => None,
+            };
+            nonzero = nonzero && source == Some(0);
+            if guard.is_some() && guard.map(|(offset, _)| offset) != source {
+                close_guard(&mut guard, output);
+            }
+
+            // Checked accesses are reported at the accessing instruction. Multiplications depending on the same cell
+            // only need a single check.
+            let offset: isize = instruction.offset;
+            if self.options.is_checked() && guard.is_none() && offset != 0 {
+                let position: String = self.position(instruction, true);
+                self.reserve(0, offset.min(0), offset.max(0), &position, output);
+            }
+
+            let cell: &str = llvm_type(self.options.cell_type);
+            match instruction.operation {
+                Operation::Add(delta) if self.options.overflow == Overflow::Wrap => {
+                    // Change the value at the cell, wrapping around at the bounds of the cell type.
+                    let (address, value): (String, String) = self.load(instruction.offset, output);
+                    let sum: String = self.register();
+                    output.push_str(&format!("  {sum} = add {cell} {value}, {delta}\n",
+                                             sum = sum, cell = cell, value = value,
+                                             delta = wrapped(delta, self.options.cell_type)));
+                    store(cell, &sum, &address, output);
+                },
+                Operation::Add(delta) => {
+                    // Change the value at the cell using the runtime function.
+                    let (address, value): (String, String) = self.load(instruction.offset, output);
+                    let sum: String = self.add(instruction, &value, &delta.to_string(), output);
+                    store(cell, &sum, &address, output);
+                },
+                Operation::Move(offset) if self.options.is_reserving() => {
+                    // Go to another cell, making sure that it and the cells accessed next are on the tape.
+                    let (low, high): (isize, isize) = if self.options.is_checked() {
+                        (0, 0)
+                    } else {
+                        accessed_range(following, false)
+                    };
+                    let position: String = self.position(instruction, self.options.is_checked());
+                    self.reserve(offset, low, high, &position, output);
+                },
+                Operation::Move(offset) => {
+                    // Go to another cell.
+                    self.advance(offset, output);
+                },
+                Operation::Read => {
+                    // Read a single character into the cell.
+                    self.runtime.read = true;
+                    let (address, value): (String, String) = self.load(instruction.offset, output);
+                    let character: String = self.register();
+                    output.push_str(&format!("  {character} = call {cell} @bf_read({cell} {value})\n",
+                                             character = character, cell = cell, value = value));
+                    store(cell, &character, &address, output);
+                },
+                Operation::Write => {
+                    // Print the character of the cell.
+                    let (_, value): (String, String) = self.load(instruction.offset, output);
+                    let character: String = match self.options.cell_type {
+                        CellType::U32 => value,
+                        cell_type => {
+                            let character: String = self.register();
+                            let conversion: &str = if cell_type == CellType::I64 { "trunc" } else { "zext" };
+                            output.push_str(&format!("  {character} = {conversion} {cell} {value} to i32\n",
+                                                     character = character, conversion = conversion, cell = cell,
+                                                     value = value));
+                            character
+                        },
+                    };
+                    let written: String = self.register();
+                    output.push_str(&format!("  {written} = call i32 @putchar(i32 {character})\n",
+                                             written = written, character = character));
+                },
+                Operation::Loop(ref body) => {
+                    // Repeat the body as long as the current cell is not zero.
+                    let label: String = self.label("loop");
+                    output.push_str(&format!("  br label %{label}\n\n{label}:\n", label = label));
+                    let condition: String = self.is_nonzero(0, output);
+                    output.push_str(&format!("  br i1 {condition}, label %{label}.body, label %{label}.end\n\n\
+                                              {label}.body:\n",
+                                             condition = condition, label = label));
+                    self.reserve_segment(body, output);
+                    loops.push(label);
+                    nonzero = true;
+                },
+                Operation::Clear => {
+                    // Set the cell to zero.
+                    let address: String = self.address(instruction.offset, output);
+                    store(cell, "0", &address, output);
+                },
+                Operation::MulAdd { offset, factor } if self.options.overflow == Overflow::Wrap => {
+                    // Add a multiple of the cell to another cell, wrapping around at the bounds of the cell type.
+                    self.multiply(instruction, offset, &mut guard, nonzero, output);
+                    let (_, value): (String, String) = self.load(instruction.offset, output);
+                    let product: String = match wrapped(factor, self.options.cell_type) {
+                        1 => value,
+                        factor => {
+                            let product: String = self.register();
+                            output.push_str(&format!("  {product} = mul {cell} {value}, {factor}\n",
+                                                     product = product, cell = cell, value = value, factor = factor));
+                            product
+                        },
+                    };
+                    let target: isize = instruction.offset.wrapping_add(offset);
+                    let (address, value): (String, String) = self.load(target, output);
+                    let sum: String = self.register();
+                    output.push_str(&format!("  {sum} = add {cell} {value}, {product}\n",
+                                             sum = sum, cell = cell, value = value, product = product));
+                    store(cell, &sum, &address, output);
+                },
+                Operation::MulAdd { offset, factor } => {
+                    // Add a multiple of the cell to another cell using the runtime function.
+                    self.multiply(instruction, offset, &mut guard, nonzero, output);
+                    let (_, value): (String, String) = self.load(instruction.offset, output);
+                    let wide: String = self.register();
+                    let extension: &str = if self.options.cell_type.is_signed() { "sext" } else { "zext" };
+                    output.push_str(&format!("  {wide} = {extension} {cell} {value} to i128\n",
+                                             wide = wide, extension = extension, cell = cell, value = value));
+                    let product: String = self.register();
+                    output.push_str(&format!("  {product} = mul i128 {wide}, {factor}\n",
+                                             product = product, wide = wide, factor = factor));
+                    let target: isize = instruction.offset.wrapping_add(offset);
+                    let (address, value): (String, String) = self.load(target, output);
+                    let sum: String = self.add(instruction, &value, &product, output);
+                    store(cell, &sum, &address, output);
+                },
+                Operation::Scan { stride } if (stride == 1 || stride == -1) && self.can_search_memory() => {
+                    // Search the tape for the next zero cell.
+                    self.search(stride, output);
+                },
+                Operation::Scan { stride } => {
+                    // Step through the cells until reaching a zero cell.
+                    let label: String = self.label("scan");
+                    output.push_str(&format!("  br label %{label}\n\n{label}:\n", label = label));
+                    let condition: String = self.is_nonzero(0, output);
+                    output.push_str(&format!("  br i1 {condition}, label %{label}.step, label %{label}.end\n\n\
+                                              {label}.step:\n",
+                                             condition = condition, label = label));
+                    if self.options.is_reserving() {
+                        let position: String = self.position(instruction, self.options.is_checked());
+                        self.reserve(stride, 0, 0, &position, output);
+                    } else {
+                        self.advance(stride, output);
+                    }
+                    output.push_str(&format!("  br label %{label}\n\n{label}.end:\n", label = label));
+                },
+            }
+
+            // Scans only end on a cell on the tape, but the cells accessed next might not be.
+            if let Operation::Scan { .. } = instruction.operation {
+                self.reserve_segment(following, output);
+            }
+        }
+
+        close_guard(&mut guard, output);
+    }
+
+    /// Append the call adding the `delta` operand of type `i128` to the cell `value` using the runtime function, which
+    /// reports overflows at the `instruction` if the options require it, to the `output`. The register holding the
+    /// result is returned.
+    fn add(&mut self, instruction: &Instruction, value: &str, delta: &str, output: &mut String) -> String {
+        self.runtime.add = true;
+        let position: String = self.position(instruction, self.options.overflow == Overflow::Error);
+        let sum: String = self.register();
+        output.push_str(&format!("  {sum} = call {cell} @bf_add({cell} {value}, i128 {delta}{position})\n",
+                                 sum = sum, cell = llvm_type(self.options.cell_type), value = value, delta = delta,
+                                 position = position));
+        sum
+    }
+
+    /// Prepare the multiplication of the `instruction`'s cell with another cell `offset` cells away from it, appending
+    /// the code to the `output`. Like in the replaced loop, the target cell is only accessed if the instruction's cell
+    /// is not zero, so the multiplication is placed within a condition, which is opened unless the previous
+    /// multiplication depends on the same cell and the `guard` is still open, or the instruction's cell is known to be
+    /// `nonzero`. Within the condition, a growing or checked tape is made to contain the target cell.
+    fn multiply(&mut self, instruction: &Instruction, offset: isize, guard: &mut Option<(isize, usize)>,
+                nonzero: bool, output: &mut String)
+    {
+        if guard.is_none() && !nonzero {
+            let label: String = self.label("multiply");
+            let condition: String = self.is_nonzero(instruction.offset, output);
+            output.push_str(&format!("  br i1 {condition}, label %{label}, label %{label}.end\n\n{label}:\n",
+                                     condition = condition, label = label));
+            *guard = Some((instruction.offset, self.labels));
+        }
+
+        if self.options.is_reserving() {
+            let target: isize = instruction.offset.wrapping_add(offset);
+            let position: String = self.position(instruction, self.options.is_checked());
+            self.reserve(0, target.min(0), target.max(0), &position, output);
+        }
+    }
+
+    /// Append the call searching the tape in the direction of the `stride` for the next zero cell to the `output`.
+    fn search(&mut self, stride: isize, output: &mut String) {
+        self.runtime.search = true;
+        let ptr: String = self.register();
+        output.push_str(&format!("  {ptr} = load i64, ptr %ptr\n", ptr = ptr));
+        let found: String = self.register();
+        let function: &str = if stride < 0 { "bf_search_left" } else { "bf_search_right" };
+        output.push_str(&format!("  {found} = call i64 @{function}(i64 {ptr})\n",
+                                 found = found, function = function, ptr = ptr));
+        output.push_str(&format!("  store i64 {found}, ptr %ptr\n", found = found));
+    }
+
+    /// Append the call moving the pointer by `offset` cells and making sure that the cells from `low` to `high` cells
+    /// away from the new current cell are on the tape to the `output`. The `position` argument is passed on to report
+    /// errors on checked tapes. The cells before the start of a tape wrapping around do not have to be on the tape.
+    fn reserve(&mut self, offset: isize, low: isize, high: isize, position: &str, output: &mut String) {
+        let low: isize = if self.options.is_wrapping() { 0 } else { low };
+        if offset == 0 && low == 0 && high == 0 {
+            return;
+        }
+
+        self.runtime.reserve = true;
+        let ptr: String = self.register();
+        output.push_str(&format!("  {ptr} = load i64, ptr %ptr\n", ptr = ptr));
+        let moved: String = self.register();
+        output.push_str(&format!("  {moved} = call i64 @bf_reserve(i64 {ptr}, i64 {offset}, i64 {low}, i64 {high}\
+                                  {position})\n",
+                                 moved = moved, ptr = ptr, offset = offset, low = low, high = high,
+                                 position = position));
+        output.push_str(&format!("  store i64 {moved}, ptr %ptr\n", moved = moved));
+    }
+
+    /// Make sure that a growing tape contains all cells accessed by the `instructions` up to the next pointer
+    /// movement, appending the code to the `output`. Checked tapes are handled for each instruction instead.
+    fn reserve_segment(&mut self, instructions: &[Instruction], output: &mut String) {
+        if let Some((low, high)) = reserved_range(instructions, &self.options) {
+            self.reserve(0, low, high, "", output);
+        }
+    }
+
+    /// Append the code moving the pointer by `offset` cells on an unchecked tape with a fixed size, wrapping around at
+    /// its ends, to the `output`.
+    fn advance(&mut self, offset: isize, output: &mut String) {
+        let ptr: String = self.register();
+        output.push_str(&format!("  {ptr} = load i64, ptr %ptr\n", ptr = ptr));
+        let moved: String = self.index(&ptr, offset, output);
+        output.push_str(&format!("  store i64 {moved}, ptr %ptr\n", moved = moved));
+    }
+
+    /// Append the code computing the index of the cell `offset` cells away from the cell at the index `ptr` to the
+    /// `output`, wrapping around at the ends of an unchecked tape with a fixed size, and at the start of an unchecked
+    /// tape growing to the right. The register holding the index is returned.
+    fn index(&mut self, ptr: &str, offset: isize, output: &mut String) -> String {
+        let offset: isize = self.wrap(offset);
+        if offset == 0 {
+            return String::from(ptr);
+        }
+
+        let index: String = self.register();
+        if self.options.is_reserving() && self.options.is_wrapping() && offset < 0 {
+            self.runtime.wrap_left = true;
+            output.push_str(&format!("  {index} = call i64 @bf_wrap_left(i64 {ptr}, i64 {distance})\n",
+                                     index = index, ptr = ptr, distance = offset.unsigned_abs()));
+        } else if self.options.is_reserving() {
+            output.push_str(&format!("  {index} = add i64 {ptr}, {offset}\n",
+                                     index = index, ptr = ptr, offset = offset));
+        } else {
+            self.runtime.wrap = true;
+            output.push_str(&format!("  {index} = call i64 @bf_wrap(i64 {ptr}, i64 {offset})\n",
+                                     index = index, ptr = ptr, offset = offset));
+        }
+        index
+    }
+
+    /// Reduce the `offset` to the number of cells on an unchecked tape with a fixed size, on which the pointer wraps
+    /// around at the ends.
+    fn wrap(&self, offset: isize) -> isize {
+        if self.options.is_reserving() {
+            return offset;
+        }
+
+        let length: isize = isize::try_from(self.options.tape_size.max(1)).unwrap_or(isize::MAX);
+        offset % length
+    }
+
+    /// Append the code computing the address of the cell `offset` cells away from the current cell to the `output`.
+    /// The register holding the address is returned.
+    fn address(&mut self, offset: isize, output: &mut String) -> String {
+        let ptr: String = self.register();
+        output.push_str(&format!("  {ptr} = load i64, ptr %ptr\n", ptr = ptr));
+        let index: String = self.index(&ptr, offset, output);
+
+        // A growing tape might have been moved.
+        let tape: String = match self.options.tape_growth {
+            TapeGrowth::Fixed => String::from("@tape"),
+            TapeGrowth::Right | TapeGrowth::Both => {
+                let tape: String = self.register();
+                output.push_str(&format!("  {tape} = load ptr, ptr @tape\n", tape = tape));
+                tape
+            },
+        };
+
+        let address: String = self.register();
+        output.push_str(&format!("  {address} = getelementptr inbounds {cell}, ptr {tape}, i64 {index}\n",
+                                 address = address, cell = llvm_type(self.options.cell_type), tape = tape,
+                                 index = index));
+        address
+    }
+
+    /// Append the code loading the value of the cell `offset` cells away from the current cell to the `output`. The
+    /// registers holding the cell's address and its value are returned.
+    fn load(&mut self, offset: isize, output: &mut String) -> (String, String) {
+        let address: String = self.address(offset, output);
+        let value: String = self.register();
+        output.push_str(&format!("  {value} = load {cell}, ptr {address}\n",
+                                 value = value, cell = llvm_type(self.options.cell_type), address = address));
+        (address, value)
+    }
+
+    /// Append the code comparing the cell `offset` cells away from the current cell to zero to the `output`. The
+    /// register holding the condition that the cell is not zero is returned.
+    fn is_nonzero(&mut self, offset: isize, output: &mut String) -> String {
+        let (_, value): (String, String) = self.load(offset, output);
+        let condition: String = self.register();
+        output.push_str(&format!("  {condition} = icmp ne {cell} {value}, 0\n",
+                                 condition = condition, cell = llvm_type(self.options.cell_type), value = value));
+        condition
+    }
+
+    /// Get the name of a new virtual register.
+    fn register(&mut self) -> String {
+        self.registers += 1;
+        format!("%t{number}", number = self.registers)
+    }
+
+    /// Get a new label for basic blocks of the given `kind`.
+    fn label(&mut self, kind: &str) -> String {
+        self.labels += 1;
+        format!("{kind}{number}", kind = kind, number = self.labels)
+    }
+
+    /// Determine if scans with a stride of one can search the tape's memory directly, i.e. if the cells are bytes and
+    /// the tape's size is known at compile time and does not have to be checked.
+    fn can_search_memory(&self) -> bool {
+        self.options.cell_type == CellType::U8 && !self.options.is_reserving()
+    }
+
+    /// Get the declaration of the tape and the `LLVM` functions called by the generated code.
+    fn runtime(&self) -> String {
+        let cell: &str = llvm_type(self.options.cell_type);
+        let tape_size: String = self.options.tape_size.max(1).to_string();
+        let mut runtime: String = self.failing_runtime();
+        match self.options.tape_growth {
+            TapeGrowth::Fixed => {
+                runtime.push_str(&FIXED_TAPE.replace("{tape_size}", &tape_size).replace("{cell}", cell));
+                if self.runtime.reserve {
+                    runtime.push_str(&CHECKED_FIXED_TAPE.replace("{tape_size}", &tape_size));
+                }
+                if self.runtime.wrap {
+                    runtime.push_str(&WRAPPING_TAPE.replace("{tape_size}", &tape_size));
+                }
+                if self.runtime.search {
+                    runtime.push_str(&SEARCHING_RUNTIME.replace("{tape_size}", &tape_size));
+                }
+            },
+            TapeGrowth::Right | TapeGrowth::Both => runtime.push_str(&self.growing_runtime()),
+        }
+
+        if self.runtime.read {
+            let conversion: &str = match self.options.cell_type {
+                CellType::U8 | CellType::U16 => "trunc",
+                CellType::U32 => "bitcast",
+                CellType::I64 => "zext",
+            };
+            let eof: &str = match self.options.eof {
+                EofBehavior::Zero => "0",
+                EofBehavior::MinusOne => "-1",
+                EofBehavior::Unchanged => "%value",
+            };
+            runtime.push_str(&READING_RUNTIME.replace("{cell}", cell)
+                .replace("{conversion}", conversion)
+                .replace("{eof}", eof));
+        }
+
+        if self.runtime.add {
+            let template: &str = match self.options.overflow {
+                Overflow::Error => CHECKED_RUNTIME,
+                Overflow::Wrap | Overflow::Saturate => SATURATING_RUNTIME,
+            };
+            let (min, max): (&str, &str) = match self.options.cell_type {
+                CellType::U8 => ("0", "255"),
+                CellType::U16 => ("0", "65535"),
+                CellType::U32 => ("0", "4294967295"),
+                CellType::I64 => ("-9223372036854775808", "9223372036854775807"),
+            };
+            runtime.push_str(&template.replace("{cell}", cell)
+                .replace("{extension}", if self.options.cell_type.is_signed() { "sext" } else { "zext" })
+                .replace("{min}", min)
+                .replace("{max}", max));
+        }
+
+        runtime
+    }
+
+    /// Get the declaration of a growing tape and the `LLVM` functions allocating it and moving the pointer on it.
+    fn growing_runtime(&self) -> String {
+        let checked: bool = self.options.is_checked();
+        let both: bool = self.options.tape_growth == TapeGrowth::Both;
+        let cell_size: &str = match self.options.cell_type {
+            CellType::U8 => "1",
+            CellType::U16 => "2",
+            CellType::U32 => "4",
+            CellType::I64 => "8",
+        };
+
+        let wrapping: bool = self.options.is_wrapping();
+        let runtime: String = GROWING_TAPE.replace("{tape_size}", &self.options.tape_size.max(1).to_string())
+            .replace("{position}", if wrapping { WRAPPING_POSITION } else { MOVING_POSITION })
+            .replace("{id_parameter}", if checked { ", i32 %id" } else { "" })
+            .replace("{check_left}", if checked { CHECKING_LEFT } else { "" })
+            .replace("{grow_left}", if both { GROWING_LEFT } else { "" })
+            .replace("{before}", if both { "%before" } else { "0" })
+            .replace("{cell_size}", cell_size)
+            .replace("{cell}", llvm_type(self.options.cell_type));
+        if self.runtime.wrap_left {
+            runtime + WRAPPING_LEFT
+        } else {
+            runtime
+        }
+    }
+
+    /// Get the table of source positions and the `LLVM` function reporting errors at them, if the generated code
+    /// reports any errors.
+    fn failing_runtime(&self) -> String {
+        if self.positions.is_empty() {
+            return String::new();
+        }
+
+        let positions: String = self.positions.iter()
+            .enumerate()
+            .map(|(index, position)| format!("  [2 x i32] [i32 {lineno}, i32 {position}]{separator}\n",
+                                             lineno = position.lineno, position = position.position,
+                                             separator = if index + 1 < self.positions.len() { "," } else { "" }))
+            .collect();
+
+        FAILING_RUNTIME.replace("{count}", &self.positions.len().to_string())
+            .replace("{positions}", &positions)
+    }
+
+    /// Get the additional argument passing the ID of the `instruction`'s source position to the runtime functions,
+    /// adding the position to the table. Empty unless the position might be `reported`.
+    fn position(&mut self, instruction: &Instruction, reported: bool) -> String {
+        if !reported {
+            return String::new();
+        }
+
+        self.positions.push(instruction.span.start);
+        format!(", i32 {id}", id = self.positions.len() - 1)
+    }
+}
+
+/// Close the condition of the multiplications if the `guard` is open, appending the code to the `output`.
+fn close_guard(guard: &mut Option<(isize, usize)>, output: &mut String) {
+    if let Some((_, label)) = guard.take() {
+        output.push_str(&format!("  br label %multiply{label}.end\n\nmultiply{label}.end:\n", label = label));
+    }
+}
+
+/// Append the code storing the `value` of the given `cell` type at the `address` to the `output`.
+fn store(cell: &str, value: &str, address: &str, output: &mut String) {
+    output.push_str(&format!("  store {cell} {value}, ptr {address}\n", cell = cell, value = value, address = address));
+}
+
+/// Get the `LLVM` type of cells of the given `cell_type`.
+fn llvm_type(cell_type: CellType) -> &'static str {
+    match cell_type {
+        CellType::U8 => "i8",
+        CellType::U16 => "i16",
+        CellType::U32 => "i32",
+        CellType::I64 => "i64",
+    }
+}
+
+/// Get the `value` of a change to a cell of the given `cell_type`, reduced to the cell type's range when wrapping
+/// around at its bounds.
+fn wrapped(value: i32, cell_type: CellType) -> i64 {
+    let value: i64 = i64::from(value);
+    match cell_type {
+        CellType::U8 => value.rem_euclid(0x100),
+        CellType::U16 => value.rem_euclid(0x1_0000),
+        CellType::U32 | CellType::I64 => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use CellType;
+    use EofBehavior;
+    use Instruction;
+    use LlvmGenerator;
+    use MetaData;
+    use Operation;
+    use Options;
+    use Overflow;
+    use Program;
+    use Span;
+    use TapeGrowth;
+    use super::Runtime;
+
+    #[test]
+    fn test_new() {
+        let generator = LlvmGenerator::new();
+        assert_eq!(generator, LlvmGenerator {
+            options: Options::default(),
+            registers: 0,
+            labels: 0,
+            positions: Vec::new(),
+            runtime: Runtime::default(),
+        });
+    }
+
+    #[test]
+    fn test_generate() {
+        let template: &str = "{runtime}define i32 @main() {\nentry:\n{setup}";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(259), span),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::new(Operation::MulAdd { offset: 1, factor: -1 }, span),
+                    Instruction::new(Operation::Clear, span),
+                ]), span),
+                Instruction::at_offset(Operation::Write, -1, span),
+                Instruction::new(Operation::Scan { stride: 2 }, span),
+            ],
+        };
+        let expected: &str = "\
+@tape = internal global [30000 x i8] zeroinitializer
+
+define internal i64 @bf_wrap(i64 %ptr, i64 %offset) {
+entry:
+  %negative = icmp slt i64 %offset, 0
+  %raised = add i64 %offset, 30000
+  %distance = select i1 %negative, i64 %raised, i64 %offset
+  %position = add i64 %ptr, %distance
+  %inside = icmp ult i64 %position, 30000
+  %reduced = sub i64 %position, 30000
+  %result = select i1 %inside, i64 %position, i64 %reduced
+  ret i64 %result
+}
+
+define i32 @main() {
+entry:
+  %t1 = load i64, ptr %ptr
+  %t2 = getelementptr inbounds i8, ptr @tape, i64 %t1
+  %t3 = load i8, ptr %t2
+  %t4 = add i8 %t3, 3
+  store i8 %t4, ptr %t2
+  br label %loop1
+
+loop1:
+  %t5 = load i64, ptr %ptr
+  %t6 = getelementptr inbounds i8, ptr @tape, i64 %t5
+  %t7 = load i8, ptr %t6
+  %t8 = icmp ne i8 %t7, 0
+  br i1 %t8, label %loop1.body, label %loop1.end
+
+loop1.body:
+  %t9 = load i64, ptr %ptr
+  %t10 = getelementptr inbounds i8, ptr @tape, i64 %t9
+  %t11 = load i8, ptr %t10
+  %t12 = mul i8 %t11, 255
+  %t13 = load i64, ptr %ptr
+  %t14 = call i64 @bf_wrap(i64 %t13, i64 1)
+  %t15 = getelementptr inbounds i8, ptr @tape, i64 %t14
+  %t16 = load i8, ptr %t15
+  %t17 = add i8 %t16, %t12
+  store i8 %t17, ptr %t15
+  %t18 = load i64, ptr %ptr
+  %t19 = getelementptr inbounds i8, ptr @tape, i64 %t18
+  store i8 0, ptr %t19
+  br label %loop1
+
+loop1.end:
+  %t20 = load i64, ptr %ptr
+  %t21 = call i64 @bf_wrap(i64 %t20, i64 -1)
+  %t22 = getelementptr inbounds i8, ptr @tape, i64 %t21
+  %t23 = load i8, ptr %t22
+  %t24 = zext i8 %t23 to i32
+  %t25 = call i32 @putchar(i32 %t24)
+  br label %scan2
+
+scan2:
+  %t26 = load i64, ptr %ptr
+  %t27 = getelementptr inbounds i8, ptr @tape, i64 %t26
+  %t28 = load i8, ptr %t27
+  %t29 = icmp ne i8 %t28, 0
+  br i1 %t29, label %scan2.step, label %scan2.end
+
+scan2.step:
+  %t30 = load i64, ptr %ptr
+  %t31 = call i64 @bf_wrap(i64 %t30, i64 2)
+  store i64 %t31, ptr %ptr
+  br label %scan2
+
+scan2.end:
+  ret i32 0
+}
+";
+
+        let mut generator = LlvmGenerator::new();
+        assert_eq!(generator.generate(template, &program), expected);
+
+        // Generating the same program again starts from scratch.
+        assert_eq!(generator.generate(template, &program), expected);
+    }
+
+    #[test]
+    fn test_generate_guard() {
+        let template: &str = "";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::MulAdd { offset: 1, factor: 2 }, span),
+                Instruction::new(Operation::MulAdd { offset: 2, factor: 1 }, span),
+                Instruction::new(Operation::Clear, span),
+            ],
+        };
+
+        let generated: String = LlvmGenerator::new().generate(template, &program);
+        assert!(generated.starts_with("  %t1 = load i64, ptr %ptr
+  %t2 = getelementptr inbounds i8, ptr @tape, i64 %t1
+  %t3 = load i8, ptr %t2
+  %t4 = icmp ne i8 %t3, 0
+  br i1 %t4, label %multiply1, label %multiply1.end
+
+multiply1:
+"));
+        assert!(generated.contains("  %t8 = mul i8 %t7, 2\n"));
+        assert!(generated.contains("  %t21 = add i8 %t20, %t16\n"));
+        assert!(generated.contains("  br label %multiply1.end
+
+multiply1.end:
+  %t22 = load i64, ptr %ptr
+"));
+        assert_eq!(generated.matches("br i1").count(), 1);
+    }
+
+    #[test]
+    fn test_generate_cell_types() {
+        let template: &str = "{runtime}";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(-70_000), 1, span),
+                Instruction::new(Operation::Write, span),
+            ],
+        };
+
+        let options = Options { cell_type: CellType::U16, tape_size: 16, ..Options::default() };
+        let generated: String = LlvmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("@tape = internal global [16 x i16] zeroinitializer\n"));
+        assert!(generated.contains("  %t5 = add i16 %t4, 61072\n"));
+        assert!(generated.contains("  %t9 = zext i16 %t8 to i32\n"));
+
+        let options = Options { cell_type: CellType::U32, ..Options::default() };
+        let generated: String = LlvmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("  %t5 = add i32 %t4, -70000\n"));
+        assert!(generated.contains("  %t9 = call i32 @putchar(i32 %t8)\n"));
+
+        let options = Options { cell_type: CellType::I64, ..Options::default() };
+        let generated: String = LlvmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("  %t5 = add i64 %t4, -70000\n"));
+        assert!(generated.contains("  %t9 = trunc i64 %t8 to i32\n"));
+    }
+
+    #[test]
+    fn test_generate_overflow() {
+        let template: &str = "{runtime}";
+        let span = Span::at(MetaData { lineno: 2, position: 3 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(5), span),
+                Instruction::new(Operation::MulAdd { offset: -1, factor: -1 }, span),
+            ],
+        };
+
+        let options = Options { overflow: Overflow::Saturate, ..Options::default() };
+        let generated: String = LlvmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("define internal i8 @bf_add(i8 %value, i128 %delta) {\n"));
+        assert!(generated.contains("  %bounded = select i1 %low, i128 0, i128 %sum\n"));
+        assert!(generated.contains("  %clamped = select i1 %high, i128 255, i128 %bounded\n"));
+        assert!(generated.contains("  %t4 = call i8 @bf_add(i8 %t3, i128 5)\n"));
+        assert!(generated.contains("  %t12 = zext i8 %t11 to i128\n  %t13 = mul i128 %t12, -1\n"));
+        assert!(generated.contains("  %t18 = call i8 @bf_add(i8 %t17, i128 %t13)\n"));
+        assert!(!generated.contains("@bf_fail"));
+
+        let options = Options { overflow: Overflow::Error, cell_type: CellType::I64, ..Options::default() };
+        let generated: String = LlvmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("\
+@bf_positions = private unnamed_addr constant [2 x [2 x i32]] [
+  [2 x i32] [i32 2, i32 3],
+  [2 x i32] [i32 2, i32 3]
+]
+"));
+        assert!(generated.contains("define internal i64 @bf_add(i64 %value, i128 %delta, i32 %id) {\n"));
+        assert!(generated.contains("  %low = icmp slt i128 %sum, -9223372036854775808\n"));
+        assert!(generated.contains("  %t4 = call i64 @bf_add(i64 %t3, i128 5, i32 0)\n"));
+        assert!(generated.contains("  %t12 = sext i64 %t11 to i128\n"));
+        assert!(generated.contains("  %t18 = call i64 @bf_add(i64 %t17, i128 %t13, i32 1)\n"));
+    }
+
+    #[test]
+    fn test_generate_eof() {
+        let template: &str = "{runtime}";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![Instruction::at_offset(Operation::Read, 2, span)],
+        };
+
+        let expectations: [(EofBehavior, CellType, &str, &str); 4] = [
+            (EofBehavior::Zero, CellType::U8, "trunc i32 %character to i8", "select i1 %eof, i8 0, i8 %cell"),
+            (EofBehavior::MinusOne, CellType::U16, "trunc i32 %character to i16", "select i1 %eof, i16 -1, i16 %cell"),
+            (EofBehavior::MinusOne, CellType::U32, "bitcast i32 %character to i32",
+             "select i1 %eof, i32 -1, i32 %cell"),
+            (EofBehavior::Unchanged, CellType::I64, "zext i32 %character to i64",
+             "select i1 %eof, i64 %value, i64 %cell"),
+        ];
+        for &(eof, cell_type, conversion, selection) in expectations.iter() {
+            let options = Options { eof, cell_type, ..Options::default() };
+            let generated: String = LlvmGenerator::with_options(options).generate(template, &program);
+            assert!(generated.contains(&format!("  %cell = {conversion}\n", conversion = conversion)));
+            assert!(generated.contains(&format!("  %result = {selection}\n", selection = selection)));
+            assert!(generated.contains("  %t5 = call "));
+        }
+    }
+
+    #[test]
+    fn test_generate_search() {
+        let template: &str = "{runtime}";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Scan { stride: 1 }, span),
+                Instruction::new(Operation::Scan { stride: -1 }, span),
+            ],
+        };
+
+        let options = Options { tape_size: 100, ..Options::default() };
+        let generated: String = LlvmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("declare ptr @memchr(ptr, i32, i64)\n"));
+        assert!(generated.contains("  %length = sub i64 100, %ptr\n"));
+        assert!(generated.contains("  %rest = sub i64 100, %length\n"));
+        assert!(generated.contains("  %t1 = load i64, ptr %ptr\n  %t2 = call i64 @bf_search_right(i64 %t1)\n"));
+        assert!(generated.contains("  %t3 = load i64, ptr %ptr\n  %t4 = call i64 @bf_search_left(i64 %t3)\n"));
+
+        let options = Options { cell_type: CellType::U16, ..Options::default() };
+        let generated: String = LlvmGenerator::with_options(options).generate(template, &program);
+        assert!(!generated.contains("@memchr"));
+        assert!(generated.contains("label %scan1.step"));
+    }
+
+    #[test]
+    fn test_generate_growing_tape() {
+        let template: &str = "{runtime}{setup}";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), -1, span),
+                Instruction::new(Operation::Move(2), span),
+                Instruction::at_offset(Operation::Add(1), 3, span),
+            ],
+        };
+
+        let options = Options { tape_growth: TapeGrowth::Right, cell_type: CellType::U32, ..Options::default() };
+        let generated: String = LlvmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("@tape = internal global ptr null\n@tape_size = internal global i64 30000\n"));
+        assert!(generated.contains("  %memory = call ptr @calloc(i64 %size, i64 4)\n"));
+        assert!(generated.contains("define internal i64 @bf_reserve(i64 %ptr, i64 %offset, i64 %low, i64 %high) {\n"));
+        assert!(generated.contains("  %start = getelementptr inbounds i32, ptr %tape, i64 0\n"));
+        assert!(!generated.contains("%before ="));
+
+        // Like the interpreter, the cells before the tape's start wrap around to its current end.
+        assert!(generated.contains("  %position = phi i64 [ %unwrapped, %entry ], [ %wrapped, %wrap ]\n"));
+        assert!(generated.contains("define internal i64 @bf_wrap_left(i64 %ptr, i64 %distance) {\n"));
+        assert!(generated.contains("  call void @bf_allocate()
+  %t1 = load i64, ptr %ptr
+  %t2 = call i64 @bf_wrap_left(i64 %t1, i64 1)
+  %t3 = load ptr, ptr @tape
+  %t4 = getelementptr inbounds i32, ptr %t3, i64 %t2
+"));
+        assert!(generated.contains("  %t8 = call i64 @bf_reserve(i64 %t7, i64 2, i64 0, i64 3)\n"));
+
+        let options = Options { tape_growth: TapeGrowth::Both, checked: true, ..Options::default() };
+        let generated: String = LlvmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("  %before = select i1 %underflow, i64 %grown.before, i64 0\n"));
+        assert!(generated.contains("  %start = getelementptr inbounds i8, ptr %tape, i64 %before\n"));
+        assert!(generated.contains("entry:\n  %position = add i64 %ptr, %offset\n"));
+        assert!(!generated.contains("@bf_fail"));
+        assert!(!generated.contains("@bf_wrap_left"));
+    }
+
+    #[test]
+    fn test_generate_checked() {
+        let template: &str = "{runtime}";
+        let position = |lineno: usize, position: usize| Span::at(MetaData { lineno, position });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), -1, position(1, 1)),
+                Instruction::new(Operation::Move(2), position(1, 3)),
+                Instruction::new(Operation::Scan { stride: 1 }, position(2, 5)),
+            ],
+        };
+
+        let options = Options { checked: true, tape_size: 8, ..Options::default() };
+        let generated: String = LlvmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("\
+@bf_positions = private unnamed_addr constant [3 x [2 x i32]] [
+  [2 x i32] [i32 1, i32 1],
+  [2 x i32] [i32 1, i32 3],
+  [2 x i32] [i32 2, i32 5]
+]
+"));
+        assert!(generated.contains("@bf_reserve(i64 %ptr, i64 %offset, i64 %low, i64 %high, i32 %id) {\n"));
+        assert!(generated.contains("  %overflow = icmp sge i64 %last, 8\n"));
+        assert!(generated.contains("  %t2 = call i64 @bf_reserve(i64 %t1, i64 0, i64 -1, i64 0, i32 0)\n"));
+        assert!(generated.contains("  %t9 = call i64 @bf_reserve(i64 %t8, i64 2, i64 0, i64 0, i32 1)\n"));
+        assert!(generated.contains("  %t15 = call i64 @bf_reserve(i64 %t14, i64 1, i64 0, i64 0, i32 2)\n"));
+
+        let options = Options { checked: true, tape_growth: TapeGrowth::Right, ..Options::default() };
+        let generated: String = LlvmGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("  br i1 %underflow, label %underflow.fail, label %grow.right\n"));
+        assert!(generated.contains("@bf_reserve(i64 %ptr, i64 %offset, i64 %low, i64 %high, i32 %id) {\n"));
+    }
+}
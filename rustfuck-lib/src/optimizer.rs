@@ -0,0 +1,411 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementations of an optimizer, rewriting programs into equivalent, but more efficient ones.
+
+use std::mem;
+use std::vec;
+
+use Options;
+use Overflow;
+use ir::Instruction;
+use ir::Operation;
+use ir::Program;
+use ir::Span;
+
+/// An optimizer rewriting common idioms in programs into more efficient operations.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Optimizer {
+
+    /// The options under which the optimized program will be executed.
+    options: Options,
+}
+
+impl Optimizer {
+    /// Initialize a new optimizer for programs executed with the default options.
+    pub fn new() -> Optimizer {
+        Optimizer::with_options(Options::default())
+    }
+
+    /// Initialize a new optimizer for programs executed with the given `options`.
+    ///
+    /// Loops are only simplified if the result is the same under the `options`' cell type and overflow behavior.
+    pub fn with_options(options: Options) -> Optimizer {
+        Optimizer {
+            options,
+        }
+    }
+
+    /// Optimize the given `program`.
+    ///
+    /// Loops that only add constants to cells, end on the cell they started on, and decrement that cell by one in each
+    /// iteration are replaced by `MulAdd` operations for each of the other cells, followed by a `Clear`. Loops that
+    /// only move the pointer are replaced by a `Scan`. Afterwards, pointer movements are sunk into the offsets of the
+    /// following operations, and are only executed before loops, scans, and input or output operations, and at the end
+    /// of loop bodies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustfuck_lib::Lexer;
+    /// use rustfuck_lib::Operation;
+    /// use rustfuck_lib::Optimizer;
+    /// use rustfuck_lib::Program;
+    /// use rustfuck_lib::language::Brainfuck;
+    ///
+    /// let tokens = Brainfuck::new("[->+++<]").tokenize();
+    /// let program = Optimizer::new().optimize(Program::new(&tokens).unwrap());
+    ///
+    /// let operations: Vec<Operation> = program.instructions.iter()
+    ///     .map(|instruction| instruction.operation.clone())
+    ///     .collect();
+    /// assert_eq!(operations, vec![Operation::MulAdd { offset: 1, factor: 3 }, Operation::Clear]);
+    /// ```
+    pub fn optimize(&self, mut program: Program) -> Program {
+        let instructions: Vec<Instruction> = mem::take(&mut program.instructions);
+        let instructions: Vec<Instruction> = simplify_loops(instructions, &self.options);
+        let instructions: Vec<Instruction> = sink_moves(instructions);
+
+        Program {
+            instructions,
+        }
+    }
+}
+
+/// Replace all simple loops in the `instructions`, including those nested in other loops, by straight-line operations.
+///
+/// The bodies of nested loops are simplified first. The enclosing blocks are kept on a stack, so deeply nested loops
+/// cannot overflow the call stack.
+fn simplify_loops(instructions: Vec<Instruction>, options: &Options) -> Vec<Instruction> {
+    // The enclosing blocks of the current loop body, innermost last: their simplified and their remaining
+    // instructions, and the source code of the loop.
+    let mut enclosing: Vec<(Vec<Instruction>, vec::IntoIter<Instruction>, Span)> = Vec::new();
+    let mut optimized: Vec<Instruction> = Vec::with_capacity(instructions.len());
+    let mut remaining: vec::IntoIter<Instruction> = instructions.into_iter();
+
+    loop {
+        while let Some(Instruction { operation, span, .. }) = remaining.next() {
+            match operation {
+                Operation::Loop(body) => {
+                    let outer: Vec<Instruction> = mem::replace(&mut optimized, Vec::with_capacity(body.len()));
+                    enclosing.push((outer, mem::replace(&mut remaining, body.into_iter()), span));
+                },
+                operation => optimized.push(Instruction::new(operation, span)),
+            }
+        }
+
+        // At the end of a loop body, return to the enclosing block, replacing the loop if possible.
+        let (outer, outer_remaining, span) = match enclosing.pop() {
+            Some(block) => block,
+            None => return optimized,
+        };
+
+        remaining = outer_remaining;
+        let body: Vec<Instruction> = mem::replace(&mut optimized, outer);
+        match simplify_loop(&body, options) {
+            Some(operations) => {
+                optimized.extend(operations.into_iter()
+                    .map(|operation| Instruction::new(operation, span)));
+            },
+            None => optimized.push(Instruction::new(Operation::Loop(body), span)),
+        }
+    }
+}
+
+/// Sink all pointer movements in the `instructions`, including those nested in loops, into the offsets of the
+/// following operations.
+///
+/// The pointer is only moved directly before loops, scans, and input or output operations, and at the end of the
+/// `instructions` and of each loop body. The enclosing blocks are kept on a stack, so deeply nested loops cannot
+/// overflow the call stack.
+fn sink_moves(instructions: Vec<Instruction>) -> Vec<Instruction> {
+    // The enclosing blocks of the current loop body, innermost last: their rewritten and their remaining instructions,
+    // and the source code of the loop. Their movements have been executed before entering the loop.
+    let mut enclosing: Vec<(Vec<Instruction>, vec::IntoIter<Instruction>, Span)> = Vec::new();
+    let mut optimized: Vec<Instruction> = Vec::with_capacity(instructions.len());
+    let mut remaining: vec::IntoIter<Instruction> = instructions.into_iter();
+
+    // The movement that has not been executed yet, and the source code it was built from.
+    let mut pending: Option<(isize, Span)> = None;
+
+    loop {
+        while let Some(Instruction { operation, offset, span }) = remaining.next() {
+            match operation {
+                Operation::Move(distance) => {
+                    pending = match pending {
+                        Some((pending_distance, pending_span)) => {
+                            let span = Span { start: pending_span.start, end: span.end };
+                            Some((pending_distance.wrapping_add(distance), span))
+                        },
+                        None => Some((distance, span)),
+                    };
+                },
+                Operation::Loop(body) => {
+                    materialize(&mut optimized, pending.take());
+                    let outer: Vec<Instruction> = mem::replace(&mut optimized, Vec::with_capacity(body.len()));
+                    enclosing.push((outer, mem::replace(&mut remaining, body.into_iter()), span));
+                },
+                Operation::Read | Operation::Write | Operation::Scan { .. } => {
+                    materialize(&mut optimized, pending.take());
+                    optimized.push(Instruction::at_offset(operation, offset, span));
+                },
+                operation => {
+                    let pending_distance: isize = pending.map_or(0, |(distance, _)| distance);
+                    optimized.push(Instruction::at_offset(operation, offset.wrapping_add(pending_distance), span));
+                },
+            }
+        }
+
+        // At the end of a block, execute its pending movement, and return to the enclosing block, if any.
+        materialize(&mut optimized, pending.take());
+        let (outer, outer_remaining, span) = match enclosing.pop() {
+            Some(block) => block,
+            None => return optimized,
+        };
+
+        remaining = outer_remaining;
+        let body: Vec<Instruction> = mem::replace(&mut optimized, outer);
+        optimized.push(Instruction::new(Operation::Loop(body), span));
+    }
+}
+
+/// Append the `pending` pointer movement to the `instructions`, unless it does not move the pointer at all.
+fn materialize(instructions: &mut Vec<Instruction>, pending: Option<(isize, Span)>) {
+    if let Some((distance, span)) = pending {
+        if distance != 0 {
+            instructions.push(Instruction::new(Operation::Move(distance), span));
+        }
+    }
+}
+
+/// Replace the loop with the given `body` by straight-line operations, if possible.
+///
+/// This is possible if the loop only adds constants to cells, ends on the cell it started on, and changes that cell
+/// by one in each iteration. Loops changing the current cell by `+1` can only be simplified if they do not change any
+/// other cells. Loops consisting of a single pointer movement are replaced by a scan.
+///
+/// Loops only reach zero by incrementing if the cells wrap around, and by decrementing if the cells wrap around or
+/// cannot be negative. Otherwise, they run until the overflow behavior in the `options` stops them and are kept.
+fn simplify_loop(body: &[Instruction], options: &Options) -> Option<Vec<Operation>> {
+    if let [Instruction { operation: Operation::Move(stride), .. }] = *body {
+        return Some(vec![Operation::Scan { stride }]);
+    }
+
+    // The total changes to each cell in one iteration, in order of their first change.
+    let mut changes: Vec<(isize, i32)> = Vec::new();
+    let mut offset: isize = 0;
+
+    for instruction in body {
+        match instruction.operation {
+            Operation::Add(delta) => {
+                match changes.iter_mut().find(|change| change.0 == offset) {
+                    Some(change) => change.1 = change.1.wrapping_add(delta),
+                    None => changes.push((offset, delta)),
+                }
+            },
+            Operation::Move(distance) => offset = offset.wrapping_add(distance),
+            _ => return None,
+        }
+    }
+
+    if offset != 0 {
+        return None;
+    }
+
+    let origin: i32 = changes.iter().find(|change| change.0 == 0).map_or(0, |change| change.1);
+    let mut operations: Vec<Operation> = changes.into_iter()
+        .filter(|&(offset, factor)| offset != 0 && factor != 0)
+        .map(|(offset, factor)| Operation::MulAdd { offset, factor })
+        .collect();
+
+    let wrapping: bool = options.overflow == Overflow::Wrap;
+    match origin {
+        -1 if wrapping || !options.cell_type.is_signed() => {},
+        1 if wrapping && operations.is_empty() => {},
+        _ => return None,
+    }
+
+    operations.push(Operation::Clear);
+    Some(operations)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use CellType;
+    use Instruction;
+    use Lexer;
+    use MetaData;
+    use Operation;
+    use Optimizer;
+    use Options;
+    use Overflow;
+    use Program;
+    use Span;
+    use language::Brainfuck;
+
+    /// Optimize the `Brainfuck` `source` and return the operations of the program's top level.
+    fn optimize(source: &str) -> Vec<Operation> {
+        let tokens = Brainfuck::new(source).tokenize();
+        let program = Optimizer::new().optimize(Program::new(&tokens).unwrap());
+        program.instructions.iter()
+            .map(|instruction| instruction.operation.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_optimize_clear() {
+        assert_eq!(optimize("+[-]"), vec![Operation::Add(1), Operation::Clear]);
+        assert_eq!(optimize("[+]"), vec![Operation::Clear]);
+    }
+
+    #[test]
+    fn test_optimize_clear_without_wrapping() {
+        let optimize = |source: &str, cell_type: CellType, overflow: Overflow| -> Vec<Operation> {
+            let options = Options { cell_type, overflow, ..Options::default() };
+            let tokens = Brainfuck::new(source).tokenize();
+            let program = Optimizer::with_options(options).optimize(Program::new(&tokens).unwrap());
+            program.instructions.iter()
+                .map(|instruction| instruction.operation.clone())
+                .collect()
+        };
+
+        // Decrementing always reaches zero on unsigned cells.
+        assert_eq!(optimize("[-]", CellType::U16, Overflow::Saturate), vec![Operation::Clear]);
+        assert_eq!(optimize("[->+<]", CellType::U8, Overflow::Error), vec![
+            Operation::MulAdd { offset: 1, factor: 1 },
+            Operation::Clear,
+        ]);
+        assert_eq!(optimize("[-]", CellType::I64, Overflow::Wrap), vec![Operation::Clear]);
+        assert!(matches!(optimize("[-]", CellType::I64, Overflow::Saturate)[0], Operation::Loop(_)));
+        assert!(matches!(optimize("[->+<]", CellType::I64, Overflow::Error)[0], Operation::Loop(_)));
+
+        // Incrementing only reaches zero by wrapping around.
+        assert_eq!(optimize("[+]", CellType::U32, Overflow::Wrap), vec![Operation::Clear]);
+        assert!(matches!(optimize("[+]", CellType::U8, Overflow::Saturate)[0], Operation::Loop(_)));
+        assert!(matches!(optimize("[+]", CellType::U8, Overflow::Error)[0], Operation::Loop(_)));
+    }
+
+    #[test]
+    fn test_optimize_copy() {
+        assert_eq!(optimize("[->+<]"), vec![Operation::MulAdd { offset: 1, factor: 1 }, Operation::Clear]);
+        assert_eq!(optimize("[<<+>>-]"), vec![Operation::MulAdd { offset: -2, factor: 1 }, Operation::Clear]);
+    }
+
+    #[test]
+    fn test_optimize_multiply() {
+        assert_eq!(optimize("[->++>+++<<]"), vec![
+            Operation::MulAdd { offset: 1, factor: 2 },
+            Operation::MulAdd { offset: 2, factor: 3 },
+            Operation::Clear,
+        ]);
+        assert_eq!(optimize("[>--<->+<]"), vec![Operation::MulAdd { offset: 1, factor: -1 }, Operation::Clear]);
+    }
+
+    #[test]
+    fn test_optimize_nested() {
+        let operations: Vec<Operation> = optimize("[>[-]<-]");
+        assert_eq!(operations.len(), 1);
+        match operations[0] {
+            Operation::Loop(ref body) => {
+                let body: Vec<(Operation, isize)> = body.iter()
+                    .map(|instruction| (instruction.operation.clone(), instruction.offset))
+                    .collect();
+                assert_eq!(body, vec![(Operation::Clear, 1), (Operation::Add(-1), 0)]);
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_optimize_offsets() {
+        let tokens = Brainfuck::new(">>+<<-\n>[->>+<<]>.<<").tokenize();
+        let program = Optimizer::new().optimize(Program::new(&tokens).unwrap());
+
+        let span = |start: (usize, usize), end: (usize, usize)| Span {
+            start: MetaData { lineno: start.0, position: start.1 },
+            end: MetaData { lineno: end.0, position: end.1 },
+        };
+        assert_eq!(program.instructions, vec![
+            Instruction::at_offset(Operation::Add(1), 2, span((1, 3), (1, 3))),
+            Instruction::at_offset(Operation::Add(-1), 0, span((1, 6), (1, 6))),
+            Instruction::at_offset(Operation::MulAdd { offset: 2, factor: 1 }, 1, span((2, 2), (2, 9))),
+            Instruction::at_offset(Operation::Clear, 1, span((2, 2), (2, 9))),
+            Instruction::new(Operation::Move(2), span((1, 1), (2, 10))),
+            Instruction::new(Operation::Write, span((2, 11), (2, 11))),
+            Instruction::new(Operation::Move(-2), span((2, 12), (2, 13))),
+        ]);
+    }
+
+    #[test]
+    fn test_optimize_offsets_in_loops() {
+        let operations: Vec<Operation> = optimize("+[>+>,<<-]");
+        match operations[1] {
+            Operation::Loop(ref body) => {
+                let body: Vec<(Operation, isize)> = body.iter()
+                    .map(|instruction| (instruction.operation.clone(), instruction.offset))
+                    .collect();
+                assert_eq!(body, vec![
+                    (Operation::Add(1), 1),
+                    (Operation::Move(2), 0),
+                    (Operation::Read, 0),
+                    (Operation::Add(-1), -2),
+                    (Operation::Move(-2), 0),
+                ]);
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_optimize_scan() {
+        assert_eq!(optimize("[>]"), vec![Operation::Scan { stride: 1 }]);
+        assert_eq!(optimize("[<]"), vec![Operation::Scan { stride: -1 }]);
+        assert_eq!(optimize("[>>>>]"), vec![Operation::Scan { stride: 4 }]);
+        assert_eq!(optimize("[<<<]"), vec![Operation::Scan { stride: -3 }]);
+        assert_eq!(optimize("[>><<<]"), vec![Operation::Scan { stride: -1 }]);
+    }
+
+    #[test]
+    fn test_optimize_nested_scans() {
+        let tokens = Brainfuck::new("+[>[>>]<[<]\n>-]").tokenize();
+        let program = Optimizer::new().optimize(Program::new(&tokens).unwrap());
+
+        let span = |start: (usize, usize), end: (usize, usize)| Span {
+            start: MetaData { lineno: start.0, position: start.1 },
+            end: MetaData { lineno: end.0, position: end.1 },
+        };
+        assert_eq!(program.instructions, vec![
+            Instruction::new(Operation::Add(1), span((1, 1), (1, 1))),
+            Instruction::new(Operation::Loop(vec![
+                Instruction::new(Operation::Move(1), span((1, 3), (1, 3))),
+                Instruction::new(Operation::Scan { stride: 2 }, span((1, 4), (1, 7))),
+                Instruction::new(Operation::Move(-1), span((1, 8), (1, 8))),
+                Instruction::new(Operation::Scan { stride: -1 }, span((1, 9), (1, 11))),
+                Instruction::at_offset(Operation::Add(-1), 1, span((2, 2), (2, 2))),
+                Instruction::new(Operation::Move(1), span((2, 1), (2, 1))),
+            ]), span((1, 2), (2, 3))),
+        ]);
+    }
+
+    #[test]
+    fn test_optimize_unsimplifiable() {
+        // The pointer does not return to the starting cell.
+        assert!(matches!(optimize("[->+]")[0], Operation::Loop(_)));
+
+        // The starting cell is not changed by one.
+        assert!(matches!(optimize("[-->+<]")[0], Operation::Loop(_)));
+        assert!(matches!(optimize("[+>+<]")[0], Operation::Loop(_)));
+
+        // The loop does more than moving the pointer.
+        assert!(matches!(optimize("[>-]")[0], Operation::Loop(_)));
+        assert!(matches!(optimize("[>[<]]")[0], Operation::Loop(_)));
+
+        // The loop has side effects.
+        assert!(matches!(optimize("[-.]")[0], Operation::Loop(_)));
+        assert!(matches!(optimize("[-,]")[0], Operation::Loop(_)));
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Options controlling how programs are translated and executed.
+
+/// The options controlling how programs are translated and executed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Options {
+
+    /// Rewrite common idioms into more efficient operations before generating code or executing the program.
+    pub optimize: bool,
+
+    /// The type of the cells on the tape.
+    pub cell_type: CellType,
+
+    /// The behavior when a cell's value leaves the range of its type.
+    pub overflow: Overflow,
+
+    /// The initial number of cells on the tape. Must be at least `1`.
+    pub tape_size: usize,
+
+    /// The directions in which the tape grows when the pointer leaves it.
+    pub tape_growth: TapeGrowth,
+
+    /// The change to the current cell when reading at the end of the input.
+    pub eof: EofBehavior,
+
+    /// Abort the execution with a runtime error if a cell outside the tape is accessed, instead of wrapping around at
+    /// the tape's ends.
+    pub checked: bool,
+}
+
+/// The types a cell on the tape can have.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CellType {
+
+    /// Unsigned 8-bit cells.
+    U8,
+
+    /// Unsigned 16-bit cells.
+    U16,
+
+    /// Unsigned 32-bit cells.
+    U32,
+
+    /// Signed 64-bit cells.
+    I64,
+}
+
+/// The behaviors when a cell's value leaves the range of its type.
+///
+/// Overflows are checked after each operation of the intermediate representation, i.e. a run of increments and
+/// decrements is treated as a single change of the cell.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Overflow {
+
+    /// Wrap around to the other end of the range.
+    Wrap,
+
+    /// Stay at the end of the range that has been exceeded.
+    Saturate,
+
+    /// Abort the execution with a runtime error.
+    Error,
+}
+
+/// The directions in which the tape can grow beyond its initial size.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TapeGrowth {
+
+    /// The tape keeps its initial size.
+    Fixed,
+
+    /// The tape grows when the pointer moves past its last cell.
+    Right,
+
+    /// The tape grows when the pointer moves past its last or before its first cell.
+    Both,
+}
+
+/// The changes to the current cell when reading at the end of the input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EofBehavior {
+
+    /// Set the cell to zero.
+    Zero,
+
+    /// Set the cell to `-1`, i.e. to the largest value of unsigned cell types.
+    MinusOne,
+
+    /// Leave the cell unchanged.
+    Unchanged,
+}
+
+impl CellType {
+    /// The smallest value a cell of this type can hold.
+    pub fn min(self) -> i64 {
+        match self {
+            CellType::U8 | CellType::U16 | CellType::U32 => 0,
+            CellType::I64 => i64::MIN,
+        }
+    }
+
+    /// The largest value a cell of this type can hold.
+    pub fn max(self) -> i64 {
+        match self {
+            CellType::U8 => i64::from(u8::MAX),
+            CellType::U16 => i64::from(u16::MAX),
+            CellType::U32 => i64::from(u32::MAX),
+            CellType::I64 => i64::MAX,
+        }
+    }
+
+    /// Determine if cells of this type can hold negative values.
+    pub fn is_signed(self) -> bool {
+        self.min() < 0
+    }
+}
+
+impl Options {
+    /// Determine if accesses to cells outside the tape are reported. A tape growing in both directions contains all
+    /// cells ever accessed, so it never reports any.
+    pub fn is_checked(&self) -> bool {
+        self.checked && self.tape_growth != TapeGrowth::Both
+    }
+
+    /// Determine if generated code has to make sure that the accessed cells are on the tape, i.e. if the tape grows or
+    /// is checked.
+    pub fn is_reserving(&self) -> bool {
+        self.tape_growth != TapeGrowth::Fixed || self.is_checked()
+    }
+
+    /// Determine if the pointer wraps around at the tape's ends, i.e. if the tape is not checked and does not grow to
+    /// the left. A tape growing to the right only wraps around at its start, continuing at its current end.
+    pub fn is_wrapping(&self) -> bool {
+        !self.checked && self.tape_growth != TapeGrowth::Both
+    }
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            optimize: true,
+            cell_type: CellType::U8,
+            overflow: Overflow::Wrap,
+            tape_size: 30_000,
+            tape_growth: TapeGrowth::Fixed,
+            eof: EofBehavior::Unchanged,
+            checked: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use CellType;
+    use Options;
+    use TapeGrowth;
+
+    #[test]
+    fn test_cell_type_range() {
+        assert_eq!((CellType::U8.min(), CellType::U8.max()), (0, 255));
+        assert_eq!((CellType::U16.min(), CellType::U16.max()), (0, 65_535));
+        assert_eq!((CellType::U32.min(), CellType::U32.max()), (0, 4_294_967_295));
+        assert_eq!((CellType::I64.min(), CellType::I64.max()), (i64::MIN, i64::MAX));
+    }
+
+    #[test]
+    fn test_cell_type_is_signed() {
+        assert!(!CellType::U8.is_signed());
+        assert!(!CellType::U32.is_signed());
+        assert!(CellType::I64.is_signed());
+    }
+
+    #[test]
+    fn test_options_is_checked() {
+        let checked = |tape_growth: TapeGrowth, checked: bool| Options { tape_growth, checked, ..Options::default() };
+        assert!(!checked(TapeGrowth::Fixed, false).is_checked());
+        assert!(checked(TapeGrowth::Fixed, true).is_checked());
+        assert!(checked(TapeGrowth::Right, true).is_checked());
+        assert!(!checked(TapeGrowth::Both, true).is_checked());
+    }
+
+    #[test]
+    fn test_options_is_reserving() {
+        let checked = |tape_growth: TapeGrowth, checked: bool| Options { tape_growth, checked, ..Options::default() };
+        assert!(!checked(TapeGrowth::Fixed, false).is_reserving());
+        assert!(checked(TapeGrowth::Fixed, true).is_reserving());
+        assert!(checked(TapeGrowth::Right, false).is_reserving());
+        assert!(checked(TapeGrowth::Both, true).is_reserving());
+    }
+
+    #[test]
+    fn test_options_is_wrapping() {
+        let checked = |tape_growth: TapeGrowth, checked: bool| Options { tape_growth, checked, ..Options::default() };
+        assert!(checked(TapeGrowth::Fixed, false).is_wrapping());
+        assert!(!checked(TapeGrowth::Fixed, true).is_wrapping());
+        assert!(checked(TapeGrowth::Right, false).is_wrapping());
+        assert!(!checked(TapeGrowth::Right, true).is_wrapping());
+        assert!(!checked(TapeGrowth::Both, false).is_wrapping());
+    }
+}
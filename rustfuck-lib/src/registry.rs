@@ -0,0 +1,130 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Definitions of a registry collecting extensions of the compiler, like backends and languages, by their names.
+
+use std::fmt;
+
+/// An extension of the compiler identified by its name, which can be collected in a `Registry`.
+pub trait Named {
+    /// Get the name identifying the extension, e.g. on the command line.
+    fn name(&self) -> &str;
+}
+
+/// A collection of extensions, identified by their names.
+///
+/// # Examples
+///
+/// ```
+/// use rustfuck_lib::Named;
+/// use rustfuck_lib::Registry;
+///
+/// /// A greeting in some language.
+/// trait Greeting: Named {
+///     fn greet(&self) -> &str;
+/// }
+///
+/// struct English;
+///
+/// impl Named for English {
+///     fn name(&self) -> &str {
+///         "english"
+///     }
+/// }
+///
+/// impl Greeting for English {
+///     fn greet(&self) -> &str {
+///         "Hello World!"
+///     }
+/// }
+///
+/// let mut registry: Registry<dyn Greeting> = Registry::empty();
+/// registry.register(Box::new(English));
+///
+/// assert_eq!(registry.get("english").map(Greeting::greet), Some("Hello World!"));
+/// assert_eq!(registry.names(), ["english"]);
+/// ```
+pub struct Registry<T: ?Sized + Named> {
+
+    /// The registered extensions, in the order in which they have been registered.
+    items: Vec<Box<T>>,
+}
+
+impl<T: ?Sized + Named> Registry<T> {
+    /// Initialize a new registry without any extensions.
+    pub fn empty() -> Registry<T> {
+        Registry {
+            items: Vec::new(),
+        }
+    }
+
+    /// Add the `item` to the registry. It replaces any registered extension with the same name, keeping its place.
+    pub fn register(&mut self, item: Box<T>) {
+        match self.items.iter().position(|registered| registered.name() == item.name()) {
+            Some(index) => self.items[index] = item,
+            None => self.items.push(item),
+        }
+    }
+
+    /// Get the extension with the given `name`, if there is one.
+    pub fn get(&self, name: &str) -> Option<&T> {
+        self.iter().find(|item| item.name() == name)
+    }
+
+    /// Get the names of all registered extensions, in the order in which they have been registered.
+    pub fn names(&self) -> Vec<&str> {
+        self.iter().map(|item| item.name()).collect()
+    }
+
+    /// Iterate over all registered extensions, in the order in which they have been registered.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter().map(|item| &**item)
+    }
+}
+
+impl<T: ?Sized + Named> fmt::Debug for Registry<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Registry")
+            .field("items", &self.names())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Named;
+    use Registry;
+
+    /// An extension with a name and a value.
+    struct Item(&'static str, u8);
+
+    impl Named for Item {
+        fn name(&self) -> &str {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let registry: Registry<Item> = Registry::empty();
+        assert!(registry.names().is_empty());
+        assert!(registry.get("first").is_none());
+        assert_eq!(format!("{:?}", registry), "Registry { items: [] }");
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry: Registry<Item> = Registry::empty();
+        registry.register(Box::new(Item("first", 1)));
+        registry.register(Box::new(Item("second", 2)));
+        registry.register(Box::new(Item("first", 3)));
+        assert_eq!(registry.names(), ["first", "second"]);
+        assert_eq!(registry.get("first").map(|item| item.1), Some(3));
+        assert_eq!(registry.iter().map(|item| item.1).collect::<Vec<u8>>(), [3, 2]);
+        assert_eq!(format!("{:?}", registry), "Registry { items: [\"first\", \"second\"] }");
+    }
+}
@@ -0,0 +1,1007 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementations of a generator for creating `Rust` code from intermediate compile results.
+
+use std::convert::TryFrom;
+
+use CellType;
+use EofBehavior;
+use Options;
+use Overflow;
+use TapeGrowth;
+use ir::Instruction;
+use ir::Operation;
+use ir::Program;
+use ir::Step;
+use ir::accessed_range;
+use ir::reserved_range;
+use ir::walk;
+use lexer::MetaData;
+
+/// The `Rust` function moving the pointer by `offset` cells and making sure that the cells from `low` to `high` cells
+/// away from the new current cell are on the tape. `{id_parameter}` and `{result}` must be replaced by the parameter
+/// and the result type for reporting errors, `{low}` by the name of the parameter `low`, marked as unused if the tape
+/// does not need it, `{mutable}` by the keyword allowing the code `{left}` and `{right}` to shift the position when
+/// handling the tape's start and end, and `{position}` by the returned index.
+static RESERVING_RUNTIME: &str = "\
+fn reserve(tape: &mut Vec<Cell>, ptr: usize, offset: isize, {low}: isize, high: isize{id_parameter}) -> {result} {
+    let {mutable}position = ptr as isize + offset;
+{left}{right}    {position}
+}
+
+";
+
+/// The `Rust` code growing the tape when cells before its start are accessed.
+static GROWING_LEFT: &str = "    if position + low < 0 {
+        let before = (-(position + low)).max(tape.len() as isize) as usize;
+        tape.resize(tape.len() + before, 0);
+        tape.rotate_right(before);
+        position += before as isize;
+    }
+";
+
+/// The `Rust` code growing the tape when cells past its end are accessed.
+static GROWING_RIGHT: &str = "    if position + high >= tape.len() as isize {
+        let length = (position + high + 1).max(2 * tape.len() as isize) as usize;
+        tape.resize(length, 0);
+    }
+";
+
+/// The `Rust` code wrapping a position before the start of a tape growing only to the right around to the tape's
+/// current end, like the interpreter. The other accessed cells before the start are wrapped when accessing them.
+static WRAPPING_LEFT: &str = "    if position < 0 {
+        position = position.rem_euclid(tape.len() as isize);
+    }
+";
+
+/// The `Rust` code reporting accesses before the tape's start.
+static CHECKING_LEFT: &str = "    if position + low < 0 {
+        return Err(fail(\"pointer underflow\", id));
+    }
+";
+
+/// The `Rust` code reporting accesses past the tape's end.
+static CHECKING_RIGHT: &str = "    if position + high >= tape.len() as isize {
+        return Err(fail(\"pointer overflow\", id));
+    }
+";
+
+/// The `Rust` function getting the index `offset` cells away from the index `ptr` on a tape with a fixed size, wrapping
+/// around at the tape's ends. The distance must be smaller than the tape's size, which must replace `{tape_size}`.
+static WRAPPING_RUNTIME: &str = "\
+fn wrap(ptr: usize, offset: isize) -> usize {
+    let length: usize = {tape_size};
+    let position: usize = ptr + if offset < 0 { (offset + length as isize) as usize } else { offset as usize };
+    if position < length { position } else { position - length }
+}
+
+";
+
+/// The `Rust` function getting the cell `distance` cells to the left of the index `ptr` on a tape growing only to the
+/// right, wrapping around at the tape's start.
+static WRAPPING_LEFT_RUNTIME: &str = "\
+fn wrap_left(tape: &mut [Cell], ptr: usize, distance: usize) -> &mut Cell {
+    let length = tape.len();
+    &mut tape[(ptr + length - distance % length) % length]
+}
+
+";
+
+/// The `Rust` function searching a tape with a fixed size for the next zero cell to the right of the current cell. Like
+/// the interpreter, it continues at the tape's start if there is no zero cell before reaching the end, and never
+/// returns if there is no zero cell at all.
+static SEARCHING_RIGHT: &str = "\
+fn search_right(tape: &[Cell], ptr: usize) -> usize {
+    match tape[ptr..].iter().position(|&cell| cell == 0) {
+        Some(distance) => ptr + distance,
+        None => tape[..ptr].iter().position(|&cell| cell == 0).unwrap_or_else(|| loop {}),
+    }
+}
+
+";
+
+/// The `Rust` function searching a tape with a fixed size for the next zero cell to the left of the current cell,
+/// continuing at the tape's end like `search_right` continues at its start.
+static SEARCHING_LEFT: &str = "\
+fn search_left(tape: &[Cell], ptr: usize) -> usize {
+    match tape[..=ptr].iter().rposition(|&cell| cell == 0) {
+        Some(position) => position,
+        None => ptr + 1 + tape[ptr + 1..].iter().rposition(|&cell| cell == 0).unwrap_or_else(|| loop {}),
+    }
+}
+
+";
+
+/// The `Rust` function creating an error with a message for the source position with the given `id`. The placeholder
+/// `{count}` must be replaced by the number of source positions, `{positions}` by their initializers.
+static FAILING_RUNTIME: &str = "\
+static POSITIONS: [(usize, usize); {count}] = [
+{positions}];
+
+fn fail(message: &str, id: usize) -> io::Error {
+    let (lineno, position) = POSITIONS[id];
+    io::Error::new(io::ErrorKind::InvalidData, format!(\"{} at line {}, position {}\", message, lineno, position))
+}
+
+";
+
+/// The `Rust` function reading a character, if there is any left in the input.
+static READING_RUNTIME: &str = "\
+fn read<R: Read>(input: &mut R) -> io::Result<Option<Cell>> {
+    let mut buffer = [0; 1];
+    match input.read_exact(&mut buffer) {
+        Ok(()) => Ok(Some(Cell::from(buffer[0]))),
+        Err(ref error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+";
+
+/// The `Rust` function changing cells that saturate at their bounds.
+static SATURATING_RUNTIME: &str = "\
+fn add(value: Cell, delta: i128) -> Cell {
+    let sum = i128::from(value) + delta;
+    sum.max(i128::from(Cell::MIN)).min(i128::from(Cell::MAX)) as Cell
+}
+
+";
+
+/// The `Rust` function changing cells that fails when leaving their bounds.
+static CHECKED_RUNTIME: &str = "\
+fn add(value: Cell, delta: i128, id: usize) -> io::Result<Cell> {
+    let sum = i128::from(value) + delta;
+    if sum < i128::from(Cell::MIN) || sum > i128::from(Cell::MAX) {
+        return Err(fail(\"cell overflow\", id));
+    }
+    Ok(sum as Cell)
+}
+
+";
+
+/// The runtime functions called by the generated `Rust` code.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Runtime {
+
+    /// Cells are changed by calling `add`.
+    add: bool,
+
+    /// Characters are read by calling `read`.
+    read: bool,
+
+    /// The pointer is moved by calling `reserve`.
+    reserve: bool,
+
+    /// Zero cells to the right are searched by calling `search_right`.
+    search_right: bool,
+
+    /// Zero cells to the left are searched by calling `search_left`.
+    search_left: bool,
+
+    /// The cells are accessed by calling `wrap`.
+    wrap: bool,
+
+    /// The cells before the start of a tape growing to the right are accessed by calling `wrap_left`.
+    wrap_left: bool,
+}
+
+/// A generator for creating `Rust` code from intermediate compile results.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RustGenerator {
+
+    /// The options controlling the generated code.
+    options: Options,
+
+    /// The current level of indentation.
+    indentation_level: u32,
+
+    /// The source positions reported by the generated code, indexed by the IDs passed to the runtime functions.
+    positions: Vec<MetaData>,
+
+    /// The runtime functions called by the generated code.
+    runtime: Runtime,
+}
+
+impl RustGenerator {
+    /// Initialize a new generator with the default options.
+    pub fn new() -> RustGenerator {
+        RustGenerator::with_options(Options::default())
+    }
+
+    /// Initialize a new generator with the given `options`.
+    pub fn with_options(options: Options) -> RustGenerator {
+        RustGenerator {
+            options,
+            indentation_level: 1,
+            positions: Vec::new(),
+            runtime: Runtime::default(),
+        }
+    }
+
+    /// Generate the `Rust` code from the given `program`, using the specified `template`.
+    ///
+    /// The placeholder `{cell_type}` in the `template` is replaced by the `Rust` type of the cells, `{runtime}` by the
+    /// functions the generated code needs, and `{tape_size}` by the initial number of cells. The `template` must end
+    /// within a function returning an `io::Result<()>`, in which the tape `tape`, the index `ptr` of the current cell,
+    /// the reader `input`, and the writer `output` are defined.
+    pub fn generate(&mut self, template: &str, program: &Program) -> String {
+        // The runtime functions depend on the code calling them.
+        self.positions.clear();
+        self.runtime = Runtime::default();
+        let mut code = String::new();
+        self.generate_block(&program.instructions, &mut code);
+
+        let mut output = template.replace("{cell_type}", rust_type(self.options.cell_type))
+            .replace("{runtime}", &self.runtime())
+            .replace("{tape_size}", &self.options.tape_size.max(1).to_string());
+        output.push_str(&code);
+
+        // Flush the output and close the function.
+        output.push_str(&self.indent("output.flush()\n"));
+        output.push_str("}\n");
+
+        output
+    }
+
+    /// Generate the `Rust` code for the given list of `instructions` and the bodies of their loops, appending it to
+    /// the `output`.
+    fn generate_block(&mut self, instructions: &[Instruction], output: &mut String) {
+        // The current cell is on the tape when entering a block, but the other accessed cells might not be.
+        self.reserve_segment(instructions, output);
+
+        // The offset of the cell on which the currently generated multiplications depend, and if the current cell
+        // is still known not to be zero.
+        let mut guard: Option<isize> = None;
+        let mut nonzero: bool = false;
+        for step in walk(instructions) {
+            let (instruction, following): (&Instruction, &[Instruction]) = match step {
+                Step::Instruction(instruction, following) => (instruction, following),
+                Step::LoopEnd(_, following) => {
+                    // Close the loop, which only ends on a cell on the tape, but the cells accessed next might not be.
+                    self.close_guard(&mut guard, output);
+                    self.indentation_level -= 1;
+                    output.push_str(&self.indent("}\n"));
+                    nonzero = false;
+                    self.reserve_segment(following, output);
+                    continue;
+                },
+            };
+
+            let source: Option<isize> = match instruction.operation {
+                Operation::MulAdd { .. } => Some(instruction.offset),
+                _ => None,
+            };
+            nonzero = nonzero && source == Some(0);
+            if guard.is_some() && guard != source {
+                self.close_guard(&mut guard, output);
+            }
+
+            // Checked accesses are reported at the accessing instruction. Multiplications depending on the same cell
+            // only need a single check.
+            let offset: isize = instruction.offset;
+            if self.options.is_checked() && guard.is_none() && offset != 0 {
+                let position: String = self.position(instruction, true);
+                self.reserve(0, offset.min(0), offset.max(0), &position, output);
+            }
+
+            let cell: String = self.cell(instruction.offset);
+            match instruction.operation {
+                Operation::Add(delta) if self.options.overflow == Overflow::Wrap => {
+                    // Change the value at the cell, wrapping around at the bounds of the cell type.
+                    let method: &str = if delta < 0 { "wrapping_sub" } else { "wrapping_add" };
+                    let line: String = format!("{cell} = {cell}.{method}({value});\n",
+                                               cell = cell, method = method,
+                                               value = magnitude(delta, self.options.cell_type));
+                    output.push_str(&self.indent(&line));
+                },
+                Operation::Add(delta) => {
+                    // Change the value at the cell using the runtime function.
+                    let line: String = self.add(instruction, &cell, &delta.to_string());
+                    output.push_str(&self.indent(&line));
+                },
+                Operation::Move(offset) if self.options.is_reserving() => {
+                    // Go to another cell, making sure that it and the cells accessed next are on the tape.
+                    let (low, high): (isize, isize) = if self.options.is_checked() {
+                        (0, 0)
+                    } else {
+                        accessed_range(following, false)
+                    };
+                    let position: String = self.position(instruction, self.options.is_checked());
+                    self.reserve(offset, low, high, &position, output);
+                },
+                Operation::Move(offset) => {
+                    // Go to another cell, wrapping around at the tape's ends.
+                    self.advance(offset, output);
+                },
+                Operation::Read => {
+                    // Read a single character into the cell.
+                    self.runtime.read = true;
+                    let eof: String = match self.options.eof {
+                        EofBehavior::Zero => String::from("0"),
+                        EofBehavior::MinusOne if self.options.cell_type.is_signed() => String::from("-1"),
+                        EofBehavior::MinusOne => String::from("Cell::MAX"),
+                        EofBehavior::Unchanged => {
+                            // As an argument, the cell does not need any parentheses.
+                            let value: Option<&str> = cell.strip_prefix('(').and_then(|cell| cell.strip_suffix(')'));
+                            String::from(value.unwrap_or(&cell))
+                        },
+                    };
+                    let line: String = format!("{cell} = read(input)?.unwrap_or({eof});\n", cell = cell, eof = eof);
+                    output.push_str(&self.indent(&line));
+                },
+                Operation::Write => {
+                    // Print the character of the cell.
+                    let line: String = format!("output.write_all(&{cell}.to_le_bytes()[..1])?;\n", cell = cell);
+                    output.push_str(&self.indent(&line));
+                },
+                Operation::Loop(ref body) => {
+                    // Repeat the body as long as the current cell is not zero.
+                    output.push_str(&self.indent("while tape[ptr] != 0 {\n"));
+                    self.indentation_level += 1;
+                    self.reserve_segment(body, output);
+                    nonzero = true;
+                },
+                Operation::Clear => {
+                    // Set the cell to zero.
+                    output.push_str(&self.indent(&format!("{cell} = 0;\n", cell = cell)));
+                },
+                Operation::MulAdd { offset, factor } if self.options.overflow == Overflow::Wrap => {
+                    // Add a multiple of the cell to another cell, wrapping around at the bounds of the cell type.
+                    let target: String = self.cell(instruction.offset.wrapping_add(offset));
+                    let method: &str = if factor < 0 { "wrapping_sub" } else { "wrapping_add" };
+                    let product: String = match magnitude(factor, self.options.cell_type) {
+                        1 => cell,
+                        factor => format!("{cell}.wrapping_mul({factor})", cell = cell, factor = factor),
+                    };
+                    let line: String = format!("{target} = {target}.{method}({product});\n",
+                                               target = target, method = method, product = product);
+                    self.multiply(instruction, offset, &line, &mut guard, nonzero, output);
+                },
+                Operation::MulAdd { offset, factor } => {
+                    // Add a multiple of the cell to another cell using the runtime function.
+                    let target: String = self.cell(instruction.offset.wrapping_add(offset));
+                    let product: String = format!("i128::from({cell}) * {factor}", cell = cell, factor = factor);
+                    let line: String = self.add(instruction, &target, &product);
+                    self.multiply(instruction, offset, &line, &mut guard, nonzero, output);
+                },
+                Operation::Scan { stride: 1 } if !self.options.is_reserving() => {
+                    // Search the tape to the right for the next zero cell.
+                    self.runtime.search_right = true;
+                    output.push_str(&self.indent("ptr = search_right(&tape, ptr);\n"));
+                },
+                Operation::Scan { stride: -1 } if !self.options.is_reserving() => {
+                    // Search the tape to the left for the next zero cell.
+                    self.runtime.search_left = true;
+                    output.push_str(&self.indent("ptr = search_left(&tape, ptr);\n"));
+                },
+                Operation::Scan { stride } => {
+                    // Step through the cells until reaching a zero cell.
+                    output.push_str(&self.indent("while tape[ptr] != 0 {\n"));
+                    self.indentation_level += 1;
+                    if self.options.is_reserving() {
+                        let position: String = self.position(instruction, self.options.is_checked());
+                        self.reserve(stride, 0, 0, &position, output);
+                    } else {
+                        self.advance(stride, output);
+                    }
+                    self.indentation_level -= 1;
+                    output.push_str(&self.indent("}\n"));
+                },
+            }
+
+            // Scans only end on a cell on the tape, but the cells accessed next might not be.
+            if let Operation::Scan { .. } = instruction.operation {
+                self.reserve_segment(following, output);
+            }
+        }
+
+        self.close_guard(&mut guard, output);
+    }
+
+    /// Get the line adding the `delta` expression to the `cell` using the runtime function, which reports overflows at
+    /// the `instruction` if the options require it.
+    fn add(&mut self, instruction: &Instruction, cell: &str, delta: &str) -> String {
+        self.runtime.add = true;
+        let failing: bool = self.options.overflow == Overflow::Error;
+        let position: String = self.position(instruction, failing);
+        format!("{cell} = add({cell}, {delta}{position}){propagation};\n",
+                cell = cell, delta = delta, position = position, propagation = if failing { "?" } else { "" })
+    }
+
+    /// Append the `line` adding a multiple of the `instruction`'s cell to the cell `offset` cells away from it to the
+    /// `output`. Like in the replaced loop, the target cell is only accessed if the instruction's cell is not zero, so
+    /// the line is placed within a condition, which is opened unless the previous multiplication depends on the same
+    /// cell and the `guard` is still open, or the instruction's cell is known to be `nonzero`. Within the condition, a
+    /// growing or checked tape is made to contain the target cell.
+    fn multiply(&mut self, instruction: &Instruction, offset: isize, line: &str, guard: &mut Option<isize>,
+                nonzero: bool, output: &mut String)
+    {
+        if guard.is_none() && !nonzero {
+            let line: String = format!("if {cell} != 0 {{\n", cell = self.cell(instruction.offset));
+            output.push_str(&self.indent(&line));
+            self.indentation_level += 1;
+            *guard = Some(instruction.offset);
+        }
+
+        if self.options.is_reserving() {
+            let target: isize = instruction.offset.wrapping_add(offset);
+            let position: String = self.position(instruction, self.options.is_checked());
+            self.reserve(0, target.min(0), target.max(0), &position, output);
+        }
+
+        output.push_str(&self.indent(line));
+    }
+
+    /// Close the condition of the multiplications if the `guard` is open, appending the code to the `output`.
+    fn close_guard(&mut self, guard: &mut Option<isize>, output: &mut String) {
+        if guard.take().is_some() {
+            self.indentation_level -= 1;
+            output.push_str(&self.indent("}\n"));
+        }
+    }
+
+    /// Append the call moving the pointer by `offset` cells and making sure that the cells from `low` to `high` cells
+    /// away from the new current cell are on the tape to the `output`. The `position` argument is passed on to report
+    /// errors on checked tapes. The cells before the start of a tape wrapping around do not have to be on the tape.
+    fn reserve(&mut self, offset: isize, low: isize, high: isize, position: &str, output: &mut String) {
+        let low: isize = if self.options.is_wrapping() { 0 } else { low };
+        if offset == 0 && low == 0 && high == 0 {
+            return;
+        }
+
+        self.runtime.reserve = true;
+        let line: String = format!("ptr = reserve(&mut tape, ptr, {offset}, {low}, {high}{position}){propagation};\n",
+                                   offset = offset, low = low, high = high, position = position,
+                                   propagation = if self.options.is_checked() { "?" } else { "" });
+        output.push_str(&self.indent(&line));
+    }
+
+    /// Append the code moving the pointer by `offset` cells on a tape with a fixed size that is not checked, wrapping
+    /// around at its ends, to the `output`.
+    fn advance(&mut self, offset: isize, output: &mut String) {
+        self.runtime.wrap = true;
+        let line: String = format!("ptr = wrap(ptr, {offset});\n", offset = self.wrap(offset));
+        output.push_str(&self.indent(&line));
+    }
+
+    /// Make sure that a growing tape contains all cells accessed by the `instructions` up to the next pointer
+    /// movement, appending the code to the `output`. Checked tapes are handled for each instruction instead.
+    fn reserve_segment(&mut self, instructions: &[Instruction], output: &mut String) {
+        if let Some((low, high)) = reserved_range(instructions, &self.options) {
+            self.reserve(0, low, high, "", output);
+        }
+    }
+
+    /// Get the `Rust` expression for the cell `offset` cells away from the current cell. On a tape that is not checked,
+    /// the offset wraps around at the tape's ends, of which a tape growing to the right only has its start.
+    fn cell(&mut self, offset: isize) -> String {
+        match self.wrap(offset) {
+            0 => String::from("tape[ptr]"),
+            offset if self.options.is_reserving() && self.options.is_wrapping() && offset < 0 => {
+                self.runtime.wrap_left = true;
+                format!("(*wrap_left(&mut tape, ptr, {distance}))", distance = offset.unsigned_abs())
+            },
+            offset if self.options.is_reserving() && offset < 0 => {
+                format!("tape[ptr - {distance}]", distance = offset.unsigned_abs())
+            },
+            offset if self.options.is_reserving() => format!("tape[ptr + {distance}]", distance = offset),
+            offset => {
+                self.runtime.wrap = true;
+                format!("tape[wrap(ptr, {offset})]", offset = offset)
+            },
+        }
+    }
+
+    /// Reduce the `offset` to less than the size of a tape with a fixed size that is not checked, keeping its sign.
+    /// Other offsets are returned unchanged.
+    fn wrap(&self, offset: isize) -> isize {
+        if self.options.is_reserving() {
+            return offset;
+        }
+
+        let length: isize = isize::try_from(self.options.tape_size.max(1)).unwrap_or(isize::MAX);
+        offset % length
+    }
+
+    /// Get the `Rust` functions called by the generated code.
+    fn runtime(&self) -> String {
+        let mut runtime: String = self.failing_runtime();
+        if self.runtime.reserve {
+            runtime.push_str(&self.reserving_runtime());
+        }
+
+        if self.runtime.wrap {
+            let tape_size: String = self.options.tape_size.max(1).to_string();
+            runtime.push_str(&WRAPPING_RUNTIME.replace("{tape_size}", &tape_size));
+        }
+
+        if self.runtime.wrap_left {
+            runtime.push_str(WRAPPING_LEFT_RUNTIME);
+        }
+
+        if self.runtime.search_right {
+            runtime.push_str(SEARCHING_RIGHT);
+        }
+
+        if self.runtime.search_left {
+            runtime.push_str(SEARCHING_LEFT);
+        }
+
+        if self.runtime.read {
+            runtime.push_str(READING_RUNTIME);
+        }
+
+        if self.runtime.add {
+            match self.options.overflow {
+                Overflow::Error => runtime.push_str(CHECKED_RUNTIME),
+                Overflow::Wrap | Overflow::Saturate => runtime.push_str(SATURATING_RUNTIME),
+            }
+        }
+
+        runtime
+    }
+
+    /// Get the table of source positions and the `Rust` function creating errors at them, if the generated code reports
+    /// any errors.
+    fn failing_runtime(&self) -> String {
+        if self.positions.is_empty() {
+            return String::new();
+        }
+
+        let positions: String = self.positions.iter()
+            .map(|position| format!("    ({lineno}, {position}),\n",
+                                    lineno = position.lineno, position = position.position))
+            .collect();
+
+        FAILING_RUNTIME.replace("{count}", &self.positions.len().to_string())
+            .replace("{positions}", &positions)
+    }
+
+    /// Get the `Rust` function moving the pointer on the tape.
+    fn reserving_runtime(&self) -> String {
+        let checked: bool = self.options.is_checked();
+        let (left, right): (&str, &str) = match self.options.tape_growth {
+            TapeGrowth::Fixed => (CHECKING_LEFT, CHECKING_RIGHT),
+            TapeGrowth::Right if checked => (CHECKING_LEFT, GROWING_RIGHT),
+            TapeGrowth::Right => (WRAPPING_LEFT, GROWING_RIGHT),
+            TapeGrowth::Both => (GROWING_LEFT, GROWING_RIGHT),
+        };
+        let wrapping: bool = self.options.is_wrapping();
+        let mutable: bool = wrapping || self.options.tape_growth == TapeGrowth::Both;
+
+        RESERVING_RUNTIME.replace("{id_parameter}", if checked { ", id: usize" } else { "" })
+            .replace("{result}", if checked { "io::Result<usize>" } else { "usize" })
+            .replace("{low}", if wrapping { "_low" } else { "low" })
+            .replace("{mutable}", if mutable { "mut " } else { "" })
+            .replace("{left}", left)
+            .replace("{right}", right)
+            .replace("{position}", if checked { "Ok(position as usize)" } else { "position as usize" })
+    }
+
+    /// Get the additional argument passing the ID of the `instruction`'s source position to the runtime functions,
+    /// adding the position to the table. Empty unless the position might be `reported`.
+    fn position(&mut self, instruction: &Instruction, reported: bool) -> String {
+        if !reported {
+            return String::new();
+        }
+
+        self.positions.push(instruction.span.start);
+        format!(", {id}", id = self.positions.len() - 1)
+    }
+
+    /// Indent the given `line` respective to the current indentation level.
+    fn indent(&self, line: &str) -> String {
+        let mut indentation = String::new();
+        for _ in 0..self.indentation_level {
+            indentation.push_str("    ");
+        }
+
+        indentation.push_str(line);
+        indentation
+    }
+}
+
+/// Get the `Rust` type of cells of the given `cell_type`.
+fn rust_type(cell_type: CellType) -> &'static str {
+    match cell_type {
+        CellType::U8 => "u8",
+        CellType::U16 => "u16",
+        CellType::U32 => "u32",
+        CellType::I64 => "i64",
+    }
+}
+
+/// Get the absolute `value` of a change to a cell of the given `cell_type`, reduced to the cell type's range when
+/// wrapping around at its bounds.
+fn magnitude(value: i32, cell_type: CellType) -> u32 {
+    let magnitude: u32 = value.unsigned_abs();
+    match cell_type {
+        CellType::U8 => magnitude % 0x100,
+        CellType::U16 => magnitude % 0x1_0000,
+        CellType::U32 | CellType::I64 => magnitude,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use CellType;
+    use EofBehavior;
+    use Instruction;
+    use MetaData;
+    use Operation;
+    use Options;
+    use Overflow;
+    use Program;
+    use RustGenerator;
+    use Span;
+    use TapeGrowth;
+    use super::Runtime;
+
+    #[test]
+    fn test_new() {
+        let generator = RustGenerator::new();
+        assert_eq!(generator, RustGenerator {
+            options: Options::default(),
+            indentation_level: 1,
+            positions: Vec::new(),
+            runtime: Runtime::default(),
+        });
+    }
+
+    #[test]
+    fn test_generate() {
+        let template: &str = "{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(3), span),
+                Instruction::at_offset(Operation::Add(-300), 2, span),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::new(Operation::MulAdd { offset: 1, factor: 1 }, span),
+                    Instruction::new(Operation::MulAdd { offset: -2, factor: -1 }, span),
+                    Instruction::new(Operation::MulAdd { offset: 3, factor: 4 }, span),
+                    Instruction::new(Operation::Clear, span),
+                ]), span),
+                Instruction::at_offset(Operation::Read, -1, span),
+                Instruction::at_offset(Operation::Write, -1, span),
+                Instruction::new(Operation::Move(3), span),
+                Instruction::new(Operation::Move(-1), span),
+                Instruction::new(Operation::Scan { stride: 1 }, span),
+                Instruction::new(Operation::Scan { stride: -1 }, span),
+                Instruction::new(Operation::Scan { stride: 4 }, span),
+            ],
+        };
+        let expected: &str = "\
+fn wrap(ptr: usize, offset: isize) -> usize {
+    let length: usize = 30000;
+    let position: usize = ptr + if offset < 0 { (offset + length as isize) as usize } else { offset as usize };
+    if position < length { position } else { position - length }
+}
+
+fn search_right(tape: &[Cell], ptr: usize) -> usize {
+    match tape[ptr..].iter().position(|&cell| cell == 0) {
+        Some(distance) => ptr + distance,
+        None => tape[..ptr].iter().position(|&cell| cell == 0).unwrap_or_else(|| loop {}),
+    }
+}
+
+fn search_left(tape: &[Cell], ptr: usize) -> usize {
+    match tape[..=ptr].iter().rposition(|&cell| cell == 0) {
+        Some(position) => position,
+        None => ptr + 1 + tape[ptr + 1..].iter().rposition(|&cell| cell == 0).unwrap_or_else(|| loop {}),
+    }
+}
+
+fn read<R: Read>(input: &mut R) -> io::Result<Option<Cell>> {
+    let mut buffer = [0; 1];
+    match input.read_exact(&mut buffer) {
+        Ok(()) => Ok(Some(Cell::from(buffer[0]))),
+        Err(ref error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+{
+    tape[ptr] = tape[ptr].wrapping_add(3);
+    tape[wrap(ptr, 2)] = tape[wrap(ptr, 2)].wrapping_sub(44);
+    while tape[ptr] != 0 {
+        tape[wrap(ptr, 1)] = tape[wrap(ptr, 1)].wrapping_add(tape[ptr]);
+        tape[wrap(ptr, -2)] = tape[wrap(ptr, -2)].wrapping_sub(tape[ptr]);
+        tape[wrap(ptr, 3)] = tape[wrap(ptr, 3)].wrapping_add(tape[ptr].wrapping_mul(4));
+        tape[ptr] = 0;
+    }
+    tape[wrap(ptr, -1)] = read(input)?.unwrap_or(tape[wrap(ptr, -1)]);
+    output.write_all(&tape[wrap(ptr, -1)].to_le_bytes()[..1])?;
+    ptr = wrap(ptr, 3);
+    ptr = wrap(ptr, -1);
+    ptr = search_right(&tape, ptr);
+    ptr = search_left(&tape, ptr);
+    while tape[ptr] != 0 {
+        ptr = wrap(ptr, 4);
+    }
+    output.flush()
+}
+";
+
+        let mut generator = RustGenerator::new();
+        assert_eq!(generator.generate(template, &program), expected);
+    }
+
+    #[test]
+    fn test_generate_cell_types() {
+        let template: &str = "type Cell = {cell_type};\n{runtime}{\n    \
+                              let mut tape: Vec<Cell> = vec![0; {tape_size}];\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(-70_000), 1, span),
+                Instruction::new(Operation::MulAdd { offset: 1, factor: 300 }, span),
+            ],
+        };
+
+        let options = Options { cell_type: CellType::U16, tape_size: 16, ..Options::default() };
+        let generated: String = RustGenerator::with_options(options).generate(template, &program);
+        assert_eq!(generated, "type Cell = u16;
+fn wrap(ptr: usize, offset: isize) -> usize {
+    let length: usize = 16;
+    let position: usize = ptr + if offset < 0 { (offset + length as isize) as usize } else { offset as usize };
+    if position < length { position } else { position - length }
+}
+
+{
+    let mut tape: Vec<Cell> = vec![0; 16];
+    tape[wrap(ptr, 1)] = tape[wrap(ptr, 1)].wrapping_sub(4464);
+    if tape[ptr] != 0 {
+        tape[wrap(ptr, 1)] = tape[wrap(ptr, 1)].wrapping_add(tape[ptr].wrapping_mul(300));
+    }
+    output.flush()
+}
+");
+
+        let options = Options { cell_type: CellType::I64, ..Options::default() };
+        let generated: String = RustGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("type Cell = i64;\n"));
+        assert!(generated.contains("    tape[wrap(ptr, 1)] = tape[wrap(ptr, 1)].wrapping_sub(70000);\n"));
+    }
+
+    #[test]
+    fn test_generate_overflow() {
+        let template: &str = "{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 2, position: 3 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(5), span),
+                Instruction::new(Operation::MulAdd { offset: -1, factor: -1 }, span),
+            ],
+        };
+
+        let options = Options { overflow: Overflow::Saturate, ..Options::default() };
+        let generated: String = RustGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("fn wrap(ptr: usize, offset: isize) -> usize {\n"));
+        assert!(generated.contains("\n\nfn add(value: Cell, delta: i128) -> Cell {\n"));
+        assert!(generated.ends_with("{
+    tape[ptr] = add(tape[ptr], 5);
+    if tape[ptr] != 0 {
+        tape[wrap(ptr, -1)] = add(tape[wrap(ptr, -1)], i128::from(tape[ptr]) * -1);
+    }
+    output.flush()
+}
+"));
+
+        let options = Options { overflow: Overflow::Error, ..Options::default() };
+        let generated: String = RustGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("static POSITIONS: [(usize, usize); 2] = [\n    (2, 3),\n    (2, 3),\n];\n"));
+        assert!(generated.contains("fn add(value: Cell, delta: i128, id: usize) -> io::Result<Cell> {\n"));
+        assert!(generated.ends_with("{
+    tape[ptr] = add(tape[ptr], 5, 0)?;
+    if tape[ptr] != 0 {
+        tape[wrap(ptr, -1)] = add(tape[wrap(ptr, -1)], i128::from(tape[ptr]) * -1, 1)?;
+    }
+    output.flush()
+}
+"));
+    }
+
+    #[test]
+    fn test_generate_eof() {
+        let template: &str = "{\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![Instruction::at_offset(Operation::Read, 2, span)],
+        };
+
+        let expectations: [(EofBehavior, CellType, &str); 4] = [
+            (EofBehavior::Zero, CellType::U8, "unwrap_or(0)"),
+            (EofBehavior::MinusOne, CellType::U8, "unwrap_or(Cell::MAX)"),
+            (EofBehavior::MinusOne, CellType::I64, "unwrap_or(-1)"),
+            (EofBehavior::Unchanged, CellType::U8, "unwrap_or(tape[wrap(ptr, 2)])"),
+        ];
+        for &(eof, cell_type, expected) in expectations.iter() {
+            let options = Options { eof, cell_type, ..Options::default() };
+            let generated: String = RustGenerator::with_options(options).generate(template, &program);
+            let line: String = format!("    tape[wrap(ptr, 2)] = read(input)?.{expected};\n", expected = expected);
+            assert!(generated.contains(&line));
+        }
+    }
+
+    #[test]
+    fn test_generate_wrapping_tape() {
+        let template: &str = "{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), 5, span),
+                Instruction::at_offset(Operation::MulAdd { offset: -3, factor: 2 }, -1, span),
+                Instruction::new(Operation::Move(-6), span),
+                Instruction::new(Operation::Scan { stride: 9 }, span),
+            ],
+        };
+
+        // Offsets are reduced to less than the tape's size, and the runtime function wraps the remaining distance.
+        let options = Options { tape_size: 4, ..Options::default() };
+        let generated: String = RustGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("fn wrap(ptr: usize, offset: isize) -> usize {\n    let length: usize = 4;\n"));
+        assert!(generated.ends_with("{
+    tape[wrap(ptr, 1)] = tape[wrap(ptr, 1)].wrapping_add(1);
+    if tape[wrap(ptr, -1)] != 0 {
+        tape[ptr] = tape[ptr].wrapping_add(tape[wrap(ptr, -1)].wrapping_mul(2));
+    }
+    ptr = wrap(ptr, -2);
+    while tape[ptr] != 0 {
+        ptr = wrap(ptr, 1);
+    }
+    output.flush()
+}
+"));
+
+        // Checked tapes are accessed directly.
+        let options = Options { tape_size: 4, checked: true, ..Options::default() };
+        let generated: String = RustGenerator::with_options(options).generate(template, &program);
+        assert!(!generated.contains("wrap("));
+    }
+
+    #[test]
+    fn test_generate_growing_tape() {
+        let template: &str = "{runtime}{\n";
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), -1, span),
+                Instruction::new(Operation::Move(2), span),
+                Instruction::at_offset(Operation::Add(1), 3, span),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::at_offset(Operation::MulAdd { offset: -3, factor: 2 }, 1, span),
+                    Instruction::new(Operation::Move(1), span),
+                ]), span),
+                Instruction::new(Operation::Scan { stride: 1 }, span),
+                Instruction::at_offset(Operation::Write, -2, span),
+            ],
+        };
+
+        // Like the interpreter, the cells before the tape's start wrap around to its current end.
+        let options = Options { tape_growth: TapeGrowth::Right, ..Options::default() };
+        let generated: String = RustGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("\
+fn reserve(tape: &mut Vec<Cell>, ptr: usize, offset: isize, _low: isize, high: isize) -> usize {
+    let mut position = ptr as isize + offset;
+    if position < 0 {
+        position = position.rem_euclid(tape.len() as isize);
+    }
+    if position + high >= tape.len() as isize {
+"));
+        assert!(generated.contains("\
+fn wrap_left(tape: &mut [Cell], ptr: usize, distance: usize) -> &mut Cell {
+    let length = tape.len();
+    &mut tape[(ptr + length - distance % length) % length]
+}
+"));
+        assert!(generated.ends_with("{
+    (*wrap_left(&mut tape, ptr, 1)) = (*wrap_left(&mut tape, ptr, 1)).wrapping_add(1);
+    ptr = reserve(&mut tape, ptr, 2, 0, 3);
+    tape[ptr + 3] = tape[ptr + 3].wrapping_add(1);
+    while tape[ptr] != 0 {
+        ptr = reserve(&mut tape, ptr, 0, 0, 1);
+        if tape[ptr + 1] != 0 {
+            (*wrap_left(&mut tape, ptr, 2)) = (*wrap_left(&mut tape, ptr, 2))\
+                .wrapping_add(tape[ptr + 1].wrapping_mul(2));
+        }
+        ptr = reserve(&mut tape, ptr, 1, 0, 0);
+    }
+    while tape[ptr] != 0 {
+        ptr = reserve(&mut tape, ptr, 1, 0, 0);
+    }
+    output.write_all(&(*wrap_left(&mut tape, ptr, 2)).to_le_bytes()[..1])?;
+    output.flush()
+}
+"));
+
+        let options = Options { tape_growth: TapeGrowth::Both, checked: true, ..Options::default() };
+        let generated: String = RustGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("\
+fn reserve(tape: &mut Vec<Cell>, ptr: usize, offset: isize, low: isize, high: isize) -> usize {
+    let mut position = ptr as isize + offset;
+    if position + low < 0 {
+"));
+        assert!(generated.contains("    ptr = reserve(&mut tape, ptr, 2, 0, 3);\n"));
+    }
+
+    #[test]
+    fn test_generate_checked() {
+        let template: &str = "{runtime}{\n";
+        let position = |lineno: usize, position: usize| Span::at(MetaData { lineno, position });
+        let program = Program {
+            instructions: vec![
+                Instruction::at_offset(Operation::Add(1), -1, position(1, 1)),
+                Instruction::new(Operation::Move(2), position(1, 3)),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::at_offset(Operation::MulAdd { offset: -3, factor: 2 }, 1, position(2, 1)),
+                    Instruction::new(Operation::Scan { stride: 1 }, position(2, 5)),
+                ]), position(1, 4)),
+            ],
+        };
+
+        let options = Options { checked: true, ..Options::default() };
+        let generated: String = RustGenerator::with_options(options).generate(template, &program);
+        assert!(generated.starts_with("static POSITIONS: [(usize, usize); 5] = [
+    (1, 1),
+    (1, 3),
+    (2, 1),
+    (2, 1),
+    (2, 5),
+];
+"));
+        assert!(generated.contains("\
+fn reserve(tape: &mut Vec<Cell>, ptr: usize, offset: isize, low: isize, high: isize, id: usize) -> io::Result<usize> {
+    let position = ptr as isize + offset;
+    if position + low < 0 {
+        return Err(fail(\"pointer underflow\", id));
+    }
+    if position + high >= tape.len() as isize {
+        return Err(fail(\"pointer overflow\", id));
+    }
+    Ok(position as usize)
+}
+"));
+        assert!(generated.ends_with("{
+    ptr = reserve(&mut tape, ptr, 0, -1, 0, 0)?;
+    tape[ptr - 1] = tape[ptr - 1].wrapping_add(1);
+    ptr = reserve(&mut tape, ptr, 2, 0, 0, 1)?;
+    while tape[ptr] != 0 {
+        ptr = reserve(&mut tape, ptr, 0, 0, 1, 2)?;
+        if tape[ptr + 1] != 0 {
+            ptr = reserve(&mut tape, ptr, 0, -2, 0, 3)?;
+            tape[ptr - 2] = tape[ptr - 2].wrapping_add(tape[ptr + 1].wrapping_mul(2));
+        }
+        while tape[ptr] != 0 {
+            ptr = reserve(&mut tape, ptr, 1, 0, 0, 4)?;
+        }
+    }
+    output.flush()
+}
+"));
+
+        let options = Options { checked: true, tape_growth: TapeGrowth::Right, ..Options::default() };
+        let generated: String = RustGenerator::with_options(options).generate(template, &program);
+        assert!(generated.contains("        return Err(fail(\"pointer underflow\", id));\n"));
+        assert!(!generated.contains("        return Err(fail(\"pointer overflow\", id));\n"));
+    }
+
+    #[test]
+    fn test_indent_level_0() {
+        let mut generator = RustGenerator::new();
+        generator.indentation_level = 0;
+        assert_eq!(generator.indent("ptr += 1;"), String::from("ptr += 1;"));
+    }
+
+    #[test]
+    fn test_indent_level_2() {
+        let mut generator = RustGenerator::new();
+        generator.indentation_level = 2;
+        assert_eq!(generator.indent("ptr += 1;"), String::from("        ptr += 1;"));
+    }
+}
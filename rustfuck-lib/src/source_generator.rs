@@ -0,0 +1,282 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Generation of source code in the languages of the `Brainfuck` family, translating programs between them.
+
+use std::fmt;
+
+use Result;
+use Token;
+use language::Language;
+
+/// The layout of generated source code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Layout {
+
+    /// The maximum number of characters in a line, or `0` to write all commands into a single line. Commands longer
+    /// than the width are written into lines of their own.
+    pub line_width: usize,
+
+    /// The number of commands in a group, or `0` to not group commands. Groups are separated by an additional space.
+    pub group_size: usize,
+}
+
+impl Default for Layout {
+    fn default() -> Layout {
+        Layout {
+            line_width: 80,
+            group_size: 0,
+        }
+    }
+}
+
+/// A generator of source code in any language of the `Brainfuck` family.
+pub struct SourceGenerator<'a> {
+
+    /// The language in which the source code is written.
+    language: &'a dyn Language,
+
+    /// The layout of the source code.
+    layout: Layout,
+}
+
+impl<'a> SourceGenerator<'a> {
+    /// Initialize a generator of source code in the given `language` with the default layout.
+    pub fn new(language: &'a dyn Language) -> SourceGenerator<'a> {
+        SourceGenerator::with_layout(language, Layout::default())
+    }
+
+    /// Initialize a generator of source code in the given `language` with the given `layout`.
+    pub fn with_layout(language: &'a dyn Language, layout: Layout) -> SourceGenerator<'a> {
+        SourceGenerator {
+            language,
+            layout,
+        }
+    }
+
+    /// Generate the source code spelling the commands of the `tokens`. Tokenizing the source code in the generator's
+    /// language results in the same commands.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustfuck_lib::Lexer;
+    /// use rustfuck_lib::Layout;
+    /// use rustfuck_lib::SourceGenerator;
+    /// use rustfuck_lib::language::Brainfuck;
+    /// use rustfuck_lib::language::Dialect;
+    ///
+    /// let tokens = Brainfuck::new("++[->+<]").tokenize();
+    /// let layout = Layout { line_width: 20, group_size: 2 };
+    ///
+    /// let generated: String = SourceGenerator::with_layout(&Dialect::Brainfuck, layout).generate(&tokens);
+    /// assert_eq!(generated, "++ [- >+ <]\n");
+    ///
+    /// let generated: String = SourceGenerator::with_layout(&Dialect::Ook, layout).generate(&tokens);
+    /// assert_eq!(generated, "Ook. Ook. Ook. Ook.\nOok! Ook? Ook! Ook!\nOok. Ook? Ook. Ook.\nOok? Ook. Ook? Ook!\n");
+    /// ```
+    pub fn generate(&self, tokens: &[Token]) -> String {
+        let separator: &str = self.language.separator();
+        let mut source = String::new();
+        let mut line_length: usize = 0;
+
+        for (index, token) in tokens.iter().enumerate() {
+            let command: &str = self.language.spell(token);
+            let command_length: usize = command.chars().count();
+
+            if index > 0 {
+                let starts_group: bool = self.layout.group_size > 0 && index % self.layout.group_size == 0;
+                let gap: String = if starts_group {
+                    format!("{separator} ", separator = separator)
+                } else {
+                    String::from(separator)
+                };
+                let gap_length: usize = gap.chars().count();
+
+                if self.layout.line_width > 0 && line_length + gap_length + command_length > self.layout.line_width {
+                    source.push('\n');
+                    line_length = 0;
+                } else {
+                    source.push_str(&gap);
+                    line_length += gap_length;
+                }
+            }
+
+            source.push_str(command);
+            line_length += command_length;
+        }
+
+        if !source.is_empty() {
+            source.push('\n');
+        }
+
+        source
+    }
+
+    /// Translate the `source` code written in the language `from` into the generator's language.
+    ///
+    /// Fails with a syntax error if the `source` is not a valid program in the language `from`.
+    pub fn translate(&self, source: &str, from: &dyn Language) -> Result<String> {
+        let tokens: Vec<Token> = from.tokenize(source)?;
+        Ok(self.generate(&tokens))
+    }
+}
+
+impl<'a> fmt::Debug for SourceGenerator<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("SourceGenerator")
+            .field("language", &self.language.name())
+            .field("layout", &self.layout)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::mem;
+
+    use Error;
+    use Layout;
+    use MetaData;
+    use SourceGenerator;
+    use SyntaxError;
+    use Token;
+    use language::Dialect;
+    use language::Language;
+    use language::SubstitutionLanguage;
+
+    /// The layouts with which generated source code is tested.
+    const LAYOUTS: [Layout; 5] = [
+        Layout { line_width: 0, group_size: 0 },
+        Layout { line_width: 80, group_size: 0 },
+        Layout { line_width: 16, group_size: 5 },
+        Layout { line_width: 1, group_size: 1 },
+        Layout { line_width: 0, group_size: 8 },
+    ];
+
+    /// Get the languages in which generated source code is tested.
+    fn languages() -> Vec<Box<dyn Language>> {
+        let blub: [&str; 8] = [
+            "Blub. Blub.", "Blub! Blub!", "Blub. Blub?", "Blub? Blub.", "Blub. Blub!", "Blub! Blub.", "Blub! Blub?",
+            "Blub? Blub!",
+        ];
+        let prefixes: [&str; 8] = ["a", "aa", "aaa", "b", "ab", "ba", "bab", "\u{1F600}"];
+
+        vec![
+            Box::new(Dialect::Brainfuck),
+            Box::new(Dialect::Ook),
+            Box::new(SubstitutionLanguage::from_mapping(include_str!("../../examples/alphuck.toml")).unwrap()),
+            Box::new(SubstitutionLanguage::new("blub", &["blub"], blub).unwrap()),
+            Box::new(SubstitutionLanguage::new("prefixes", &[], prefixes).unwrap()),
+        ]
+    }
+
+    /// Get the kinds of the `tokens`, ignoring their lexemes and positions.
+    fn kinds(tokens: &[Token]) -> Vec<mem::Discriminant<Token>> {
+        tokens.iter().map(mem::discriminant).collect()
+    }
+
+    /// Replace the positions of the `tokens` by the start of the source.
+    fn without_positions(tokens: Vec<Token>) -> Vec<Token> {
+        let start = MetaData { lineno: 1, position: 1 };
+        tokens.into_iter()
+            .map(|token| match token {
+                Token::Add(lexeme, _) => Token::Add(lexeme, start),
+                Token::Sub(lexeme, _) => Token::Sub(lexeme, start),
+                Token::Right(lexeme, _) => Token::Right(lexeme, start),
+                Token::Left(lexeme, _) => Token::Left(lexeme, start),
+                Token::Read(lexeme, _) => Token::Read(lexeme, start),
+                Token::Write(lexeme, _) => Token::Write(lexeme, start),
+                Token::BeginLoop(lexeme, _) => Token::BeginLoop(lexeme, start),
+                Token::EndLoop(lexeme, _) => Token::EndLoop(lexeme, start),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_new() {
+        let generator = SourceGenerator::new(&Dialect::Ook);
+        assert_eq!(generator.layout, Layout { line_width: 80, group_size: 0 });
+        assert_eq!(format!("{:?}", generator),
+                   "SourceGenerator { language: \"ook\", layout: Layout { line_width: 80, group_size: 0 } }");
+    }
+
+    #[test]
+    fn test_generate_empty() {
+        assert_eq!(SourceGenerator::new(&Dialect::Brainfuck).generate(&[]), "");
+    }
+
+    #[test]
+    fn test_generate_layout() {
+        let tokens: Vec<Token> = Dialect::Brainfuck.tokenize("+++++[->+++<]>.").unwrap();
+
+        let generated: String = SourceGenerator::with_layout(&Dialect::Brainfuck, LAYOUTS[0]).generate(&tokens);
+        assert_eq!(generated, "+++++[->+++<]>.\n");
+
+        let generated: String = SourceGenerator::with_layout(&Dialect::Brainfuck, LAYOUTS[2]).generate(&tokens);
+        assert_eq!(generated, "+++++ [->++ +<]>\n.\n");
+
+        let layout = Layout { line_width: 6, group_size: 5 };
+        let generated: String = SourceGenerator::with_layout(&Dialect::Brainfuck, layout).generate(&tokens);
+        assert_eq!(generated, "+++++\n[->++\n+<]>.\n");
+
+        let generated: String = SourceGenerator::with_layout(&Dialect::Ook, LAYOUTS[2]).generate(&tokens[..6]);
+        assert_eq!(generated, "Ook. Ook.\nOok. Ook.\nOok. Ook.\nOok. Ook.\nOok. Ook.\nOok! Ook?\n");
+
+        let layout = Layout { line_width: 40, group_size: 2 };
+        let generated: String = SourceGenerator::with_layout(&Dialect::Ook, layout).generate(&tokens[..6]);
+        assert_eq!(generated, "Ook. Ook. Ook. Ook.  Ook. Ook. Ook. Ook.\nOok. Ook. Ook! Ook?\n");
+    }
+
+    #[test]
+    fn test_generate_round_trip() {
+        let tokens: Vec<Token> = Dialect::Brainfuck.tokenize(include_str!("../../examples/hello.bf")).unwrap();
+
+        for language in languages() {
+            for &layout in &LAYOUTS {
+                let generator = SourceGenerator::with_layout(&*language, layout);
+                let generated: String = generator.generate(&tokens);
+                let translated: Vec<Token> = language.tokenize(&generated).unwrap();
+                assert_eq!(kinds(&translated), kinds(&tokens), "{} with {:?}", language.name(), layout);
+
+                // Regenerating the translated tokens results in the same tokens, including their lexemes.
+                let regenerated: Vec<Token> = language.tokenize(&generator.generate(&translated)).unwrap();
+                assert_eq!(without_positions(regenerated), without_positions(translated),
+                           "{} with {:?}", language.name(), layout);
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_line_width() {
+        let tokens: Vec<Token> = Dialect::Brainfuck.tokenize(include_str!("../../examples/mandelbrot.bf")).unwrap();
+
+        for language in languages() {
+            let layout = Layout { line_width: 24, group_size: 0 };
+            let generated: String = SourceGenerator::with_layout(&*language, layout).generate(&tokens);
+            assert!(generated.lines().all(|line| line.chars().count() <= 24), "{}", language.name());
+        }
+    }
+
+    #[test]
+    fn test_translate() {
+        let generator = SourceGenerator::with_layout(&Dialect::Brainfuck, LAYOUTS[0]);
+        let translated: String = generator.translate(include_str!("../../examples/hello.ook"), &Dialect::Ook).unwrap();
+        let expected: String = include_str!("../../examples/hello.bf").chars()
+            .filter(|character| "+-<>,.[]".contains(*character))
+            .collect();
+        assert_eq!(translated, expected + "\n");
+
+        let result = generator.translate("Ook. Ook. Ook.", &Dialect::Ook);
+        match result {
+            Err(Error::Syntax(error)) => {
+                assert_eq!(error, SyntaxError::IncompleteCommand(MetaData { lineno: 1, position: 11 }))
+            },
+            _ => panic!("Unexpected result: {:?}", result),
+        }
+    }
+}
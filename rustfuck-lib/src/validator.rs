@@ -0,0 +1,96 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Validation of token lists before they are executed or translated.
+
+use Result;
+use SyntaxError;
+use lexer::MetaData;
+use lexer::Token;
+
+/// Check that the given `tokens` form a syntactically valid program.
+///
+/// Every `BeginLoop` must be followed by a matching `EndLoop`, and every `EndLoop` must be preceded by a matching
+/// `BeginLoop`. If this is not the case, the error points at the offending token.
+///
+/// # Examples
+///
+/// ```
+/// use rustfuck_lib::Error;
+/// use rustfuck_lib::Lexer;
+/// use rustfuck_lib::MetaData;
+/// use rustfuck_lib::SyntaxError;
+/// use rustfuck_lib::language::Brainfuck;
+/// use rustfuck_lib::validate;
+///
+/// let tokens = Brainfuck::new("+[[-]").tokenize();
+/// match validate(&tokens) {
+///     Err(Error::Syntax(SyntaxError::UnclosedLoop(position))) => {
+///         assert_eq!(position, MetaData { lineno: 1, position: 2 });
+///     },
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn validate(tokens: &[Token]) -> Result<()> {
+    let mut open_loops: Vec<MetaData> = Vec::new();
+
+    for token in tokens {
+        match *token {
+            Token::BeginLoop(_, position) => open_loops.push(position),
+            Token::EndLoop(_, position) => {
+                let _ = open_loops.pop().ok_or(SyntaxError::UnopenedLoop(position))?;
+            },
+            _ => {},
+        }
+    }
+
+    // Report the outermost loop that has not been closed.
+    match open_loops.first() {
+        Some(position) => Err(SyntaxError::UnclosedLoop(*position).into()),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Error;
+    use Lexer;
+    use MetaData;
+    use SyntaxError;
+    use language::Brainfuck;
+
+    use super::validate;
+
+    #[test]
+    fn test_validate_empty() {
+        assert!(validate(&[]).is_ok());
+    }
+
+    #[test]
+    fn test_validate_balanced() {
+        let tokens = Brainfuck::new("+[>[-]<[>+<-]]").tokenize();
+        assert!(validate(&tokens).is_ok());
+    }
+
+    #[test]
+    fn test_validate_unclosed_loop() {
+        let tokens = Brainfuck::new("+[>[-]\n<[>+<-]").tokenize();
+        assert!(match validate(&tokens) {
+            Err(Error::Syntax(error)) => error == SyntaxError::UnclosedLoop(MetaData { lineno: 1, position: 2 }),
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn test_validate_unopened_loop() {
+        let tokens = Brainfuck::new("+[-]\n-]]").tokenize();
+        assert!(match validate(&tokens) {
+            Err(Error::Syntax(error)) => error == SyntaxError::UnopenedLoop(MetaData { lineno: 2, position: 2 }),
+            _ => false,
+        });
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementation of a just-in-time compiler executing bytecode as native machine code.
+
+use std::io::Read;
+use std::io::Write;
+
+use Options;
+use Result;
+use super::Bytecode;
+use super::VirtualMachine;
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+use super::native;
+
+/// A just-in-time compiler, translating bytecode to machine code when executing it.
+///
+/// Machine code is only generated on `x86-64` Linux. On other platforms, the bytecode is executed by the virtual
+/// machine instead. Either way, the behavior is the same as the virtual machine's.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Jit {
+
+    /// The virtual machine providing the tape, and executing all operations the machine code does not handle itself.
+    machine: VirtualMachine,
+}
+
+impl Jit {
+    /// Initialize a new just-in-time compiler with an empty tape and the default options.
+    pub fn new() -> Jit {
+        Jit::with_options(Options::default())
+    }
+
+    /// Initialize a new just-in-time compiler with an empty tape and the given `options`.
+    pub fn with_options(options: Options) -> Jit {
+        Jit {
+            machine: VirtualMachine::with_options(options),
+        }
+    }
+
+    /// Determine if bytecode is translated to machine code on this platform.
+    pub fn is_native() -> bool {
+        cfg!(all(target_arch = "x86_64", target_os = "linux"))
+    }
+
+    /// Execute the given `bytecode`, reading from `input` and writing to `output`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustfuck_lib::Bytecode;
+    /// use rustfuck_lib::Jit;
+    /// use rustfuck_lib::Lexer;
+    /// use rustfuck_lib::Program;
+    /// use rustfuck_lib::language::Brainfuck;
+    ///
+    /// let tokens = Brainfuck::new("++++++++[>++++++++<-]>+.").tokenize();
+    /// let bytecode = Bytecode::new(&Program::new(&tokens).unwrap());
+    /// let mut output: Vec<u8> = Vec::new();
+    ///
+    /// let mut jit = Jit::new();
+    /// jit.run(&bytecode, &mut "".as_bytes(), &mut output).unwrap();
+    /// assert_eq!(output, b"A");
+    /// ```
+    pub fn run<R: Read, W: Write>(&mut self, bytecode: &Bytecode, input: &mut R, output: &mut W) -> Result<()> {
+        self.execute(bytecode, input, output)
+    }
+
+    /// Execute the `bytecode` as machine code.
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn execute<R: Read, W: Write>(&mut self, bytecode: &Bytecode, input: &mut R, output: &mut W) -> Result<()> {
+        native::execute(&mut self.machine, bytecode, input, output)
+    }
+
+    /// Execute the `bytecode` on the virtual machine, as there is no machine code for this platform.
+    #[cfg(not(all(target_arch = "x86_64", target_os = "linux")))]
+    fn execute<R: Read, W: Write>(&mut self, bytecode: &Bytecode, input: &mut R, output: &mut W) -> Result<()> {
+        self.machine.run(bytecode, input, output)
+    }
+}
+
+impl Default for Jit {
+    fn default() -> Jit {
+        Jit::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::fs;
+    use std::io;
+    use std::io::Write;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    use Bytecode;
+    use CellType;
+    use EofBehavior;
+    use Error;
+    use Interpreter;
+    use Jit;
+    use Lexer;
+    use MetaData;
+    use Optimizer;
+    use Options;
+    use Overflow;
+    use Program;
+    use RuntimeError;
+    use TapeGrowth;
+    use VirtualMachine;
+    use language::Brainfuck;
+
+    /// A writer failing on every write.
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buffer: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("closed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Run the `Brainfuck` `source` with the given `input` under the `options` with a new just-in-time compiler and on
+    /// a new virtual machine, and return the output or the runtime error of the just-in-time compiler after asserting
+    /// that the virtual machine's results are the same.
+    fn run(source: &str, input: &str, options: Options) -> Result<Vec<u8>, RuntimeError> {
+        let tokens = Brainfuck::new(source).tokenize();
+        let program = Optimizer::with_options(options).optimize(Program::new(&tokens).unwrap());
+        let bytecode = Bytecode::new(&program);
+
+        let mut output: Vec<u8> = Vec::new();
+        let mut jit = Jit::with_options(options);
+        let result = jit.run(&bytecode, &mut input.as_bytes(), &mut output);
+
+        let mut expected_output: Vec<u8> = Vec::new();
+        let mut machine = VirtualMachine::with_options(options);
+        let expected_result = machine.run(&bytecode, &mut input.as_bytes(), &mut expected_output);
+        assert_eq!(output, expected_output);
+        assert_eq!(jit.machine, machine);
+
+        match (result, expected_result) {
+            (Ok(()), Ok(())) => Ok(output),
+            (Err(Error::Runtime(error)), Err(Error::Runtime(expected))) if error == expected => Err(error),
+            (result, expected) => panic!("Unexpected result: {:?} instead of {:?}", result, expected),
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        assert_eq!(Jit::new(), Jit::with_options(Options::default()));
+        assert_eq!(Jit::new().machine, VirtualMachine::new());
+    }
+
+    #[test]
+    fn test_is_native() {
+        assert_eq!(Jit::is_native(), cfg!(all(target_arch = "x86_64", target_os = "linux")));
+    }
+
+    #[test]
+    fn test_run_hello_world() {
+        let source: &str = include_str!("../../../examples/hello.bf");
+        assert_eq!(run(source, "", Options::default()), Ok(b"Hello World!\n".to_vec()));
+
+        let options = Options { optimize: false, ..Options::default() };
+        assert_eq!(run(source, "", options), Ok(b"Hello World!\n".to_vec()));
+    }
+
+    #[test]
+    fn test_run_read() {
+        let options = Options::default();
+        assert_eq!(run(",+.,+.,+.", "HAL", options), Ok(b"IBM".to_vec()));
+
+        for &eof in &[EofBehavior::Zero, EofBehavior::MinusOne, EofBehavior::Unchanged] {
+            for &cell_type in &[CellType::U8, CellType::U16, CellType::I64] {
+                let options = Options { eof, cell_type, ..Options::default() };
+                let _ = run("+++,.>+++,.", "a", options);
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let tokens = Brainfuck::new("+.").tokenize();
+        let bytecode = Bytecode::new(&Program::new(&tokens).unwrap());
+        let result = Jit::new().run(&bytecode, &mut "".as_bytes(), &mut FailingWriter);
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn test_run_overflow() {
+        let sources: [&str; 2] = [
+            "--.+.>++++++++++++++++[->++++++++++++++++<]>+.--.>+[->[-<+>]<]",
+            "+++++++[>+++++++++++<-]>[>++++++++++<-]>[>+++++++++<-]>[<<<+>>>-]<<<.[-]+++[>--------<-]>.",
+        ];
+        for &overflow in &[Overflow::Wrap, Overflow::Saturate, Overflow::Error] {
+            for &cell_type in &[CellType::U8, CellType::U16, CellType::U32, CellType::I64] {
+                for source in &sources {
+                    let options = Options { cell_type, overflow, ..Options::default() };
+                    let _ = run(source, "", options);
+                }
+            }
+        }
+
+        let options = Options { overflow: Overflow::Saturate, ..Options::default() };
+        assert_eq!(run("--.+.>++++++++++++++++[->++++++++++++++++<]>+.--.", "", options), Ok(vec![0, 1, 255, 253]));
+
+        let options = Options { cell_type: CellType::U16, overflow: Overflow::Error, ..Options::default() };
+        let position = MetaData { lineno: 2, position: 2 };
+        assert_eq!(run("+++.\n>---", "", options), Err(RuntimeError::CellOverflow(position)));
+    }
+
+    #[test]
+    fn test_run_tape() {
+        let sources: [&str; 6] = [">>>+<<<.", "+\n<+.", ">>>>+.", "+>+>+[>]+.", "+[-<<+>>]<<.", ">>+[<]<+."];
+        for &tape_growth in &[TapeGrowth::Fixed, TapeGrowth::Right, TapeGrowth::Both] {
+            for &checked in &[false, true] {
+                for source in &sources {
+                    let options = Options { checked, tape_size: 4, tape_growth, ..Options::default() };
+                    let _ = run(source, "", options);
+                }
+            }
+        }
+
+        let options = Options { checked: true, tape_size: 4, ..Options::default() };
+        let position = MetaData { lineno: 1, position: 8 };
+        assert_eq!(run("+>+>+>+[>]", "", options), Err(RuntimeError::PointerOverflow(position)));
+    }
+
+    #[test]
+    fn test_run_scan() {
+        let source: &str = ">+>+>>+>>+<<<<<<[>]<.>>+[>>]<.<[<]>.<<<[<<]>[>>>>]<<.";
+        assert_eq!(run(source, "", Options::default()), Ok(vec![0, 1, 2, 0]));
+        assert_eq!(run("+>+[<]+>.", "", Options::default()), Ok(vec![1]));
+
+        let options = Options { tape_size: 4, tape_growth: TapeGrowth::Both, ..Options::default() };
+        let _ = run("+>+>+>+[>>]+<<<<<[<]+.", "", options);
+    }
+
+    #[test]
+    fn test_run_examples() {
+        let directory: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples");
+        for entry in fs::read_dir(directory).unwrap() {
+            let path: PathBuf = entry.unwrap().path();
+            if path.extension().is_none_or(|extension| extension != "bf") {
+                continue;
+            }
+
+            let source: String = fs::read_to_string(&path).unwrap();
+            let program = Optimizer::new().optimize(Program::new(&Brainfuck::new(&source).tokenize()).unwrap());
+
+            let mut output: Vec<u8> = Vec::new();
+            Jit::new().run(&Bytecode::new(&program), &mut "".as_bytes(), &mut output).unwrap();
+
+            let mut expected_output: Vec<u8> = Vec::new();
+            Interpreter::new().run(&program, &mut "".as_bytes(), &mut expected_output).unwrap();
+            assert_eq!(output, expected_output, "{path:?}", path = path);
+        }
+    }
+}
@@ -0,0 +1,621 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementations of a bytecode format, a virtual machine executing it, and a just-in-time compiler.
+//!
+//! Unlike the tree of instructions in the intermediate representation, the bytecode is a flat list of operations, in
+//! which loops are replaced by jumps to precomputed targets. The virtual machine executes it in a single dispatch loop
+//! without any recursion.
+
+mod jit;
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+mod native;
+
+pub use self::jit::Jit;
+
+use std::convert::TryFrom;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::iter;
+use std::io::Write;
+
+use CellType;
+use EofBehavior;
+use Error;
+use Options;
+use Overflow;
+use Result;
+use RuntimeError;
+use TapeGrowth;
+use ir::Instruction;
+use ir::Operation;
+use ir::Program;
+use ir::Step;
+use ir::walk;
+use lexer::MetaData;
+
+/// A program compiled to bytecode for the virtual machine.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Bytecode {
+
+    /// The operations of the program, in the order in which they are executed unless jumping.
+    code: Vec<Opcode>,
+
+    /// The source position of each operation, reported by runtime errors.
+    positions: Vec<MetaData>,
+}
+
+/// A single operation of the bytecode, together with all its operands.
+///
+/// Offsets are given relative to the current cell, jump targets as indices into the bytecode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Opcode {
+
+    /// Add `delta` to the cell `offset` cells away from the current cell.
+    Add {
+        /// The distance from the current cell to the changed cell.
+        offset: isize,
+
+        /// The value to add. Negative values decrement the cell.
+        delta: i64,
+    },
+
+    /// Move the pointer by the given number of cells.
+    Move(isize),
+
+    /// Read a single character into the cell the given number of cells away from the current cell.
+    Read(isize),
+
+    /// Print the content of the cell the given number of cells away from the current cell.
+    Write(isize),
+
+    /// Set the cell the given number of cells away from the current cell to zero.
+    Clear(isize),
+
+    /// Add the value of the `source` cell, multiplied by `factor`, to the `target` cell, unless the source cell is
+    /// zero.
+    MulAdd {
+        /// The distance from the current cell to the cell providing the value.
+        source: isize,
+
+        /// The distance from the current cell to the changed cell.
+        target: isize,
+
+        /// The factor by which the value of the source cell is multiplied.
+        factor: i64,
+    },
+
+    /// Move the pointer by the given number of cells until the current cell is zero.
+    Scan(isize),
+
+    /// Continue at the given index if the current cell is zero.
+    JumpIfZero(usize),
+
+    /// Continue at the given index if the current cell is not zero.
+    JumpUnlessZero(usize),
+}
+
+/// A virtual machine for executing programs compiled to bytecode.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VirtualMachine {
+
+    /// The options controlling the execution.
+    options: Options,
+
+    /// The cells of the memory tape. Each cell's value is kept within the range of the cell type.
+    tape: Vec<i64>,
+
+    /// The index of the current cell.
+    pointer: usize,
+
+    /// The bits of a cell's value if cells wrap around on overflows. The bits of any sum or product of such values
+    /// give the wrapped result.
+    mask: Option<i64>,
+}
+
+impl Bytecode {
+    /// Compile the given `program` to bytecode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustfuck_lib::Bytecode;
+    /// use rustfuck_lib::Lexer;
+    /// use rustfuck_lib::Opcode;
+    /// use rustfuck_lib::Program;
+    /// use rustfuck_lib::language::Brainfuck;
+    ///
+    /// let tokens = Brainfuck::new("+[>.<-]").tokenize();
+    /// let bytecode = Bytecode::new(&Program::new(&tokens).unwrap());
+    ///
+    /// assert_eq!(bytecode.code(), [
+    ///     Opcode::Add { offset: 0, delta: 1 },
+    ///     Opcode::JumpIfZero(7),
+    ///     Opcode::Move(1),
+    ///     Opcode::Write(0),
+    ///     Opcode::Move(-1),
+    ///     Opcode::Add { offset: 0, delta: -1 },
+    ///     Opcode::JumpUnlessZero(2),
+    /// ]);
+    /// ```
+    pub fn new(program: &Program) -> Bytecode {
+        let mut bytecode = Bytecode::default();
+        bytecode.compile(&program.instructions);
+        bytecode
+    }
+
+    /// Get the operations of the bytecode.
+    pub fn code(&self) -> &[Opcode] {
+        &self.code
+    }
+
+    /// Append the operations for the given list of `instructions` to the bytecode.
+    fn compile(&mut self, instructions: &[Instruction]) {
+        // The indices of the jumps starting the loops currently compiled, innermost last.
+        let mut starts: Vec<usize> = Vec::new();
+        for step in walk(instructions) {
+            let instruction: &Instruction = match step {
+                Step::Instruction(instruction, _) => instruction,
+                Step::LoopEnd(instruction, _) => {
+                    // Repeat the body unless the current cell is zero. The jump at the start of the loop skips it.
+                    let start: usize = starts.pop().expect("The loop has been started.");
+                    let _ = self.push(Opcode::JumpUnlessZero(start + 1), instruction.span.end);
+                    self.code[start] = Opcode::JumpIfZero(self.code.len());
+                    continue;
+                },
+            };
+
+            let offset: isize = instruction.offset;
+            let opcode: Opcode = match instruction.operation {
+                Operation::Add(delta) => Opcode::Add { offset, delta: i64::from(delta) },
+                Operation::Move(distance) => Opcode::Move(distance),
+                Operation::Read => Opcode::Read(offset),
+                Operation::Write => Opcode::Write(offset),
+                Operation::Clear => Opcode::Clear(offset),
+                Operation::MulAdd { offset: target, factor } => Opcode::MulAdd {
+                    source: offset,
+                    target: offset.wrapping_add(target),
+                    factor: i64::from(factor),
+                },
+                Operation::Scan { stride } => Opcode::Scan(stride),
+                Operation::Loop(_) => {
+                    // Skip the loop if the current cell is zero. The target of the jump is only known after compiling
+                    // the body.
+                    starts.push(self.push(Opcode::JumpIfZero(0), instruction.span.start));
+                    continue;
+                },
+            };
+
+            let _ = self.push(opcode, instruction.span.start);
+        }
+    }
+
+    /// Append the `opcode` from the source `position` to the bytecode, returning its index.
+    fn push(&mut self, opcode: Opcode, position: MetaData) -> usize {
+        self.code.push(opcode);
+        self.positions.push(position);
+        self.code.len() - 1
+    }
+}
+
+impl VirtualMachine {
+    /// Initialize a new virtual machine with an empty tape and the default options.
+    pub fn new() -> VirtualMachine {
+        VirtualMachine::with_options(Options::default())
+    }
+
+    /// Initialize a new virtual machine with an empty tape and the given `options`.
+    pub fn with_options(options: Options) -> VirtualMachine {
+        let cell_type: CellType = options.cell_type;
+        let mask: Option<i64> = match options.overflow {
+            Overflow::Wrap if cell_type.is_signed() => Some(-1),
+            Overflow::Wrap => Some(cell_type.max()),
+            Overflow::Saturate | Overflow::Error => None,
+        };
+
+        VirtualMachine {
+            options,
+            tape: vec![0; options.tape_size.max(1)],
+            pointer: 0,
+            mask,
+        }
+    }
+
+    /// Execute the given `bytecode`, reading from `input` and writing to `output`.
+    ///
+    /// The behavior is the same as the interpreter's for the program from which the bytecode was compiled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustfuck_lib::Bytecode;
+    /// use rustfuck_lib::Lexer;
+    /// use rustfuck_lib::Program;
+    /// use rustfuck_lib::VirtualMachine;
+    /// use rustfuck_lib::language::Brainfuck;
+    ///
+    /// let tokens = Brainfuck::new("++++++++[>++++++++<-]>+.").tokenize();
+    /// let bytecode = Bytecode::new(&Program::new(&tokens).unwrap());
+    /// let mut output: Vec<u8> = Vec::new();
+    ///
+    /// let mut machine = VirtualMachine::new();
+    /// machine.run(&bytecode, &mut "".as_bytes(), &mut output).unwrap();
+    /// assert_eq!(output, b"A");
+    /// ```
+    pub fn run<R: Read, W: Write>(&mut self, bytecode: &Bytecode, input: &mut R, output: &mut W) -> Result<()> {
+        let mut counter: usize = 0;
+        while counter < bytecode.code.len() {
+            counter = self.step(bytecode, counter, input, output)?;
+        }
+
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Execute the operation at index `counter` in the `bytecode`, reading from `input` and writing to `output`, and
+    /// return the index of the operation to execute next.
+    #[inline(always)]
+    fn step<R: Read, W: Write>(&mut self, bytecode: &Bytecode, counter: usize, input: &mut R, output: &mut W)
+                               -> Result<usize>
+    {
+        match bytecode.code[counter] {
+            Opcode::Add { offset, delta } => {
+                let cell: usize = self.index(offset, bytecode, counter)?;
+                self.tape[cell] = self.add(self.tape[cell], delta, 1, bytecode, counter)?;
+            },
+            Opcode::Move(distance) => {
+                self.pointer = self.index(distance, bytecode, counter)?;
+            },
+            Opcode::Read(offset) => {
+                let cell: usize = self.index(offset, bytecode, counter)?;
+                if let Some(value) = read(self.options, input)? {
+                    self.tape[cell] = value;
+                }
+            },
+            Opcode::Write(offset) => {
+                // Print the lowest byte of the cell.
+                let cell: usize = self.index(offset, bytecode, counter)?;
+                output.write_all(&self.tape[cell].to_le_bytes()[..1])?;
+            },
+            Opcode::Clear(offset) => {
+                let cell: usize = self.index(offset, bytecode, counter)?;
+                self.tape[cell] = 0;
+            },
+            Opcode::MulAdd { source, target, factor } => {
+                // Like the replaced loop, do not access the target cell if there is nothing to add. Growing the tape
+                // to reach the target may shift the indices of all cells.
+                let cell: usize = self.index(source, bytecode, counter)?;
+                if self.tape[cell] != 0 {
+                    let target: usize = self.index(target, bytecode, counter)?;
+                    let cell: usize = self.index(source, bytecode, counter)?;
+                    self.tape[target] = self.add(self.tape[target], self.tape[cell], factor, bytecode, counter)?;
+                }
+            },
+            Opcode::Scan(stride) => {
+                self.scan(stride, bytecode.positions[counter])?;
+            },
+            Opcode::JumpIfZero(target) if self.tape[self.pointer] == 0 => return Ok(target),
+            Opcode::JumpUnlessZero(target) if self.tape[self.pointer] != 0 => return Ok(target),
+            Opcode::JumpIfZero(_) | Opcode::JumpUnlessZero(_) => {},
+        }
+
+        Ok(counter + 1)
+    }
+
+    /// Add the product of `value` and `factor` to the `cell` value, handling overflows according to the options. The
+    /// position of the operation at index `counter` in the `bytecode` is reported if the overflow is an error.
+    #[inline]
+    fn add(&self, cell: i64, value: i64, factor: i64, bytecode: &Bytecode, counter: usize) -> Result<i64> {
+        if let Some(mask) = self.mask {
+            return Ok(cell.wrapping_add(value.wrapping_mul(factor)) & mask);
+        }
+
+        let min: i128 = i128::from(self.options.cell_type.min());
+        let max: i128 = i128::from(self.options.cell_type.max());
+        let sum: i128 = i128::from(cell) + i128::from(value) * i128::from(factor);
+        let result: i128 = if sum < min || sum > max {
+            match self.options.overflow {
+                Overflow::Wrap => (sum - min).rem_euclid(max - min + 1) + min,
+                Overflow::Saturate => sum.clamp(min, max),
+                Overflow::Error => return Err(RuntimeError::CellOverflow(bytecode.positions[counter]).into()),
+            }
+        } else {
+            sum
+        };
+
+        Ok(i64::try_from(result).expect("The result is within the range of the cell type."))
+    }
+
+    /// Get the index of the cell `offset` cells away from the current cell, on behalf of the operation at index
+    /// `counter` in the `bytecode`.
+    #[inline]
+    fn index(&mut self, offset: isize, bytecode: &Bytecode, counter: usize) -> Result<usize> {
+        match self.pointer.checked_add_signed(offset) {
+            Some(index) if index < self.tape.len() => Ok(index),
+            _ => self.reach(offset, bytecode.positions[counter]),
+        }
+    }
+
+    /// Get the index of the cell `offset` cells away from the current cell, which lies beyond an end of the tape.
+    ///
+    /// Like in the interpreter, the tape grows in that direction if the options allow it, at least doubling its size.
+    /// Otherwise, the index wraps around to the other end, or, in checked mode, the operation at `position` fails with
+    /// a runtime error. Growing the tape to the left shifts the indices of all cells, including the pointer.
+    #[cold]
+    fn reach(&mut self, offset: isize, position: MetaData) -> Result<usize> {
+        let length: usize = self.tape.len();
+        let distance: usize = offset.unsigned_abs();
+        let growth: TapeGrowth = self.options.tape_growth;
+
+        if offset >= 0 && growth != TapeGrowth::Fixed {
+            let index: usize = self.pointer + distance;
+            self.tape.resize((index + 1).max(length * 2), 0);
+            return Ok(index);
+        }
+
+        if offset < 0 && growth == TapeGrowth::Both {
+            let extension: usize = (distance - self.pointer).max(length);
+            let _ = self.tape.splice(0..0, iter::repeat_n(0, extension));
+            self.pointer += extension;
+            return Ok(self.pointer - distance);
+        }
+
+        if self.options.checked {
+            return Err(if offset < 0 {
+                RuntimeError::PointerUnderflow(position)
+            } else {
+                RuntimeError::PointerOverflow(position)
+            }.into());
+        }
+
+        let distance: usize = distance % length;
+        if offset < 0 {
+            Ok((self.pointer + length - distance) % length)
+        } else {
+            Ok((self.pointer + distance) % length)
+        }
+    }
+
+    /// Move the pointer to the first zero cell reached from the current cell by moving in steps of `stride` cells.
+    ///
+    /// The tape is searched up to the end in the direction of the `stride` at once. Only if there is no zero cell on
+    /// the way, the search continues cell by cell beyond that end. The `position` is reported if this leaves the tape
+    /// in checked mode.
+    fn scan(&mut self, stride: isize, position: MetaData) -> Result<()> {
+        let step: usize = stride.unsigned_abs();
+        let found: Option<usize> = if stride < 0 {
+            self.tape[..=self.pointer].iter()
+                .rev()
+                .step_by(step)
+                .position(|&cell| cell == 0)
+                .map(|steps| self.pointer - steps * step)
+        } else {
+            self.tape[self.pointer..].iter()
+                .step_by(step)
+                .position(|&cell| cell == 0)
+                .map(|steps| self.pointer + steps * step)
+        };
+
+        match found {
+            Some(index) => self.pointer = index,
+            None => {
+                while self.tape[self.pointer] != 0 {
+                    self.pointer = match self.pointer.checked_add_signed(stride) {
+                        Some(index) if index < self.tape.len() => index,
+                        _ => self.reach(stride, position)?,
+                    };
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for VirtualMachine {
+    fn default() -> VirtualMachine {
+        VirtualMachine::new()
+    }
+}
+
+/// Read a single character from the `input`, and return the new value of the cell into which it is read, or `None` if
+/// the cell stays unchanged at the end of the `input`, as specified in the `options`.
+fn read<R: Read>(options: Options, input: &mut R) -> Result<Option<i64>> {
+    let mut buffer = [0_u8; 1];
+    match input.read_exact(&mut buffer) {
+        Ok(()) => Ok(Some(i64::from(buffer[0]))),
+        Err(ref error) if error.kind() == ErrorKind::UnexpectedEof => {
+            let cell_type: CellType = options.cell_type;
+            Ok(match options.eof {
+                EofBehavior::Zero => Some(0),
+                EofBehavior::MinusOne if cell_type.is_signed() => Some(-1),
+                EofBehavior::MinusOne => Some(cell_type.max()),
+                EofBehavior::Unchanged => None,
+            })
+        },
+        Err(error) => Err(Error::from(error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Bytecode;
+    use CellType;
+    use EofBehavior;
+    use Error;
+    use Interpreter;
+    use Lexer;
+    use MetaData;
+    use Opcode;
+    use Optimizer;
+    use Options;
+    use Overflow;
+    use Program;
+    use RuntimeError;
+    use TapeGrowth;
+    use VirtualMachine;
+    use language::Brainfuck;
+
+    /// Run the `Brainfuck` `source` with the given `input` under the `options` on a new virtual machine and on a new
+    /// interpreter, and return the output or the runtime error of the virtual machine after asserting that the
+    /// interpreter's results are the same.
+    fn run(source: &str, input: &str, options: Options) -> Result<Vec<u8>, RuntimeError> {
+        let tokens = Brainfuck::new(source).tokenize();
+        let program = Optimizer::with_options(options).optimize(Program::new(&tokens).unwrap());
+
+        let mut output: Vec<u8> = Vec::new();
+        let mut machine = VirtualMachine::with_options(options);
+        let result = machine.run(&Bytecode::new(&program), &mut input.as_bytes(), &mut output);
+
+        let mut expected_output: Vec<u8> = Vec::new();
+        let mut interpreter = Interpreter::with_options(options);
+        let expected_result = interpreter.run(&program, &mut input.as_bytes(), &mut expected_output);
+        assert_eq!(output, expected_output);
+
+        match (result, expected_result) {
+            (Ok(()), Ok(())) => Ok(output),
+            (Err(Error::Runtime(error)), Err(Error::Runtime(expected))) if error == expected => Err(error),
+            (result, expected) => panic!("Unexpected result: {:?} instead of {:?}", result, expected),
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let machine = VirtualMachine::new();
+        assert_eq!(machine, VirtualMachine {
+            options: Options::default(),
+            tape: vec![0; 30_000],
+            pointer: 0,
+            mask: Some(255),
+        });
+    }
+
+    #[test]
+    fn test_with_options() {
+        let mask = |cell_type: CellType, overflow: Overflow| {
+            VirtualMachine::with_options(Options { cell_type, overflow, ..Options::default() }).mask
+        };
+
+        assert_eq!(mask(CellType::U16, Overflow::Wrap), Some(65_535));
+        assert_eq!(mask(CellType::U32, Overflow::Wrap), Some(4_294_967_295));
+        assert_eq!(mask(CellType::I64, Overflow::Wrap), Some(-1));
+        assert_eq!(mask(CellType::U8, Overflow::Saturate), None);
+        assert_eq!(mask(CellType::U8, Overflow::Error), None);
+    }
+
+    #[test]
+    fn test_bytecode_new() {
+        let position = |lineno: usize, position: usize| MetaData { lineno, position };
+        let tokens = Brainfuck::new("+>[\n-[<+>-]>]<[<],.").tokenize();
+        let program = Optimizer::new().optimize(Program::new(&tokens).unwrap());
+        let bytecode = Bytecode::new(&program);
+
+        assert_eq!(bytecode.code(), [
+            Opcode::Add { offset: 0, delta: 1 },
+            Opcode::Move(1),
+            Opcode::JumpIfZero(8),
+            Opcode::Add { offset: 0, delta: -1 },
+            Opcode::MulAdd { source: 0, target: -1, factor: 1 },
+            Opcode::Clear(0),
+            Opcode::Move(1),
+            Opcode::JumpUnlessZero(3),
+            Opcode::Move(-1),
+            Opcode::Scan(-1),
+            Opcode::Read(0),
+            Opcode::Write(0),
+        ]);
+        assert_eq!(bytecode.positions[2], position(1, 3));
+        assert_eq!(bytecode.positions[4], position(2, 2));
+        assert_eq!(bytecode.positions[7], position(2, 9));
+        assert_eq!(bytecode.positions[9], position(2, 11));
+    }
+
+    #[test]
+    fn test_run_hello_world() {
+        let source: &str = include_str!("../../../examples/hello.bf");
+        assert_eq!(run(source, "", Options::default()), Ok(b"Hello World!\n".to_vec()));
+
+        let options = Options { optimize: false, ..Options::default() };
+        let tokens = Brainfuck::new(source).tokenize();
+        let bytecode = Bytecode::new(&Program::new(&tokens).unwrap());
+        let mut output: Vec<u8> = Vec::new();
+        VirtualMachine::with_options(options).run(&bytecode, &mut "".as_bytes(), &mut output).unwrap();
+        assert_eq!(output, b"Hello World!\n");
+    }
+
+    #[test]
+    fn test_run_read() {
+        let options = Options::default();
+        assert_eq!(run(",+.,+.,+.", "HAL", options), Ok(b"IBM".to_vec()));
+
+        for &eof in &[EofBehavior::Zero, EofBehavior::MinusOne, EofBehavior::Unchanged] {
+            for &cell_type in &[CellType::U8, CellType::U16, CellType::I64] {
+                let options = Options { eof, cell_type, ..Options::default() };
+                let _ = run("+++,.>+++,.", "a", options);
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_overflow() {
+        let source: &str = "--.+.>++++++++++++++++[->++++++++++++++++<]>+.--.>+[->[-<+>]<]";
+        for &overflow in &[Overflow::Wrap, Overflow::Saturate, Overflow::Error] {
+            for &cell_type in &[CellType::U8, CellType::U16, CellType::U32, CellType::I64] {
+                let options = Options { cell_type, overflow, ..Options::default() };
+                let _ = run(source, "", options);
+            }
+        }
+
+        let options = Options { overflow: Overflow::Saturate, ..Options::default() };
+        assert_eq!(run("--.+.>++++++++++++++++[->++++++++++++++++<]>+.--.", "", options), Ok(vec![0, 1, 255, 253]));
+
+        let options = Options { cell_type: CellType::U16, overflow: Overflow::Error, ..Options::default() };
+        let position = MetaData { lineno: 2, position: 2 };
+        assert_eq!(run("+++.\n>---", "", options), Err(RuntimeError::CellOverflow(position)));
+    }
+
+    #[test]
+    fn test_run_tape() {
+        let sources: [&str; 6] = [">>>+<<<.", "+\n<+.", ">>>>+.", "+>+>+[>]+.", "+[-<<+>>]<<.", ">>+[<]<+."];
+        for &tape_growth in &[TapeGrowth::Fixed, TapeGrowth::Right, TapeGrowth::Both] {
+            for &checked in &[false, true] {
+                for source in &sources {
+                    let options = Options { checked, tape_size: 4, tape_growth, ..Options::default() };
+                    let _ = run(source, "", options);
+                }
+            }
+        }
+
+        let options = Options { checked: true, tape_size: 4, ..Options::default() };
+        let position = MetaData { lineno: 1, position: 8 };
+        assert_eq!(run("+>+>+>+[>]", "", options), Err(RuntimeError::PointerOverflow(position)));
+
+        let options = Options { tape_size: 2, tape_growth: TapeGrowth::Both, ..Options::default() };
+        let tokens = Brainfuck::new("+<++<+++<<<+>>>>>.").tokenize();
+        let program = Optimizer::with_options(options).optimize(Program::new(&tokens).unwrap());
+        let mut machine = VirtualMachine::with_options(options);
+        machine.run(&Bytecode::new(&program), &mut "".as_bytes(), &mut Vec::new()).unwrap();
+        assert_eq!(machine.tape, [0, 1, 0, 0, 3, 2, 1, 0]);
+        assert_eq!(machine.pointer, 6);
+    }
+
+    #[test]
+    fn test_run_scan() {
+        let source: &str = ">+>+>>+>>+<<<<<<[>]<.>>+[>>]<.<[<]>.<<<[<<]>[>>>>]<<.";
+        assert_eq!(run(source, "", Options::default()), Ok(vec![0, 1, 2, 0]));
+        assert_eq!(run("+>+[<]+>.", "", Options::default()), Ok(vec![1]));
+
+        let options = Options { tape_size: 4, tape_growth: TapeGrowth::Both, ..Options::default() };
+        let _ = run("+>+>+>+[>>]+<<<<<[<]+.", "", options);
+    }
+
+    #[test]
+    fn test_run_deeply_nested_loops() {
+        let source: String = format!("+{open}-{close}+.", open = "[".repeat(20_000), close = "]".repeat(20_000));
+        assert_eq!(run(&source, "", Options::default()), Ok(vec![1]));
+    }
+}
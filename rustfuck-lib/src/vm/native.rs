@@ -0,0 +1,605 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementations of the translation of bytecode to `x86-64` machine code, and of its execution on Linux.
+//!
+//! The machine code works directly on the virtual machine's tape, with the tape's address in `%rbx`, the index of the
+//! current cell in `%r12`, the number of cells in `%r13`, and the context shared with Rust in `%r14`. Each cell takes
+//! 64 bits, as on the virtual machine. Input and output are handled by callbacks into Rust.
+//!
+//! Whatever the machine code does not handle itself, like accesses beyond the ends of the tape or cell overflows that
+//! do not wrap around, makes it return the index of the affected operation before changing anything. The virtual
+//! machine then executes that operation, and the machine code continues at the next one. Thus, the behavior is exactly
+//! the virtual machine's.
+
+use std::convert::TryFrom;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::mem;
+use std::os::raw::c_int;
+use std::os::raw::c_void;
+use std::ptr;
+
+use CellType;
+use Error;
+use Options;
+use Overflow;
+use Result;
+use super::Bytecode;
+use super::Opcode;
+use super::VirtualMachine;
+use x86_64::Arithmetic;
+use x86_64::Assembler;
+use x86_64::Condition;
+use x86_64::Label;
+use x86_64::Memory;
+use x86_64::Register;
+use x86_64::Size;
+
+/// Pages may be read.
+const PROT_READ: c_int = 0x1;
+
+/// Pages may be written.
+const PROT_WRITE: c_int = 0x2;
+
+/// Pages may be executed.
+const PROT_EXEC: c_int = 0x4;
+
+/// The mapping is not shared with other processes.
+const MAP_PRIVATE: c_int = 0x02;
+
+/// The mapping is not backed by a file.
+const MAP_ANONYMOUS: c_int = 0x20;
+
+/// The size of a cell in bytes.
+const CELL_SIZE: isize = 8;
+
+/// The offset of the tape's address in the context.
+const TAPE: i32 = 0;
+
+/// The offset of the number of cells in the context.
+const LENGTH: i32 = 8;
+
+/// The offset of the index of the current cell in the context.
+const POINTER: i32 = 16;
+
+/// The offset of the callback for reading in the context.
+const READ: i32 = 24;
+
+/// The offset of the callback for writing in the context.
+const WRITE: i32 = 32;
+
+extern "C" {
+    /// Map `length` bytes of memory with the given `protection`.
+    fn mmap(address: *mut c_void, length: usize, protection: c_int, flags: c_int, file: c_int, offset: i64)
+            -> *mut c_void;
+
+    /// Change the `protection` of the `length` bytes of mapped memory at `address`.
+    fn mprotect(address: *mut c_void, length: usize, protection: c_int) -> c_int;
+
+    /// Unmap the `length` bytes of mapped memory at `address`.
+    fn munmap(address: *mut c_void, length: usize) -> c_int;
+}
+
+/// The machine code's entry point, called with the context and the address of the operation at which to start. It
+/// returns the index of the operation at which it stopped.
+type Function<'a, R, W> = unsafe extern "C" fn(*mut Context<'a, R, W>, *const u8) -> u64;
+
+/// The state shared between the machine code and the callbacks into Rust.
+#[repr(C)]
+struct Context<'a, R, W> {
+
+    /// The address of the tape's first cell.
+    tape: *mut i64,
+
+    /// The number of cells on the tape.
+    length: usize,
+
+    /// The index of the current cell.
+    pointer: usize,
+
+    /// The callback reading a character into the cell at the given address. It returns zero on success.
+    read: extern "C" fn(*mut Context<'a, R, W>, *mut i64) -> u64,
+
+    /// The callback writing the lowest byte of the given cell value. It returns zero on success.
+    write: extern "C" fn(*mut Context<'a, R, W>, i64) -> u64,
+
+    /// The options controlling the execution.
+    options: Options,
+
+    /// The input from which the program reads.
+    input: &'a mut R,
+
+    /// The output to which the program writes.
+    output: &'a mut W,
+
+    /// The error with which a callback failed, if any.
+    error: Option<Error>,
+}
+
+/// Machine code in memory mapped for execution.
+#[derive(Debug)]
+struct MachineCode {
+
+    /// The address of the mapped memory, where the machine code starts.
+    address: *mut c_void,
+
+    /// The number of mapped bytes.
+    length: usize,
+}
+
+impl MachineCode {
+    /// Map memory for the code in the `assembler`, copy the code into it, and make it executable.
+    fn new(assembler: Assembler) -> io::Result<MachineCode> {
+        // Reading and writing the mapped memory is safe, as it is not used by anything else.
+        let length: usize = assembler.len().max(1);
+        let address: *mut c_void = unsafe {
+            mmap(ptr::null_mut(), length, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0)
+        };
+        if address.addr() == usize::MAX {
+            return Err(io::Error::last_os_error());
+        }
+
+        let machine_code = MachineCode { address, length };
+        let code: Vec<u8> = assembler.finish(u64::try_from(address.addr()).expect("Addresses fit into 64 bits."));
+        unsafe {
+            ptr::copy_nonoverlapping(code.as_ptr(), address.cast::<u8>(), code.len());
+            if mprotect(address, length, PROT_READ | PROT_EXEC) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(machine_code)
+    }
+
+    /// Get the address `offset` bytes after the start of the machine code.
+    fn at(&self, offset: usize) -> *const u8 {
+        self.address.cast::<u8>().wrapping_add(offset).cast_const()
+    }
+
+    /// Get the entry point of the machine code, which is at its start.
+    ///
+    /// Calling the function is only safe if the machine code has been created by a translator for `R` and `W`.
+    fn function<'a, R, W>(&self) -> Function<'a, R, W> {
+        unsafe { mem::transmute::<*mut c_void, Function<'a, R, W>>(self.address) }
+    }
+}
+
+impl Drop for MachineCode {
+    fn drop(&mut self) {
+        // Nothing refers to the machine code anymore. Failing to unmap it only leaks memory.
+        let _ = unsafe { munmap(self.address, self.length) };
+    }
+}
+
+/// A translator from bytecode to machine code.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Translator {
+
+    /// The options controlling the generated code.
+    options: Options,
+
+    /// The assembler encoding the generated code.
+    assembler: Assembler,
+
+    /// The labels of the operations, followed by the label of the end of the program.
+    operations: Vec<Label>,
+
+    /// The labels of the code returning the index of each operation, if any code needs to return at the operation.
+    exits: Vec<Option<Label>>,
+
+    /// The label of the code returning from the machine code, with the returned index in `%rax`.
+    leave: Label,
+}
+
+impl Translator {
+    /// Initialize a new translator with the given `options`.
+    fn new(options: Options) -> Translator {
+        let mut assembler = Assembler::new();
+        let leave: Label = assembler.label();
+        Translator {
+            options,
+            assembler,
+            operations: Vec::new(),
+            exits: Vec::new(),
+            leave,
+        }
+    }
+
+    /// Translate the `bytecode`, returning the assembler with the machine code and the offsets of the operations
+    /// within the code, followed by the offset of the end of the program.
+    fn translate(mut self, bytecode: &Bytecode) -> (Assembler, Vec<usize>) {
+        let length: usize = bytecode.code.len();
+        self.operations = (0..=length).map(|_| self.assembler.label()).collect();
+        self.exits = vec![None; length];
+
+        // Save the registers that must be preserved for the caller, with `%r15` only keeping the stack aligned for the
+        // callbacks. Then, load the context, and jump to the operation in `%rsi`.
+        for &register in &[Register::Rbx, Register::R12, Register::R13, Register::R14, Register::R15] {
+            self.assembler.push(register);
+        }
+        self.assembler.mov(Size::Quad, Register::Rdi, Register::R14);
+        self.assembler.mov(Size::Quad, Memory::at(Register::R14, TAPE), Register::Rbx);
+        self.assembler.mov(Size::Quad, Memory::at(Register::R14, LENGTH), Register::R13);
+        self.assembler.mov(Size::Quad, Memory::at(Register::R14, POINTER), Register::R12);
+        self.assembler.jmp_indirect(Register::Rsi);
+
+        for (counter, &opcode) in bytecode.code.iter().enumerate() {
+            self.assembler.bind(self.operations[counter]);
+            if is_encodable(opcode) {
+                self.generate(counter, opcode);
+            } else {
+                let exit: Label = self.exit(counter);
+                self.assembler.jmp(exit);
+            }
+        }
+
+        // Return the index after the last operation at the end of the program.
+        self.assembler.bind(self.operations[length]);
+        self.assembler.mov(Size::Double, to_i64(length), Register::Rax);
+        self.assembler.bind(self.leave);
+        self.assembler.mov(Size::Quad, Register::R12, Memory::at(Register::R14, POINTER));
+        for &register in &[Register::R15, Register::R14, Register::R13, Register::R12, Register::Rbx] {
+            self.assembler.pop(register);
+        }
+        self.assembler.ret();
+
+        for (counter, exit) in self.exits.iter().enumerate() {
+            if let Some(exit) = *exit {
+                self.assembler.bind(exit);
+                self.assembler.mov(Size::Double, to_i64(counter), Register::Rax);
+                self.assembler.jmp(self.leave);
+            }
+        }
+
+        let offsets: Vec<usize> = self.operations.iter()
+            .map(|&label| self.assembler.offset(label).expect("All operations have been generated."))
+            .collect();
+        (self.assembler, offsets)
+    }
+
+    /// Generate the code for the `opcode` at index `counter` in the bytecode.
+    fn generate(&mut self, counter: usize, opcode: Opcode) {
+        match opcode {
+            Opcode::Add { offset, delta } => {
+                let cell: Memory = self.reach(offset, counter);
+                let delta: i32 = i32::try_from(delta).expect("The change is encodable.");
+                if self.is_wrapping() {
+                    self.assembler.arithmetic(Arithmetic::Add, self.cell_size(), delta, cell);
+                } else {
+                    self.assembler.mov(Size::Quad, cell, Register::Rax);
+                    self.assembler.arithmetic(Arithmetic::Add, Size::Quad, delta, Register::Rax);
+                    self.store(cell, counter);
+                }
+            },
+            Opcode::Move(distance) => {
+                self.check(distance, counter);
+                self.assembler.mov(Size::Quad, Register::Rax, Register::R12);
+            },
+            Opcode::Read(offset) => {
+                let cell: Memory = self.reach(offset, counter);
+                self.assembler.lea(cell, Register::Rsi);
+                self.call(READ, counter);
+            },
+            Opcode::Write(offset) => {
+                let cell: Memory = self.reach(offset, counter);
+                self.assembler.mov(Size::Quad, cell, Register::Rsi);
+                self.call(WRITE, counter);
+            },
+            Opcode::Clear(offset) => {
+                let cell: Memory = self.reach(offset, counter);
+                self.assembler.mov(Size::Quad, 0, cell);
+            },
+            Opcode::MulAdd { source, target, factor } => {
+                // Like the replaced loop, do not access the target cell if there is nothing to add.
+                let skip: Label = self.assembler.label();
+                let source: Memory = self.reach(source, counter);
+                self.assembler.arithmetic(Arithmetic::Cmp, Size::Quad, 0, source);
+                self.assembler.jcc(Condition::Equal, skip);
+                let target: Memory = self.reach(target, counter);
+                self.multiply(source, target, factor, counter);
+                self.assembler.bind(skip);
+            },
+            Opcode::Scan(stride) => {
+                let scan: Label = self.assembler.label();
+                let end: Label = self.assembler.label();
+                self.assembler.bind(scan);
+                self.assembler.arithmetic(Arithmetic::Cmp, Size::Quad, 0, cell(0));
+                self.assembler.jcc(Condition::Equal, end);
+                self.check(stride, counter);
+                self.assembler.mov(Size::Quad, Register::Rax, Register::R12);
+                self.assembler.jmp(scan);
+                self.assembler.bind(end);
+            },
+            Opcode::JumpIfZero(target) => {
+                self.assembler.arithmetic(Arithmetic::Cmp, Size::Quad, 0, cell(0));
+                self.assembler.jcc(Condition::Equal, self.operations[target]);
+            },
+            Opcode::JumpUnlessZero(target) => {
+                self.assembler.arithmetic(Arithmetic::Cmp, Size::Quad, 0, cell(0));
+                self.assembler.jcc(Condition::NotEqual, self.operations[target]);
+            },
+        }
+    }
+
+    /// Add the value of the `source` cell multiplied by `factor` to the `target` cell on behalf of the operation at
+    /// index `counter`.
+    fn multiply(&mut self, source: Memory, target: Memory, factor: i64, counter: usize) {
+        let factor: i32 = i32::try_from(factor).expect("The factor is encodable.");
+        self.assembler.mov(Size::Quad, source, Register::Rax);
+        if self.is_wrapping() {
+            // Only the lowest bits of the product matter.
+            let operation: Arithmetic = if factor == -1 { Arithmetic::Sub } else { Arithmetic::Add };
+            if factor != 1 && factor != -1 {
+                self.assembler.imul(Size::Quad, factor, Register::Rax, Register::Rax);
+            }
+            self.assembler.arithmetic(operation, self.cell_size(), Register::Rax, target);
+            return;
+        }
+
+        // The product of an unsigned cell and the factor does not overflow.
+        self.assembler.imul(Size::Quad, factor, Register::Rax, Register::Rax);
+        if self.options.cell_type.is_signed() {
+            let exit: Label = self.exit(counter);
+            self.assembler.jcc(Condition::Overflow, exit);
+        }
+        self.assembler.arithmetic(Arithmetic::Add, Size::Quad, target, Register::Rax);
+        self.store(target, counter);
+    }
+
+    /// Store the sum in `%rax` in the `cell` if it is within the range of the cell type, or let the operation at index
+    /// `counter` handle the overflow otherwise. For signed cells, the sum's calculation must have set the flags.
+    fn store(&mut self, cell: Memory, counter: usize) {
+        let exit: Label = self.exit(counter);
+        if self.options.cell_type.is_signed() {
+            self.assembler.jcc(Condition::Overflow, exit);
+        } else {
+            // Negative sums are above the maximum when compared as unsigned numbers.
+            self.assembler.mov(Size::Quad, self.options.cell_type.max(), Register::Rcx);
+            self.assembler.arithmetic(Arithmetic::Cmp, Size::Quad, Register::Rcx, Register::Rax);
+            self.assembler.jcc(Condition::Above, exit);
+        }
+        self.assembler.mov(Size::Quad, Register::Rax, cell);
+    }
+
+    /// Call the callback at the `callback` offset in the context, and let the operation at index `counter` report the
+    /// error if it fails. The callback's second argument must be in `%rsi`.
+    fn call(&mut self, callback: i32, counter: usize) {
+        self.assembler.mov(Size::Quad, Register::R14, Register::Rdi);
+        self.assembler.call_indirect(Memory::at(Register::R14, callback));
+        self.assembler.test(Size::Quad, Register::Rax, Register::Rax);
+        let exit: Label = self.exit(counter);
+        self.assembler.jcc(Condition::NotEqual, exit);
+    }
+
+    /// Get the address of the cell `offset` cells away from the current cell, after making sure that it is on the
+    /// tape on behalf of the operation at index `counter`.
+    fn reach(&mut self, offset: isize, counter: usize) -> Memory {
+        if offset != 0 {
+            self.check(offset, counter);
+        }
+
+        cell(offset)
+    }
+
+    /// Load the index of the cell `offset` cells away from the current cell into `%rax`, and let the operation at
+    /// index `counter` handle the access if it is not on the tape. Negative indices are beyond the end of the tape
+    /// when compared as unsigned numbers.
+    fn check(&mut self, offset: isize, counter: usize) {
+        let offset: i32 = i32::try_from(offset).expect("The offset is encodable.");
+        self.assembler.lea(Memory::at(Register::R12, offset), Register::Rax);
+        self.assembler.arithmetic(Arithmetic::Cmp, Size::Quad, Register::R13, Register::Rax);
+        let exit: Label = self.exit(counter);
+        self.assembler.jcc(Condition::AboveEqual, exit);
+    }
+
+    /// Get the label of the code returning the index `counter`, creating it if necessary.
+    fn exit(&mut self, counter: usize) -> Label {
+        if let Some(exit) = self.exits[counter] {
+            return exit;
+        }
+
+        let exit: Label = self.assembler.label();
+        self.exits[counter] = Some(exit);
+        exit
+    }
+
+    /// Get the size of the cell values, which are stored in the lowest bytes of the cells.
+    fn cell_size(&self) -> Size {
+        match self.options.cell_type {
+            CellType::U8 => Size::Byte,
+            CellType::U16 => Size::Word,
+            CellType::U32 => Size::Double,
+            CellType::I64 => Size::Quad,
+        }
+    }
+
+    /// Determine if the cells wrap around on overflows.
+    fn is_wrapping(&self) -> bool {
+        self.options.overflow == Overflow::Wrap
+    }
+}
+
+/// Execute the `bytecode` on the `machine`, running as much of it as possible as machine code, and reading from `input`
+/// and writing to `output`.
+pub fn execute<R: Read, W: Write>(machine: &mut VirtualMachine, bytecode: &Bytecode, input: &mut R, output: &mut W)
+                                  -> Result<()>
+{
+    let (assembler, offsets): (Assembler, Vec<usize>) = Translator::new(machine.options).translate(bytecode);
+    let machine_code: MachineCode = MachineCode::new(assembler)?;
+    let function: Function<R, W> = machine_code.function();
+    let mut context = Context {
+        tape: ptr::null_mut(),
+        length: 0,
+        pointer: 0,
+        read: read_cell::<R, W>,
+        write: write_cell::<R, W>,
+        options: machine.options,
+        input,
+        output,
+        error: None,
+    };
+
+    let length: usize = bytecode.code.len();
+    let mut counter: usize = 0;
+    while counter < length {
+        // The virtual machine may have grown the tape since the last call.
+        context.tape = machine.tape.as_mut_ptr();
+        context.length = machine.tape.len();
+        context.pointer = machine.pointer;
+
+        // The machine code has been translated for the context's types, and only accesses the cells on the tape.
+        let stopped: u64 = unsafe { function(&mut context, machine_code.at(offsets[counter])) };
+        counter = usize::try_from(stopped).expect("The machine code returns an index of the bytecode.");
+        machine.pointer = context.pointer;
+        if let Some(error) = context.error.take() {
+            return Err(error);
+        }
+
+        if counter < length {
+            counter = machine.step(bytecode, counter, context.input, context.output)?;
+        }
+    }
+
+    context.output.flush()?;
+    Ok(())
+}
+
+/// Read a single character into the `cell` as the virtual machine would. Errors are stored in the `context`.
+extern "C" fn read_cell<R: Read, W: Write>(context: *mut Context<R, W>, cell: *mut i64) -> u64 {
+    // The machine code passes the context with which it has been called, and a cell on the tape.
+    let context: &mut Context<R, W> = unsafe { &mut *context };
+    match super::read(context.options, context.input) {
+        Ok(Some(value)) => unsafe { *cell = value },
+        Ok(None) => {},
+        Err(error) => {
+            context.error = Some(error);
+            return 1;
+        },
+    }
+
+    0
+}
+
+/// Write the lowest byte of the cell `value` to the output. Errors are stored in the `context`.
+extern "C" fn write_cell<R: Read, W: Write>(context: *mut Context<R, W>, value: i64) -> u64 {
+    // The machine code passes the context with which it has been called.
+    let context: &mut Context<R, W> = unsafe { &mut *context };
+    match context.output.write_all(&value.to_le_bytes()[..1]) {
+        Ok(()) => 0,
+        Err(error) => {
+            context.error = Some(Error::from(error));
+            1
+        },
+    }
+}
+
+/// Determine if the machine code can encode all offsets and values of the `opcode`. Otherwise, the virtual machine
+/// executes it.
+fn is_encodable(opcode: Opcode) -> bool {
+    let (offsets, value): ([isize; 2], i64) = match opcode {
+        Opcode::Add { offset, delta } => ([offset, 0], delta),
+        Opcode::Move(offset) | Opcode::Read(offset) | Opcode::Write(offset) | Opcode::Clear(offset)
+        | Opcode::Scan(offset) => ([offset, 0], 0),
+        Opcode::MulAdd { source, target, factor } => ([source, target], factor),
+        Opcode::JumpIfZero(_) | Opcode::JumpUnlessZero(_) => ([0, 0], 0),
+    };
+
+    let is_displacement = |offset: &isize| {
+        offset.checked_mul(CELL_SIZE).is_some_and(|bytes| i32::try_from(bytes).is_ok())
+    };
+    offsets.iter().all(is_displacement) && i32::try_from(value).is_ok()
+}
+
+/// Get the address of the cell `offset` cells away from the current cell.
+fn cell(offset: isize) -> Memory {
+    let displacement: isize = offset * CELL_SIZE;
+    let displacement: i32 = i32::try_from(displacement).expect("The offset is encodable.");
+    Memory::indexed(Register::Rbx, Register::R12, Size::Quad, displacement)
+}
+
+/// Convert the `value` to a signed 64 bit number.
+fn to_i64(value: usize) -> i64 {
+    i64::try_from(value).expect("The value fits into 64 bits.")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::convert::TryFrom;
+    use std::mem;
+
+    use Bytecode;
+    use Lexer;
+    use Opcode;
+    use Options;
+    use Overflow;
+    use Program;
+    use language::Brainfuck;
+    use super::Context;
+    use super::LENGTH;
+    use super::POINTER;
+    use super::READ;
+    use super::TAPE;
+    use super::Translator;
+    use super::WRITE;
+    use super::is_encodable;
+
+    /// The context's type for reading and writing byte slices.
+    type SliceContext<'a> = Context<'a, &'a [u8], Vec<u8>>;
+
+    #[test]
+    fn test_context() {
+        let offset = |offset: usize| i32::try_from(offset).unwrap();
+        assert_eq!(offset(mem::offset_of!(SliceContext, tape)), TAPE);
+        assert_eq!(offset(mem::offset_of!(SliceContext, length)), LENGTH);
+        assert_eq!(offset(mem::offset_of!(SliceContext, pointer)), POINTER);
+        assert_eq!(offset(mem::offset_of!(SliceContext, read)), READ);
+        assert_eq!(offset(mem::offset_of!(SliceContext, write)), WRITE);
+    }
+
+    #[test]
+    fn test_is_encodable() {
+        assert!(is_encodable(Opcode::Add { offset: -3, delta: 255 }));
+        assert!(is_encodable(Opcode::MulAdd { source: 1, target: 268_435_455, factor: -2_147_483_648 }));
+        assert!(is_encodable(Opcode::JumpIfZero(usize::MAX)));
+        assert!(!is_encodable(Opcode::Move(268_435_456)));
+        assert!(!is_encodable(Opcode::Scan(isize::MIN)));
+        assert!(!is_encodable(Opcode::Add { offset: 0, delta: 2_147_483_648 }));
+    }
+
+    #[test]
+    fn test_translate() {
+        let tokens = Brainfuck::new("+[>.<-]").tokenize();
+        let bytecode = Bytecode::new(&Program::new(&tokens).unwrap());
+        let options = Options { overflow: Overflow::Saturate, ..Options::default() };
+        let (assembler, offsets) = Translator::new(options).translate(&bytecode);
+        assert_eq!(offsets, [25, 53, 64, 81, 101, 118, 146, 157]);
+
+        let code: Vec<u8> = assembler.finish(0);
+        assert_eq!(&code[..53], &[
+            // Save the registers, load the context, and jump to the first operation.
+            0x53, 0x41, 0x54, 0x41, 0x55, 0x41, 0x56, 0x41, 0x57,
+            0x49, 0x89, 0xFE,
+            0x49, 0x8B, 0x1E,
+            0x4D, 0x8B, 0x6E, 0x08,
+            0x4D, 0x8B, 0x66, 0x10,
+            0xFF, 0xE6,
+            // Add, leaving the overflow to the virtual machine.
+            0x4A, 0x8B, 0x04, 0xE3,
+            0x48, 0x83, 0xC0, 0x01,
+            0x48, 0xC7, 0xC1, 0xFF, 0x00, 0x00, 0x00,
+            0x48, 0x39, 0xC8,
+            0x0F, 0x87, 0x80, 0x00, 0x00, 0x00,
+            0x4A, 0x89, 0x04, 0xE3,
+        ][..]);
+
+        // The end of the program returns the index after the last operation.
+        assert_eq!(&code[157..163], &[0xC7, 0xC0, 0x07, 0x00, 0x00, 0x00][..]);
+        assert_eq!(code.len(), 232);
+    }
+}
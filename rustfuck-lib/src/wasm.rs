@@ -0,0 +1,960 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementations of a `WebAssembly` module representation for the subset of instructions needed by the
+//! `WebAssembly` generator, which can be written in the text format or encoded in the binary format.
+//!
+//! Functions are referenced by identifiers created before the functions are imported or defined, as the index of a
+//! function depends on the number of imported functions.
+
+use std::convert::TryFrom;
+
+/// The types of values.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ValueType {
+    /// 32 bit integers.
+    I32,
+
+    /// 64 bit integers.
+    I64,
+}
+
+impl ValueType {
+    /// Get the name of the type in the text format.
+    fn name(self) -> &'static str {
+        match self {
+            ValueType::I32 => "i32",
+            ValueType::I64 => "i64",
+        }
+    }
+
+    /// Get the encoding of the type in the binary format.
+    fn code(self) -> u8 {
+        match self {
+            ValueType::I32 => 0x7F,
+            ValueType::I64 => 0x7E,
+        }
+    }
+}
+
+/// The widths of memory accesses. Narrow accesses operate on 32 bit integers, zero-extending the loaded value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Access {
+    /// A single byte.
+    Byte,
+
+    /// Two bytes.
+    Half,
+
+    /// A 32 bit integer.
+    Word,
+
+    /// A 64 bit integer.
+    Quad,
+}
+
+impl Access {
+    /// Get the binary logarithm of the number of bytes accessed, which is the natural alignment of the access.
+    fn alignment(self) -> u32 {
+        match self {
+            Access::Byte => 0,
+            Access::Half => 1,
+            Access::Word => 2,
+            Access::Quad => 3,
+        }
+    }
+}
+
+/// An identifier of a function, which is bound to a function index when the function is imported or defined.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Function(usize);
+
+/// The instructions, named operators to distinguish them from the intermediate representation. Blocks, loops, and
+/// conditions neither take nor return values.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operator {
+    /// Trap.
+    Unreachable,
+
+    /// Open a block, to whose end branches jump.
+    Block,
+
+    /// Open a loop, to whose start branches jump.
+    Loop,
+
+    /// Open a block that is only executed if the condition is not zero.
+    If,
+
+    /// Close the innermost block, loop, or condition.
+    End,
+
+    /// Branch to the block, loop, or condition the given number of levels out.
+    Br(u32),
+
+    /// Branch to the block, loop, or condition the given number of levels out if the condition is not zero.
+    BrIf(u32),
+
+    /// Return from the function.
+    Return,
+
+    /// Call the function.
+    Call(Function),
+
+    /// Choose the first of two values if the condition is not zero, the second one otherwise.
+    Select,
+
+    /// Get the value of the local variable with the given index.
+    LocalGet(u32),
+
+    /// Set the local variable with the given index.
+    LocalSet(u32),
+
+    /// Set the local variable with the given index, keeping the value on the stack.
+    LocalTee(u32),
+
+    /// Get the value of the global variable with the given index.
+    GlobalGet(u32),
+
+    /// Set the global variable with the given index.
+    GlobalSet(u32),
+
+    /// Load a value of the given width from the address plus the constant offset.
+    Load(Access, u32),
+
+    /// Store a value of the given width at the address plus the constant offset.
+    Store(Access, u32),
+
+    /// Get the size of the memory in pages.
+    MemorySize,
+
+    /// Grow the memory by the given number of pages, returning the old size or `-1` on failure.
+    MemoryGrow,
+
+    /// Copy a number of bytes from a source address to a destination address, which may overlap.
+    MemoryCopy,
+
+    /// Fill a number of bytes starting at an address with a value.
+    MemoryFill,
+
+    /// A 32 bit constant.
+    I32Const(i32),
+
+    /// A 64 bit constant.
+    I64Const(i64),
+
+    /// Test if a 32 bit integer is zero.
+    I32Eqz,
+
+    /// Compare 32 bit integers for inequality.
+    I32Ne,
+
+    /// Compare signed 32 bit integers.
+    I32LtS,
+
+    /// Compare signed 32 bit integers.
+    I32GeS,
+
+    /// Add 32 bit integers.
+    I32Add,
+
+    /// Subtract 32 bit integers.
+    I32Sub,
+
+    /// Multiply 32 bit integers.
+    I32Mul,
+
+    /// Divide unsigned 32 bit integers.
+    I32DivU,
+
+    /// Get the remainder of the division of unsigned 32 bit integers.
+    I32RemU,
+
+    /// Combine 32 bit integers with a bitwise and.
+    I32And,
+
+    /// Combine 32 bit integers with a bitwise or.
+    I32Or,
+
+    /// Shift a 32 bit integer to the left.
+    I32Shl,
+
+    /// Shift a signed 32 bit integer to the right.
+    I32ShrS,
+
+    /// Shift an unsigned 32 bit integer to the right.
+    I32ShrU,
+
+    /// Test if a 64 bit integer is zero.
+    I64Eqz,
+
+    /// Compare 64 bit integers for equality.
+    I64Eq,
+
+    /// Compare signed 64 bit integers.
+    I64LtS,
+
+    /// Compare unsigned 64 bit integers.
+    I64LtU,
+
+    /// Compare signed 64 bit integers.
+    I64GtS,
+
+    /// Compare unsigned 64 bit integers.
+    I64GtU,
+
+    /// Add 64 bit integers.
+    I64Add,
+
+    /// Multiply 64 bit integers.
+    I64Mul,
+
+    /// Combine 64 bit integers with a bitwise and.
+    I64And,
+
+    /// Shift a 64 bit integer to the left.
+    I64Shl,
+
+    /// Shift a signed 64 bit integer to the right.
+    I64ShrS,
+
+    /// Truncate a 64 bit integer to 32 bits.
+    I32WrapI64,
+
+    /// Sign-extend a 32 bit integer to 64 bits.
+    I64ExtendI32S,
+
+    /// Zero-extend a 32 bit integer to 64 bits.
+    I64ExtendI32U,
+}
+
+impl Operator {
+    /// Get the name of the instruction in the text format.
+    fn name(self) -> &'static str {
+        match self {
+            Operator::Unreachable => "unreachable",
+            Operator::Block => "block",
+            Operator::Loop => "loop",
+            Operator::If => "if",
+            Operator::End => "end",
+            Operator::Br(_) => "br",
+            Operator::BrIf(_) => "br_if",
+            Operator::Return => "return",
+            Operator::Call(_) => "call",
+            Operator::Select => "select",
+            Operator::LocalGet(_) => "local.get",
+            Operator::LocalSet(_) => "local.set",
+            Operator::LocalTee(_) => "local.tee",
+            Operator::GlobalGet(_) => "global.get",
+            Operator::GlobalSet(_) => "global.set",
+            Operator::Load(Access::Byte, _) => "i32.load8_u",
+            Operator::Load(Access::Half, _) => "i32.load16_u",
+            Operator::Load(Access::Word, _) => "i32.load",
+            Operator::Load(Access::Quad, _) => "i64.load",
+            Operator::Store(Access::Byte, _) => "i32.store8",
+            Operator::Store(Access::Half, _) => "i32.store16",
+            Operator::Store(Access::Word, _) => "i32.store",
+            Operator::Store(Access::Quad, _) => "i64.store",
+            Operator::MemorySize => "memory.size",
+            Operator::MemoryGrow => "memory.grow",
+            Operator::MemoryCopy => "memory.copy",
+            Operator::MemoryFill => "memory.fill",
+            Operator::I32Const(_) => "i32.const",
+            Operator::I64Const(_) => "i64.const",
+            Operator::I32Eqz => "i32.eqz",
+            Operator::I32Ne => "i32.ne",
+            Operator::I32LtS => "i32.lt_s",
+            Operator::I32GeS => "i32.ge_s",
+            Operator::I32Add => "i32.add",
+            Operator::I32Sub => "i32.sub",
+            Operator::I32Mul => "i32.mul",
+            Operator::I32DivU => "i32.div_u",
+            Operator::I32RemU => "i32.rem_u",
+            Operator::I32And => "i32.and",
+            Operator::I32Or => "i32.or",
+            Operator::I32Shl => "i32.shl",
+            Operator::I32ShrS => "i32.shr_s",
+            Operator::I32ShrU => "i32.shr_u",
+            Operator::I64Eqz => "i64.eqz",
+            Operator::I64Eq => "i64.eq",
+            Operator::I64LtS => "i64.lt_s",
+            Operator::I64LtU => "i64.lt_u",
+            Operator::I64GtS => "i64.gt_s",
+            Operator::I64GtU => "i64.gt_u",
+            Operator::I64Add => "i64.add",
+            Operator::I64Mul => "i64.mul",
+            Operator::I64And => "i64.and",
+            Operator::I64Shl => "i64.shl",
+            Operator::I64ShrS => "i64.shr_s",
+            Operator::I32WrapI64 => "i32.wrap_i64",
+            Operator::I64ExtendI32S => "i64.extend_i32_s",
+            Operator::I64ExtendI32U => "i64.extend_i32_u",
+        }
+    }
+
+    /// Get the opcode of the instruction in the binary format.
+    fn opcode(self) -> &'static [u8] {
+        match self {
+            Operator::Unreachable => &[0x00],
+            Operator::Block => &[0x02, 0x40],
+            Operator::Loop => &[0x03, 0x40],
+            Operator::If => &[0x04, 0x40],
+            Operator::End => &[0x0B],
+            Operator::Br(_) => &[0x0C],
+            Operator::BrIf(_) => &[0x0D],
+            Operator::Return => &[0x0F],
+            Operator::Call(_) => &[0x10],
+            Operator::Select => &[0x1B],
+            Operator::LocalGet(_) => &[0x20],
+            Operator::LocalSet(_) => &[0x21],
+            Operator::LocalTee(_) => &[0x22],
+            Operator::GlobalGet(_) => &[0x23],
+            Operator::GlobalSet(_) => &[0x24],
+            Operator::Load(Access::Byte, _) => &[0x2D],
+            Operator::Load(Access::Half, _) => &[0x2F],
+            Operator::Load(Access::Word, _) => &[0x28],
+            Operator::Load(Access::Quad, _) => &[0x29],
+            Operator::Store(Access::Byte, _) => &[0x3A],
+            Operator::Store(Access::Half, _) => &[0x3B],
+            Operator::Store(Access::Word, _) => &[0x36],
+            Operator::Store(Access::Quad, _) => &[0x37],
+            Operator::MemorySize => &[0x3F, 0x00],
+            Operator::MemoryGrow => &[0x40, 0x00],
+            Operator::MemoryCopy => &[0xFC, 0x0A, 0x00, 0x00],
+            Operator::MemoryFill => &[0xFC, 0x0B, 0x00],
+            Operator::I32Const(_) => &[0x41],
+            Operator::I64Const(_) => &[0x42],
+            Operator::I32Eqz => &[0x45],
+            Operator::I32Ne => &[0x47],
+            Operator::I32LtS => &[0x48],
+            Operator::I32GeS => &[0x4E],
+            Operator::I32Add => &[0x6A],
+            Operator::I32Sub => &[0x6B],
+            Operator::I32Mul => &[0x6C],
+            Operator::I32DivU => &[0x6E],
+            Operator::I32RemU => &[0x70],
+            Operator::I32And => &[0x71],
+            Operator::I32Or => &[0x72],
+            Operator::I32Shl => &[0x74],
+            Operator::I32ShrS => &[0x75],
+            Operator::I32ShrU => &[0x76],
+            Operator::I64Eqz => &[0x50],
+            Operator::I64Eq => &[0x51],
+            Operator::I64LtS => &[0x53],
+            Operator::I64LtU => &[0x54],
+            Operator::I64GtS => &[0x55],
+            Operator::I64GtU => &[0x56],
+            Operator::I64Add => &[0x7C],
+            Operator::I64Mul => &[0x7E],
+            Operator::I64And => &[0x83],
+            Operator::I64Shl => &[0x86],
+            Operator::I64ShrS => &[0x87],
+            Operator::I32WrapI64 => &[0xA7],
+            Operator::I64ExtendI32S => &[0xAC],
+            Operator::I64ExtendI32U => &[0xAD],
+        }
+    }
+}
+
+/// A function of the module.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Definition {
+    /// A function provided by the environment.
+    Import {
+        /// The name of the module from which the function is imported.
+        module: &'static str,
+
+        /// The name under which the function is imported.
+        field: &'static str,
+
+        /// The name of the function in the text format.
+        name: &'static str,
+
+        /// The types of the parameters.
+        parameters: Vec<ValueType>,
+
+        /// The type of the result, if any.
+        result: Option<ValueType>,
+    },
+
+    /// A function defined in the module.
+    Code {
+        /// The name of the function in the text format.
+        name: &'static str,
+
+        /// The names and types of the parameters.
+        parameters: Vec<(&'static str, ValueType)>,
+
+        /// The type of the result, if any.
+        result: Option<ValueType>,
+
+        /// The names and types of the local variables following the parameters.
+        locals: Vec<(&'static str, ValueType)>,
+
+        /// The instructions of the function's body.
+        body: Vec<Operator>,
+    },
+}
+
+impl Definition {
+    /// Get the name of the function in the text format.
+    fn name(&self) -> &'static str {
+        match *self {
+            Definition::Import { name, .. } | Definition::Code { name, .. } => name,
+        }
+    }
+
+    /// Get the types of the function's parameters and its result.
+    fn signature(&self) -> (Vec<ValueType>, Option<ValueType>) {
+        match *self {
+            Definition::Import { ref parameters, result, .. } => (parameters.clone(), result),
+            Definition::Code { ref parameters, result, .. } => {
+                (parameters.iter().map(|&(_, value_type)| value_type).collect(), result)
+            },
+        }
+    }
+}
+
+/// A global variable of the module.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Global {
+    /// The name of the variable in the text format.
+    name: &'static str,
+
+    /// The type of the variable.
+    value_type: ValueType,
+
+    /// The initial value of the variable.
+    value: i64,
+}
+
+/// A `WebAssembly` module with a single memory, exporting the memory and a function.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Module {
+
+    /// The functions by their identifiers, if they have been imported or defined yet.
+    functions: Vec<Option<Definition>>,
+
+    /// The identifiers of the imported functions in the order of their indices.
+    imports: Vec<Function>,
+
+    /// The identifiers of the defined functions in the order of their indices.
+    definitions: Vec<Function>,
+
+    /// The mutable global variables.
+    globals: Vec<Global>,
+
+    /// The initial size of the memory in pages.
+    pages: u32,
+
+    /// The initial contents of the memory at the given addresses.
+    data: Vec<(u32, Vec<u8>)>,
+
+    /// The exported function and the name under which it is exported.
+    export: Option<(&'static str, Function)>,
+}
+
+impl Module {
+    /// Create an empty module.
+    pub fn new() -> Module {
+        Module::default()
+    }
+
+    /// Create a new identifier for a function that is imported or defined later.
+    pub fn function(&mut self) -> Function {
+        self.functions.push(None);
+        Function(self.functions.len() - 1)
+    }
+
+    /// Import the `function` `field` from the environment's `module`, calling it `name` in the text format.
+    pub fn import(&mut self, function: Function, module: &'static str, field: &'static str, name: &'static str,
+                  parameters: &[ValueType], result: Option<ValueType>)
+    {
+        let parameters: Vec<ValueType> = parameters.to_vec();
+        self.bind(function, Definition::Import { module, field, name, parameters, result });
+        self.imports.push(function);
+    }
+
+    /// Define the `function` with the given `name` in the text format, the named `parameters` and `locals`, and the
+    /// instructions of its `body`.
+    pub fn define(&mut self, function: Function, name: &'static str, parameters: &[(&'static str, ValueType)],
+                  result: Option<ValueType>, locals: &[(&'static str, ValueType)], body: Vec<Operator>)
+    {
+        let parameters: Vec<(&'static str, ValueType)> = parameters.to_vec();
+        let locals: Vec<(&'static str, ValueType)> = locals.to_vec();
+        self.bind(function, Definition::Code { name, parameters, result, locals, body });
+        self.definitions.push(function);
+    }
+
+    /// Add a mutable global variable with the given `name` in the text format and its initial `value`, returning its
+    /// index.
+    pub fn global(&mut self, name: &'static str, value_type: ValueType, value: i64) -> u32 {
+        self.globals.push(Global { name, value_type, value });
+        to_u32(self.globals.len() - 1)
+    }
+
+    /// Set the initial size of the memory to the given number of `pages` of 64 KiB.
+    pub fn memory(&mut self, pages: u32) {
+        self.pages = pages;
+    }
+
+    /// Initialize the memory at the `address` with the `bytes`.
+    pub fn data(&mut self, address: u32, bytes: &[u8]) {
+        self.data.push((address, bytes.to_vec()));
+    }
+
+    /// Export the `function` under the given `name`.
+    pub fn export(&mut self, name: &'static str, function: Function) {
+        self.export = Some((name, function));
+    }
+
+    /// Write the module in the text format.
+    pub fn text(&self) -> String {
+        let mut output = String::from("(module\n");
+        for &function in &self.imports {
+            let definition: &Option<Definition> = &self.functions[function.0];
+            if let Some(Definition::Import { module, field, name, ref parameters, result }) = *definition {
+                output.push_str(&format!("  (import \"{module}\" \"{field}\" (func ${name}{signature}))\n",
+                                         module = module, field = field, name = name,
+                                         signature = signature_text(parameters, result)));
+            }
+        }
+
+        output.push_str(&format!("  (memory (export \"memory\") {pages})\n", pages = self.pages));
+        for global in &self.globals {
+            output.push_str(&format!("  (global ${name} (mut {value_type}) ({value_type}.const {value}))\n",
+                                     name = global.name, value_type = global.value_type.name(),
+                                     value = global.value));
+        }
+
+        for &(address, ref bytes) in &self.data {
+            output.push_str(&format!("  (data (i32.const {address}) \"{bytes}\")\n",
+                                     address = address, bytes = escape(bytes)));
+        }
+
+        for &function in &self.definitions {
+            if let Some(Definition::Code { name, ref parameters, result, ref locals, ref body }) =
+                self.functions[function.0]
+            {
+                output.push_str(&format!("\n  (func ${name}", name = name));
+                if let Some((export, _)) = self.export.filter(|&(_, exported)| exported == function) {
+                    output.push_str(&format!(" (export \"{export}\")", export = export));
+                }
+                for &(parameter, value_type) in parameters {
+                    output.push_str(&format!(" (param ${parameter} {value_type})",
+                                             parameter = parameter, value_type = value_type.name()));
+                }
+                if let Some(result) = result {
+                    output.push_str(&format!(" (result {result})", result = result.name()));
+                }
+                output.push('\n');
+                if !locals.is_empty() {
+                    let locals: Vec<String> = locals.iter()
+                        .map(|&(local, value_type)| format!("(local ${local} {value_type})",
+                                                            local = local, value_type = value_type.name()))
+                        .collect();
+                    output.push_str(&format!("    {locals}\n", locals = locals.join(" ")));
+                }
+
+                let variables: Vec<&'static str> = parameters.iter()
+                    .chain(locals.iter())
+                    .map(|&(variable, _)| variable)
+                    .collect();
+                let mut depth: usize = 0;
+                for &operator in body {
+                    if operator == Operator::End {
+                        depth -= 1;
+                    }
+                    output.push_str(&"  ".repeat(depth + 2));
+                    output.push_str(&self.operator_text(operator, &variables));
+                    output.push('\n');
+                    if let Operator::Block | Operator::Loop | Operator::If = operator {
+                        depth += 1;
+                    }
+                }
+                output.push_str("  )\n");
+            }
+        }
+
+        output.push_str(")\n");
+        output
+    }
+
+    /// Encode the module in the binary format.
+    ///
+    /// Panics if a called function has neither been imported nor defined.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output: Vec<u8> = vec![0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00];
+
+        // The distinct signatures of all functions.
+        let mut types: Vec<(Vec<ValueType>, Option<ValueType>)> = Vec::new();
+        let mut type_index = |definition: &Definition| -> u32 {
+            let signature = definition.signature();
+            if let Some(index) = types.iter().position(|existing| *existing == signature) {
+                return to_u32(index);
+            }
+
+            types.push(signature);
+            to_u32(types.len() - 1)
+        };
+
+        let mut imports: Vec<u8> = Vec::new();
+        unsigned(&mut imports, to_u32(self.imports.len()));
+        for &function in &self.imports {
+            let definition: &Definition = self.definition(function);
+            if let Definition::Import { module, field, .. } = *definition {
+                name(&mut imports, module);
+                name(&mut imports, field);
+                imports.push(0x00);
+                unsigned(&mut imports, type_index(definition));
+            }
+        }
+
+        let mut functions: Vec<u8> = Vec::new();
+        unsigned(&mut functions, to_u32(self.definitions.len()));
+        for &function in &self.definitions {
+            unsigned(&mut functions, type_index(self.definition(function)));
+        }
+
+        let mut signatures: Vec<u8> = Vec::new();
+        unsigned(&mut signatures, to_u32(types.len()));
+        for &(ref parameters, result) in &types {
+            signatures.push(0x60);
+            unsigned(&mut signatures, to_u32(parameters.len()));
+            signatures.extend(parameters.iter().map(|parameter| parameter.code()));
+            unsigned(&mut signatures, to_u32(result.iter().count()));
+            signatures.extend(result.iter().map(|result| result.code()));
+        }
+
+        let mut memory: Vec<u8> = vec![0x01, 0x00];
+        unsigned(&mut memory, self.pages);
+
+        let mut globals: Vec<u8> = Vec::new();
+        unsigned(&mut globals, to_u32(self.globals.len()));
+        for global in &self.globals {
+            globals.extend_from_slice(&[global.value_type.code(), 0x01]);
+            match global.value_type {
+                ValueType::I32 => {
+                    let value: i32 = i32::try_from(global.value).expect("The value fits into 32 bits.");
+                    self.encode_operator(&mut globals, Operator::I32Const(value));
+                },
+                ValueType::I64 => self.encode_operator(&mut globals, Operator::I64Const(global.value)),
+            }
+            self.encode_operator(&mut globals, Operator::End);
+        }
+
+        let mut exports: Vec<u8> = Vec::new();
+        unsigned(&mut exports, to_u32(1 + self.export.iter().count()));
+        name(&mut exports, "memory");
+        exports.extend_from_slice(&[0x02, 0x00]);
+        if let Some((export, function)) = self.export {
+            name(&mut exports, export);
+            exports.push(0x00);
+            unsigned(&mut exports, self.index(function));
+        }
+
+        let mut code: Vec<u8> = Vec::new();
+        unsigned(&mut code, to_u32(self.definitions.len()));
+        for &function in &self.definitions {
+            if let Definition::Code { ref locals, ref body, .. } = *self.definition(function) {
+                // Consecutive local variables of the same type are declared together.
+                let mut groups: Vec<(u32, ValueType)> = Vec::new();
+                for &(_, value_type) in locals {
+                    match groups.last_mut() {
+                        Some(&mut (ref mut count, last)) if last == value_type => *count += 1,
+                        _ => groups.push((1, value_type)),
+                    }
+                }
+
+                let mut encoded: Vec<u8> = Vec::new();
+                unsigned(&mut encoded, to_u32(groups.len()));
+                for &(count, value_type) in &groups {
+                    unsigned(&mut encoded, count);
+                    encoded.push(value_type.code());
+                }
+                for &operator in body {
+                    self.encode_operator(&mut encoded, operator);
+                }
+                self.encode_operator(&mut encoded, Operator::End);
+
+                unsigned(&mut code, to_u32(encoded.len()));
+                code.extend_from_slice(&encoded);
+            }
+        }
+
+        let mut data: Vec<u8> = Vec::new();
+        unsigned(&mut data, to_u32(self.data.len()));
+        for &(address, ref bytes) in &self.data {
+            data.push(0x00);
+            self.encode_operator(&mut data, Operator::I32Const(i32::try_from(address).expect("The address fits.")));
+            self.encode_operator(&mut data, Operator::End);
+            unsigned(&mut data, to_u32(bytes.len()));
+            data.extend_from_slice(bytes);
+        }
+
+        let sections: [(u8, Vec<u8>); 8] = [
+            (1, signatures),
+            (2, imports),
+            (3, functions),
+            (5, memory),
+            (6, globals),
+            (7, exports),
+            (10, code),
+            (11, data),
+        ];
+        for &(id, ref section) in sections.iter() {
+            output.push(id);
+            unsigned(&mut output, to_u32(section.len()));
+            output.extend_from_slice(section);
+        }
+
+        output
+    }
+
+    /// Get the definition of the `function`.
+    ///
+    /// Panics if the function has neither been imported nor defined.
+    fn definition(&self, function: Function) -> &Definition {
+        match self.functions[function.0] {
+            Some(ref definition) => definition,
+            None => panic!("Function {} has neither been imported nor defined", function.0),
+        }
+    }
+
+    /// Get the index of the `function`, counting the imported functions first.
+    fn index(&self, function: Function) -> u32 {
+        let index: usize = match self.imports.iter().position(|&import| import == function) {
+            Some(index) => index,
+            None => match self.definitions.iter().position(|&definition| definition == function) {
+                Some(index) => self.imports.len() + index,
+                None => panic!("Function {} has neither been imported nor defined", function.0),
+            },
+        };
+        to_u32(index)
+    }
+
+    /// Write the `operator` in the text format, naming the local variables after the given `variables`.
+    fn operator_text(&self, operator: Operator, variables: &[&'static str]) -> String {
+        let name: &str = operator.name();
+        match operator {
+            Operator::Br(depth) | Operator::BrIf(depth) => format!("{name} {depth}", name = name, depth = depth),
+            Operator::Call(function) => {
+                format!("{name} ${function}", name = name, function = self.definition(function).name())
+            },
+            Operator::LocalGet(local) | Operator::LocalSet(local) | Operator::LocalTee(local) => {
+                format!("{name} ${local}", name = name, local = variables[to_usize(local)])
+            },
+            Operator::GlobalGet(global) | Operator::GlobalSet(global) => {
+                format!("{name} ${global}", name = name, global = self.globals[to_usize(global)].name)
+            },
+            Operator::Load(_, 0) | Operator::Store(_, 0) => String::from(name),
+            Operator::Load(_, offset) | Operator::Store(_, offset) => {
+                format!("{name} offset={offset}", name = name, offset = offset)
+            },
+            Operator::I32Const(value) => format!("{name} {value}", name = name, value = value),
+            Operator::I64Const(value) => format!("{name} {value}", name = name, value = value),
+            _ => String::from(name),
+        }
+    }
+
+    /// Append the `operator` encoded in the binary format to the `output`.
+    fn encode_operator(&self, output: &mut Vec<u8>, operator: Operator) {
+        output.extend_from_slice(operator.opcode());
+        match operator {
+            Operator::Br(depth) | Operator::BrIf(depth) => unsigned(output, depth),
+            Operator::Call(function) => unsigned(output, self.index(function)),
+            Operator::LocalGet(index) | Operator::LocalSet(index) | Operator::LocalTee(index) |
+            Operator::GlobalGet(index) | Operator::GlobalSet(index) => unsigned(output, index),
+            Operator::Load(access, offset) | Operator::Store(access, offset) => {
+                unsigned(output, access.alignment());
+                unsigned(output, offset);
+            },
+            Operator::I32Const(value) => signed(output, i64::from(value)),
+            Operator::I64Const(value) => signed(output, value),
+            _ => {},
+        }
+    }
+
+    /// Bind the `function` to its `definition`.
+    ///
+    /// Panics if the function has already been imported or defined.
+    fn bind(&mut self, function: Function, definition: Definition) {
+        assert!(self.functions[function.0].is_none(), "Function {} bound twice", function.0);
+        self.functions[function.0] = Some(definition);
+    }
+}
+
+/// Get the types of the `parameters` and the `result` of a function in the text format.
+fn signature_text(parameters: &[ValueType], result: Option<ValueType>) -> String {
+    let mut signature = String::new();
+    if !parameters.is_empty() {
+        let parameters: Vec<&str> = parameters.iter().map(|parameter| parameter.name()).collect();
+        signature.push_str(&format!(" (param {parameters})", parameters = parameters.join(" ")));
+    }
+    if let Some(result) = result {
+        signature.push_str(&format!(" (result {result})", result = result.name()));
+    }
+    signature
+}
+
+/// Write the `bytes` as the contents of a string in the text format, escaping all but printable `ASCII` characters.
+fn escape(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|&byte| match byte {
+            b'"' | b'\\' => format!("\\{character}", character = char::from(byte)),
+            0x20..=0x7E => char::from(byte).to_string(),
+            _ => format!("\\{byte:02x}", byte = byte),
+        })
+        .collect()
+}
+
+/// Append the `value` encoded as an unsigned `LEB128` number to the `output`.
+fn unsigned(output: &mut Vec<u8>, value: u32) {
+    let mut value: u32 = value;
+    loop {
+        let byte: u8 = u8::try_from(value & 0x7F).expect("The value has seven bits.");
+        value >>= 7;
+        if value == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+/// Append the `value` encoded as a signed `LEB128` number to the `output`.
+fn signed(output: &mut Vec<u8>, value: i64) {
+    let mut value: i64 = value;
+    loop {
+        let byte: u8 = u8::try_from(value & 0x7F).expect("The value has seven bits.");
+        value >>= 7;
+        let sign: bool = byte & 0x40 != 0;
+        if (value == 0 && !sign) || (value == -1 && sign) {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+/// Append the `name` encoded as a vector of bytes to the `output`.
+fn name(output: &mut Vec<u8>, name: &str) {
+    unsigned(output, to_u32(name.len()));
+    output.extend_from_slice(name.as_bytes());
+}
+
+/// Convert the `value` to a 32 bit number.
+fn to_u32(value: usize) -> u32 {
+    u32::try_from(value).expect("The module is too large")
+}
+
+/// Convert the 32 bit `value` to an index.
+fn to_usize(value: u32) -> usize {
+    usize::try_from(value).expect("The index is within the address space")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Access;
+    use super::Operator;
+    use super::Module;
+    use super::ValueType;
+    use super::signed;
+    use super::unsigned;
+
+    #[test]
+    fn test_leb128() {
+        let mut output: Vec<u8> = Vec::new();
+        unsigned(&mut output, 0);
+        unsigned(&mut output, 127);
+        unsigned(&mut output, 624_485);
+        signed(&mut output, -1);
+        signed(&mut output, 63);
+        signed(&mut output, 64);
+        signed(&mut output, -123_456);
+        assert_eq!(output, vec![
+            0x00,
+            0x7F,
+            0xE5, 0x8E, 0x26,
+            0x7F,
+            0x3F,
+            0xC0, 0x00,
+            0xC0, 0xBB, 0x78,
+        ]);
+    }
+
+    #[test]
+    fn test_text() {
+        let mut module = Module::new();
+        let write = module.function();
+        let run = module.function();
+        module.import(write, "env", "write_byte", "write_byte", &[ValueType::I32], None);
+        let size: u32 = module.global("size", ValueType::I32, 8);
+        module.memory(1);
+        module.data(16, b"a\"\n");
+        module.define(run, "run", &[], None, &[("ptr", ValueType::I32)], vec![
+            Operator::Block,
+            Operator::GlobalGet(size),
+            Operator::LocalTee(0),
+            Operator::I32Eqz,
+            Operator::BrIf(0),
+            Operator::LocalGet(0),
+            Operator::Load(Access::Byte, 16),
+            Operator::Call(write),
+            Operator::End,
+        ]);
+        module.export("run", run);
+        assert_eq!(module.text(), "\
+(module
+  (import \"env\" \"write_byte\" (func $write_byte (param i32)))
+  (memory (export \"memory\") 1)
+  (global $size (mut i32) (i32.const 8))
+  (data (i32.const 16) \"a\\\"\\0a\")
+
+  (func $run (export \"run\")
+    (local $ptr i32)
+    block
+      global.get $size
+      local.tee $ptr
+      i32.eqz
+      br_if 0
+      local.get $ptr
+      i32.load8_u offset=16
+      call $write_byte
+    end
+  )
+)
+");
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut module = Module::new();
+        let run = module.function();
+        let read = module.function();
+        module.define(run, "run", &[], Some(ValueType::I64), &[("value", ValueType::I32)], vec![
+            Operator::Call(read),
+            Operator::LocalSet(0),
+            Operator::I64Const(-2),
+        ]);
+        module.import(read, "env", "read_byte", "read_byte", &[], Some(ValueType::I32));
+        module.memory(2);
+        module.export("run", run);
+        assert_eq!(module.encode(), vec![
+            0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+            0x01, 0x09, 0x02, 0x60, 0x00, 0x01, 0x7F, 0x60, 0x00, 0x01, 0x7E,
+            0x02, 0x11, 0x01, 0x03, b'e', b'n', b'v', 0x09, b'r', b'e', b'a', b'd', b'_', b'b', b'y', b't', b'e',
+            0x00, 0x00,
+            0x03, 0x02, 0x01, 0x01,
+            0x05, 0x03, 0x01, 0x00, 0x02,
+            0x06, 0x01, 0x00,
+            0x07, 0x10, 0x02, 0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00, 0x03, b'r', b'u', b'n', 0x00,
+            0x01,
+            0x0A, 0x0C, 0x01, 0x0A, 0x01, 0x01, 0x7F, 0x10, 0x00, 0x21, 0x00, 0x42, 0x7E, 0x0B,
+            0x0B, 0x01, 0x00,
+        ]);
+    }
+}
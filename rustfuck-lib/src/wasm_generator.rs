@@ -0,0 +1,1786 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementations of a generator for creating `WebAssembly` modules in the text or the binary format from
+//! intermediate compile results.
+//!
+//! The modules export their memory, which holds a few buffers and constants followed by the tape, and a function
+//! running the program. Throughout the program, the address of the current cell is kept in the local variable
+//! `$ptr`. Growing tapes grow the memory, and are moved within it when growing to the left.
+
+use std::convert::TryFrom;
+use std::mem;
+
+use CellType;
+use EofBehavior;
+use Options;
+use Overflow;
+use TapeGrowth;
+use ir::Instruction;
+use ir::Operation;
+use ir::Program;
+use ir::Step;
+use ir::accessed_range;
+use ir::reserved_range;
+use ir::walk;
+use lexer::MetaData;
+use wasm::Access;
+use wasm::Function;
+use wasm::Module;
+use wasm::Operator;
+use wasm::ValueType;
+
+/// The address of the vector describing the buffer passed to the `WASI` input and output functions.
+const IO_VECTOR: i32 = 0;
+
+/// The address at which the `WASI` input and output functions store the number of transferred bytes.
+const IO_COUNT: i32 = 8;
+
+/// The address of the buffer for the character read from the `WASI` standard input.
+const INPUT: i32 = 12;
+
+/// The address of the messages.
+const MESSAGES_ADDRESS: u32 = 16;
+
+/// The texts of the error messages, all but the last one terminated by a zero byte.
+static MESSAGES: &[u8] = b"cell overflow\0pointer underflow\0pointer overflow\0 at line \0, position \0out of memory\n";
+
+/// The address of the message for cell overflows.
+const CELL_OVERFLOW: i32 = 16;
+
+/// The address of the message for accesses before the start of the tape.
+const POINTER_UNDERFLOW: i32 = 30;
+
+/// The address of the message for accesses past the end of the tape.
+const POINTER_OVERFLOW: i32 = 48;
+
+/// The address of the text before the line of a source position.
+const AT_LINE: i32 = 65;
+
+/// The address of the text before the position within the line of a source position.
+const AT_POSITION: i32 = 75;
+
+/// The address of the message for failed memory allocations.
+const OUT_OF_MEMORY: i32 = 87;
+
+/// The size of the buffer collecting the output in bytes.
+const OUTPUT_SIZE: u32 = 4096;
+
+/// The size of the buffer for error messages in bytes.
+const ERROR_SIZE: u32 = 128;
+
+/// The size of a memory page in bytes.
+const PAGE_SIZE: u32 = 0x1_0000;
+
+/// The index of the local variable holding the address of the current cell.
+const PTR: u32 = 0;
+
+/// The index of the local variable holding the character read last.
+const CHARACTER: u32 = 1;
+
+/// The interfaces through which generated `WebAssembly` modules communicate with their environment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WasmInterface {
+    /// The module exports the function `run`, executing the program, and imports the functions it needs from the
+    /// module `env`: `read_byte`, returning the next byte of the input or `-1` at its end, `write_byte`, printing the
+    /// given byte, and `fail`, aborting the program with the message of the given length at the given address in the
+    /// exported memory.
+    Host,
+
+    /// The module is a `WASI` command, exporting the function `_start` and importing `fd_read`, `fd_write`, and
+    /// `proc_exit` from `wasi_snapshot_preview1`.
+    Wasi,
+}
+
+/// The functions in the module.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Functions {
+
+    /// The function running the program.
+    run: Function,
+
+    /// The host's function returning the next byte of the input, or `-1` at its end.
+    read_byte: Function,
+
+    /// The host's function printing a byte.
+    write_byte: Function,
+
+    /// The host's function aborting the program with the message of the given length at the given address.
+    host_fail: Function,
+
+    /// The `WASI` function reading from a file descriptor.
+    fd_read: Function,
+
+    /// The `WASI` function writing to a file descriptor.
+    fd_write: Function,
+
+    /// The `WASI` function exiting the program with the given code.
+    proc_exit: Function,
+
+    /// The function writing the output buffer to the standard output.
+    flush: Function,
+
+    /// The function writing the given number of bytes at the given address to the given file descriptor.
+    write_all: Function,
+
+    /// The function appending a byte to the output buffer.
+    write: Function,
+
+    /// The function returning the next character from the input, or the value for the end of the input.
+    read: Function,
+
+    /// The function adding a 128 bit number, given by its lower and upper half, to an extended cell value.
+    add: Function,
+
+    /// The function adding the 128 bit product of two numbers to an extended cell value.
+    multiply: Function,
+
+    /// The function moving the pointer by a number of cells and making sure that the cells in a given range around
+    /// the new current cell are on the tape.
+    reserve: Function,
+
+    /// The function aborting the program with a message for a source position.
+    fail: Function,
+
+    /// The function aborting the program with a message of the given length at the given address and an exit code.
+    abort: Function,
+
+    /// The function appending a zero-terminated string to a buffer.
+    append_string: Function,
+
+    /// The function appending a decimal number to a buffer.
+    append_number: Function,
+
+    /// The function moving an address by a number of bytes on a tape with a fixed size, wrapping around at its end.
+    wrap: Function,
+
+    /// The function moving an address back by a number of cells on a tape growing to the right, wrapping around at
+    /// its start.
+    wrap_left: Function,
+}
+
+impl Functions {
+    /// Create the identifiers of all functions in the `module`.
+    fn new(module: &mut Module) -> Functions {
+        Functions {
+            run: module.function(),
+            read_byte: module.function(),
+            write_byte: module.function(),
+            host_fail: module.function(),
+            fd_read: module.function(),
+            fd_write: module.function(),
+            proc_exit: module.function(),
+            flush: module.function(),
+            write_all: module.function(),
+            write: module.function(),
+            read: module.function(),
+            add: module.function(),
+            multiply: module.function(),
+            reserve: module.function(),
+            fail: module.function(),
+            abort: module.function(),
+            append_string: module.function(),
+            append_number: module.function(),
+            wrap: module.function(),
+            wrap_left: module.function(),
+        }
+    }
+}
+
+/// The addresses of the buffers and the tape in memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Layout {
+
+    /// The table of source positions reported in error messages.
+    positions: u32,
+
+    /// The buffer collecting the output.
+    output: u32,
+
+    /// The buffer for error messages.
+    error: u32,
+
+    /// The tape.
+    tape: u32,
+}
+
+/// The runtime functions called by the generated code.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Runtime {
+
+    /// Cells are changed by calling `$bf_add`.
+    add: bool,
+
+    /// Multiples of cells are added by calling `$bf_multiply`.
+    multiply: bool,
+
+    /// Characters are read by calling `$bf_read`.
+    read: bool,
+
+    /// Characters are written by calling `$bf_write` or the host's function.
+    write: bool,
+
+    /// The pointer is moved by calling `$bf_reserve`.
+    reserve: bool,
+
+    /// The pointer is moved around the ends of a fixed tape by calling `$bf_wrap`.
+    wrap: bool,
+
+    /// The cells before the current cell on a tape growing to the right are accessed by calling `$bf_wrap_left`.
+    wrap_left: bool,
+}
+
+/// A generator for creating `WebAssembly` modules in the text or the binary format from intermediate compile results.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WasmGenerator {
+
+    /// The options controlling the generated code.
+    options: Options,
+
+    /// The interface through which the generated module communicates with its environment.
+    interface: WasmInterface,
+
+    /// The module containing the generated code.
+    module: Module,
+
+    /// The identifiers of the functions in the module.
+    functions: Functions,
+
+    /// The body of the function running the program.
+    code: Vec<Operator>,
+
+    /// The source positions reported by the generated code, indexed by the IDs passed to the runtime functions.
+    positions: Vec<MetaData>,
+
+    /// The runtime functions needed by the generated code.
+    runtime: Runtime,
+}
+
+impl Default for WasmGenerator {
+    fn default() -> WasmGenerator {
+        WasmGenerator::new()
+    }
+}
+
+impl WasmGenerator {
+    /// Initialize a new generator with the default options.
+    pub fn new() -> WasmGenerator {
+        WasmGenerator::with_options(Options::default())
+    }
+
+    /// Initialize a new generator with the given `options`.
+    pub fn with_options(options: Options) -> WasmGenerator {
+        let mut module = Module::new();
+        let functions = Functions::new(&mut module);
+        WasmGenerator {
+            options,
+            interface: WasmInterface::Host,
+            module,
+            functions,
+            code: Vec::new(),
+            positions: Vec::new(),
+            runtime: Runtime::default(),
+        }
+    }
+
+    /// Generate a module in the `WebAssembly` text format from the given `program`, communicating with its
+    /// environment through the given `interface`.
+    pub fn generate_text(&mut self, program: &Program, interface: WasmInterface) -> String {
+        self.generate(program, interface).text()
+    }
+
+    /// Generate a module in the `WebAssembly` binary format from the given `program`, communicating with its
+    /// environment through the given `interface`.
+    pub fn generate_binary(&mut self, program: &Program, interface: WasmInterface) -> Vec<u8> {
+        self.generate(program, interface).encode()
+    }
+
+    /// Generate the module from the given `program`, communicating with its environment through the given
+    /// `interface`.
+    fn generate(&mut self, program: &Program, interface: WasmInterface) -> Module {
+        // The runtime functions and the memory layout depend on the code calling them.
+        self.interface = interface;
+        self.module = Module::new();
+        self.functions = Functions::new(&mut self.module);
+        self.code.clear();
+        self.positions.clear();
+        self.runtime = Runtime::default();
+        self.generate_block(&program.instructions);
+
+        let layout: Layout = self.allocate();
+
+        // Start at the tape's first cell, and flush the output at the end.
+        let mut body: Vec<Operator> = vec![Operator::I32Const(to_i32(layout.tape)), Operator::LocalSet(PTR)];
+        body.append(&mut self.code);
+        if interface == WasmInterface::Wasi {
+            body.push(Operator::Call(self.functions.flush));
+        }
+        let locals: &[(&str, ValueType)] = if self.runtime.read {
+            &[("ptr", ValueType::I32), ("character", ValueType::I32)]
+        } else {
+            &[("ptr", ValueType::I32)]
+        };
+        self.module.define(self.functions.run, "run", &[], None, locals, body);
+        let export: &str = match interface {
+            WasmInterface::Host => "run",
+            WasmInterface::Wasi => "_start",
+        };
+        self.module.export(export, self.functions.run);
+
+        self.generate_runtime(layout);
+        mem::replace(&mut self.module, Module::new())
+    }
+
+    /// Generate the code for the given list of `instructions` and the bodies of their loops.
+    fn generate_block(&mut self, instructions: &[Instruction]) {
+        // The current cell is on the tape when entering a block, but the other accessed cells might not be.
+        self.reserve_segment(instructions);
+
+        // The offset of the cell on which the currently generated multiplications depend, and if the current cell is
+        // still known not to be zero.
+        let mut guard: Option<isize> = None;
+        let mut nonzero: bool = false;
+        for step in walk(instructions) {
+            let (instruction, following): (&Instruction, &[Instruction]) = match step {
+                Step::Instruction(instruction, following) => (instruction, following),
+                Step::LoopEnd(_, following) => {
+                    // Close the loop, which only ends on a cell on the tape, but the cells accessed next might not be.
+                    self.close_guard(&mut guard);
+                    self.emit(&[Operator::Br(0), Operator::End, Operator::End]);
+                    nonzero = false;
+                    self.reserve_segment(following);
+                    continue;
+                },
+            };
+
+            let source: Option<isize> = match instruction.operation {
+                Operation::MulAdd { .. } => Some(instruction.offset),
+                _ => None,
+            };
+            nonzero = nonzero && source == Some(0);
+            if guard.is_some() && guard != source {
+                self.close_guard(&mut guard);
+            }
+
+            // Checked accesses are reported at the accessing instruction. Multiplications depending on the same cell
+            // only need a single check.
+            let offset: isize = instruction.offset;
+            if self.options.is_checked() && guard.is_none() && offset != 0 {
+                let position: Option<usize> = self.position(instruction, true);
+                self.reserve(0, offset.min(0), offset.max(0), position);
+            }
+
+            match instruction.operation {
+                Operation::Add(delta) if self.options.overflow == Overflow::Wrap => {
+                    // Change the value at the cell, wrapping around at the bounds of the cell type.
+                    let cell: u32 = self.address(instruction.offset);
+                    self.load(instruction.offset);
+                    self.constant(wrapped(delta, self.options.cell_type));
+                    self.emit(&[self.typed(Operator::I32Add, Operator::I64Add)]);
+                    self.store(cell);
+                },
+                Operation::Add(delta) => {
+                    // Change the value at the cell using the runtime function.
+                    let cell: u32 = self.address(instruction.offset);
+                    self.load(instruction.offset);
+                    self.extend();
+                    let upper: i64 = if delta < 0 { -1 } else { 0 };
+                    self.emit(&[Operator::I64Const(i64::from(delta)), Operator::I64Const(upper)]);
+                    self.add(instruction, self.functions.add, cell);
+                },
+                Operation::Move(offset) if self.options.is_reserving() => {
+                    // Go to another cell, making sure that it and the cells accessed next are on the tape.
+                    let (low, high): (isize, isize) = if self.options.is_checked() {
+                        (0, 0)
+                    } else {
+                        accessed_range(following, false)
+                    };
+                    let position: Option<usize> = self.position(instruction, self.options.is_checked());
+                    self.reserve(offset, low, high, position);
+                },
+                Operation::Move(offset) => {
+                    // Go to another cell.
+                    self.advance(offset);
+                },
+                Operation::Read if self.options.eof == EofBehavior::Unchanged => {
+                    // Read a single character into the cell, keeping the cell's value at the end of the input.
+                    self.runtime.read = true;
+                    self.emit(&[
+                        Operator::Call(self.functions.read),
+                        Operator::LocalTee(CHARACTER),
+                        Operator::I32Const(0),
+                        Operator::I32GeS,
+                        Operator::If,
+                    ]);
+                    let cell: u32 = self.address(instruction.offset);
+                    self.emit(&[Operator::LocalGet(CHARACTER)]);
+                    self.extend_character();
+                    self.store(cell);
+                    self.emit(&[Operator::End]);
+                },
+                Operation::Read => {
+                    // Read a single character into the cell.
+                    self.runtime.read = true;
+                    let cell: u32 = self.address(instruction.offset);
+                    self.emit(&[Operator::Call(self.functions.read)]);
+                    self.extend_character();
+                    self.store(cell);
+                },
+                Operation::Write => {
+                    // Print the character of the cell, i.e. its lowest byte.
+                    self.runtime.write = true;
+                    self.load(instruction.offset);
+                    if self.options.cell_type == CellType::I64 {
+                        self.emit(&[Operator::I32WrapI64]);
+                    }
+                    if self.options.cell_type != CellType::U8 {
+                        self.emit(&[Operator::I32Const(0xFF), Operator::I32And]);
+                    }
+                    let write: Function = match self.interface {
+                        WasmInterface::Host => self.functions.write_byte,
+                        WasmInterface::Wasi => self.functions.write,
+                    };
+                    self.emit(&[Operator::Call(write)]);
+                },
+                Operation::Loop(ref body) => {
+                    // Repeat the body as long as the current cell is not zero.
+                    self.emit(&[Operator::Block, Operator::Loop]);
+                    self.is_zero(0);
+                    self.emit(&[Operator::BrIf(1)]);
+                    self.reserve_segment(body);
+                    nonzero = true;
+                },
+                Operation::Clear => {
+                    // Set the cell to zero.
+                    let cell: u32 = self.address(instruction.offset);
+                    self.constant(0);
+                    self.store(cell);
+                },
+                Operation::MulAdd { offset, factor } if self.options.overflow == Overflow::Wrap => {
+                    // Add a multiple of the cell to another cell, wrapping around at the bounds of the cell type.
+                    self.multiply(instruction, offset, &mut guard, nonzero);
+                    let target: isize = instruction.offset.wrapping_add(offset);
+                    let cell: u32 = self.address(target);
+                    self.load(target);
+                    self.load(instruction.offset);
+                    let factor: i64 = wrapped(factor, self.options.cell_type);
+                    if factor != 1 {
+                        self.constant(factor);
+                        self.emit(&[self.typed(Operator::I32Mul, Operator::I64Mul)]);
+                    }
+                    self.emit(&[self.typed(Operator::I32Add, Operator::I64Add)]);
+                    self.store(cell);
+                },
+                Operation::MulAdd { offset, factor } => {
+                    // Add a multiple of the cell to another cell using the runtime function.
+                    self.multiply(instruction, offset, &mut guard, nonzero);
+                    self.runtime.multiply = true;
+                    let target: isize = instruction.offset.wrapping_add(offset);
+                    let cell: u32 = self.address(target);
+                    self.load(target);
+                    self.extend();
+                    self.load(instruction.offset);
+                    self.extend();
+                    self.emit(&[Operator::I64Const(i64::from(factor))]);
+                    self.add(instruction, self.functions.multiply, cell);
+                },
+                Operation::Scan { stride } => {
+                    // Step through the cells until reaching a zero cell.
+                    self.emit(&[Operator::Block, Operator::Loop]);
+                    self.is_zero(0);
+                    self.emit(&[Operator::BrIf(1)]);
+                    if self.options.is_reserving() {
+                        let position: Option<usize> = self.position(instruction, self.options.is_checked());
+                        self.reserve(stride, 0, 0, position);
+                    } else {
+                        self.advance(stride);
+                    }
+                    self.emit(&[Operator::Br(0), Operator::End, Operator::End]);
+                },
+            }
+
+            // Scans only end on a cell on the tape, but the cells accessed next might not be.
+            if let Operation::Scan { .. } = instruction.operation {
+                self.reserve_segment(following);
+            }
+        }
+
+        self.close_guard(&mut guard);
+    }
+
+    /// Call the runtime `function` changing a cell, which reports overflows at the `instruction` if the options
+    /// require it, and store the result in the `cell`, whose address is already on the stack.
+    fn add(&mut self, instruction: &Instruction, function: Function, cell: u32) {
+        self.runtime.add = true;
+        if let Some(id) = self.position(instruction, self.options.overflow == Overflow::Error) {
+            self.emit(&[Operator::I32Const(to_i32(id))]);
+        }
+        self.emit(&[Operator::Call(function)]);
+        if self.options.cell_type != CellType::I64 {
+            self.emit(&[Operator::I32WrapI64]);
+        }
+        self.store(cell);
+    }
+
+    /// Prepare the multiplication of the `instruction`'s cell with another cell `offset` cells away from it. Like in
+    /// the replaced loop, the target cell is only accessed if the instruction's cell is not zero, so the
+    /// multiplication is placed within a condition, which is opened unless the previous multiplication depends on the
+    /// same cell and the `guard` is still open, or the instruction's cell is known to be `nonzero`. Within the
+    /// condition, a growing or checked tape is made to contain the target cell.
+    fn multiply(&mut self, instruction: &Instruction, offset: isize, guard: &mut Option<isize>, nonzero: bool) {
+        if guard.is_none() && !nonzero {
+            self.load(instruction.offset);
+            if self.options.cell_type == CellType::I64 {
+                self.emit(&[Operator::I64Eqz, Operator::I32Eqz]);
+            }
+            self.emit(&[Operator::If]);
+            *guard = Some(instruction.offset);
+        }
+
+        if self.options.is_reserving() {
+            let target: isize = instruction.offset.wrapping_add(offset);
+            let position: Option<usize> = self.position(instruction, self.options.is_checked());
+            self.reserve(0, target.min(0), target.max(0), position);
+        }
+    }
+
+    /// Close the condition of the multiplications if the `guard` is open.
+    fn close_guard(&mut self, guard: &mut Option<isize>) {
+        if guard.take().is_some() {
+            self.emit(&[Operator::End]);
+        }
+    }
+
+    /// Call the runtime function moving the pointer by `offset` cells and making sure that the cells from `low` to
+    /// `high` cells away from the new current cell are on the tape. The ID of the source `position` is passed on to
+    /// report errors on checked tapes. The cells before the start of a tape wrapping around do not have to be on the
+    /// tape.
+    fn reserve(&mut self, offset: isize, low: isize, high: isize, position: Option<usize>) {
+        let low: isize = if self.options.is_wrapping() { low.max(0) } else { low };
+        if offset == 0 && low == 0 && high == 0 {
+            return;
+        }
+
+        self.runtime.reserve = true;
+        self.emit(&[
+            Operator::LocalGet(PTR),
+            Operator::I32Const(to_i32(offset)),
+            Operator::I32Const(to_i32(low)),
+            Operator::I32Const(to_i32(high)),
+        ]);
+        if let Some(id) = position {
+            self.emit(&[Operator::I32Const(to_i32(id))]);
+        }
+        self.emit(&[Operator::Call(self.functions.reserve), Operator::LocalSet(PTR)]);
+    }
+
+    /// Make sure that a growing tape contains all cells accessed by the `instructions` up to the next pointer
+    /// movement. Checked tapes are handled for each instruction instead.
+    fn reserve_segment(&mut self, instructions: &[Instruction]) {
+        if let Some((low, high)) = reserved_range(instructions, &self.options) {
+            self.reserve(0, low, high, None);
+        }
+    }
+
+    /// Move the pointer by `offset` cells on an unchecked tape with a fixed size, wrapping around at its ends.
+    fn advance(&mut self, offset: isize) {
+        if self.wrap(offset) != 0 {
+            self.index(offset);
+            self.emit(&[Operator::LocalSet(PTR)]);
+        }
+    }
+
+    /// Push the address of the cell `offset` cells away from the current cell on an unchecked tape with a fixed size
+    /// onto the stack, wrapping around at the tape's ends.
+    fn index(&mut self, offset: isize) {
+        self.runtime.wrap = true;
+        let offset: isize = self.wrap(offset);
+        let distance: isize = if offset < 0 { offset + to_isize(self.tape_size()) } else { offset };
+        self.emit(&[
+            Operator::LocalGet(PTR),
+            Operator::I32Const(to_i32(distance.saturating_mul(self.cell_bytes()))),
+            Operator::Call(self.functions.wrap),
+        ]);
+    }
+
+    /// Reduce the `offset` to the number of cells on an unchecked tape with a fixed size, on which the pointer wraps
+    /// around at the ends.
+    fn wrap(&self, offset: isize) -> isize {
+        if self.options.is_reserving() {
+            offset
+        } else {
+            offset % to_isize(self.tape_size())
+        }
+    }
+
+    /// Push the address of the cell `offset` cells away from the current cell onto the stack, except for the constant
+    /// offset that can be added by the memory access, which is returned.
+    fn address(&mut self, offset: isize) -> u32 {
+        if self.wrap(offset) != 0 && !self.options.is_reserving() {
+            self.index(offset);
+            return 0;
+        }
+
+        if offset < 0 && self.options.is_reserving() && self.options.is_wrapping() {
+            self.runtime.wrap_left = true;
+            self.emit(&[
+                Operator::LocalGet(PTR),
+                Operator::I32Const(to_i32(offset.unsigned_abs())),
+                Operator::Call(self.functions.wrap_left),
+            ]);
+            return 0;
+        }
+
+        let displacement: i32 = to_i32(offset.saturating_mul(self.cell_bytes()));
+        self.emit(&[Operator::LocalGet(PTR)]);
+        if let Ok(displacement) = u32::try_from(displacement) {
+            return displacement;
+        }
+
+        self.emit(&[Operator::I32Const(displacement), Operator::I32Add]);
+        0
+    }
+
+    /// Push the value of the cell `offset` cells away from the current cell onto the stack.
+    fn load(&mut self, offset: isize) {
+        let displacement: u32 = self.address(offset);
+        self.emit(&[Operator::Load(self.access(), displacement)]);
+    }
+
+    /// Store the value on the stack in the cell whose address is below it on the stack, adding the constant
+    /// `displacement`.
+    fn store(&mut self, displacement: u32) {
+        self.emit(&[Operator::Store(self.access(), displacement)]);
+    }
+
+    /// Push the condition that the cell `offset` cells away from the current cell is zero onto the stack.
+    fn is_zero(&mut self, offset: isize) {
+        self.load(offset);
+        self.emit(&[self.typed(Operator::I32Eqz, Operator::I64Eqz)]);
+    }
+
+    /// Push the `value` as a constant of the type on which the cells are operated onto the stack.
+    fn constant(&mut self, value: i64) {
+        let constant: Operator = match self.options.cell_type {
+            CellType::I64 => Operator::I64Const(value),
+            _ => Operator::I32Const(to_i32(value)),
+        };
+        self.emit(&[constant]);
+    }
+
+    /// Extend the cell value on the stack to a 64 bit number.
+    fn extend(&mut self) {
+        if self.options.cell_type != CellType::I64 {
+            self.emit(&[Operator::I64ExtendI32U]);
+        }
+    }
+
+    /// Extend the character on the stack, which is `-1` at the end of the input, to the type on which the cells are
+    /// operated.
+    fn extend_character(&mut self) {
+        if self.options.cell_type == CellType::I64 {
+            self.emit(&[Operator::I64ExtendI32S]);
+        }
+    }
+
+    /// Append the `operators` to the body of the function running the program.
+    fn emit(&mut self, operators: &[Operator]) {
+        self.code.extend_from_slice(operators);
+    }
+
+    /// Get the operator for 64 bit cells if the cells have 64 bits, the `narrow` one for 32 bit numbers otherwise.
+    fn typed(&self, narrow: Operator, wide: Operator) -> Operator {
+        if self.options.cell_type == CellType::I64 { wide } else { narrow }
+    }
+
+    /// Get the width of memory accesses to cells.
+    fn access(&self) -> Access {
+        match self.options.cell_type {
+            CellType::U8 => Access::Byte,
+            CellType::U16 => Access::Half,
+            CellType::U32 => Access::Word,
+            CellType::I64 => Access::Quad,
+        }
+    }
+
+    /// Get the binary logarithm of the size of the cells in bytes.
+    fn cell_shift(&self) -> i32 {
+        match self.options.cell_type {
+            CellType::U8 => 0,
+            CellType::U16 => 1,
+            CellType::U32 => 2,
+            CellType::I64 => 3,
+        }
+    }
+
+    /// Get the size of the cells in bytes.
+    fn cell_bytes(&self) -> isize {
+        1 << self.cell_shift()
+    }
+
+    /// Get the initial number of cells on the tape.
+    fn tape_size(&self) -> usize {
+        self.options.tape_size.max(1)
+    }
+
+    /// Determine if the module contains the function aborting the program, i.e. if errors are reported at source
+    /// positions, or if the memory might be exhausted by a growing tape.
+    fn is_aborting(&self) -> bool {
+        !self.positions.is_empty() || (self.options.tape_growth != TapeGrowth::Fixed && self.runtime.reserve)
+    }
+
+    /// Lay out the buffers and the tape in memory, initializing the memory with the messages and the table of source
+    /// positions if they are needed.
+    fn allocate(&mut self) -> Layout {
+        let positions: u32 = align(MESSAGES_ADDRESS + to_u32(MESSAGES.len()), 4);
+        let mut address: u32 = positions + 8 * to_u32(self.positions.len());
+        let output: u32 = address;
+        if self.interface == WasmInterface::Wasi {
+            address += OUTPUT_SIZE;
+        }
+        let error: u32 = address;
+        if !self.positions.is_empty() {
+            address += ERROR_SIZE;
+        }
+        let tape: u32 = align(address, 8);
+
+        let tape_bytes: usize = self.tape_size().saturating_mul(1 << self.cell_shift());
+        let end: u32 = tape.checked_add(to_u32(tape_bytes)).expect("The tape fits into the memory.");
+        self.module.memory(end.div_ceil(PAGE_SIZE));
+
+        if self.is_aborting() {
+            self.module.data(MESSAGES_ADDRESS, MESSAGES);
+        }
+        if !self.positions.is_empty() {
+            let mut table: Vec<u8> = Vec::with_capacity(8 * self.positions.len());
+            for position in &self.positions {
+                table.extend_from_slice(&u32::try_from(position.lineno).unwrap_or(u32::MAX).to_le_bytes());
+                table.extend_from_slice(&u32::try_from(position.position).unwrap_or(u32::MAX).to_le_bytes());
+            }
+            self.module.data(positions, &table);
+        }
+
+        Layout { positions, output, error, tape }
+    }
+
+    /// Import and generate the functions called by the generated code.
+    fn generate_runtime(&mut self, layout: Layout) {
+        let aborting: bool = self.is_aborting();
+        let functions: Functions = self.functions;
+        match self.interface {
+            WasmInterface::Host => {
+                if self.runtime.read {
+                    self.module.import(functions.read_byte, "env", "read_byte", "read_byte", &[],
+                                       Some(ValueType::I32));
+                }
+                if self.runtime.write {
+                    self.module.import(functions.write_byte, "env", "write_byte", "write_byte", &[ValueType::I32],
+                                       None);
+                }
+                if aborting {
+                    self.module.import(functions.host_fail, "env", "fail", "fail",
+                                       &[ValueType::I32, ValueType::I32], None);
+                }
+            },
+            WasmInterface::Wasi => {
+                let io: [ValueType; 4] = [ValueType::I32; 4];
+                if self.runtime.read {
+                    self.module.import(functions.fd_read, "wasi_snapshot_preview1", "fd_read", "fd_read", &io,
+                                       Some(ValueType::I32));
+                }
+                self.module.import(functions.fd_write, "wasi_snapshot_preview1", "fd_write", "fd_write", &io,
+                                   Some(ValueType::I32));
+                if aborting {
+                    self.module.import(functions.proc_exit, "wasi_snapshot_preview1", "proc_exit", "proc_exit",
+                                       &[ValueType::I32], None);
+                }
+                self.generate_output_runtime(layout);
+            },
+        }
+
+        if self.runtime.read {
+            self.generate_reading_runtime();
+        }
+
+        if self.runtime.add {
+            self.generate_arithmetic_runtime();
+        }
+
+        match self.options.tape_growth {
+            TapeGrowth::Fixed if self.runtime.reserve => self.generate_checked_fixed_tape(layout),
+            TapeGrowth::Fixed if self.runtime.wrap => self.generate_wrapping_tape(layout),
+            TapeGrowth::Fixed => {},
+            TapeGrowth::Right | TapeGrowth::Both if self.runtime.reserve || self.runtime.wrap_left => {
+                let size: u32 = self.module.global("tape_size", ValueType::I32, to_i64(self.tape_size()));
+                if self.runtime.reserve {
+                    self.generate_growing_reserve(layout, size);
+                }
+                if self.runtime.wrap_left {
+                    self.generate_wrapping_left(layout, size);
+                }
+            },
+            TapeGrowth::Right | TapeGrowth::Both => {},
+        }
+
+        if aborting {
+            self.generate_failing_runtime(layout);
+        }
+    }
+
+    /// Generate the `WASI` functions `$bf_write_all`, writing a number of bytes at an address to a file descriptor,
+    /// `$bf_flush`, writing the output buffer to the standard output, and `$bf_write`, appending a byte to the buffer.
+    fn generate_output_runtime(&mut self, layout: Layout) {
+        let functions: Functions = self.functions;
+        let length: u32 = self.module.global("output_length", ValueType::I32, 0);
+        let (descriptor, address, remaining): (u32, u32, u32) = (0, 1, 2);
+        self.module.define(functions.write_all, "bf_write_all",
+                           &[("descriptor", ValueType::I32), ("address", ValueType::I32),
+                             ("length", ValueType::I32)],
+                           None, &[], vec![
+            Operator::Block,
+            Operator::Loop,
+            Operator::LocalGet(remaining),
+            Operator::I32Eqz,
+            Operator::BrIf(1),
+            Operator::I32Const(IO_VECTOR),
+            Operator::LocalGet(address),
+            Operator::Store(Access::Word, 0),
+            Operator::I32Const(IO_VECTOR),
+            Operator::LocalGet(remaining),
+            Operator::Store(Access::Word, 4),
+            Operator::LocalGet(descriptor),
+            Operator::I32Const(IO_VECTOR),
+            Operator::I32Const(1),
+            Operator::I32Const(IO_COUNT),
+            Operator::Call(functions.fd_write),
+            Operator::BrIf(1),
+            Operator::LocalGet(address),
+            Operator::I32Const(IO_COUNT),
+            Operator::Load(Access::Word, 0),
+            Operator::I32Add,
+            Operator::LocalSet(address),
+            Operator::LocalGet(remaining),
+            Operator::I32Const(IO_COUNT),
+            Operator::Load(Access::Word, 0),
+            Operator::I32Sub,
+            Operator::LocalSet(remaining),
+            Operator::Br(0),
+            Operator::End,
+            Operator::End,
+        ]);
+
+        self.module.define(functions.flush, "bf_flush", &[], None, &[], vec![
+            Operator::I32Const(1),
+            Operator::I32Const(to_i32(layout.output)),
+            Operator::GlobalGet(length),
+            Operator::Call(functions.write_all),
+            Operator::I32Const(0),
+            Operator::GlobalSet(length),
+        ]);
+
+        if self.runtime.write {
+            // Flush the buffer once it is full.
+            self.module.define(functions.write, "bf_write", &[("byte", ValueType::I32)], None, &[], vec![
+                Operator::GlobalGet(length),
+                Operator::LocalGet(0),
+                Operator::Store(Access::Byte, layout.output),
+                Operator::GlobalGet(length),
+                Operator::I32Const(1),
+                Operator::I32Add,
+                Operator::GlobalSet(length),
+                Operator::GlobalGet(length),
+                Operator::I32Const(to_i32(OUTPUT_SIZE)),
+                Operator::I32Ne,
+                Operator::BrIf(0),
+                Operator::Call(functions.flush),
+            ]);
+        }
+    }
+
+    /// Generate the function `$bf_read`, returning the next character from the input, or the value for the end of
+    /// the input.
+    fn generate_reading_runtime(&mut self) {
+        let eof: i32 = if self.options.eof == EofBehavior::Zero { 0 } else { -1 };
+        let functions: Functions = self.functions;
+        match self.interface {
+            WasmInterface::Host => {
+                self.module.define(functions.read, "bf_read", &[], Some(ValueType::I32),
+                                   &[("character", ValueType::I32)], vec![
+                    Operator::Call(functions.read_byte),
+                    Operator::LocalTee(0),
+                    Operator::I32Const(0),
+                    Operator::I32LtS,
+                    Operator::If,
+                    Operator::I32Const(eof),
+                    Operator::Return,
+                    Operator::End,
+                    Operator::LocalGet(0),
+                ]);
+            },
+            WasmInterface::Wasi => {
+                // The output is flushed first, so prompts are visible before waiting for the input.
+                self.module.define(functions.read, "bf_read", &[], Some(ValueType::I32), &[], vec![
+                    Operator::Call(functions.flush),
+                    Operator::I32Const(IO_VECTOR),
+                    Operator::I32Const(INPUT),
+                    Operator::Store(Access::Word, 0),
+                    Operator::I32Const(IO_VECTOR),
+                    Operator::I32Const(1),
+                    Operator::Store(Access::Word, 4),
+                    Operator::I32Const(0),
+                    Operator::I32Const(IO_VECTOR),
+                    Operator::I32Const(1),
+                    Operator::I32Const(IO_COUNT),
+                    Operator::Call(functions.fd_read),
+                    Operator::I32Const(IO_COUNT),
+                    Operator::Load(Access::Word, 0),
+                    Operator::I32Eqz,
+                    Operator::I32Or,
+                    Operator::If,
+                    Operator::I32Const(eof),
+                    Operator::Return,
+                    Operator::End,
+                    Operator::I32Const(INPUT),
+                    Operator::Load(Access::Byte, 0),
+                ]);
+            },
+        }
+    }
+
+    /// Generate the function `$bf_add`, adding a 128 bit number, given by its lower and upper half, to an extended
+    /// cell value and returning the new value, and the function `$bf_multiply`, adding the 128 bit product of two
+    /// numbers to an extended cell value, if it is needed. Sums outside the bounds of the cells are saturated, or
+    /// abort the program with the source position given by the last parameter.
+    fn generate_arithmetic_runtime(&mut self) {
+        let functions: Functions = self.functions;
+        let checked: bool = self.options.overflow == Overflow::Error;
+        let id: u32 = 3;
+        let first_local: u32 = if checked { 4 } else { 3 };
+        let mut parameters: Vec<(&'static str, ValueType)> = vec![
+            ("value", ValueType::I64),
+            ("low", ValueType::I64),
+            ("high", ValueType::I64),
+        ];
+        if checked {
+            parameters.push(("id", ValueType::I32));
+        }
+
+        // Add the halves with carry, extending the value's sign into the upper half.
+        let (value, low, high, sum): (u32, u32, u32, u32) = (0, 1, 2, first_local);
+        let mut body: Vec<Operator> = vec![
+            Operator::LocalGet(low),
+            Operator::LocalGet(value),
+            Operator::I64Add,
+            Operator::LocalSet(sum),
+            Operator::LocalGet(high),
+            Operator::LocalGet(value),
+            Operator::I64Const(63),
+            Operator::I64ShrS,
+            Operator::I64Add,
+            Operator::LocalGet(sum),
+            Operator::LocalGet(low),
+            Operator::I64LtU,
+            Operator::I64ExtendI32U,
+            Operator::I64Add,
+            Operator::LocalSet(high),
+        ];
+        let bounds: [(i64, Operator, Operator); 2] = [
+            (self.options.cell_type.min(), Operator::I64LtS, Operator::I64LtU),
+            (self.options.cell_type.max(), Operator::I64GtS, Operator::I64GtU),
+        ];
+        for &(bound, signed, unsigned) in bounds.iter() {
+            // Compare the 128 bit sum with the sign-extended bound.
+            let upper: i64 = bound >> 63;
+            body.extend_from_slice(&[
+                Operator::LocalGet(high),
+                Operator::I64Const(upper),
+                signed,
+                Operator::LocalGet(high),
+                Operator::I64Const(upper),
+                Operator::I64Eq,
+                Operator::LocalGet(sum),
+                Operator::I64Const(bound),
+                unsigned,
+                Operator::I32And,
+                Operator::I32Or,
+                Operator::If,
+            ]);
+            if checked {
+                body.extend_from_slice(&[
+                    Operator::I32Const(CELL_OVERFLOW),
+                    Operator::LocalGet(id),
+                    Operator::Call(functions.fail),
+                    Operator::Unreachable,
+                ]);
+            } else {
+                body.extend_from_slice(&[Operator::I64Const(bound), Operator::Return]);
+            }
+            body.push(Operator::End);
+        }
+        body.push(Operator::LocalGet(sum));
+        self.module.define(functions.add, "bf_add", &parameters, Some(ValueType::I64), &[("sum", ValueType::I64)],
+                           body);
+
+        if !self.runtime.multiply {
+            return;
+        }
+
+        // Multiply the signed upper and the unsigned lower 32 bits of the source separately, and add the partial
+        // products shifted into place.
+        let (source, factor): (u32, u32) = (1, 2);
+        let (upper, lower, product): (u32, u32, u32) = (first_local, first_local + 1, first_local + 2);
+        parameters[1] = ("source", ValueType::I64);
+        parameters[2] = ("factor", ValueType::I64);
+        let mut body: Vec<Operator> = vec![
+            Operator::LocalGet(source),
+            Operator::I64Const(32),
+            Operator::I64ShrS,
+            Operator::LocalGet(factor),
+            Operator::I64Mul,
+            Operator::LocalSet(upper),
+            Operator::LocalGet(source),
+            Operator::I64Const(0xFFFF_FFFF),
+            Operator::I64And,
+            Operator::LocalGet(factor),
+            Operator::I64Mul,
+            Operator::LocalSet(lower),
+            Operator::LocalGet(value),
+            Operator::LocalGet(upper),
+            Operator::I64Const(32),
+            Operator::I64Shl,
+            Operator::LocalGet(lower),
+            Operator::I64Add,
+            Operator::LocalTee(product),
+            Operator::LocalGet(upper),
+            Operator::I64Const(32),
+            Operator::I64ShrS,
+            Operator::LocalGet(lower),
+            Operator::I64Const(63),
+            Operator::I64ShrS,
+            Operator::I64Add,
+            Operator::LocalGet(product),
+            Operator::LocalGet(upper),
+            Operator::I64Const(32),
+            Operator::I64Shl,
+            Operator::I64LtU,
+            Operator::I64ExtendI32U,
+            Operator::I64Add,
+        ];
+        if checked {
+            body.push(Operator::LocalGet(id));
+        }
+        body.push(Operator::Call(functions.add));
+        self.module.define(functions.multiply, "bf_multiply", &parameters, Some(ValueType::I64),
+                           &[("upper", ValueType::I64), ("lower", ValueType::I64), ("product", ValueType::I64)],
+                           body);
+    }
+
+    /// Generate the function `$bf_reserve`, moving the pointer by a number of cells on a tape with a fixed size and
+    /// aborting the program with the given source position if the cells in the given range around the new current
+    /// cell are not on the tape.
+    fn generate_checked_fixed_tape(&mut self, layout: Layout) {
+        let functions: Functions = self.functions;
+        let tape: i32 = to_i32(layout.tape);
+        let (ptr, offset, low, high, id, index): (u32, u32, u32, u32, u32, u32) = (0, 1, 2, 3, 4, 5);
+        self.module.define(functions.reserve, "bf_reserve", &reserve_parameters(true), Some(ValueType::I32),
+                           &[("index", ValueType::I32)], vec![
+            Operator::LocalGet(ptr),
+            Operator::I32Const(tape),
+            Operator::I32Sub,
+            Operator::I32Const(self.cell_shift()),
+            Operator::I32ShrS,
+            Operator::LocalGet(offset),
+            Operator::I32Add,
+            Operator::LocalTee(index),
+            Operator::LocalGet(low),
+            Operator::I32Add,
+            Operator::I32Const(0),
+            Operator::I32LtS,
+            Operator::If,
+            Operator::I32Const(POINTER_UNDERFLOW),
+            Operator::LocalGet(id),
+            Operator::Call(functions.fail),
+            Operator::Unreachable,
+            Operator::End,
+            Operator::LocalGet(index),
+            Operator::LocalGet(high),
+            Operator::I32Add,
+            Operator::I32Const(to_i32(self.tape_size())),
+            Operator::I32GeS,
+            Operator::If,
+            Operator::I32Const(POINTER_OVERFLOW),
+            Operator::LocalGet(id),
+            Operator::Call(functions.fail),
+            Operator::Unreachable,
+            Operator::End,
+            Operator::LocalGet(index),
+            Operator::I32Const(self.cell_shift()),
+            Operator::I32Shl,
+            Operator::I32Const(tape),
+            Operator::I32Add,
+        ]);
+    }
+
+    /// Generate the function `$bf_wrap`, moving an address on a tape with a fixed size by a number of bytes smaller
+    /// than the tape's size, and continuing at the tape's start when passing its end.
+    fn generate_wrapping_tape(&mut self, layout: Layout) {
+        let end: usize = self.tape_size().saturating_mul(1 << self.cell_shift());
+        let (ptr, distance, position): (u32, u32, u32) = (0, 1, 2);
+        self.module.define(self.functions.wrap, "bf_wrap", &[("ptr", ValueType::I32), ("distance", ValueType::I32)],
+                           Some(ValueType::I32), &[("position", ValueType::I32)], vec![
+            Operator::LocalGet(ptr),
+            Operator::LocalGet(distance),
+            Operator::I32Add,
+            Operator::LocalTee(position),
+            Operator::LocalGet(position),
+            Operator::I32Const(to_i32(end)),
+            Operator::I32Sub,
+            Operator::LocalGet(position),
+            Operator::I32Const(to_i32(layout.tape + to_u32(end))),
+            Operator::I32LtS,
+            Operator::Select,
+        ]);
+    }
+
+    /// Generate the function `$bf_reserve`, moving the pointer by a number of cells on a growing tape and growing the
+    /// tape until the cells in the given range around the new current cell are on it. Accesses before the tape's
+    /// start are reported at the given source position if they are checked. On a tape wrapping around, a new position
+    /// before the tape's start continues at its current end. The number of cells on the tape is kept in the global
+    /// variable `size`.
+    fn generate_growing_reserve(&mut self, layout: Layout, size: u32) {
+        let functions: Functions = self.functions;
+        let checked: bool = self.options.is_checked();
+        let wrapping: bool = self.options.is_wrapping();
+        let both: bool = self.options.tape_growth == TapeGrowth::Both;
+        let tape: i32 = to_i32(layout.tape);
+        let shift: i32 = self.cell_shift();
+        let (ptr, offset, low, high, id): (u32, u32, u32, u32, u32) = (0, 1, 2, 3, 4);
+        let first_local: u32 = if checked { 5 } else { 4 };
+        let (index, first, last, before, after, grown, pages): (u32, u32, u32, u32, u32, u32, u32) = (
+            first_local, first_local + 1, first_local + 2, first_local + 3, first_local + 4, first_local + 5,
+            first_local + 6,
+        );
+
+        // Skip the growth if all cells are on the tape.
+        let mut body: Vec<Operator> = vec![
+            Operator::LocalGet(ptr),
+            Operator::I32Const(tape),
+            Operator::I32Sub,
+            Operator::I32Const(shift),
+            Operator::I32ShrS,
+            Operator::LocalGet(offset),
+            Operator::I32Add,
+            Operator::LocalTee(index),
+            Operator::LocalGet(low),
+            Operator::I32Add,
+            Operator::LocalSet(first),
+            Operator::LocalGet(index),
+            Operator::LocalGet(high),
+            Operator::I32Add,
+            Operator::LocalSet(last),
+            Operator::Block,
+            Operator::LocalGet(first),
+            Operator::I32Const(0),
+            Operator::I32GeS,
+            Operator::LocalGet(last),
+            Operator::GlobalGet(size),
+            Operator::I32LtS,
+            Operator::I32And,
+            Operator::BrIf(0),
+        ];
+        if checked {
+            body.extend_from_slice(&[
+                Operator::LocalGet(first),
+                Operator::I32Const(0),
+                Operator::I32LtS,
+                Operator::If,
+                Operator::I32Const(POINTER_UNDERFLOW),
+                Operator::LocalGet(id),
+                Operator::Call(functions.fail),
+                Operator::Unreachable,
+                Operator::End,
+            ]);
+        }
+        if wrapping {
+            body.extend(wrapped_left(index, size));
+            body.extend_from_slice(&[
+                Operator::LocalTee(index),
+                Operator::LocalGet(high),
+                Operator::I32Add,
+                Operator::LocalSet(last),
+            ]);
+        }
+
+        // Compute the number of cells to add before the tape's start and after its end, at least doubling the
+        // tape's size.
+        if both {
+            body.extend_from_slice(&[
+                Operator::LocalGet(first),
+                Operator::I32Const(0),
+                Operator::I32LtS,
+                Operator::If,
+                Operator::GlobalGet(size),
+                Operator::I32Const(0),
+                Operator::LocalGet(first),
+                Operator::I32Sub,
+                Operator::LocalTee(before),
+                Operator::LocalGet(before),
+                Operator::GlobalGet(size),
+                Operator::I32LtS,
+                Operator::Select,
+                Operator::LocalSet(before),
+                Operator::End,
+            ]);
+        }
+        body.extend_from_slice(&[
+            Operator::LocalGet(last),
+            Operator::GlobalGet(size),
+            Operator::I32GeS,
+            Operator::If,
+            Operator::GlobalGet(size),
+            Operator::LocalGet(last),
+            Operator::I32Const(1),
+            Operator::I32Add,
+            Operator::GlobalGet(size),
+            Operator::I32Sub,
+            Operator::LocalTee(after),
+            Operator::LocalGet(after),
+            Operator::GlobalGet(size),
+            Operator::I32LtS,
+            Operator::Select,
+            Operator::LocalSet(after),
+            Operator::End,
+            Operator::GlobalGet(size),
+            Operator::LocalGet(before),
+            Operator::I32Add,
+            Operator::LocalGet(after),
+            Operator::I32Add,
+            Operator::LocalSet(grown),
+        ]);
+
+        // Grow the memory if the grown tape does not fit into it.
+        body.extend_from_slice(&[
+            Operator::LocalGet(grown),
+            Operator::I32Const(shift),
+            Operator::I32Shl,
+            Operator::I32Const(tape + to_i32(PAGE_SIZE - 1)),
+            Operator::I32Add,
+            Operator::I32Const(16),
+            Operator::I32ShrU,
+            Operator::MemorySize,
+            Operator::I32Sub,
+            Operator::LocalSet(pages),
+            Operator::I32Const(0),
+            Operator::LocalGet(pages),
+            Operator::I32LtS,
+            Operator::If,
+            Operator::LocalGet(pages),
+            Operator::MemoryGrow,
+            Operator::I32Const(0),
+            Operator::I32LtS,
+            Operator::If,
+            Operator::I32Const(OUT_OF_MEMORY),
+            Operator::I32Const(14),
+            Operator::I32Const(1),
+            Operator::Call(functions.abort),
+            Operator::End,
+            Operator::End,
+        ]);
+
+        // Move the cells behind the ones added before the tape's start, which are cleared.
+        if both {
+            body.extend_from_slice(&[
+                Operator::LocalGet(before),
+                Operator::If,
+                Operator::I32Const(tape),
+                Operator::LocalGet(before),
+                Operator::I32Const(shift),
+                Operator::I32Shl,
+                Operator::I32Add,
+                Operator::I32Const(tape),
+                Operator::GlobalGet(size),
+                Operator::I32Const(shift),
+                Operator::I32Shl,
+                Operator::MemoryCopy,
+                Operator::I32Const(tape),
+                Operator::I32Const(0),
+                Operator::LocalGet(before),
+                Operator::I32Const(shift),
+                Operator::I32Shl,
+                Operator::MemoryFill,
+                Operator::LocalGet(index),
+                Operator::LocalGet(before),
+                Operator::I32Add,
+                Operator::LocalSet(index),
+                Operator::End,
+            ]);
+        }
+        body.extend_from_slice(&[
+            Operator::LocalGet(grown),
+            Operator::GlobalSet(size),
+            Operator::End,
+            Operator::LocalGet(index),
+            Operator::I32Const(shift),
+            Operator::I32Shl,
+            Operator::I32Const(tape),
+            Operator::I32Add,
+        ]);
+
+        let locals: [(&str, ValueType); 7] = [
+            ("index", ValueType::I32),
+            ("first", ValueType::I32),
+            ("last", ValueType::I32),
+            ("before", ValueType::I32),
+            ("after", ValueType::I32),
+            ("grown", ValueType::I32),
+            ("pages", ValueType::I32),
+        ];
+        self.module.define(functions.reserve, "bf_reserve", &reserve_parameters(checked), Some(ValueType::I32),
+                           &locals, body);
+    }
+
+    /// Generate the function `$bf_wrap_left`, moving an address on a tape growing to the right back by a number of
+    /// cells, and continuing at the tape's current end when passing its start. The number of cells on the tape is kept
+    /// in the global variable `size`.
+    fn generate_wrapping_left(&mut self, layout: Layout, size: u32) {
+        let tape: i32 = to_i32(layout.tape);
+        let shift: i32 = self.cell_shift();
+        let (ptr, distance, position): (u32, u32, u32) = (0, 1, 2);
+        let mut body: Vec<Operator> = vec![
+            Operator::LocalGet(ptr),
+            Operator::I32Const(tape),
+            Operator::I32Sub,
+            Operator::I32Const(shift),
+            Operator::I32ShrS,
+            Operator::LocalGet(distance),
+            Operator::I32Sub,
+            Operator::LocalSet(position),
+        ];
+        body.extend(wrapped_left(position, size));
+        body.extend_from_slice(&[
+            Operator::I32Const(shift),
+            Operator::I32Shl,
+            Operator::I32Const(tape),
+            Operator::I32Add,
+        ]);
+        self.module.define(self.functions.wrap_left, "bf_wrap_left",
+                           &[("ptr", ValueType::I32), ("distance", ValueType::I32)], Some(ValueType::I32),
+                           &[("position", ValueType::I32)], body);
+    }
+
+    /// Generate the function `$bf_abort`, aborting the program with a message of a given length at a given address
+    /// and an exit code, and, if errors are reported at source positions, the function `$bf_fail`, aborting the
+    /// program with the message for a source position, and its helper functions.
+    fn generate_failing_runtime(&mut self, layout: Layout) {
+        let functions: Functions = self.functions;
+        let (message, length, code): (u32, u32, u32) = (0, 1, 2);
+        let body: Vec<Operator> = match self.interface {
+            // The host is not told the exit code, as it does not exit itself.
+            WasmInterface::Host => vec![
+                Operator::LocalGet(message),
+                Operator::LocalGet(length),
+                Operator::Call(functions.host_fail),
+                Operator::Unreachable,
+            ],
+            WasmInterface::Wasi => vec![
+                Operator::Call(functions.flush),
+                Operator::I32Const(2),
+                Operator::LocalGet(message),
+                Operator::LocalGet(length),
+                Operator::Call(functions.write_all),
+                Operator::LocalGet(code),
+                Operator::Call(functions.proc_exit),
+                Operator::Unreachable,
+            ],
+        };
+        self.module.define(functions.abort, "bf_abort",
+                           &[("message", ValueType::I32), ("length", ValueType::I32), ("code", ValueType::I32)],
+                           None, &[], body);
+
+        if self.positions.is_empty() {
+            return;
+        }
+
+        // Write the message, the line, and the position within the line into the error buffer.
+        let error: i32 = to_i32(layout.error);
+        let (id, end): (u32, u32) = (1, 2);
+        self.module.define(functions.fail, "bf_fail", &[("message", ValueType::I32), ("id", ValueType::I32)], None,
+                           &[("end", ValueType::I32)], vec![
+            Operator::I32Const(error),
+            Operator::LocalGet(message),
+            Operator::Call(functions.append_string),
+            Operator::I32Const(AT_LINE),
+            Operator::Call(functions.append_string),
+            Operator::LocalGet(id),
+            Operator::I32Const(3),
+            Operator::I32Shl,
+            Operator::Load(Access::Word, layout.positions),
+            Operator::Call(functions.append_number),
+            Operator::I32Const(AT_POSITION),
+            Operator::Call(functions.append_string),
+            Operator::LocalGet(id),
+            Operator::I32Const(3),
+            Operator::I32Shl,
+            Operator::Load(Access::Word, layout.positions + 4),
+            Operator::Call(functions.append_number),
+            Operator::LocalTee(end),
+            Operator::I32Const(10),
+            Operator::Store(Access::Byte, 0),
+            Operator::I32Const(error),
+            Operator::LocalGet(end),
+            Operator::I32Const(1),
+            Operator::I32Add,
+            Operator::I32Const(error),
+            Operator::I32Sub,
+            Operator::I32Const(3),
+            Operator::Call(functions.abort),
+        ]);
+
+        let (destination, source, byte): (u32, u32, u32) = (0, 1, 2);
+        self.module.define(functions.append_string, "bf_append_string",
+                           &[("destination", ValueType::I32), ("source", ValueType::I32)], Some(ValueType::I32),
+                           &[("byte", ValueType::I32)], vec![
+            Operator::Block,
+            Operator::Loop,
+            Operator::LocalGet(source),
+            Operator::Load(Access::Byte, 0),
+            Operator::LocalTee(byte),
+            Operator::I32Eqz,
+            Operator::BrIf(1),
+            Operator::LocalGet(destination),
+            Operator::LocalGet(byte),
+            Operator::Store(Access::Byte, 0),
+            Operator::LocalGet(destination),
+            Operator::I32Const(1),
+            Operator::I32Add,
+            Operator::LocalSet(destination),
+            Operator::LocalGet(source),
+            Operator::I32Const(1),
+            Operator::I32Add,
+            Operator::LocalSet(source),
+            Operator::Br(0),
+            Operator::End,
+            Operator::End,
+            Operator::LocalGet(destination),
+        ]);
+
+        // Count the digits first, then write them from the last one backwards.
+        let (number, rest, end): (u32, u32, u32) = (1, 2, 3);
+        self.module.define(functions.append_number, "bf_append_number",
+                           &[("destination", ValueType::I32), ("number", ValueType::I32)], Some(ValueType::I32),
+                           &[("rest", ValueType::I32), ("end", ValueType::I32)], vec![
+            Operator::LocalGet(number),
+            Operator::LocalSet(rest),
+            Operator::Loop,
+            Operator::LocalGet(destination),
+            Operator::I32Const(1),
+            Operator::I32Add,
+            Operator::LocalSet(destination),
+            Operator::LocalGet(rest),
+            Operator::I32Const(10),
+            Operator::I32DivU,
+            Operator::LocalTee(rest),
+            Operator::BrIf(0),
+            Operator::End,
+            Operator::LocalGet(destination),
+            Operator::LocalSet(end),
+            Operator::Loop,
+            Operator::LocalGet(destination),
+            Operator::I32Const(1),
+            Operator::I32Sub,
+            Operator::LocalTee(destination),
+            Operator::LocalGet(number),
+            Operator::I32Const(10),
+            Operator::I32RemU,
+            Operator::I32Const(48),
+            Operator::I32Add,
+            Operator::Store(Access::Byte, 0),
+            Operator::LocalGet(number),
+            Operator::I32Const(10),
+            Operator::I32DivU,
+            Operator::LocalTee(number),
+            Operator::BrIf(0),
+            Operator::End,
+            Operator::LocalGet(end),
+        ]);
+    }
+
+    /// Get the ID of the `instruction`'s source position passed to the runtime functions, adding the position to the
+    /// table. Empty unless the position might be `reported`.
+    fn position(&mut self, instruction: &Instruction, reported: bool) -> Option<usize> {
+        if !reported {
+            return None;
+        }
+
+        self.positions.push(instruction.span.start);
+        Some(self.positions.len() - 1)
+    }
+}
+
+/// Get the operators pushing the cell index in the local variable `index` onto the stack, wrapped around at the start
+/// of a tape with the number of cells in the global variable `size` if it is negative.
+fn wrapped_left(index: u32, size: u32) -> Vec<Operator> {
+    vec![
+        Operator::GlobalGet(size),
+        Operator::I32Const(1),
+        Operator::I32Sub,
+        Operator::I32Const(-1),
+        Operator::LocalGet(index),
+        Operator::I32Sub,
+        Operator::GlobalGet(size),
+        Operator::I32RemU,
+        Operator::I32Sub,
+        Operator::LocalGet(index),
+        Operator::LocalGet(index),
+        Operator::I32Const(0),
+        Operator::I32LtS,
+        Operator::Select,
+    ]
+}
+
+/// Get the parameters of the function `$bf_reserve`, including the ID of the source position if accesses outside
+/// the tape are `checked`.
+fn reserve_parameters(checked: bool) -> Vec<(&'static str, ValueType)> {
+    let mut parameters: Vec<(&'static str, ValueType)> = vec![
+        ("ptr", ValueType::I32),
+        ("offset", ValueType::I32),
+        ("low", ValueType::I32),
+        ("high", ValueType::I32),
+    ];
+    if checked {
+        parameters.push(("id", ValueType::I32));
+    }
+    parameters
+}
+
+/// Round the `value` up to the next multiple of the `alignment`.
+fn align(value: u32, alignment: u32) -> u32 {
+    value.div_ceil(alignment) * alignment
+}
+
+/// Get the `value` of a change to a cell of the given `cell_type`, reduced to the cell type's range when wrapping
+/// around at its bounds.
+fn wrapped(value: i32, cell_type: CellType) -> i64 {
+    let value: i64 = i64::from(value);
+    match cell_type {
+        CellType::U8 => value.rem_euclid(0x100),
+        CellType::U16 => value.rem_euclid(0x1_0000),
+        CellType::U32 | CellType::I64 => value,
+    }
+}
+
+/// Convert the `value` to a signed 32 bit number.
+fn to_i32<T>(value: T) -> i32 where i32: TryFrom<T> {
+    i32::try_from(value).unwrap_or_else(|_| panic!("The value fits into 32 bits."))
+}
+
+/// Convert the `value` to a signed 64 bit number.
+fn to_i64(value: usize) -> i64 {
+    i64::try_from(value).expect("The value fits into 64 bits.")
+}
+
+/// Convert the `value` to a signed number.
+fn to_isize(value: usize) -> isize {
+    isize::try_from(value).expect("The value fits into a signed number.")
+}
+
+/// Convert the `value` to an unsigned 32 bit number.
+fn to_u32(value: usize) -> u32 {
+    u32::try_from(value).expect("The value fits into 32 bits.")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::convert::TryFrom;
+
+    use CellType;
+    use Instruction;
+    use MetaData;
+    use Operation;
+    use Options;
+    use Overflow;
+    use Program;
+    use Span;
+    use TapeGrowth;
+    use WasmGenerator;
+    use WasmInterface;
+    use super::AT_LINE;
+    use super::AT_POSITION;
+    use super::CELL_OVERFLOW;
+    use super::MESSAGES;
+    use super::MESSAGES_ADDRESS;
+    use super::OUT_OF_MEMORY;
+    use super::POINTER_OVERFLOW;
+    use super::POINTER_UNDERFLOW;
+
+    #[test]
+    fn test_messages() {
+        let message = |address: i32, length: usize| -> &[u8] {
+            let start: usize = usize::try_from(address).unwrap() - usize::try_from(MESSAGES_ADDRESS).unwrap();
+            &MESSAGES[start..start + length]
+        };
+        assert_eq!(message(CELL_OVERFLOW, 14), b"cell overflow\0");
+        assert_eq!(message(POINTER_UNDERFLOW, 18), b"pointer underflow\0");
+        assert_eq!(message(POINTER_OVERFLOW, 17), b"pointer overflow\0");
+        assert_eq!(message(AT_LINE, 10), b" at line \0");
+        assert_eq!(message(AT_POSITION, 12), b", position \0");
+        assert_eq!(message(OUT_OF_MEMORY, 14), b"out of memory\n");
+    }
+
+    #[test]
+    fn test_generate_text() {
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(259), span),
+                Instruction::new(Operation::Loop(vec![
+                    Instruction::new(Operation::MulAdd { offset: 1, factor: -1 }, span),
+                    Instruction::new(Operation::Clear, span),
+                ]), span),
+                Instruction::at_offset(Operation::Write, -1, span),
+            ],
+        };
+
+        // The cell's value is known not to be zero within the loop, so the multiplication is not guarded.
+        let text: String = WasmGenerator::new().generate_text(&program, WasmInterface::Host);
+        assert_eq!(text, "\
+(module
+  (import \"env\" \"write_byte\" (func $write_byte (param i32)))
+  (memory (export \"memory\") 1)
+
+  (func $run (export \"run\")
+    (local $ptr i32)
+    i32.const 104
+    local.set $ptr
+    local.get $ptr
+    local.get $ptr
+    i32.load8_u
+    i32.const 3
+    i32.add
+    i32.store8
+    block
+      loop
+        local.get $ptr
+        i32.load8_u
+        i32.eqz
+        br_if 1
+        local.get $ptr
+        i32.const 1
+        call $bf_wrap
+        local.get $ptr
+        i32.const 1
+        call $bf_wrap
+        i32.load8_u
+        local.get $ptr
+        i32.load8_u
+        i32.const 255
+        i32.mul
+        i32.add
+        i32.store8
+        local.get $ptr
+        i32.const 0
+        i32.store8
+        br 0
+      end
+    end
+    local.get $ptr
+    i32.const 29999
+    call $bf_wrap
+    i32.load8_u
+    call $write_byte
+  )
+
+  (func $bf_wrap (param $ptr i32) (param $distance i32) (result i32)
+    (local $position i32)
+    local.get $ptr
+    local.get $distance
+    i32.add
+    local.tee $position
+    local.get $position
+    i32.const 30000
+    i32.sub
+    local.get $position
+    i32.const 30104
+    i32.lt_s
+    select
+  )
+)
+");
+    }
+
+    #[test]
+    fn test_generate_binary() {
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![Instruction::new(Operation::Write, span)],
+        };
+
+        let module: Vec<u8> = WasmGenerator::new().generate_binary(&program, WasmInterface::Host);
+        assert_eq!(&module[..8], b"\0asm\x01\0\0\0");
+        assert!(module.windows(4).any(|window| window == b"\x03run"));
+
+        // WASI commands export their entry point as `_start`.
+        let module: Vec<u8> = WasmGenerator::new().generate_binary(&program, WasmInterface::Wasi);
+        assert!(module.windows(7).any(|window| window == b"\x06_start"));
+        assert!(module.windows(9).any(|window| window == b"\x08fd_write"));
+        assert!(!module.windows(8).any(|window| window == b"\x07fd_read"));
+    }
+
+    #[test]
+    fn test_generate_runtime() {
+        let span = Span::at(MetaData { lineno: 1, position: 1 });
+        let program = Program {
+            instructions: vec![
+                Instruction::new(Operation::Add(1), span),
+                Instruction::new(Operation::Move(1), span),
+            ],
+        };
+
+        // Only the functions needed by the program are included.
+        let text: String = WasmGenerator::new().generate_text(&program, WasmInterface::Host);
+        assert!(!text.contains("(import"));
+        assert!(!text.contains("(data"));
+        assert!(text.contains("(func $bf_wrap"));
+        assert!(!text.contains("$bf_add"));
+        assert!(!text.contains("$bf_reserve"));
+
+        let options = Options { overflow: Overflow::Error, ..Options::default() };
+        let text: String = WasmGenerator::with_options(options).generate_text(&program, WasmInterface::Host);
+        assert!(text.contains("(import \"env\" \"fail\" (func $fail (param i32 i32)))"));
+        assert!(text.contains("(data (i32.const 16) \"cell overflow\\00"));
+        assert!(text.contains("(data (i32.const 104) \"\\01\\00\\00\\00\\01\\00\\00\\00\")"));
+        assert!(text.contains("(func $bf_add (param $value i64) (param $low i64) (param $high i64) (param $id i32)"));
+        assert!(text.contains("(func $bf_fail"));
+        assert!(!text.contains("(func $bf_reserve"));
+
+        // Growing tapes reserve their cells, and might exhaust the memory.
+        let options = Options { cell_type: CellType::U16, tape_growth: TapeGrowth::Right, ..Options::default() };
+        let text: String = WasmGenerator::with_options(options).generate_text(&program, WasmInterface::Host);
+        assert!(text.contains("(memory (export \"memory\") 1)"));
+        assert!(text.contains("(global $tape_size (mut i32) (i32.const 30000))"));
+        assert!(text.contains("(func $bf_reserve (param $ptr i32) (param $offset i32) (param $low i32) \
+                               (param $high i32) (result i32)"));
+        assert!(text.contains("(func $bf_abort"));
+        assert!(!text.contains("(func $bf_fail"));
+
+        // Cells before the start of an unchecked tape growing to the right are wrapped around at its current end.
+        let program = Program {
+            instructions: vec![Instruction::at_offset(Operation::Add(1), -1, span)],
+        };
+        let options = Options { tape_growth: TapeGrowth::Right, ..Options::default() };
+        let text: String = WasmGenerator::with_options(options).generate_text(&program, WasmInterface::Host);
+        assert!(text.contains("(global $tape_size (mut i32) (i32.const 30000))"));
+        assert!(text.contains("(func $bf_wrap_left (param $ptr i32) (param $distance i32) (result i32)"));
+        assert!(text.contains("    i32.rem_u\n"));
+        assert!(!text.contains("(func $bf_reserve"));
+
+        let options = Options { cell_type: CellType::I64, tape_size: 10_000, ..Options::default() };
+        let text: String = WasmGenerator::with_options(options).generate_text(&program, WasmInterface::Host);
+        assert!(text.contains("(memory (export \"memory\") 2)"));
+        assert!(text.contains("    i64.store\n"));
+    }
+}
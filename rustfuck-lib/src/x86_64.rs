@@ -0,0 +1,745 @@
+// Copyright 2018 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implementations of an encoder for the subset of `x86-64` machine code needed by the native code generators.
+//!
+//! Instructions are written in `AT&T` operand order as in the generated assembly, i.e. the source operand comes first
+//! and the destination operand last.
+
+use std::convert::TryFrom;
+
+/// The general purpose registers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Register {
+    /// `%rax`.
+    Rax,
+
+    /// `%rcx`.
+    Rcx,
+
+    /// `%rdx`.
+    Rdx,
+
+    /// `%rbx`.
+    Rbx,
+
+    /// `%rsi`.
+    Rsi,
+
+    /// `%rdi`.
+    Rdi,
+
+    /// `%r8`.
+    R8,
+
+    /// `%r9`.
+    R9,
+
+    /// `%r10`.
+    R10,
+
+    /// `%r12`.
+    R12,
+
+    /// `%r13`.
+    R13,
+
+    /// `%r14`.
+    R14,
+
+    /// `%r15`.
+    R15,
+}
+
+impl Register {
+    /// Get the number of the register used in the encoding of instructions.
+    fn number(self) -> u8 {
+        match self {
+            Register::Rax => 0,
+            Register::Rcx => 1,
+            Register::Rdx => 2,
+            Register::Rbx => 3,
+            Register::Rsi => 6,
+            Register::Rdi => 7,
+            Register::R8 => 8,
+            Register::R9 => 9,
+            Register::R10 => 10,
+            Register::R12 => 12,
+            Register::R13 => 13,
+            Register::R14 => 14,
+            Register::R15 => 15,
+        }
+    }
+}
+
+/// The sizes of the operands of instructions.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Size {
+    /// 8 bits.
+    Byte,
+
+    /// 16 bits.
+    Word,
+
+    /// 32 bits.
+    Double,
+
+    /// 64 bits.
+    Quad,
+}
+
+impl Size {
+    /// Get the number of bytes of the size.
+    pub fn bytes(self) -> usize {
+        match self {
+            Size::Byte => 1,
+            Size::Word => 2,
+            Size::Double => 4,
+            Size::Quad => 8,
+        }
+    }
+
+    /// Get the scale factor of the size in an address.
+    fn scale(self) -> u8 {
+        match self {
+            Size::Byte => 0,
+            Size::Word => 1,
+            Size::Double => 2,
+            Size::Quad => 3,
+        }
+    }
+}
+
+/// A location in the generated code or data, which is bound to an address after it has been created.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Label(usize);
+
+/// An address in memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Memory {
+    /// The address in the `base` register, plus the `index` register scaled by the given size, plus a
+    /// `displacement`.
+    Relative {
+        /// The register containing the base address.
+        base: Register,
+
+        /// The register and the scale of the index, if any.
+        index: Option<(Register, Size)>,
+
+        /// The constant part of the address.
+        displacement: i32,
+    },
+
+    /// The address of a label.
+    Absolute(Label),
+}
+
+impl Memory {
+    /// Get the address `displacement` bytes after the address in the `base` register.
+    pub fn at(base: Register, displacement: i32) -> Memory {
+        Memory::Relative { base, index: None, displacement }
+    }
+
+    /// Get the address of the element at `index` in the array of elements with the given `size` starting
+    /// `displacement` bytes after the address in the `base` register.
+    pub fn indexed(base: Register, index: Register, size: Size, displacement: i32) -> Memory {
+        Memory::Relative { base, index: Some((index, size)), displacement }
+    }
+}
+
+/// The operands of instructions.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operand {
+    /// A register.
+    Register(Register),
+
+    /// A value in memory.
+    Memory(Memory),
+
+    /// A constant value.
+    Immediate(i64),
+}
+
+impl From<Register> for Operand {
+    fn from(register: Register) -> Operand {
+        Operand::Register(register)
+    }
+}
+
+impl From<Memory> for Operand {
+    fn from(memory: Memory) -> Operand {
+        Operand::Memory(memory)
+    }
+}
+
+impl From<i32> for Operand {
+    fn from(value: i32) -> Operand {
+        Operand::Immediate(i64::from(value))
+    }
+}
+
+impl From<i64> for Operand {
+    fn from(value: i64) -> Operand {
+        Operand::Immediate(value)
+    }
+}
+
+/// The binary arithmetic operations sharing a common encoding.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Arithmetic {
+    /// Addition.
+    Add,
+
+    /// Addition with carry.
+    Adc,
+
+    /// Subtraction with borrow.
+    Sbb,
+
+    /// Subtraction.
+    Sub,
+
+    /// Exclusive or.
+    Xor,
+
+    /// Comparison, i.e. subtraction without storing the result.
+    Cmp,
+}
+
+impl Arithmetic {
+    /// Get the number of the operation used in the encoding of instructions.
+    fn number(self) -> u8 {
+        match self {
+            Arithmetic::Add => 0,
+            Arithmetic::Adc => 2,
+            Arithmetic::Sbb => 3,
+            Arithmetic::Sub => 5,
+            Arithmetic::Xor => 6,
+            Arithmetic::Cmp => 7,
+        }
+    }
+}
+
+/// The conditions of jumps and conditional moves.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Condition {
+    /// Signed overflow.
+    Overflow,
+
+    /// Unsigned greater than or equal.
+    AboveEqual,
+
+    /// Unsigned greater than.
+    Above,
+
+    /// Equal, or zero.
+    Equal,
+
+    /// Not equal, or not zero.
+    NotEqual,
+
+    /// Negative.
+    Sign,
+
+    /// Not negative.
+    NotSign,
+
+    /// Signed less than.
+    Less,
+
+    /// Signed greater than or equal.
+    GreaterEqual,
+
+    /// Signed less than or equal.
+    LessEqual,
+}
+
+impl Condition {
+    /// Get the number of the condition used in the encoding of instructions.
+    fn number(self) -> u8 {
+        match self {
+            Condition::Overflow => 0x0,
+            Condition::AboveEqual => 0x3,
+            Condition::Above => 0x7,
+            Condition::Equal => 0x4,
+            Condition::NotEqual => 0x5,
+            Condition::Sign => 0x8,
+            Condition::NotSign => 0x9,
+            Condition::Less => 0xC,
+            Condition::GreaterEqual => 0xD,
+            Condition::LessEqual => 0xE,
+        }
+    }
+}
+
+/// The ways in which the address of a label is inserted into the code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Reference {
+    /// A 32 bit offset from the end of the reference.
+    Relative,
+
+    /// The 32 bit address.
+    Absolute,
+}
+
+/// An assembler collecting encoded instructions and data, and resolving references to labels.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Assembler {
+
+    /// The encoded instructions and data.
+    code: Vec<u8>,
+
+    /// The offsets from the start of the code to which the labels are bound, if they are bound yet.
+    labels: Vec<Option<usize>>,
+
+    /// The offsets of the references to labels within the code, together with the referenced labels.
+    references: Vec<(usize, Label, Reference)>,
+}
+
+impl Assembler {
+    /// Initialize a new assembler without any code.
+    pub fn new() -> Assembler {
+        Assembler::default()
+    }
+
+    /// Get the number of bytes of the code so far.
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    /// Create a new label, which still has to be bound.
+    pub fn label(&mut self) -> Label {
+        self.labels.push(None);
+        Label(self.labels.len() - 1)
+    }
+
+    /// Bind the `label` to the current end of the code.
+    pub fn bind(&mut self, label: Label) {
+        let offset: usize = self.code.len();
+        self.bind_to(label, offset);
+    }
+
+    /// Bind the `label` to the given `offset` from the start of the code, which may be after the end of the code.
+    pub fn bind_to(&mut self, label: Label, offset: usize) {
+        self.labels[label.0] = Some(offset);
+    }
+
+    /// Get the offset from the start of the code to which the `label` is bound, if it is bound yet.
+    pub fn offset(&self, label: Label) -> Option<usize> {
+        self.labels[label.0]
+    }
+
+    /// Append the raw `data` to the code.
+    pub fn data(&mut self, data: &[u8]) {
+        self.code.extend_from_slice(data);
+    }
+
+    /// Get the code with all references to labels resolved, assuming it will be placed at the `base` address.
+    ///
+    /// Panics if a referenced label has not been bound or if an address does not fit into the reference.
+    pub fn finish(mut self, base: u64) -> Vec<u8> {
+        for &(offset, label, reference) in &self.references {
+            let target: usize = self.labels[label.0].expect("Referenced label has not been bound");
+            let value: i64 = match reference {
+                Reference::Relative => to_i64(target) - to_i64(offset) - 4,
+                Reference::Absolute => i64::try_from(base).expect("Base address too large") + to_i64(target),
+            };
+            let value: i32 = i32::try_from(value).expect("Label address out of range");
+            self.code[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        self.code
+    }
+
+    /// Move the `source` to the `destination`.
+    pub fn mov(&mut self, size: Size, source: impl Into<Operand>, destination: impl Into<Operand>) {
+        match (source.into(), destination.into()) {
+            (Operand::Register(source), destination) => {
+                let opcode: u8 = if size == Size::Byte { 0x88 } else { 0x89 };
+                self.encode(size, &[opcode], source.number(), destination);
+            },
+            (Operand::Memory(source), Operand::Register(destination)) => {
+                let opcode: u8 = if size == Size::Byte { 0x8A } else { 0x8B };
+                self.encode(size, &[opcode], destination.number(), Operand::Memory(source));
+            },
+            (Operand::Immediate(value), Operand::Register(destination))
+                if size == Size::Quad && i32::try_from(value).is_err() => {
+                // The immediate value only fits into the long form.
+                let number: u8 = destination.number();
+                self.code.push(0x48 | (number >> 3));
+                self.code.push(0xB8 | (number & 7));
+                self.code.extend_from_slice(&value.to_le_bytes());
+            },
+            (Operand::Immediate(value), destination) => {
+                let opcode: u8 = if size == Size::Byte { 0xC6 } else { 0xC7 };
+                self.encode(size, &[opcode], 0, destination);
+                self.immediate(size, value);
+            },
+            (source, destination) => panic!("Invalid operands {:?} and {:?}", source, destination),
+        }
+    }
+
+    /// Move the 32 bit address of the `label` into the `destination` register.
+    pub fn mov_address(&mut self, label: Label, destination: Register) {
+        let number: u8 = destination.number();
+        if number >= 8 {
+            self.code.push(0x41);
+        }
+        self.code.push(0xB8 | (number & 7));
+        self.reference(label, Reference::Absolute);
+    }
+
+    /// Move the `source` zero-extended to 32 bits into the `destination` register.
+    pub fn movzx(&mut self, size: Size, source: impl Into<Operand>, destination: Register) {
+        let opcode: u8 = if size == Size::Byte { 0xB6 } else { 0xB7 };
+        self.encode(Size::Double, &[0x0F, opcode], destination.number(), source.into());
+    }
+
+    /// Move the `source` into the `destination` register if the `condition` holds.
+    pub fn cmov(&mut self, condition: Condition, size: Size, source: impl Into<Operand>, destination: Register) {
+        self.encode(size, &[0x0F, 0x40 | condition.number()], destination.number(), source.into());
+    }
+
+    /// Load the `address` into the `destination` register.
+    pub fn lea(&mut self, address: Memory, destination: Register) {
+        self.encode(Size::Quad, &[0x8D], destination.number(), Operand::Memory(address));
+    }
+
+    /// Apply the `operation` to the `destination` and the `source`, storing the result in the `destination`.
+    pub fn arithmetic(&mut self, operation: Arithmetic, size: Size, source: impl Into<Operand>,
+                      destination: impl Into<Operand>)
+    {
+        let number: u8 = operation.number();
+        let wide: u8 = if size == Size::Byte { 0 } else { 1 };
+        match (source.into(), destination.into()) {
+            (Operand::Register(source), destination) => {
+                self.encode(size, &[(number << 3) | wide], source.number(), destination);
+            },
+            (Operand::Memory(source), Operand::Register(destination)) => {
+                self.encode(size, &[(number << 3) | 2 | wide], destination.number(), Operand::Memory(source));
+            },
+            (Operand::Immediate(value), destination) if size != Size::Byte && i8::try_from(value).is_ok() => {
+                self.encode(size, &[0x83], number, destination);
+                self.immediate(Size::Byte, value);
+            },
+            (Operand::Immediate(value), destination) => {
+                self.encode(size, &[0x80 | wide], number, destination);
+                self.immediate(size, value);
+            },
+            (source, destination) => panic!("Invalid operands {:?} and {:?}", source, destination),
+        }
+    }
+
+    /// Set the flags according to the bitwise and of the `source` register and the `destination`.
+    pub fn test(&mut self, size: Size, source: Register, destination: impl Into<Operand>) {
+        let opcode: u8 = if size == Size::Byte { 0x84 } else { 0x85 };
+        self.encode(size, &[opcode], source.number(), destination.into());
+    }
+
+    /// Multiply the `source` with the immediate `factor`, storing the result in the `destination` register.
+    pub fn imul(&mut self, size: Size, factor: i32, source: impl Into<Operand>, destination: Register) {
+        self.encode(size, &[0x69], destination.number(), source.into());
+        self.immediate(Size::Double, i64::from(factor));
+    }
+
+    /// Multiply `%rax` with the signed 64 bit `factor`, storing the 128 bit result in `%rdx:%rax`.
+    pub fn imul_wide(&mut self, factor: impl Into<Operand>) {
+        self.encode(Size::Quad, &[0xF7], 5, factor.into());
+    }
+
+    /// Divide `%edx:%eax` by the unsigned 32 bit `divisor`, storing the quotient in `%eax` and the remainder in
+    /// `%edx`.
+    pub fn div(&mut self, divisor: impl Into<Operand>) {
+        self.encode(Size::Double, &[0xF7], 6, divisor.into());
+    }
+
+    /// Divide `%rdx:%rax` by the signed 64 bit `divisor`, storing the quotient in `%rax` and the remainder in `%rdx`.
+    pub fn idiv(&mut self, divisor: impl Into<Operand>) {
+        self.encode(Size::Quad, &[0xF7], 7, divisor.into());
+    }
+
+    /// Negate the `destination`.
+    pub fn neg(&mut self, size: Size, destination: impl Into<Operand>) {
+        let opcode: u8 = if size == Size::Byte { 0xF6 } else { 0xF7 };
+        self.encode(size, &[opcode], 3, destination.into());
+    }
+
+    /// Shift the `destination` arithmetically to the right by `count` bits.
+    pub fn sar(&mut self, size: Size, count: u8, destination: impl Into<Operand>) {
+        let opcode: u8 = if size == Size::Byte { 0xC0 } else { 0xC1 };
+        self.encode(size, &[opcode], 7, destination.into());
+        self.code.push(count);
+    }
+
+    /// Sign-extend `%rax` into `%rdx:%rax`.
+    pub fn cqto(&mut self) {
+        self.code.extend_from_slice(&[0x48, 0x99]);
+    }
+
+    /// Push the `register` onto the stack.
+    pub fn push(&mut self, register: Register) {
+        let number: u8 = register.number();
+        if number >= 8 {
+            self.code.push(0x41);
+        }
+        self.code.push(0x50 | (number & 7));
+    }
+
+    /// Pop the top of the stack into the `register`.
+    pub fn pop(&mut self, register: Register) {
+        let number: u8 = register.number();
+        if number >= 8 {
+            self.code.push(0x41);
+        }
+        self.code.push(0x58 | (number & 7));
+    }
+
+    /// Jump to the `label`.
+    pub fn jmp(&mut self, label: Label) {
+        self.code.push(0xE9);
+        self.reference(label, Reference::Relative);
+    }
+
+    /// Jump to the address in the `target` register or memory location.
+    pub fn jmp_indirect(&mut self, target: impl Into<Operand>) {
+        self.encode(Size::Double, &[0xFF], 4, target.into());
+    }
+
+    /// Jump to the `label` if the `condition` holds.
+    pub fn jcc(&mut self, condition: Condition, label: Label) {
+        self.code.extend_from_slice(&[0x0F, 0x80 | condition.number()]);
+        self.reference(label, Reference::Relative);
+    }
+
+    /// Call the function at the `label`.
+    pub fn call(&mut self, label: Label) {
+        self.code.push(0xE8);
+        self.reference(label, Reference::Relative);
+    }
+
+    /// Call the function at the address in the `target` register or memory location.
+    pub fn call_indirect(&mut self, target: impl Into<Operand>) {
+        self.encode(Size::Double, &[0xFF], 2, target.into());
+    }
+
+    /// Return from the current function.
+    pub fn ret(&mut self) {
+        self.code.push(0xC3);
+    }
+
+    /// Call the operating system.
+    pub fn syscall(&mut self) {
+        self.code.extend_from_slice(&[0x0F, 0x05]);
+    }
+
+    /// Copy `%rcx` bytes from the address in `%rsi` to the address in `%rdi`.
+    pub fn rep_movsb(&mut self) {
+        self.code.extend_from_slice(&[0xF3, 0xA4]);
+    }
+
+    /// Search at most `%rcx` bytes starting at the address in `%rdi` for the byte in `%al`.
+    pub fn repne_scasb(&mut self) {
+        self.code.extend_from_slice(&[0xF2, 0xAE]);
+    }
+
+    /// Set the direction flag, making string instructions step backwards.
+    pub fn std(&mut self) {
+        self.code.push(0xFD);
+    }
+
+    /// Clear the direction flag, making string instructions step forwards.
+    pub fn cld(&mut self) {
+        self.code.push(0xFC);
+    }
+
+    /// Append the instruction with the given `opcode` operating on operands of the given `size`, with the register or
+    /// operation number `register` and the register or memory operand `operand`.
+    fn encode(&mut self, size: Size, opcode: &[u8], register: u8, operand: Operand) {
+        if size == Size::Word {
+            self.code.push(0x66);
+        }
+
+        // The prefix extending the register numbers and the operand size. Byte operations on the lowest bytes of
+        // `%rsi` and `%rdi` need the prefix, too.
+        let (base, index): (u8, u8) = match operand {
+            Operand::Register(operand) => (operand.number(), 0),
+            Operand::Memory(Memory::Relative { base, index, .. }) => {
+                (base.number(), index.map_or(0, |(index, _)| index.number()))
+            },
+            Operand::Memory(Memory::Absolute(_)) | Operand::Immediate(_) => (0, 0),
+        };
+        let width: u8 = if size == Size::Quad { 8 } else { 0 };
+        let prefix: u8 = 0x40 | width | ((register >> 3) << 2) | ((index >> 3) << 1) | (base >> 3);
+        let low_byte: bool = size == Size::Byte && (register >= 4 || base >= 4);
+        if prefix != 0x40 || low_byte {
+            self.code.push(prefix);
+        }
+        self.code.extend_from_slice(opcode);
+
+        let register: u8 = (register & 7) << 3;
+        match operand {
+            Operand::Register(operand) => self.code.push(0xC0 | register | (operand.number() & 7)),
+            Operand::Memory(Memory::Relative { base, index, displacement }) => {
+                // The encoding without a displacement refers to `%rip` or an absolute address for some base
+                // registers, so those need an explicit displacement of zero.
+                let base: u8 = base.number() & 7;
+                let mode: u8 = if displacement == 0 && base != 5 {
+                    0x00
+                } else if i8::try_from(displacement).is_ok() {
+                    0x40
+                } else {
+                    0x80
+                };
+                match index {
+                    Some((index, size)) => {
+                        self.code.push(mode | register | 4);
+                        self.code.push((size.scale() << 6) | ((index.number() & 7) << 3) | base);
+                    },
+                    None if base == 4 => {
+                        self.code.push(mode | register | 4);
+                        self.code.push(0x24);
+                    },
+                    None => self.code.push(mode | register | base),
+                }
+                match mode {
+                    0x40 => self.immediate(Size::Byte, i64::from(displacement)),
+                    0x80 => self.immediate(Size::Double, i64::from(displacement)),
+                    _ => {},
+                }
+            },
+            Operand::Memory(Memory::Absolute(label)) => {
+                self.code.push(register | 4);
+                self.code.push(0x25);
+                self.reference(label, Reference::Absolute);
+            },
+            Operand::Immediate(value) => panic!("Invalid operand {}", value),
+        }
+    }
+
+    /// Append the lowest bytes of the `value` fitting into the given `size`, but at most 32 bits.
+    ///
+    /// Panics if the `value` does not fit into 32 bits, as the instructions would change it.
+    fn immediate(&mut self, size: Size, value: i64) {
+        assert!(i32::try_from(value).is_ok() || (size == Size::Double && u32::try_from(value).is_ok()),
+                "Immediate value {} out of range", value);
+        let bytes: usize = size.bytes().min(4);
+        self.code.extend_from_slice(&value.to_le_bytes()[..bytes]);
+    }
+
+    /// Append a placeholder for the address of the `label`, which is inserted when finishing the code.
+    fn reference(&mut self, label: Label, reference: Reference) {
+        let offset: usize = self.code.len();
+        self.references.push((offset, label, reference));
+        self.code.extend_from_slice(&[0; 4]);
+    }
+}
+
+/// Convert the `value` to a signed number.
+fn to_i64(value: usize) -> i64 {
+    i64::try_from(value).expect("Offset too large")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Arithmetic;
+    use super::Assembler;
+    use super::Condition;
+    use super::Memory;
+    use super::Register;
+    use super::Size;
+
+    #[test]
+    fn test_mov() {
+        let mut assembler = Assembler::new();
+        let cell = Memory::indexed(Register::Rbx, Register::R12, Size::Byte, 0);
+        assembler.mov(Size::Byte, cell, Register::Rdi);
+        assembler.mov(Size::Word, Register::Rax, Memory::indexed(Register::Rbx, Register::R12, Size::Word, -2));
+        assembler.mov(Size::Byte, 0, cell);
+        assembler.mov(Size::Quad, -1, Register::R8);
+        assembler.mov(Size::Quad, 30_000_000_000_i64, Register::Rcx);
+        assembler.mov(Size::Double, 60, Register::Rax);
+        assert_eq!(assembler.finish(0), vec![
+            0x42, 0x8A, 0x3C, 0x23,
+            0x66, 0x42, 0x89, 0x44, 0x63, 0xFE,
+            0x42, 0xC6, 0x04, 0x23, 0x00,
+            0x49, 0xC7, 0xC0, 0xFF, 0xFF, 0xFF, 0xFF,
+            0x48, 0xB9, 0x00, 0xAC, 0x23, 0xFC, 0x06, 0x00, 0x00, 0x00,
+            0xC7, 0xC0, 0x3C, 0x00, 0x00, 0x00,
+        ]);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut assembler = Assembler::new();
+        let cell = Memory::indexed(Register::Rbx, Register::R12, Size::Double, 4);
+        assembler.arithmetic(Arithmetic::Add, Size::Byte, 255, Memory::indexed(Register::Rbx, Register::R12,
+                                                                                   Size::Byte, 0));
+        assembler.arithmetic(Arithmetic::Add, Size::Double, -70_000, cell);
+        assembler.arithmetic(Arithmetic::Sub, Size::Double, Register::Rax, cell);
+        assembler.arithmetic(Arithmetic::Cmp, Size::Quad, Register::R15, Register::R8);
+        assembler.arithmetic(Arithmetic::Add, Size::Quad, 2, Register::R12);
+        assembler.arithmetic(Arithmetic::Sbb, Size::Quad, Memory::at(Register::R13, 0), Register::Rdx);
+        assembler.test(Size::Quad, Register::Rax, Register::Rax);
+        assembler.idiv(Register::R15);
+        assert_eq!(assembler.finish(0), vec![
+            0x42, 0x80, 0x04, 0x23, 0xFF,
+            0x42, 0x81, 0x44, 0xA3, 0x04, 0x90, 0xEE, 0xFE, 0xFF,
+            0x42, 0x29, 0x44, 0xA3, 0x04,
+            0x4D, 0x39, 0xF8,
+            0x49, 0x83, 0xC4, 0x02,
+            0x49, 0x1B, 0x55, 0x00,
+            0x48, 0x85, 0xC0,
+            0x49, 0xF7, 0xFF,
+        ]);
+    }
+
+    #[test]
+    fn test_labels() {
+        let mut assembler = Assembler::new();
+        let start = assembler.label();
+        let end = assembler.label();
+        let data = assembler.label();
+        let unbound = assembler.label();
+        assembler.bind(start);
+        assembler.jcc(Condition::Equal, end);
+        assembler.jmp(start);
+        assembler.bind(end);
+        assembler.mov_address(data, Register::Rsi);
+        assembler.mov(Size::Quad, Memory::Absolute(data), Register::Rdx);
+        assembler.bind_to(data, 0x100);
+        assert_eq!(assembler.len(), 24);
+        assert_eq!(assembler.offset(end), Some(11));
+        assert_eq!(assembler.offset(unbound), None);
+        assert_eq!(assembler.finish(0x40_0000), vec![
+            0x0F, 0x84, 0x05, 0x00, 0x00, 0x00,
+            0xE9, 0xF5, 0xFF, 0xFF, 0xFF,
+            0xBE, 0x00, 0x01, 0x40, 0x00,
+            0x48, 0x8B, 0x14, 0x25, 0x00, 0x01, 0x40, 0x00,
+        ]);
+    }
+
+    #[test]
+    fn test_jumps() {
+        let mut assembler = Assembler::new();
+        let start = assembler.label();
+        assembler.bind(start);
+        assembler.jmp_indirect(Register::Rsi);
+        assembler.jmp_indirect(Register::R8);
+        assembler.call_indirect(Register::Rax);
+        assembler.call_indirect(Memory::at(Register::R14, 24));
+        assembler.jcc(Condition::Overflow, start);
+        assembler.jcc(Condition::AboveEqual, start);
+        assert_eq!(assembler.finish(0), vec![
+            0xFF, 0xE6,
+            0x41, 0xFF, 0xE0,
+            0xFF, 0xD0,
+            0x41, 0xFF, 0x56, 0x18,
+            0x0F, 0x80, 0xEF, 0xFF, 0xFF, 0xFF,
+            0x0F, 0x83, 0xE9, 0xFF, 0xFF, 0xFF,
+        ]);
+    }
+}
@@ -9,7 +9,7 @@ for file in examples/*.bf; do
     c_file="${filestem}.c"
     program=${filestem}
 
-    cargo run --release -- -o ${c_file} ${brainfuck_file} &&
+    cargo run --release -- compile -o ${c_file} ${brainfuck_file} &&
     gcc -O3 -o ${program} ${c_file} &&
     ./${program}
 
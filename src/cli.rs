@@ -6,63 +6,499 @@
 
 //! Functions for setting up and working with the command-line interface of `rustfuck`.
 
+use std::io;
+use std::io::IsTerminal;
+use std::path::Path;
 use std::path::PathBuf;
 
 use clap::App;
+use clap::AppSettings;
 use clap::Arg;
 use clap::ArgMatches;
+use clap::SubCommand;
+use rustfuck_lib::Backend;
+use rustfuck_lib::BackendRegistry;
+use rustfuck_lib::CellType;
+use rustfuck_lib::EofBehavior;
+use rustfuck_lib::Layout;
+use rustfuck_lib::Options;
+use rustfuck_lib::Overflow;
+use rustfuck_lib::TapeGrowth;
+use rustfuck_lib::language::Dialect;
+use rustfuck_lib::language::LanguageRegistry;
 
-/// Define the command-line arguments on the `clap` application.
-pub fn setup<'a, 'b>() -> App<'a, 'b> {
+/// The commands `rustfuck` can execute.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Command {
+
+    /// Translate the `Brainfuck` source file `input` to the language of the `target` backend and write the result to
+    /// the file `output`.
+    Compile {
+        /// The `Brainfuck` source file.
+        input: PathBuf,
+
+        /// The generated source file.
+        output: PathBuf,
+
+        /// The name of the language in which the source file is written.
+        language: String,
+
+        /// The mapping file defining the language in which the source file is written, instead of `language`.
+        dialect: Option<PathBuf>,
+
+        /// The name of the backend generating the source file.
+        target: String,
+
+        /// The options controlling the compilation.
+        options: Options,
+    },
+
+    /// List the names and file extensions of all backends available for compiling.
+    ListTargets,
+
+    /// Compile the `Brainfuck` source file `input` to a native executable and write it to the file `output`.
+    Build {
+        /// The `Brainfuck` source file.
+        input: PathBuf,
+
+        /// The generated executable.
+        output: PathBuf,
+
+        /// The name of the language in which the source file is written.
+        language: String,
+
+        /// The mapping file defining the language in which the source file is written, instead of `language`.
+        dialect: Option<PathBuf>,
+
+        /// The options controlling the compilation.
+        options: Options,
+    },
+
+    /// Execute the `Brainfuck` source file `input`, connecting the program to `STDIN` and `STDOUT`.
+    Run {
+        /// The `Brainfuck` source file.
+        input: PathBuf,
+
+        /// The name of the language in which the source file is written.
+        language: String,
+
+        /// The mapping file defining the language in which the source file is written, instead of `language`.
+        dialect: Option<PathBuf>,
+
+        /// The options controlling the execution.
+        options: Options,
+
+        /// Compile the program to machine code before executing it.
+        jit: bool,
+    },
+
+    /// Translate the source file `input` from the language `from` to the language `to`, and write the result to the
+    /// file `output`, or to `STDOUT` if there is none.
+    Translate {
+        /// The source file.
+        input: PathBuf,
+
+        /// The translated source file.
+        output: Option<PathBuf>,
+
+        /// The name of the language in which the source file is written.
+        from: String,
+
+        /// The mapping file defining the language in which the source file is written, instead of `from`.
+        from_dialect: Option<PathBuf>,
+
+        /// The name of the language into which the source file is translated.
+        to: String,
+
+        /// The mapping file defining the language into which the source file is translated, instead of `to`.
+        to_dialect: Option<PathBuf>,
+
+        /// The layout of the translated source file.
+        layout: Layout,
+    },
+}
+
+/// Define the command-line arguments on the `clap` application, accepting the given `targets` as backend names and the
+/// given `languages` as language names.
+pub fn setup<'a, 'b>(targets: &[&'b str], languages: &[&'b str]) -> App<'a, 'b> {
     app_from_crate!()
         .help_message("Show this help message and exit.")
-        .arg(Arg::with_name("INPUT")
-            .help("The Brainfuck source file.")
-            .required(true))
-        .arg(Arg::with_name("output")
-            .short("o")
-            .long("output")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands)
+        .arg(Arg::with_name("color")
+            .long("color")
+            .takes_value(true)
+            .value_name("WHEN")
+            .possible_values(&["auto", "always", "never"])
+            .default_value("auto")
+            .global(true)
+            .help("Highlight error messages. By default, only if STDERR is a terminal."))
+        .subcommand(SubCommand::with_name("compile")
+            .about("Translate a Brainfuck program to C, Rust, LLVM IR, x86-64 assembly, JavaScript, or WebAssembly.")
+            .help_message("Show this help message and exit.")
+            .arg(Arg::with_name("INPUT")
+                .help("The Brainfuck source file.")
+                .required_unless("list-targets"))
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("The generated source file. [default: ./<INPUT>.<EXTENSION>]"))
+            .arg(Arg::with_name("target")
+                .long("target")
+                .takes_value(true)
+                .value_name("LANGUAGE")
+                .possible_values(targets)
+                .default_value("c")
+                .help("The generated language: a C or Rust program, a Rust library, LLVM IR, assembly, an ES module \
+                       (optionally runnable in Node.js), or WebAssembly (text or binary, with host or WASI input and \
+                       output)."))
+            .arg(Arg::with_name("list-targets")
+                .long("list-targets")
+                .help("List the available target languages with the extensions of their files, and exit."))
+            .args(&program_options(languages)))
+        .subcommand(SubCommand::with_name("build")
+            .about("Compile a Brainfuck program to a static x86-64 Linux executable.")
+            .help_message("Show this help message and exit.")
+            .arg(Arg::with_name("INPUT")
+                .help("The Brainfuck source file.")
+                .required(true))
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("The generated executable. [default: ./<INPUT> without its extension, or ./<INPUT>.out]"))
+            .args(&program_options(languages)))
+        .subcommand(SubCommand::with_name("run")
+            .about("Execute a Brainfuck program.")
+            .help_message("Show this help message and exit.")
+            .arg(Arg::with_name("INPUT")
+                .help("The Brainfuck source file.")
+                .required(true))
+            .arg(Arg::with_name("jit")
+                .long("jit")
+                .help("Compile the program to machine code before executing it. Only supported on x86-64 Linux, \
+                       other platforms execute the program on the virtual machine."))
+            .args(&program_options(languages)))
+        .subcommand(SubCommand::with_name("translate")
+            .about("Translate a program between Brainfuck, Ook!, and substitutions of Brainfuck.")
+            .help_message("Show this help message and exit.")
+            .arg(Arg::with_name("INPUT")
+                .help("The source file.")
+                .required(true))
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("The translated source file. [default: STDOUT]"))
+            .arg(Arg::with_name("from")
+                .long("from")
+                .takes_value(true)
+                .value_name("LANGUAGE")
+                .possible_values(languages)
+                .help("The language in which the program is written. [default: detected from the extension of \
+                       <INPUT>, otherwise brainfuck]"))
+            .arg(Arg::with_name("from-dialect-file")
+                .long("from-dialect-file")
+                .takes_value(true)
+                .value_name("MAPPING")
+                .conflicts_with("from")
+                .help("A TOML file defining the language in which the program is written."))
+            .arg(Arg::with_name("to")
+                .long("to")
+                .takes_value(true)
+                .value_name("LANGUAGE")
+                .possible_values(languages)
+                .required_unless("to-dialect-file")
+                .help("The language into which the program is translated."))
+            .arg(Arg::with_name("to-dialect-file")
+                .long("to-dialect-file")
+                .takes_value(true)
+                .value_name("MAPPING")
+                .conflicts_with("to")
+                .help("A TOML file defining the language into which the program is translated."))
+            .arg(Arg::with_name("line-width")
+                .long("line-width")
+                .takes_value(true)
+                .value_name("CHARACTERS")
+                .default_value("80")
+                .validator(validate_count)
+                .help("The maximum length of the translated lines, or 0 to write the program into a single line."))
+            .arg(Arg::with_name("group-size")
+                .long("group-size")
+                .takes_value(true)
+                .value_name("COMMANDS")
+                .default_value("0")
+                .validator(validate_count)
+                .help("Separate groups of this many commands by an additional space, or 0 to not group commands.")))
+}
+
+/// Define the arguments controlling how programs are translated and executed, accepting the given `languages` as
+/// language names.
+fn program_options<'a, 'b>(languages: &[&'b str]) -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("language")
+            .long("language")
+            .takes_value(true)
+            .value_name("LANGUAGE")
+            .possible_values(languages)
+            .help("The language in which the program is written. [default: detected from the extension of <INPUT>, \
+                   otherwise brainfuck]"),
+        Arg::with_name("dialect-file")
+            .long("dialect-file")
+            .takes_value(true)
+            .value_name("MAPPING")
+            .conflicts_with("language")
+            .help("A TOML file defining the language in which the program is written, by mapping the commands add, \
+                   sub, right, left, read, write, begin_loop, and end_loop to strings."),
+        Arg::with_name("no-optimize")
+            .long("no-optimize")
+            .help("Do not rewrite common idioms into more efficient operations."),
+        Arg::with_name("cell-width")
+            .long("cell-width")
+            .takes_value(true)
+            .value_name("TYPE")
+            .possible_values(&["u8", "u16", "u32", "i64"])
+            .default_value("u8")
+            .help("The type of the cells on the tape."),
+        Arg::with_name("overflow")
+            .long("overflow")
             .takes_value(true)
-            .value_name("OUTPUT")
-            .help("The generated C file. [default: ./<INPUT>.c]"))
+            .value_name("MODE")
+            .possible_values(&["wrap", "saturate", "error"])
+            .default_value("wrap")
+            .help("The behavior when a cell's value leaves the range of its type."),
+        Arg::with_name("tape-size")
+            .long("tape-size")
+            .takes_value(true)
+            .value_name("CELLS")
+            .default_value("30000")
+            .validator(validate_tape_size)
+            .help("The initial number of cells on the tape."),
+        Arg::with_name("tape-growth")
+            .long("tape-growth")
+            .takes_value(true)
+            .value_name("DIRECTION")
+            .possible_values(&["fixed", "right", "both"])
+            .default_value("fixed")
+            .help("Grow the tape when the pointer moves past its last cell (right), or past either end (both)."),
+        Arg::with_name("eof")
+            .long("eof")
+            .takes_value(true)
+            .value_name("BEHAVIOR")
+            .possible_values(&["zero", "minus-one", "unchanged"])
+            .default_value("unchanged")
+            .help("The change to the current cell when reading at the end of the input."),
+        Arg::with_name("checked")
+            .long("checked")
+            .help("Abort with an error when the pointer leaves the tape instead of wrapping around."),
+    ]
 }
 
-/// Get the `(input_file, output_file)`.
+/// Get the command the user wants to execute.
 ///
 /// # Examples
 ///
 /// ```
 /// use clap::App;
+/// use rustfuck_lib::BackendRegistry;
+/// use rustfuck_lib::language::LanguageRegistry;
 /// use cli::setup;
-/// use cli::get_arguments;
+/// use cli::get_command;
 ///
-/// let app = setup();
+/// let backends = BackendRegistry::new();
+/// let languages = LanguageRegistry::new();
+/// let app = setup(&backends.names(), &languages.names());
 /// let arg_matches = app.get_matches();
-/// let configuration = get_arguments(arg_matches);
+/// let command = get_command(&arg_matches, &backends, &languages);
 /// ```
-pub fn get_arguments(arg_matches: &ArgMatches) -> (PathBuf, PathBuf) {
-    // Get the input source.
-    let input: PathBuf = match arg_matches.value_of("INPUT") {
+pub fn get_command(arg_matches: &ArgMatches, backends: &BackendRegistry, languages: &LanguageRegistry) -> Command {
+    match arg_matches.subcommand() {
+        ("compile", Some(compile_matches)) if compile_matches.is_present("list-targets") => Command::ListTargets,
+        ("compile", Some(compile_matches)) => {
+            let input: PathBuf = get_input(compile_matches);
+            let target: &dyn Backend = get_target(compile_matches, backends);
+            let output: PathBuf = get_output(compile_matches, &input, target);
+            let target: String = target.name().to_string();
+            let language: String = get_language(compile_matches, "language", &input, languages);
+            let dialect: Option<PathBuf> = compile_matches.value_of("dialect-file").map(PathBuf::from);
+            let options: Options = get_options(compile_matches);
+            Command::Compile { input, output, language, dialect, target, options }
+        },
+        ("build", Some(build_matches)) => {
+            let input: PathBuf = get_input(build_matches);
+            let output: PathBuf = get_executable(build_matches, &input);
+            let language: String = get_language(build_matches, "language", &input, languages);
+            let dialect: Option<PathBuf> = build_matches.value_of("dialect-file").map(PathBuf::from);
+            let options: Options = get_options(build_matches);
+            Command::Build { input, output, language, dialect, options }
+        },
+        ("run", Some(run_matches)) => {
+            let input: PathBuf = get_input(run_matches);
+            let language: String = get_language(run_matches, "language", &input, languages);
+            let dialect: Option<PathBuf> = run_matches.value_of("dialect-file").map(PathBuf::from);
+            let options: Options = get_options(run_matches);
+            let jit: bool = run_matches.is_present("jit");
+            Command::Run { input, language, dialect, options, jit }
+        },
+        ("translate", Some(translate_matches)) => {
+            let input: PathBuf = get_input(translate_matches);
+            let output: Option<PathBuf> = translate_matches.value_of("output").map(PathBuf::from);
+            let from: String = get_language(translate_matches, "from", &input, languages);
+            let from_dialect: Option<PathBuf> = translate_matches.value_of("from-dialect-file").map(PathBuf::from);
+            let to: String = translate_matches.value_of("to").unwrap_or_else(|| Dialect::Brainfuck.name()).to_string();
+            let to_dialect: Option<PathBuf> = translate_matches.value_of("to-dialect-file").map(PathBuf::from);
+            let layout: Layout = get_layout(translate_matches);
+            Command::Translate { input, output, from, from_dialect, to, to_dialect, layout }
+        },
+        _ => unreachable!("No command specified."),
+    }
+}
+
+/// Determine if error messages, which are written to `STDERR`, should be highlighted using colors.
+pub fn use_colors(arg_matches: &ArgMatches) -> bool {
+    // Global arguments are only propagated to the subcommand's matches.
+    let subcommand_matches: &ArgMatches = arg_matches.subcommand().1.unwrap_or(arg_matches);
+    match subcommand_matches.value_of("color") {
+        Some("always") => true,
+        Some("never") => false,
+        _ => io::stderr().is_terminal(),
+    }
+}
+
+/// Get the input source.
+fn get_input(arg_matches: &ArgMatches) -> PathBuf {
+    match arg_matches.value_of("INPUT") {
         Some(input) => PathBuf::from(input),
         None => unreachable!("No input source specified."),
+    }
+}
+
+/// Get the name of the language in which the `input` is written: the one given in the `argument` on the command line,
+/// or else the first one among the `languages` whose files have the `input`'s extension, or else `Brainfuck`.
+fn get_language(arg_matches: &ArgMatches, argument: &str, input: &Path, languages: &LanguageRegistry) -> String {
+    match arg_matches.value_of(argument) {
+        Some(name) => name.to_string(),
+        None => {
+            // Detect the language from the input file's extension, defaulting to Brainfuck.
+            languages.for_path(input)
+                .map_or(Dialect::Brainfuck.name(), |language| language.name())
+                .to_string()
+        }
+    }
+}
+
+/// Get the options controlling how the program is translated and executed.
+fn get_options(arg_matches: &ArgMatches) -> Options {
+    let cell_type: CellType = match arg_matches.value_of("cell-width") {
+        Some("u16") => CellType::U16,
+        Some("u32") => CellType::U32,
+        Some("i64") => CellType::I64,
+        _ => CellType::U8,
     };
 
-    // Get the output file. If it is not specified, default to ./<INPUT>.c
-    let output: PathBuf = match arg_matches.value_of("output") {
+    let overflow: Overflow = match arg_matches.value_of("overflow") {
+        Some("saturate") => Overflow::Saturate,
+        Some("error") => Overflow::Error,
+        _ => Overflow::Wrap,
+    };
+
+    let tape_growth: TapeGrowth = match arg_matches.value_of("tape-growth") {
+        Some("right") => TapeGrowth::Right,
+        Some("both") => TapeGrowth::Both,
+        _ => TapeGrowth::Fixed,
+    };
+
+    let eof: EofBehavior = match arg_matches.value_of("eof") {
+        Some("zero") => EofBehavior::Zero,
+        Some("minus-one") => EofBehavior::MinusOne,
+        _ => EofBehavior::Unchanged,
+    };
+
+    Options {
+        optimize: !arg_matches.is_present("no-optimize"),
+        cell_type,
+        overflow,
+        tape_size: value_t!(arg_matches, "tape-size", usize).unwrap_or(30_000),
+        tape_growth,
+        eof,
+        checked: arg_matches.is_present("checked"),
+    }
+}
+
+/// Check that the given tape `size` is a positive number.
+fn validate_tape_size(size: String) -> Result<(), String> {
+    match size.parse::<usize>() {
+        Ok(size) if size > 0 => Ok(()),
+        _ => Err(String::from("The tape size must be a positive number.")),
+    }
+}
+
+/// Get the layout of translated source code.
+fn get_layout(arg_matches: &ArgMatches) -> Layout {
+    let default = Layout::default();
+    Layout {
+        line_width: value_t!(arg_matches, "line-width", usize).unwrap_or(default.line_width),
+        group_size: value_t!(arg_matches, "group-size", usize).unwrap_or(default.group_size),
+    }
+}
+
+/// Check that the given `count` is a non-negative number.
+fn validate_count(count: String) -> Result<(), String> {
+    match count.parse::<usize>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(String::from("The value must be a non-negative number.")),
+    }
+}
+
+/// Get the backend translating the program, among the given `backends`.
+fn get_target<'a>(arg_matches: &ArgMatches, backends: &'a BackendRegistry) -> &'a dyn Backend {
+    let name: &str = arg_matches.value_of("target").unwrap_or("c");
+    match backends.get(name) {
+        Some(backend) => backend,
+        None => unreachable!("Unknown target {name}.", name = name),
+    }
+}
+
+/// Get the output file. If it is not specified, default to `./<INPUT>.<EXTENSION>`, using the extension of the
+/// `target` backend.
+fn get_output(arg_matches: &ArgMatches, input: &Path, target: &dyn Backend) -> PathBuf {
+    match arg_matches.value_of("output") {
         Some(output) => PathBuf::from(output),
         None => {
             match input.file_stem() {
                 Some(filestem) => {
-                    // Use the input file's stem and add the C extension.
+                    // Use the input file's stem and add the target's extension.
                     let mut output: PathBuf = PathBuf::from(filestem);
-                    let _ = output.set_extension("c");
+                    let _ = output.set_extension(target.extension());
                     output
                 },
-                // If everything else fails, default to out.c.
-                None => PathBuf::from(String::from("out.c"))
+                // If everything else fails, default to out.<EXTENSION>.
+                None => PathBuf::from(format!("out.{extension}", extension = target.extension()))
             }
         }
-    };
+    }
+}
 
-    (input, output)
+/// Get the output executable. If it is not specified, default to `./<INPUT>` without the input file's extension, or to
+/// `./<INPUT>.out` if the input file has no extension, so the input is never overwritten.
+fn get_executable(arg_matches: &ArgMatches, input: &Path) -> PathBuf {
+    match arg_matches.value_of("output") {
+        Some(output) => PathBuf::from(output),
+        None => {
+            match input.file_stem() {
+                Some(filestem) if input.extension().is_some() => PathBuf::from(filestem),
+                Some(filestem) => {
+                    let mut output: PathBuf = PathBuf::from(filestem);
+                    let _ = output.set_extension("out");
+                    output
+                },
+                // If everything else fails, default to out.
+                None => PathBuf::from("out"),
+            }
+        }
+    }
 }
@@ -25,61 +25,258 @@ pub mod cli;
 pub mod quit;
 
 use std::fs::File;
+use std::io;
 use std::io::BufReader;
 use std::io::BufWriter;
 use std::io::Error as IOError;
 use std::io::Read;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::Path;
 
 use clap::App;
 use clap::ArgMatches;
+use cli::Command;
+use rustfuck_lib::Backend;
+use rustfuck_lib::BackendRegistry;
+use rustfuck_lib::Bytecode;
 use rustfuck_lib::Compiler;
+use rustfuck_lib::Emitter;
 use rustfuck_lib::Error;
+use rustfuck_lib::Jit;
+use rustfuck_lib::Layout;
+use rustfuck_lib::Options;
+use rustfuck_lib::Program;
+use rustfuck_lib::SourceGenerator;
+use rustfuck_lib::VirtualMachine;
+use rustfuck_lib::language::Language;
+use rustfuck_lib::language::LanguageRegistry;
+use rustfuck_lib::language::SubstitutionLanguage;
 
-/// Compile the `Hello world!` source code and print the generated `C` code to `STDOUT`.
+/// Execute the command given on the command line.
 fn main() {
     // Get the configuration.
-    let app: App = cli::setup();
+    let backends = BackendRegistry::new();
+    let languages = LanguageRegistry::new();
+    let app: App = cli::setup(&backends.names(), &languages.names());
     let arg_matches: ArgMatches = app.get_matches();
-    let (input_path, output_path): (PathBuf, PathBuf) = cli::get_arguments(&arg_matches);
+    let colored: bool = cli::use_colors(&arg_matches);
 
+    match cli::get_command(&arg_matches, &backends, &languages) {
+        Command::Compile { input, output, language, dialect, target, options } => {
+            let dialect: Option<SubstitutionLanguage> = dialect.map(|path| load_dialect(&path, colored));
+            let language: &dyn Language = get_language(&languages, &language, dialect.as_ref());
+            compile(&input, &output, language, &backends, &target, options, colored)
+        },
+        Command::ListTargets => list_targets(&backends),
+        Command::Build { input, output, language, dialect, options } => {
+            let dialect: Option<SubstitutionLanguage> = dialect.map(|path| load_dialect(&path, colored));
+            let language: &dyn Language = get_language(&languages, &language, dialect.as_ref());
+            build(&input, &output, language, options, colored)
+        },
+        Command::Run { input, language, dialect, options, jit } => {
+            let dialect: Option<SubstitutionLanguage> = dialect.map(|path| load_dialect(&path, colored));
+            let language: &dyn Language = get_language(&languages, &language, dialect.as_ref());
+            run(&input, language, options, jit, colored)
+        },
+        Command::Translate { input, output, from, from_dialect, to, to_dialect, layout } => {
+            let from_dialect: Option<SubstitutionLanguage> = from_dialect.map(|path| load_dialect(&path, colored));
+            let to_dialect: Option<SubstitutionLanguage> = to_dialect.map(|path| load_dialect(&path, colored));
+            let from: &dyn Language = get_language(&languages, &from, from_dialect.as_ref());
+            let to: &dyn Language = get_language(&languages, &to, to_dialect.as_ref());
+            translate(&input, output.as_deref(), from, to, layout, colored)
+        },
+    }
+}
+
+/// Get the `dialect` if there is one, or else the language with the given `name` among the `languages`.
+fn get_language<'a>(languages: &'a LanguageRegistry, name: &str, dialect: Option<&'a SubstitutionLanguage>)
+                    -> &'a dyn Language
+{
+    match dialect {
+        Some(dialect) => dialect,
+        None => languages.get(name).expect("The language has been checked on the command line."),
+    }
+}
+
+/// Load the substitution language defined by the mapping file at `path`. Errors are highlighted if `colored` is set.
+fn load_dialect(path: &Path, colored: bool) -> SubstitutionLanguage {
+    let file_name: String = path.display().to_string();
+    let mapping: String = read_source(path, colored);
+    match SubstitutionLanguage::from_mapping(&mapping) {
+        Ok(dialect) => dialect,
+        Err(error) => quit::fail_from_error(error, &Emitter::new(&file_name, &mapping, colored)),
+    }
+}
+
+/// Compile the source at `input_path` written in the `language` with the backend named `target` among the `backends`
+/// using the given `options` and write the generated code to `output_path`. Errors are highlighted if `colored` is
+/// set.
+fn compile(input_path: &Path, output_path: &Path, language: &dyn Language, backends: &BackendRegistry, target: &str,
+           options: Options, colored: bool) -> !
+{
     // Load the source.
     println!("Reading input from {path}.", path=input_path.display());
-    let input_file: File = match File::open(input_path) {
-        Ok(file) => file,
-        Err(error) => quit::fail_from_error(Error::from(error)),
-    };
-    let mut input_reader: BufReader<File> = BufReader::new(input_file);
-    let mut program = String::new();
-    match input_reader.read_to_string(&mut program) {
-        Ok(_) => {},
-        Err(error) => quit::fail_from_error(Error::from(error)),
-    }
+    let file_name: String = input_path.display().to_string();
+    let source: String = read_source(input_path, colored);
+    let emitter = Emitter::new(&file_name, &source, colored);
 
     // Compile the program.
     println!("Compiling.");
-    let compiler = Compiler::new();
-    let program: String = compiler.compile(&program);
+    let compiler = Compiler::with_options(options);
+    let backend: &dyn Backend = backends.get(target).expect("The target has been checked on the command line.");
+    let program: Vec<u8> = match compiler.compile_from(&source, language, backend) {
+        Ok(program) => program,
+        Err(error) => quit::fail_from_error(error, &emitter),
+    };
 
     // Write the output.
     println!("Writing output to {path}.", path=output_path.display());
     let output_file: File = match File::create(output_path) {
         Ok(file) => file,
-        Err(error) => quit::fail_from_error(Error::from(error)),
+        Err(error) => quit::fail_from_error(Error::from(error), &emitter),
     };
     let mut output_writer: BufWriter<File> = BufWriter::new(output_file);
-    let write_result: Result<(), IOError> = write!(output_writer, "{}", program);
+    let write_result: Result<(), IOError> = output_writer.write_all(&program);
     let flush_result: Result<(), IOError> = output_writer.flush();
 
     if let Err(error) = write_result {
-        quit::fail_from_error(Error::from(error));
+        quit::fail_from_error(Error::from(error), &emitter);
     }
 
     if let Err(error) = flush_result {
-        quit::fail_from_error(Error::from(error));
+        quit::fail_from_error(Error::from(error), &emitter);
+    }
+
+    println!("[SUCCESS]");
+    quit::succeed();
+}
+
+/// Print the names of all `backends` together with the extensions of their files.
+fn list_targets(backends: &BackendRegistry) -> ! {
+    for backend in backends.iter() {
+        println!("{name:<16} .{extension}", name = backend.name(), extension = backend.extension());
+    }
+
+    quit::succeed();
+}
+
+/// Compile the source at `input_path` written in the `language` to a native executable using the given `options` and
+/// write it to `output_path`. Errors are highlighted if `colored` is set.
+fn build(input_path: &Path, output_path: &Path, language: &dyn Language, options: Options, colored: bool) -> ! {
+    // Load the source.
+    println!("Reading input from {path}.", path=input_path.display());
+    let file_name: String = input_path.display().to_string();
+    let source: String = read_source(input_path, colored);
+    let emitter = Emitter::new(&file_name, &source, colored);
+
+    // Compile the program.
+    println!("Compiling.");
+    let compiler = Compiler::with_options(options);
+    let executable: Vec<u8> = match compiler.build_from(&source, language) {
+        Ok(executable) => executable,
+        Err(error) => quit::fail_from_error(error, &emitter),
+    };
+
+    // Write the output and make it executable.
+    println!("Writing output to {path}.", path=output_path.display());
+    let mut output_file: File = match File::create(output_path) {
+        Ok(file) => file,
+        Err(error) => quit::fail_from_error(Error::from(error), &emitter),
+    };
+    if let Err(error) = output_file.write_all(&executable) {
+        quit::fail_from_error(Error::from(error), &emitter);
+    }
+
+    if let Err(error) = make_executable(&output_file) {
+        quit::fail_from_error(Error::from(error), &emitter);
     }
 
     println!("[SUCCESS]");
     quit::succeed();
 }
+
+/// Allow everyone to execute the given `file`, and its owner to change it.
+#[cfg(unix)]
+fn make_executable(file: &File) -> Result<(), IOError> {
+    use std::fs::Permissions;
+    use std::os::unix::fs::PermissionsExt;
+
+    file.set_permissions(Permissions::from_mode(0o755))
+}
+
+/// Executables do not need any special permissions on this platform.
+#[cfg(not(unix))]
+fn make_executable(_file: &File) -> Result<(), IOError> {
+    Ok(())
+}
+
+/// Translate the source at `input_path` written in the language `from` into the language `to` with the given `layout`,
+/// and write the result to `output_path`, or to `STDOUT` if there is none. Errors are highlighted if `colored` is set.
+fn translate(input_path: &Path, output_path: Option<&Path>, from: &dyn Language, to: &dyn Language, layout: Layout,
+             colored: bool) -> !
+{
+    let file_name: String = input_path.display().to_string();
+    let source: String = read_source(input_path, colored);
+    let emitter = Emitter::new(&file_name, &source, colored);
+    let translated: String = match SourceGenerator::with_layout(to, layout).translate(&source, from) {
+        Ok(translated) => translated,
+        Err(error) => quit::fail_from_error(error, &emitter),
+    };
+
+    let result: Result<(), IOError> = match output_path {
+        Some(path) => File::create(path).and_then(|mut file| file.write_all(translated.as_bytes())),
+        None => io::stdout().write_all(translated.as_bytes()),
+    };
+    if let Err(error) = result {
+        quit::fail_from_error(Error::from(error), &emitter);
+    }
+
+    quit::succeed();
+}
+
+/// Execute the source at `input_path` written in the `language` using the given `options`, reading from `STDIN` and
+/// writing to `STDOUT`. The program is compiled to machine code first if `jit` is set. Errors are highlighted if
+/// `colored` is set.
+fn run(input_path: &Path, language: &dyn Language, options: Options, jit: bool, colored: bool) -> ! {
+    let file_name: String = input_path.display().to_string();
+    let source: String = read_source(input_path, colored);
+    let emitter = Emitter::new(&file_name, &source, colored);
+    let compiler = Compiler::with_options(options);
+    let program: Program = match compiler.parse_from(&source, language) {
+        Ok(program) => program,
+        Err(error) => quit::fail_from_error(error, &emitter),
+    };
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let bytecode = Bytecode::new(&program);
+    let result: Result<(), Error> = if jit {
+        Jit::with_options(options).run(&bytecode, &mut stdin.lock(), &mut stdout.lock())
+    } else {
+        VirtualMachine::with_options(options).run(&bytecode, &mut stdin.lock(), &mut stdout.lock())
+    };
+    if let Err(error) = result {
+        quit::fail_from_error(error, &emitter);
+    }
+
+    quit::succeed();
+}
+
+/// Read the entire source file at `path`. Errors are highlighted if `colored` is set.
+fn read_source(path: &Path, colored: bool) -> String {
+    let file_name: String = path.display().to_string();
+    let emitter = Emitter::new(&file_name, "", colored);
+
+    let input_file: File = match File::open(path) {
+        Ok(file) => file,
+        Err(error) => quit::fail_from_error(Error::from(error), &emitter),
+    };
+    let mut input_reader: BufReader<File> = BufReader::new(input_file);
+    let mut program = String::new();
+    match input_reader.read_to_string(&mut program) {
+        Ok(_) => {},
+        Err(error) => quit::fail_from_error(Error::from(error), &emitter),
+    }
+
+    program
+}
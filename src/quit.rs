@@ -6,9 +6,9 @@
 
 //! Quit the program with standardized exit codes.
 
-use std::error::Error as StdError;
 use std::process;
 
+use rustfuck_lib::Emitter;
 use rustfuck_lib::Error;
 
 /// The exit codes returned by the program.
@@ -20,20 +20,33 @@ pub enum ExitCode {
 
     /// Failure due to I/O operations (Code: `1`).
     IOFailure = 1,
+
+    /// Failure due to a malformed program (Code: `2`).
+    SyntaxError = 2,
+
+    /// Failure due to an invalid operation while executing the program (Code: `3`).
+    RuntimeError = 3,
+
+    /// Failure due to a malformed definition of a dialect (Code: `4`).
+    DialectError = 4,
 }
 
-/// Quit the program execution. The exit code and message are chosen based on the `error`.
-pub fn fail_from_error(error: Error) -> ! {
-    match error {
-        Error::IO(message) => {
-            fail_with_message(ExitCode::IOFailure, message.description());
-        }
-    }
+/// Quit the program execution. The exit code is chosen based on the `error`, the message is rendered by the
+/// `emitter`.
+pub fn fail_from_error(error: Error, emitter: &Emitter) -> ! {
+    let exit_code: ExitCode = match error {
+        Error::IO(_) => ExitCode::IOFailure,
+        Error::Syntax(_) => ExitCode::SyntaxError,
+        Error::Runtime(_) => ExitCode::RuntimeError,
+        Error::Dialect(_) => ExitCode::DialectError,
+    };
+
+    fail_with_message(exit_code, &emitter.render(&error));
 }
 
-/// Quit the program execution with the given `exit_code` and an error `message` explaining the xit.
+/// Quit the program execution with the given `exit_code` and an error `message` explaining the exit.
 pub fn fail_with_message(exit_code: ExitCode, message: &str) -> ! {
-    println!("Error: {description}", description = message);
+    eprint!("{message}", message = message);
     process::exit(exit_code as i32)
 }
 